@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::Value as Json;
+use serde_json::json;
+
+use jsruntime::Runtime;
+use jsruntime::ScriptCoverage;
+
+/// Writes the code coverage into `dir`.
+///
+/// `coverage.json` has the same shape as the result of `Profiler.takePreciseCoverage` in the
+/// Chrome DevTools Protocol.  `lcov.info` is a tracefile in the LCOV format.
+pub fn write<X>(dir: &Path, runtime: &Runtime<X>, coverage: &[ScriptCoverage]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    // Scripts are identified by their positions in the list.
+    let result = coverage
+        .iter()
+        .enumerate()
+        .map(|(index, script)| {
+            let functions = script
+                .functions
+                .iter()
+                .map(|function| {
+                    let ranges = function
+                        .ranges
+                        .iter()
+                        .map(|range| {
+                            json!({
+                                "startOffset": range.start_offset,
+                                "endOffset": range.end_offset,
+                                "count": range.count,
+                            })
+                        })
+                        .collect::<Vec<Json>>();
+                    json!({
+                        "functionName": function.function_name.as_deref().unwrap_or_default(),
+                        "ranges": ranges,
+                        "isBlockCoverage": function.is_block_coverage,
+                    })
+                })
+                .collect::<Vec<Json>>();
+            json!({
+                "scriptId": (index + 1).to_string(),
+                "url": script.url.as_deref().unwrap_or_default(),
+                "functions": functions,
+            })
+        })
+        .collect::<Vec<Json>>();
+    let json = json!({ "result": result });
+    std::fs::write(dir.join("coverage.json"), serde_json::to_string(&json)?)?;
+
+    std::fs::write(dir.join("lcov.info"), runtime.coverage_to_lcov(coverage))?;
+    Ok(())
+}
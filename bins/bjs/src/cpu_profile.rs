@@ -0,0 +1,61 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::Value as Json;
+use serde_json::json;
+
+use jsruntime::CpuProfile;
+
+/// Writes a CPU profile in the `.cpuprofile` format which Chrome DevTools can load.
+pub fn write(path: &Path, profile: &CpuProfile) -> Result<()> {
+    // Scripts are identified by their names.  "0" is used for the root node.
+    let mut scripts: Vec<&str> = vec![];
+    let nodes = profile
+        .nodes
+        .iter()
+        .map(|node| {
+            let call_frame = match node.lambda_id {
+                Some(_) => {
+                    let url = node.script.as_deref().unwrap_or_default();
+                    let script_id = match scripts.iter().position(|&script| script == url) {
+                        Some(index) => index + 1,
+                        None => {
+                            scripts.push(url);
+                            scripts.len()
+                        }
+                    };
+                    json!({
+                        "functionName": node.function.as_deref().unwrap_or_default(),
+                        "scriptId": script_id.to_string(),
+                        "url": url,
+                        // 0-based in the Chrome DevTools Protocol.
+                        "lineNumber": node.line as i64 - 1,
+                        "columnNumber": node.column as i64 - 1,
+                    })
+                }
+                None => json!({
+                    "functionName": "(root)",
+                    "scriptId": "0",
+                    "url": "",
+                    "lineNumber": -1,
+                    "columnNumber": -1,
+                }),
+            };
+            json!({
+                "id": node.id,
+                "callFrame": call_frame,
+                "hitCount": node.hit_count,
+                "children": node.children,
+            })
+        })
+        .collect::<Vec<Json>>();
+    let json = json!({
+        "nodes": nodes,
+        "startTime": profile.start_time,
+        "endTime": profile.end_time,
+        "samples": profile.samples,
+        "timeDeltas": profile.time_deltas,
+    });
+    std::fs::write(path, serde_json::to_string(&json)?)?;
+    Ok(())
+}
@@ -0,0 +1,561 @@
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+
+use anyhow::Result;
+use serde_json::Value as Json;
+use serde_json::json;
+use tungstenite::Message;
+use tungstenite::WebSocket;
+
+use jsruntime::BreakpointId;
+use jsruntime::Pause;
+use jsruntime::PauseReason;
+use jsruntime::ProgramId;
+use jsruntime::Runtime;
+use jsruntime::StackFrame;
+use jsruntime::StepAction;
+use jsruntime::Value;
+
+/// The path of the WebSocket endpoint.
+const TARGET_ID: &str = "bjs";
+
+const DEVTOOLS_FRONTEND_URL: &str = "devtools://devtools/bundled/js_app.html";
+
+/// A minimal Chrome DevTools Protocol (CDP) server.
+///
+/// The following features are supported:
+///
+///   * Pausing at `debugger` statements
+///   * Pausing at `throw` statements if enabled by `Debugger.setPauseOnExceptions`
+///   * Breakpoints and stepping at pause points located at function calls
+///   * Inspecting variables in the innermost frame
+///   * Evaluating expressions in the innermost frame
+///
+/// Breakpoints set by `Debugger.setBreakpointByUrl` are not applied to scripts parsed later.
+/// `Debugger.pause` is not supported.
+pub struct Inspector {
+    socket: Option<WebSocket<TcpStream>>,
+    scripts: Vec<Script>,
+    breakpoints: Vec<Breakpoint>,
+    next_breakpoint_id: u32,
+    debugger_enabled: bool,
+    pause_on_exceptions: bool,
+}
+
+struct Script {
+    program_id: ProgramId,
+    url: String,
+    source: String,
+}
+
+/// A breakpoint set by the client.
+///
+/// A breakpoint set by URL may be set in multiple scripts.
+struct Breakpoint {
+    id: String,
+    ids: Vec<BreakpointId>,
+}
+
+/// What to do after handling a request.
+enum Flow {
+    Continue,
+    Run,
+}
+
+impl Inspector {
+    /// Waits for a client to connect to `addr`.
+    pub fn listen(addr: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        eprintln!("Debugger listening on ws://{addr}/{TARGET_ID}");
+        let socket = loop {
+            let (mut stream, _) = listener.accept()?;
+            // DevTools looks for targets by HTTP requests before connecting to a WebSocket.
+            let mut buf = [0; 256];
+            let len = stream.peek(&mut buf)?;
+            let request = String::from_utf8_lossy(&buf[..len]);
+            if request.starts_with("GET /json") {
+                let body = if request.starts_with("GET /json/version") {
+                    json!({
+                        "Browser": concat!("bjs/", env!("CARGO_PKG_VERSION")),
+                        "Protocol-Version": "1.3",
+                    })
+                } else {
+                    json!([{
+                        "id": TARGET_ID,
+                        "type": "node",
+                        "title": "bjs",
+                        "description": "bjs instance",
+                        "url": "file://",
+                        "webSocketDebuggerUrl": format!("ws://{addr}/{TARGET_ID}"),
+                        "devtoolsFrontendUrl": format!(
+                            "{DEVTOOLS_FRONTEND_URL}?v8only=true&ws={addr}/{TARGET_ID}"
+                        ),
+                    }])
+                };
+                let body = body.to_string();
+                write!(stream, "HTTP/1.1 200 OK\r\n")?;
+                write!(stream, "Content-Type: application/json\r\n")?;
+                write!(stream, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+                continue;
+            }
+            match tungstenite::accept(stream) {
+                Ok(socket) => break socket,
+                Err(err) => eprintln!("Failed to accept a debugger: {err}"),
+            }
+        };
+        eprintln!("Debugger attached.");
+        Ok(Self {
+            socket: Some(socket),
+            scripts: vec![],
+            breakpoints: vec![],
+            next_breakpoint_id: 1,
+            debugger_enabled: false,
+            pause_on_exceptions: false,
+        })
+    }
+
+    /// Notifies the client of a parsed program.
+    pub fn script_parsed(&mut self, program_id: ProgramId, url: &str, source: &str) {
+        self.scripts.push(Script {
+            program_id,
+            url: url.to_owned(),
+            source: source.to_owned(),
+        });
+        if self.debugger_enabled {
+            self.send_script_parsed(self.scripts.len() - 1);
+        }
+    }
+
+    /// Processes requests until the client sends `Runtime.runIfWaitingForDebugger`.
+    pub fn wait_for_debugger<X>(&mut self, runtime: &mut Runtime<X>) {
+        while let Some(request) = self.receive() {
+            if let Flow::Run = self.handle_request(runtime, &request, None) {
+                break;
+            }
+        }
+    }
+
+    /// Processes requests until the client resumes the execution.
+    pub fn pause<X>(&mut self, runtime: &mut Runtime<X>, pause: &Pause) {
+        let (reason, data) = match pause.reason {
+            PauseReason::DebuggerStatement | PauseReason::Breakpoint(_) | PauseReason::Step => {
+                ("other", None)
+            }
+            PauseReason::Exception(ref exception) => {
+                if !self.pause_on_exceptions {
+                    return;
+                }
+                ("exception", Some(remote_object(exception)))
+            }
+        };
+        let call_frames = pause
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| self.call_frame(pause, index, frame))
+            .collect::<Vec<_>>();
+        let mut params = json!({
+            "callFrames": call_frames,
+            "reason": reason,
+        });
+        if let Some(data) = data {
+            params["data"] = data;
+        }
+        if let PauseReason::Breakpoint(id) = pause.reason {
+            let hit = self
+                .breakpoints
+                .iter()
+                .filter(|breakpoint| breakpoint.ids.contains(&id))
+                .map(|breakpoint| breakpoint.id.clone())
+                .collect::<Vec<_>>();
+            params["hitBreakpoints"] = json!(hit);
+        }
+        self.send_event("Debugger.paused", params);
+
+        while let Some(request) = self.receive() {
+            if let Flow::Run = self.handle_request(runtime, &request, Some(pause)) {
+                break;
+            }
+        }
+        self.send_event("Debugger.resumed", json!({}));
+    }
+
+    fn handle_request<X>(
+        &mut self,
+        runtime: &mut Runtime<X>,
+        request: &Json,
+        pause: Option<&Pause>,
+    ) -> Flow {
+        let id = request["id"].clone();
+        let params = &request["params"];
+        let method = request["method"].as_str().unwrap_or_default();
+        let (result, flow) = match method {
+            "Runtime.enable" => {
+                self.send_result(&id, Ok(json!({})));
+                self.send_event(
+                    "Runtime.executionContextCreated",
+                    json!({"context": {"id": 1, "origin": "", "name": "bjs"}}),
+                );
+                return Flow::Continue;
+            }
+            "Debugger.enable" => {
+                self.send_result(&id, Ok(json!({"debuggerId": TARGET_ID})));
+                self.debugger_enabled = true;
+                for index in 0..self.scripts.len() {
+                    self.send_script_parsed(index);
+                }
+                return Flow::Continue;
+            }
+            "Debugger.disable" => {
+                self.debugger_enabled = false;
+                (Ok(json!({})), Flow::Continue)
+            }
+            "Runtime.runIfWaitingForDebugger" => (Ok(json!({})), Flow::Run),
+            "Debugger.resume" => (Ok(json!({})), Flow::Run),
+            "Debugger.stepOver" | "Debugger.stepInto" | "Debugger.stepOut" => {
+                if pause.is_none() {
+                    (
+                        Err("Can only perform operation while paused"),
+                        Flow::Continue,
+                    )
+                } else {
+                    let action = match method {
+                        "Debugger.stepOver" => StepAction::Over,
+                        "Debugger.stepInto" => StepAction::Into,
+                        _ => StepAction::Out,
+                    };
+                    runtime.step(action);
+                    (Ok(json!({})), Flow::Run)
+                }
+            }
+            "Debugger.setPauseOnExceptions" => {
+                self.pause_on_exceptions = params["state"].as_str() != Some("none");
+                (Ok(json!({})), Flow::Continue)
+            }
+            "Debugger.getScriptSource" => {
+                let source = params["scriptId"]
+                    .as_str()
+                    .and_then(|id| id.parse::<usize>().ok())
+                    .and_then(|index| self.scripts.get(index))
+                    .map(|script| script.source.clone());
+                let result = match source {
+                    Some(source) => Ok(json!({"scriptSource": source})),
+                    None => Err("No script for id"),
+                };
+                (result, Flow::Continue)
+            }
+            "Debugger.evaluateOnCallFrame" | "Runtime.evaluate" => {
+                let expression = params["expression"].as_str().unwrap_or_default();
+                let frame = params["callFrameId"].as_str().unwrap_or("0");
+                let result = match pause {
+                    Some(pause) if frame == "0" => Ok(evaluation_result(
+                        runtime.evaluate_on_pause(pause, expression),
+                    )),
+                    Some(_) => Err("Evaluation is supported only in the top frame"),
+                    None => Err("Evaluation is supported only while paused"),
+                };
+                (result, Flow::Continue)
+            }
+            "Runtime.getProperties" => {
+                let scope = params["objectId"]
+                    .as_str()
+                    .and_then(|id| id.strip_prefix("scope:"))
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .and_then(|index| pause.and_then(|pause| pause.scopes.get(index)));
+                let properties = match scope {
+                    Some(scope) => scope
+                        .variables
+                        .iter()
+                        .map(|(name, value)| {
+                            json!({
+                                "name": name,
+                                "value": remote_object(value),
+                                "writable": false,
+                                "configurable": false,
+                                "enumerable": true,
+                                "isOwn": true,
+                            })
+                        })
+                        .collect(),
+                    // Properties of JavaScript objects are not inspectable.
+                    None => vec![],
+                };
+                (Ok(json!({"result": properties})), Flow::Continue)
+            }
+            "Debugger.setBreakpointByUrl" => {
+                let line = params["lineNumber"].as_u64().unwrap_or_default() as u32;
+                let column = params["columnNumber"].as_u64().unwrap_or_default() as u32;
+                let url = params["url"].as_str();
+                let mut ids = vec![];
+                let mut locations = vec![];
+                for index in 0..self.scripts.len() {
+                    if url.is_some_and(|url| url != self.scripts[index].url) {
+                        continue;
+                    }
+                    let program_id = self.scripts[index].program_id;
+                    if let Some((id, point)) =
+                        runtime.set_breakpoint(program_id, line + 1, column + 1)
+                    {
+                        ids.push(id);
+                        locations.push(location(index, point.line, point.column));
+                    }
+                }
+                let id = self.add_breakpoint(ids);
+                (
+                    Ok(json!({"breakpointId": id, "locations": locations})),
+                    Flow::Continue,
+                )
+            }
+            "Debugger.setBreakpoint" => {
+                let params = &params["location"];
+                let line = params["lineNumber"].as_u64().unwrap_or_default() as u32;
+                let column = params["columnNumber"].as_u64().unwrap_or_default() as u32;
+                let result = match self.script_index(&params["scriptId"]) {
+                    Some(index) => {
+                        let program_id = self.scripts[index].program_id;
+                        match runtime.set_breakpoint(program_id, line + 1, column + 1) {
+                            Some((id, point)) => {
+                                let id = self.add_breakpoint(vec![id]);
+                                Ok(json!({
+                                    "breakpointId": id,
+                                    "actualLocation": location(index, point.line, point.column),
+                                }))
+                            }
+                            None => Err("Could not resolve breakpoint"),
+                        }
+                    }
+                    None => Err("No script for id"),
+                };
+                (result, Flow::Continue)
+            }
+            "Debugger.removeBreakpoint" => {
+                let id = params["breakpointId"].as_str().unwrap_or_default();
+                if let Some(index) = self.breakpoints.iter().position(|bp| bp.id == id) {
+                    for id in self.breakpoints.remove(index).ids {
+                        runtime.remove_breakpoint(id);
+                    }
+                }
+                (Ok(json!({})), Flow::Continue)
+            }
+            "Debugger.getPossibleBreakpoints" => {
+                let start = &params["start"];
+                let end = &params["end"];
+                let result = match self.script_index(&start["scriptId"]) {
+                    Some(index) => {
+                        // Locations are 0-based in the protocol.
+                        let position = |location: &Json| {
+                            (
+                                location["lineNumber"].as_u64().unwrap_or_default(),
+                                location["columnNumber"].as_u64().unwrap_or_default(),
+                            )
+                        };
+                        let start = position(start);
+                        let end = end.is_object().then(|| position(end));
+                        let locations = runtime
+                            .pause_points(self.scripts[index].program_id)
+                            .into_iter()
+                            .filter(|point| {
+                                let point = (point.line as u64 - 1, point.column as u64 - 1);
+                                point >= start && end.is_none_or(|end| point < end)
+                            })
+                            .map(|point| location(index, point.line, point.column))
+                            .collect::<Vec<_>>();
+                        Ok(json!({"locations": locations}))
+                    }
+                    None => Err("No script for id"),
+                };
+                (result, Flow::Continue)
+            }
+            "Debugger.pause" => (Err("Not supported"), Flow::Continue),
+            // Other requests such as `Profiler.enable` are simply acknowledged.
+            _ => (Ok(json!({})), Flow::Continue),
+        };
+        self.send_result(&id, result);
+        flow
+    }
+
+    fn script_index(&self, script_id: &Json) -> Option<usize> {
+        script_id
+            .as_str()
+            .and_then(|id| id.parse::<usize>().ok())
+            .filter(|&index| index < self.scripts.len())
+    }
+
+    fn add_breakpoint(&mut self, ids: Vec<BreakpointId>) -> String {
+        let id = self.next_breakpoint_id.to_string();
+        self.next_breakpoint_id += 1;
+        self.breakpoints.push(Breakpoint {
+            id: id.clone(),
+            ids,
+        });
+        id
+    }
+
+    fn call_frame(&self, pause: &Pause, index: usize, frame: &StackFrame) -> Json {
+        let script_id = match index {
+            0 => self
+                .scripts
+                .iter()
+                .position(|script| script.program_id == pause.program_id),
+            _ => self
+                .scripts
+                .iter()
+                .position(|script| Some(&script.url) == frame.script.as_ref()),
+        };
+        // Variables are available only in the innermost frame.
+        let mut scope_chain = vec![];
+        if index == 0 {
+            for (scope_index, scope) in pause.scopes.iter().enumerate() {
+                scope_chain.push(json!({
+                    "type": if scope.function { "local" } else { "block" },
+                    "object": {
+                        "type": "object",
+                        "className": "Object",
+                        "description": "Object",
+                        "objectId": format!("scope:{scope_index}"),
+                    },
+                }));
+            }
+        }
+        scope_chain.push(json!({
+            "type": "global",
+            "object": {"type": "object", "className": "Object", "description": "global"},
+        }));
+        json!({
+            "callFrameId": index.to_string(),
+            "functionName": frame.function.as_deref().unwrap_or_default(),
+            "location": {
+                "scriptId": script_id.map(|id| id.to_string()).unwrap_or_default(),
+                "lineNumber": frame.line.saturating_sub(1),
+                "columnNumber": frame.column.saturating_sub(1),
+            },
+            "url": frame.script.as_deref().unwrap_or_default(),
+            "scopeChain": scope_chain,
+            "this": {"type": "undefined"},
+        })
+    }
+
+    fn send_script_parsed(&mut self, index: usize) {
+        let script = &self.scripts[index];
+        let lines = script.source.lines().collect::<Vec<_>>();
+        let params = json!({
+            "scriptId": index.to_string(),
+            "url": script.url,
+            "startLine": 0,
+            "startColumn": 0,
+            "endLine": lines.len(),
+            "endColumn": 0,
+            "executionContextId": 1,
+            "hash": "",
+        });
+        self.send_event("Debugger.scriptParsed", params);
+    }
+
+    fn send_result(&mut self, id: &Json, result: Result<Json, &str>) {
+        let message = match result {
+            Ok(result) => json!({"id": id, "result": result}),
+            Err(message) => json!({"id": id, "error": {"code": -32000, "message": message}}),
+        };
+        self.send(message);
+    }
+
+    fn send_event(&mut self, method: &str, params: Json) {
+        self.send(json!({"method": method, "params": params}));
+    }
+
+    fn send(&mut self, message: Json) {
+        let Some(ref mut socket) = self.socket else {
+            return;
+        };
+        if let Err(err) = socket.send(Message::text(message.to_string())) {
+            eprintln!("Debugger disconnected: {err}");
+            self.socket = None;
+        }
+    }
+
+    // Returns `None` if the client has been disconnected.
+    fn receive(&mut self) -> Option<Json> {
+        loop {
+            let socket = self.socket.as_mut()?;
+            match socket.read() {
+                Ok(Message::Text(text)) => match serde_json::from_str(text.as_str()) {
+                    Ok(request) => return Some(request),
+                    Err(err) => eprintln!("Invalid request from the debugger: {err}"),
+                },
+                Ok(Message::Close(_)) => {
+                    eprintln!("Debugger disconnected.");
+                    self.socket = None;
+                }
+                Ok(_) => (),
+                Err(err) => {
+                    eprintln!("Debugger disconnected: {err}");
+                    self.socket = None;
+                }
+            }
+        }
+    }
+}
+
+// Converts a 1-based line and column into a `Debugger.Location`.
+fn location(script_index: usize, line: u32, column: u32) -> Json {
+    json!({
+        "scriptId": script_index.to_string(),
+        "lineNumber": line - 1,
+        "columnNumber": column - 1,
+    })
+}
+
+fn evaluation_result(result: Result<Value, Value>) -> Json {
+    match result {
+        Ok(value) => json!({"result": remote_object(&value)}),
+        Err(exception) => {
+            let exception = remote_object(&exception);
+            json!({
+                "result": exception,
+                "exceptionDetails": {
+                    "exceptionId": 1,
+                    "text": "Uncaught",
+                    "lineNumber": 0,
+                    "columnNumber": 0,
+                    "exception": exception,
+                },
+            })
+        }
+    }
+}
+
+// Converts a value into a `Runtime.RemoteObject`.
+fn remote_object(value: &Value) -> Json {
+    match value {
+        Value::None | Value::Undefined => json!({"type": "undefined"}),
+        Value::Null => json!({"type": "object", "subtype": "null", "value": null}),
+        Value::Boolean(value) => json!({"type": "boolean", "value": value}),
+        Value::Number(value) if value.is_finite() => json!({
+            "type": "number",
+            "value": value,
+            "description": value.to_string(),
+        }),
+        Value::Number(value) => {
+            let value = if value.is_nan() {
+                "NaN"
+            } else if value.is_sign_positive() {
+                "Infinity"
+            } else {
+                "-Infinity"
+            };
+            json!({
+                "type": "number",
+                "unserializableValue": value,
+                "description": value,
+            })
+        }
+        Value::String(_) => json!({"type": "string", "value": value.to_string()}),
+        Value::Object(_) => json!({
+            "type": "object",
+            "className": "Object",
+            "description": "Object",
+        }),
+    }
+}
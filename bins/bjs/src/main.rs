@@ -1,14 +1,24 @@
+mod coverage;
+mod cpu_profile;
+mod inspector;
+
+use std::cell::RefCell;
 use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
 
 use anyhow::Result;
 use clap::Parser as _;
 use itertools::Itertools;
 
 use jsruntime::Runtime;
+use jsruntime::UnhandledRejection;
 use jsruntime::Value;
 
+use inspector::Inspector;
+
 #[derive(clap::Parser)]
 struct CommandLine {
     #[command(subcommand)]
@@ -93,6 +103,48 @@ struct Run {
     /// Disable optimization.
     #[arg(long)]
     no_optimize: bool,
+
+    /// A directory to store code caches of the source files.
+    ///
+    /// A source file is loaded from its code cache without parsing if the source text has not
+    /// been changed since the code cache was created.
+    #[arg(long, value_name = "DIR")]
+    code_cache: Option<PathBuf>,
+
+    /// Waits for a debugger speaking the Chrome DevTools Protocol before running the programs.
+    ///
+    /// The programs start running when the debugger sends `Runtime.runIfWaitingForDebugger`.
+    #[arg(
+        long,
+        value_name = "ADDR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "127.0.0.1:9229"
+    )]
+    inspect: Option<String>,
+
+    /// Writes a CPU profile in the `.cpuprofile` format to FILE after running the programs.
+    ///
+    /// The profile can be loaded in Chrome DevTools.
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "bjs.cpuprofile"
+    )]
+    cpu_prof: Option<PathBuf>,
+
+    /// The sampling interval of the CPU profiler in microseconds.
+    #[arg(long, value_name = "MICROSECONDS", default_value = "1000")]
+    cpu_prof_interval: u64,
+
+    /// Writes the code coverage into DIR after running the programs.
+    ///
+    /// `coverage.json` in the shape of the result of `Profiler.takePreciseCoverage` in the Chrome
+    /// DevTools Protocol and `lcov.info` in the LCOV format are written.
+    #[arg(long, value_name = "DIR")]
+    coverage: Option<PathBuf>,
 }
 
 #[derive(clap::ValueEnum, Clone)]
@@ -109,6 +161,16 @@ enum SourceType {
     Module,
 }
 
+impl SourceType {
+    fn is_module(&self, input: &Path) -> bool {
+        match self {
+            Self::Auto => input.extension().is_some_and(|ext| ext == "mjs"),
+            Self::Script => false,
+            Self::Module => true,
+        }
+    }
+}
+
 fn main() -> Result<()> {
     logging::init();
 
@@ -124,23 +186,23 @@ fn main() -> Result<()> {
         runtime.enable_runtime_assert();
     }
     runtime.register_host_function("print", print);
+    runtime.set_unhandled_rejection_callback(report_unhandled_rejection);
 
     // This is not a good practice, but we define a macro instead of a function in order to avoid
     // code clones.  By using the macro, we can avoid additional `use` directives needed for the
     // return type.
     macro_rules! parse {
         ($input:expr, $source:expr, $source_type:expr) => {{
-            let result = match $source_type {
-                SourceType::Auto => match $input.extension() {
-                    Some(ext) if ext == "js" => runtime.parse_script(&$source),
-                    Some(ext) if ext == "mjs" => runtime.parse_module(&$source),
-                    _ => runtime.parse_script(&$source),
-                },
-                SourceType::Script => runtime.parse_script(&$source),
-                SourceType::Module => runtime.parse_module(&$source),
+            let result = if $source_type.is_module(&$input) {
+                runtime.parse_module(&$source)
+            } else {
+                runtime.parse_script(&$source)
             };
             match result {
-                Ok(program_id) => program_id,
+                Ok(program_id) => {
+                    runtime.set_program_name(program_id, &$input.display().to_string());
+                    program_id
+                }
                 Err(err) => {
                     println!("Failed parsing {:?}: {err:?}", $input);
                     std::process::exit(2);
@@ -192,6 +254,26 @@ fn main() -> Result<()> {
             }
         }
         Command::Run(ref args) => {
+            let inspector = match args.inspect {
+                Some(ref addr) => {
+                    let inspector = Rc::new(RefCell::new(Inspector::listen(addr)?));
+                    // The debugger must be set before parsing programs.
+                    let cloned = inspector.clone();
+                    runtime.set_debugger(move |runtime, pause| {
+                        cloned.borrow_mut().pause(runtime, pause);
+                    });
+                    Some(inspector)
+                }
+                None => None,
+            };
+            if args.cpu_prof.is_some() {
+                // The profiler must be started before compiling programs.
+                runtime.start_profiling(Duration::from_micros(args.cpu_prof_interval));
+            }
+            if args.coverage.is_some() {
+                // The coverage must be started before compiling programs.
+                runtime.start_coverage();
+            }
             for path in args.preload_scripts.iter() {
                 println!("## {} (preload script)", path.display());
                 let source = std::fs::read_to_string(path)?;
@@ -202,20 +284,73 @@ fn main() -> Result<()> {
                     anyhow::bail!("Uncaught {v:?} in {path:?}");
                 }
             }
+            if let Some(ref dir) = args.code_cache {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut started = false;
             for (input, source) in cl.sources() {
                 println!("## {}", input.display());
-                let program_id = parse!(input, source, cl.parse_as);
+                let cache_path = args
+                    .code_cache
+                    .as_ref()
+                    .map(|dir| code_cache_path(dir, input));
+                let module = cl.parse_as.is_module(input);
+                let cached = cache_path
+                    .as_ref()
+                    .and_then(|path| std::fs::read(path).ok())
+                    .and_then(|data| runtime.load_code_cache(&source, module, &data).ok());
+                let program_id = match cached {
+                    Some(program_id) => {
+                        runtime.set_program_name(program_id, &input.display().to_string());
+                        program_id
+                    }
+                    None => {
+                        let program_id = parse!(input, source, cl.parse_as);
+                        if let Some(ref path) = cache_path {
+                            std::fs::write(path, runtime.create_code_cache(program_id))?;
+                        }
+                        program_id
+                    }
+                };
+                if let Some(ref inspector) = inspector {
+                    let mut inspector = inspector.borrow_mut();
+                    inspector.script_parsed(program_id, &input.display().to_string(), &source);
+                    if !started {
+                        inspector.wait_for_debugger(&mut runtime);
+                        started = true;
+                    }
+                }
                 if let Err(v) = runtime.run(program_id, !args.no_optimize) {
                     anyhow::bail!("Uncaught {v:?} in {input:?}");
                 }
             }
-            runtime.process_jobs();
+            if let Err(v) = runtime.run_until_idle() {
+                anyhow::bail!("Uncaught {v:?} in the event loop");
+            }
+            if let Some(ref path) = args.cpu_prof {
+                let profile = runtime.stop_profiling().unwrap();
+                cpu_profile::write(path, &profile)?;
+            }
+            if let Some(ref dir) = args.coverage {
+                let coverage = runtime.take_coverage();
+                coverage::write(dir, &runtime, &coverage)?;
+            }
         }
     }
 
     Ok(())
 }
 
+// The path of the code cache of a source file in `dir`.
+fn code_cache_path(dir: &Path, input: &Path) -> PathBuf {
+    use std::hash::Hash;
+    use std::hash::Hasher;
+
+    let mut hasher = std::hash::DefaultHasher::new();
+    input.hash(&mut hasher);
+    dir.join(format!("{:016x}.bjsc", hasher.finish()))
+}
+
 fn read_from_stdin() -> Result<String> {
     let mut source = String::new();
     std::io::stdin().read_to_string(&mut source)?;
@@ -226,6 +361,13 @@ fn print(_runtime: &mut Runtime<Context>, args: &[Value]) {
     println!("{}", args.iter().format(" "));
 }
 
+fn report_unhandled_rejection(_runtime: &mut Runtime<Context>, rejection: &UnhandledRejection) {
+    match rejection.stack {
+        Some(ref stack) => eprintln!("Uncaught (in promise) {stack}"),
+        None => eprintln!("Uncaught (in promise) {}", rejection.reason),
+    }
+}
+
 struct IrPrinter;
 
 impl jsruntime::Monitor for IrPrinter {
@@ -32,7 +32,27 @@ impl Runner {
     fn setup_runtime(&mut self) {
         self.runtime.enable_scope_cleanup_checker();
         self.runtime.enable_runtime_assert();
-        self.runtime.register_host_function("print", Self::print); // TODO
+        Self::setup_realm(&mut self.runtime);
+    }
+
+    // Defines host-defined globals including `$262` in the current realm.
+    fn setup_realm(runtime: &mut Runtime<Context>) {
+        runtime.register_host_function("print", Self::print); // TODO
+        runtime.register_function("__createRealm", Self::create_realm);
+        let program_id = runtime.parse_script(HOST_DEFINED_SCRIPT).unwrap();
+        let result = runtime.run(program_id, true);
+        assert!(result.is_ok());
+    }
+
+    // $262.createRealm()
+    fn create_realm(runtime: &mut Runtime<Context>) -> Value {
+        let realm = runtime.create_realm();
+        let current = runtime.realm();
+        runtime.set_realm(realm);
+        Self::setup_realm(runtime);
+        let value = runtime.get_global("$262").get();
+        runtime.set_realm(current);
+        value
     }
 
     fn load_harness(&mut self, test_case: &TestCase) -> Result<(), Error> {
@@ -84,6 +104,15 @@ impl Runner {
     fn print(_runtime: &mut Runtime<Context>, _args: &[Value]) {}
 }
 
+const HOST_DEFINED_SCRIPT: &str = r#"
+var $262 = {
+  global: globalThis,
+  createRealm: function () {
+    return __createRealm();
+  },
+};
+"#;
+
 struct Context;
 
 impl Default for Context {
@@ -1,4 +1,5 @@
 use std::alloc::Layout;
+use std::collections::VecDeque;
 use std::ptr::NonNull;
 
 use rustc_hash::FxHashMap;
@@ -7,8 +8,12 @@ use rustc_hash::FxHashSet;
 use crate::handle::Handle;
 use crate::handle::HandleMut;
 use crate::handle::Seq;
+use crate::snapshot::SnapshotBuilder;
 use crate::trace::Trace;
 use crate::trace::VisitList;
+use crate::weak::Ephemeron;
+use crate::weak::FinalizationCell;
+use crate::weak::WeakHandle;
 
 /// A heap memory managed by GC.
 pub struct Heap {
@@ -19,6 +24,33 @@ pub struct Heap {
     ///
     /// The set MAY contain addresses of *unmanaged* memory blocks.
     trace_targets: FxHashMap<usize, Tracer>,
+
+    /// A set of *managed* memory blocks holding weak references.
+    ///
+    /// References held in these memory blocks are not traced in the mark phase.  They are
+    /// cleared after the mark phase if their targets are not marked.
+    weak_blocks: FxHashMap<usize, WeakBlock>,
+
+    /// A queue of addresses of `FinalizationCell`s whose targets have been reclaimed.
+    ///
+    /// Memory blocks in the queue are treated as roots until they are popped.
+    finalization_queue: VecDeque<usize>,
+
+    /// The total size in bytes of *managed* memory blocks.
+    num_bytes: usize,
+
+    /// The maximum size in bytes of *managed* memory blocks.
+    ///
+    /// This is a soft limit.  Allocations never fail even if the limit is exceeded.  Instead, the
+    /// heap is marked as exhausted.
+    limit: usize,
+
+    near_limit_callback: Option<NearLimitCallback>,
+
+    /// `true` if the limit has been exceeded.
+    ///
+    /// This is boxed in order to provide a stable address to generated code polling it.
+    exhausted: Box<bool>,
 }
 
 impl Heap {
@@ -27,9 +59,54 @@ impl Heap {
         Self {
             memory_blocks: Default::default(),
             trace_targets: Default::default(),
+            weak_blocks: Default::default(),
+            finalization_queue: Default::default(),
+            num_bytes: 0,
+            limit: usize::MAX,
+            near_limit_callback: None,
+            exhausted: Box::new(false),
         }
     }
 
+    /// Returns the maximum size in bytes of managed memory blocks.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Sets the maximum size in bytes of managed memory blocks.
+    ///
+    /// There is no limit by default.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.update_exhausted();
+    }
+
+    /// Sets a callback function called when an allocation exceeds the limit.
+    ///
+    /// The callback function receives the current limit and the total size in bytes including
+    /// the allocation, and returns a new limit.  The heap is marked as exhausted if the new limit
+    /// is still exceeded.
+    pub fn set_near_limit_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(usize, usize) -> usize + 'static,
+    {
+        self.near_limit_callback = Some(Box::new(callback));
+    }
+
+    /// Returns `true` if the limit has been exceeded.
+    ///
+    /// The state is reset when the total size falls below the limit by a garbage collection.
+    pub fn is_exhausted(&self) -> bool {
+        *self.exhausted
+    }
+
+    /// Returns the address of a `bool` value which holds the same value as `is_exhausted()`.
+    ///
+    /// The address is valid while the heap is alive.
+    pub fn exhausted_flag_addr(&self) -> usize {
+        &*self.exhausted as *const bool as usize
+    }
+
     /// Populates a specified object on memory allocated from the heap.
     pub fn alloc<T>(&mut self, object: T) -> Handle<T>
     where
@@ -44,6 +121,7 @@ impl Heap {
             ptr
         };
 
+        self.account_alloc(size_of::<T>());
         self.memory_blocks
             .insert(ptr as usize, MemoryBlock::new::<T>());
         self.trace_targets.insert(ptr as usize, Tracer::new::<T>());
@@ -65,6 +143,7 @@ impl Heap {
             ptr
         };
 
+        self.account_alloc(size_of::<T>());
         self.memory_blocks
             .insert(ptr as usize, MemoryBlock::new::<T>());
         self.trace_targets.insert(ptr as usize, Tracer::new::<T>());
@@ -86,6 +165,7 @@ impl Heap {
 
         init(ptr);
 
+        self.account_alloc(layout.size());
         self.memory_blocks
             .insert(ptr.addr().get(), MemoryBlock::with_layout::<T>(layout));
         self.trace_targets
@@ -109,6 +189,7 @@ impl Heap {
 
         init(ptr);
 
+        self.account_alloc(layout.size());
         self.memory_blocks
             .insert(ptr.addr().get(), MemoryBlock::with_layout::<T>(layout));
         self.trace_targets
@@ -134,8 +215,9 @@ impl Heap {
             Handle::from_ref(ptr.cast::<T>().as_ref())
         };
 
+        self.account_alloc(layout.size());
         self.memory_blocks
-            .insert(data.as_addr(), MemoryBlock::with_layout::<T>(layout));
+            .insert(data.as_addr(), MemoryBlock::seq::<T>(layout));
         // No need to trace.
 
         Seq { data, len }
@@ -157,13 +239,59 @@ impl Heap {
 
         init(ptr);
 
+        self.account_alloc(layout.size());
         self.memory_blocks
-            .insert(data.as_addr(), MemoryBlock::with_layout::<T>(layout));
+            .insert(data.as_addr(), MemoryBlock::seq::<T>(layout));
         // No need to trace.
 
         Seq { data, len }
     }
 
+    /// Creates a weak reference to a specified target.
+    pub fn alloc_weak<T>(&mut self, target: HandleMut<T>) -> WeakHandle<T> {
+        WeakHandle::new(self.alloc_ephemeron(target, ()))
+    }
+
+    /// Populates an ephemeron on memory allocated from the heap.
+    pub fn alloc_ephemeron<K, V>(
+        &mut self,
+        key: HandleMut<K>,
+        value: V,
+    ) -> HandleMut<Ephemeron<K, V>>
+    where
+        V: Sized + Trace,
+    {
+        let ephemeron = self.alloc_mut(Ephemeron::new(key, value));
+        self.weak_blocks
+            .insert(ephemeron.as_addr(), WeakBlock::ephemeron::<K, V>());
+        ephemeron
+    }
+
+    /// Populates a finalization cell on memory allocated from the heap.
+    pub fn alloc_finalization_cell<K, V>(
+        &mut self,
+        target: HandleMut<K>,
+        held_value: V,
+    ) -> HandleMut<FinalizationCell<K, V>>
+    where
+        V: Sized + Trace,
+    {
+        let cell = self.alloc_mut(FinalizationCell::new(target, held_value));
+        self.weak_blocks
+            .insert(cell.as_addr(), WeakBlock::finalization_cell::<K, V>());
+        cell
+    }
+
+    /// Removes the first finalization cell whose target has been reclaimed and returns it, or
+    /// `None` if the finalization queue is empty.
+    ///
+    /// The type parameters must be the same as the ones used in `alloc_finalization_cell()`.
+    pub fn pop_finalization<K, V>(&mut self) -> Option<HandleMut<FinalizationCell<K, V>>> {
+        self.finalization_queue
+            .pop_front()
+            .and_then(HandleMut::from_addr)
+    }
+
     // TODO(feat): not ergonomic... need a way to prevent UAF.
     pub fn add_tracer<T>(&mut self, target: Handle<T>)
     where
@@ -187,19 +315,90 @@ impl Heap {
     /// Reclaims objects that are not reachable from a specified root objects.
     pub fn collect_garbage(&mut self, roots: &[usize]) {
         let mut state = GcState::new(roots);
+        state
+            .visit_list
+            .extend(self.finalization_queue.iter().cloned());
         self.mark(&mut state);
+        self.clear_weak_references(&state);
         self.sweep(&mut state);
+        self.update_exhausted();
+    }
+
+    fn account_alloc(&mut self, size: usize) {
+        self.num_bytes += size;
+        if self.num_bytes <= self.limit || self.is_exhausted() {
+            return;
+        }
+        if let Some(ref mut callback) = self.near_limit_callback {
+            self.limit = callback(self.limit, self.num_bytes);
+        }
+        self.update_exhausted();
+    }
+
+    fn update_exhausted(&mut self) {
+        *self.exhausted = self.num_bytes > self.limit;
     }
 
     /// Performs the mark phase.
     fn mark(&mut self, state: &mut GcState) {
-        while let Some(addr) = state.visit_list.pop() {
-            if state.visited.contains(&addr) {
+        loop {
+            while let Some(addr) = state.visit_list.pop() {
+                if state.visited.contains(&addr) {
+                    continue;
+                }
+                state.visited.insert(addr);
+                if let Some(tracer) = self.trace_targets.get(&addr) {
+                    (tracer.trace_fn)(addr, &mut state.visit_list);
+                }
+            }
+            // Values of ephemerons may make other ephemerons' keys reachable.  Repeat until no
+            // more values are traced.
+            if !self.trace_ephemerons(state) {
+                break;
+            }
+        }
+    }
+
+    /// Traces values of reachable ephemerons whose keys have been marked.
+    ///
+    /// Returns `true` if at least one value is traced.
+    fn trace_ephemerons(&self, state: &mut GcState) -> bool {
+        let mut traced = false;
+        for (addr, block) in self.weak_blocks.iter() {
+            let trace_value_fn = match block.trace_value_fn {
+                Some(trace_value_fn) => trace_value_fn,
+                None => continue,
+            };
+            if !state.visited.contains(addr) || state.traced_ephemerons.contains(addr) {
+                continue;
+            }
+            match (block.key_fn)(*addr) {
+                Some(key) if state.visited.contains(&key) => {
+                    trace_value_fn(*addr, &mut state.visit_list);
+                    state.traced_ephemerons.insert(*addr);
+                    traced = true;
+                }
+                _ => (),
+            }
+        }
+        traced
+    }
+
+    /// Clears weak references to memory blocks that will be reclaimed in the sweep phase.
+    fn clear_weak_references(&mut self, state: &GcState) {
+        for (addr, block) in self.weak_blocks.iter() {
+            if !state.visited.contains(addr) {
+                // The weak memory block itself will be reclaimed.
                 continue;
             }
-            state.visited.insert(addr);
-            if let Some(tracer) = self.trace_targets.get(&addr) {
-                (tracer.trace_fn)(addr, &mut state.visit_list);
+            match (block.key_fn)(*addr) {
+                Some(key) if !state.visited.contains(&key) => {
+                    (block.clear_fn)(*addr);
+                    if block.finalization {
+                        self.finalization_queue.push_back(*addr);
+                    }
+                }
+                _ => (),
             }
         }
     }
@@ -211,6 +410,8 @@ impl Heap {
             .extract_if(|addr, _| !state.visited.contains(addr))
         {
             self.trace_targets.remove(&addr);
+            self.weak_blocks.remove(&addr);
+            self.num_bytes -= memory.layout.size();
             if let Some(tidy_fn) = memory.tidy_fn {
                 tidy_fn(addr);
             }
@@ -223,9 +424,99 @@ impl Heap {
 
     /// Returns statistics.
     pub fn stats(&self) -> Stats {
+        let mut types: FxHashMap<&'static str, TypeStats> = Default::default();
+        for memory in self.memory_blocks.values() {
+            let stats = types.entry(memory.type_name).or_insert(TypeStats {
+                type_name: memory.type_name,
+                num_objects: 0,
+                num_bytes: 0,
+            });
+            stats.num_objects += 1;
+            stats.num_bytes += memory.layout.size();
+        }
+
+        let mut types: Vec<TypeStats> = types.into_values().collect();
+        types.sort_unstable_by(|a, b| {
+            b.num_bytes
+                .cmp(&a.num_bytes)
+                .then_with(|| a.type_name.cmp(b.type_name))
+        });
+
+        debug_assert_eq!(
+            types.iter().map(|stats| stats.num_bytes).sum::<usize>(),
+            self.num_bytes
+        );
+
         Stats {
             num_objects: self.memory_blocks.len(),
+            num_bytes: self.num_bytes,
+            types,
+        }
+    }
+
+    /// Writes a snapshot of the heap in the `.heapsnapshot` format used in Chrome DevTools.
+    ///
+    /// All memory blocks in the heap are written including unreachable ones.  `roots` are
+    /// referenced from the synthetic `(GC roots)` node.  Unmanaged tracing targets in `roots` are
+    /// not written but memory blocks referenced from them are.
+    pub fn write_snapshot<W>(&self, roots: &[usize], writer: &mut W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let mut builder = SnapshotBuilder::new();
+
+        let mut addrs: Vec<usize> = self.memory_blocks.keys().cloned().collect();
+        addrs.sort_unstable();
+        for addr in addrs.iter() {
+            let memory = &self.memory_blocks[addr];
+            builder.add_node(*addr, memory.type_name, memory.layout.size());
+        }
+
+        let mut edges = vec![];
+        for root in roots.iter() {
+            if self.memory_blocks.contains_key(root) {
+                edges.push(*root);
+            } else if let Some(tracer) = self.trace_targets.get(root) {
+                edges.extend(self.collect_edges(*root, tracer));
+            }
+        }
+        builder.add_root_edges(&edges);
+
+        for addr in addrs.iter() {
+            let edges = match self.trace_targets.get(addr) {
+                Some(tracer) => self.collect_edges(*addr, tracer),
+                None => vec![],
+            };
+            let mut weak_edges = vec![];
+            if let Some(block) = self.weak_blocks.get(addr)
+                && let Some(key) = (block.key_fn)(*addr)
+            {
+                weak_edges.push(key);
+            }
+            builder.add_edges(*addr, &edges, &weak_edges);
         }
+
+        builder.write(writer)
+    }
+
+    /// Collects addresses of managed memory blocks directly referenced from a tracing target.
+    fn collect_edges(&self, addr: usize, tracer: &Tracer) -> Vec<usize> {
+        let mut visits: VisitList = Default::default();
+        (tracer.trace_fn)(addr, &mut visits);
+        if let Some(trace_value_fn) = self
+            .weak_blocks
+            .get(&addr)
+            .and_then(|block| block.trace_value_fn)
+        {
+            trace_value_fn(addr, &mut visits);
+        }
+        let mut edges = vec![];
+        while let Some(edge) = visits.pop() {
+            if self.memory_blocks.contains_key(&edge) {
+                edges.push(edge);
+            }
+        }
+        edges
     }
 }
 
@@ -251,14 +542,36 @@ impl Drop for Heap {
 }
 
 /// Statistics of a heap at some point.
+#[derive(Debug)]
 pub struct Stats {
     pub num_objects: usize,
+
+    /// The total size in bytes of memory blocks in use.
+    pub num_bytes: usize,
+
+    /// Statistics for each type, sorted by `num_bytes` in descending order.
+    pub types: Vec<TypeStats>,
+}
+
+/// Statistics of memory blocks of a type.
+#[derive(Debug)]
+pub struct TypeStats {
+    /// The name of the type returned from `std::any::type_name()`.
+    ///
+    /// Sequences allocated by `Heap::alloc_seq()` are counted as slices of the element type.
+    pub type_name: &'static str,
+
+    pub num_objects: usize,
+
+    /// The total size in bytes of memory blocks of the type.
+    pub num_bytes: usize,
 }
 
 /// A data type holds GC states.
 struct GcState {
     visit_list: VisitList,
     visited: FxHashSet<usize>,
+    traced_ephemerons: FxHashSet<usize>,
 }
 
 impl GcState {
@@ -268,12 +581,14 @@ impl GcState {
         Self {
             visit_list,
             visited: Default::default(),
+            traced_ephemerons: Default::default(),
         }
     }
 }
 
 struct MemoryBlock {
     layout: Layout,
+    type_name: &'static str,
     tidy_fn: Option<TidyFn>,
 }
 
@@ -282,9 +597,17 @@ impl MemoryBlock {
         Self::with_layout::<T>(Layout::new::<T>())
     }
 
+    fn seq<T>(layout: Layout) -> Self {
+        Self {
+            type_name: std::any::type_name::<[T]>(),
+            ..Self::with_layout::<T>(layout)
+        }
+    }
+
     fn with_layout<T>(layout: Layout) -> Self {
         Self {
             layout,
+            type_name: std::any::type_name::<T>(),
             tidy_fn: if std::mem::needs_drop::<T>() {
                 Some(|addr| {
                     // SAFETY: `addr` is always valid.
@@ -315,5 +638,69 @@ impl Tracer {
     }
 }
 
+/// Type-erased operations on a memory block holding weak references.
+struct WeakBlock {
+    /// Returns the address of the weakly referenced target if it has not been cleared.
+    key_fn: KeyFn,
+
+    /// Traces the value if the target is reachable.
+    ///
+    /// `None` if the memory block has no value to be traced conditionally.
+    trace_value_fn: Option<TraceFn>,
+
+    /// Clears the weak reference.
+    clear_fn: TidyFn,
+
+    /// `true` if the memory block must be put into the finalization queue when it's cleared.
+    finalization: bool,
+}
+
+impl WeakBlock {
+    fn ephemeron<K, V: Trace>() -> Self {
+        Self {
+            key_fn: |addr| {
+                // SAFETY: `addr` is always valid.
+                let ephemeron = unsafe { &*(addr as *const Ephemeron<K, V>) };
+                ephemeron.key_addr()
+            },
+            trace_value_fn: Some(|addr, visits| {
+                // SAFETY: `addr` is always valid.
+                let ephemeron = unsafe { &*(addr as *const Ephemeron<K, V>) };
+                if let Some(value) = ephemeron.value() {
+                    value.trace(visits);
+                }
+            }),
+            clear_fn: |addr| {
+                // SAFETY: `addr` is always valid.
+                let ephemeron = unsafe { &mut *(addr as *mut Ephemeron<K, V>) };
+                ephemeron.clear();
+            },
+            finalization: false,
+        }
+    }
+
+    fn finalization_cell<K, V>() -> Self {
+        Self {
+            key_fn: |addr| {
+                // SAFETY: `addr` is always valid.
+                let cell = unsafe { &*(addr as *const FinalizationCell<K, V>) };
+                cell.target_addr()
+            },
+            // The held value is always traced by `Tracer`.
+            trace_value_fn: None,
+            clear_fn: |addr| {
+                // SAFETY: `addr` is always valid.
+                let cell = unsafe { &mut *(addr as *mut FinalizationCell<K, V>) };
+                cell.clear();
+            },
+            finalization: true,
+        }
+    }
+}
+
+/// See `Heap::set_near_limit_callback()`.
+type NearLimitCallback = Box<dyn FnMut(usize, usize) -> usize>;
+
+type KeyFn = fn(usize) -> Option<usize>;
 type TidyFn = fn(usize);
 type TraceFn = fn(usize, &mut VisitList);
@@ -1,11 +1,17 @@
 mod handle;
 mod heap;
+mod snapshot;
 mod trace;
+mod weak;
 
 pub use handle::Handle;
 pub use handle::HandleMut;
 pub use handle::Seq;
 pub use heap::Heap;
 pub use heap::Stats;
+pub use heap::TypeStats;
 pub use trace::Trace;
 pub use trace::VisitList;
+pub use weak::Ephemeron;
+pub use weak::FinalizationCell;
+pub use weak::WeakHandle;
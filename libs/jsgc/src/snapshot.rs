@@ -0,0 +1,277 @@
+use std::io::Write;
+
+use rustc_hash::FxHashMap;
+
+// See https://developer.chrome.com/docs/devtools/memory-problems/heap-snapshots for details of
+// the `.heapsnapshot` format.
+
+const NODE_FIELDS: &[&str] = &[
+    "type",
+    "name",
+    "id",
+    "self_size",
+    "edge_count",
+    "trace_node_id",
+];
+
+const NODE_TYPES: &[&str] = &[
+    "hidden",
+    "array",
+    "string",
+    "object",
+    "code",
+    "closure",
+    "regexp",
+    "number",
+    "native",
+    "synthetic",
+    "concatenated string",
+    "sliced string",
+    "symbol",
+    "bigint",
+];
+
+const EDGE_FIELDS: &[&str] = &["type", "name_or_index", "to_node"];
+
+const EDGE_TYPES: &[&str] = &[
+    "context", "element", "property", "internal", "hidden", "shortcut", "weak",
+];
+
+// Indexes in `NODE_TYPES`.
+const NODE_TYPE_OBJECT: usize = 3;
+const NODE_TYPE_SYNTHETIC: usize = 9;
+
+// Indexes in `EDGE_TYPES`.
+const EDGE_TYPE_ELEMENT: usize = 1;
+const EDGE_TYPE_WEAK: usize = 6;
+
+// The ID of the synthetic root node.
+//
+// Other nodes use addresses of memory blocks as their IDs so that the same memory block has the
+// same ID in snapshots taken at different points.
+const ROOT_NODE_ID: usize = 1;
+
+/// A builder of a heap snapshot.
+///
+/// Nodes must be added before edges.
+pub(crate) struct SnapshotBuilder {
+    nodes: Vec<Node>,
+    node_indexes: FxHashMap<usize, usize>,
+    strings: Vec<&'static str>,
+    string_indexes: FxHashMap<&'static str, usize>,
+}
+
+impl SnapshotBuilder {
+    pub(crate) fn new() -> Self {
+        let mut builder = Self {
+            nodes: vec![],
+            node_indexes: Default::default(),
+            strings: vec![],
+            string_indexes: Default::default(),
+        };
+        let name = builder.intern("(GC roots)");
+        builder.nodes.push(Node {
+            node_type: NODE_TYPE_SYNTHETIC,
+            name,
+            id: ROOT_NODE_ID,
+            self_size: 0,
+            edges: vec![],
+        });
+        builder
+    }
+
+    pub(crate) fn add_node(&mut self, addr: usize, type_name: &'static str, size: usize) {
+        debug_assert!(!self.node_indexes.contains_key(&addr));
+        let name = self.intern(type_name);
+        self.node_indexes.insert(addr, self.nodes.len());
+        self.nodes.push(Node {
+            node_type: NODE_TYPE_OBJECT,
+            name,
+            id: addr,
+            self_size: size,
+            edges: vec![],
+        });
+    }
+
+    pub(crate) fn add_root_edges(&mut self, targets: &[usize]) {
+        self.nodes[0].edges = self.make_edges(targets, &[]);
+    }
+
+    pub(crate) fn add_edges(&mut self, addr: usize, targets: &[usize], weak_targets: &[usize]) {
+        let edges = self.make_edges(targets, weak_targets);
+        let index = self.node_indexes[&addr];
+        self.nodes[index].edges = edges;
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let edge_count: usize = self.nodes.iter().map(|node| node.edges.len()).sum();
+
+        write!(writer, r#"{{"snapshot":{{"meta":{{"node_fields":"#)?;
+        write_string_array(writer, NODE_FIELDS)?;
+        write!(writer, r#","node_types":["#)?;
+        write_string_array(writer, NODE_TYPES)?;
+        write!(
+            writer,
+            r#","string","number","number","number","number"],"edge_fields":"#
+        )?;
+        write_string_array(writer, EDGE_FIELDS)?;
+        write!(writer, r#","edge_types":["#)?;
+        write_string_array(writer, EDGE_TYPES)?;
+        write!(writer, r#","string_or_number","node"]}},"#)?;
+        write!(
+            writer,
+            r#""node_count":{},"edge_count":{edge_count},"trace_function_count":0}},"#,
+            self.nodes.len(),
+        )?;
+
+        write!(writer, r#""nodes":["#)?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 {
+                write!(writer, ",")?;
+            }
+            write!(
+                writer,
+                "{},{},{},{},{},0",
+                node.node_type,
+                node.name,
+                node.id,
+                node.self_size,
+                node.edges.len(),
+            )?;
+        }
+
+        write!(writer, r#"],"edges":["#)?;
+        let mut first = true;
+        for edge in self.nodes.iter().flat_map(|node| node.edges.iter()) {
+            if !first {
+                write!(writer, ",")?;
+            }
+            first = false;
+            // `to_node` is an index of the first field of the target node in `nodes`.
+            write!(
+                writer,
+                "{},{},{}",
+                edge.edge_type,
+                edge.name_or_index,
+                edge.to_node * NODE_FIELDS.len(),
+            )?;
+        }
+
+        write!(
+            writer,
+            r#"],"trace_function_infos":[],"trace_tree":[],"samples":[],"locations":[],"strings":"#
+        )?;
+        write_string_array(writer, &self.strings)?;
+        write!(writer, "}}")
+    }
+
+    fn make_edges(&mut self, targets: &[usize], weak_targets: &[usize]) -> Vec<Edge> {
+        let mut edges = Vec::with_capacity(targets.len() + weak_targets.len());
+        for (i, target) in targets.iter().enumerate() {
+            if let Some(&to_node) = self.node_indexes.get(target) {
+                edges.push(Edge {
+                    edge_type: EDGE_TYPE_ELEMENT,
+                    name_or_index: i,
+                    to_node,
+                });
+            }
+        }
+        if !weak_targets.is_empty() {
+            let name = self.intern("target");
+            for target in weak_targets.iter() {
+                if let Some(&to_node) = self.node_indexes.get(target) {
+                    edges.push(Edge {
+                        edge_type: EDGE_TYPE_WEAK,
+                        name_or_index: name,
+                        to_node,
+                    });
+                }
+            }
+        }
+        edges
+    }
+
+    fn intern(&mut self, string: &'static str) -> usize {
+        if let Some(&index) = self.string_indexes.get(string) {
+            return index;
+        }
+        let index = self.strings.len();
+        self.strings.push(string);
+        self.string_indexes.insert(string, index);
+        index
+    }
+}
+
+struct Node {
+    /// An index in `NODE_TYPES`.
+    node_type: usize,
+
+    /// An index in `SnapshotBuilder::strings`.
+    name: usize,
+
+    id: usize,
+    self_size: usize,
+    edges: Vec<Edge>,
+}
+
+struct Edge {
+    /// An index in `EDGE_TYPES`.
+    edge_type: usize,
+
+    /// An index in the parent node for `element` edges, or an index in `SnapshotBuilder::strings`
+    /// for other edges.
+    name_or_index: usize,
+
+    /// An index in `SnapshotBuilder::nodes`.
+    to_node: usize,
+}
+
+fn write_string_array<W: Write>(writer: &mut W, strings: &[&str]) -> std::io::Result<()> {
+    write!(writer, "[")?;
+    for (i, string) in strings.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        write_string(writer, string)?;
+    }
+    write!(writer, "]")
+}
+
+fn write_string<W: Write>(writer: &mut W, string: &str) -> std::io::Result<()> {
+    write!(writer, "\"")?;
+    for ch in string.chars() {
+        match ch {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            '\r' => write!(writer, "\\r")?,
+            '\t' => write!(writer, "\\t")?,
+            ch if (ch as u32) < 0x20 => write!(writer, "\\u{:04x}", ch as u32)?,
+            ch => write!(writer, "{ch}")?,
+        }
+    }
+    write!(writer, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_string() {
+        let mut buf = vec![];
+        write_string(&mut buf, "a\"b\\c\n\u{1}").unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), r#""a\"b\\c\n\u0001""#);
+    }
+
+    #[test]
+    fn test_empty_snapshot() {
+        let builder = SnapshotBuilder::new();
+        let mut buf = vec![];
+        builder.write(&mut buf).unwrap();
+        let json = String::from_utf8(buf).unwrap();
+        assert!(json.contains(r#""node_count":1,"edge_count":0"#));
+        assert!(json.contains(r#""nodes":[9,0,1,0,0,0]"#));
+        assert!(json.ends_with(r#""strings":["(GC roots)"]}"#));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::handle::HandleMut;
+use crate::trace::Trace;
+use crate::trace::VisitList;
+
+/// A data type to hold a weak reference to a data type managed on the heap memory.
+///
+/// Unlike `Handle<T>` and `HandleMut<T>`, a weak handle does not keep the target alive.  The
+/// target is cleared when it becomes unreachable from the root set.
+///
+/// Internally, this is a handle of an `Ephemeron` which has no value.
+#[repr(transparent)]
+pub struct WeakHandle<T>(HandleMut<Ephemeron<T, ()>>);
+
+base::static_assert_eq!(size_of::<WeakHandle<u8>>(), size_of::<usize>());
+base::static_assert_eq!(size_of::<Option<WeakHandle<u8>>>(), size_of::<usize>());
+
+impl<T> WeakHandle<T> {
+    pub(crate) fn new(ephemeron: HandleMut<Ephemeron<T, ()>>) -> Self {
+        Self(ephemeron)
+    }
+
+    pub fn from_addr(addr: usize) -> Option<Self> {
+        HandleMut::from_addr(addr).map(Self)
+    }
+
+    pub fn as_addr(&self) -> usize {
+        self.0.as_addr()
+    }
+
+    /// Returns the target if it has not been reclaimed yet.
+    pub fn get(&self) -> Option<HandleMut<T>> {
+        self.0.key()
+    }
+}
+
+impl<T> Clone for WeakHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for WeakHandle<T> {}
+
+impl<T> Eq for WeakHandle<T> {}
+
+impl<T> PartialEq for WeakHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> std::fmt::Debug for WeakHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WeakHandle({:#x})", self.as_addr())
+    }
+}
+
+impl<T> Trace for WeakHandle<T> {
+    #[inline]
+    fn trace(&self, visits: &mut VisitList) {
+        // Keep the ephemeron alive, but not the target.
+        visits.push(self.as_addr());
+    }
+}
+
+/// A key-value pair whose value is reachable only while its key is reachable.
+///
+/// The key is held weakly.  The value is traced by the heap if and only if both the ephemeron
+/// and its key are reachable.  Both the key and the value are cleared when the key becomes
+/// unreachable.
+pub struct Ephemeron<K, V> {
+    key: Option<HandleMut<K>>,
+    value: Option<V>,
+}
+
+impl<K, V> Ephemeron<K, V> {
+    pub(crate) fn new(key: HandleMut<K>, value: V) -> Self {
+        Self {
+            key: Some(key),
+            value: Some(value),
+        }
+    }
+
+    pub fn key(&self) -> Option<HandleMut<K>> {
+        self.key
+    }
+
+    pub fn value(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.value.as_mut()
+    }
+
+    pub(crate) fn key_addr(&self) -> Option<usize> {
+        self.key.map(|key| key.as_addr())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.key = None;
+        self.value = None;
+    }
+}
+
+impl<K, V> Trace for Ephemeron<K, V> {
+    #[inline]
+    fn trace(&self, _visits: &mut VisitList) {
+        // The value will be traced by the heap after the key has been marked.
+    }
+}
+
+/// A cell to be notified when its target has been reclaimed.
+///
+/// The target is held weakly and the held value is held strongly while the cell is reachable.
+/// When the target becomes unreachable, the target is cleared and the cell is put into the
+/// finalization queue of the heap.  See `Heap::pop_finalization()`.
+pub struct FinalizationCell<K, V> {
+    target: Option<HandleMut<K>>,
+    held_value: V,
+}
+
+impl<K, V> FinalizationCell<K, V> {
+    pub(crate) fn new(target: HandleMut<K>, held_value: V) -> Self {
+        Self {
+            target: Some(target),
+            held_value,
+        }
+    }
+
+    pub fn target(&self) -> Option<HandleMut<K>> {
+        self.target
+    }
+
+    pub fn held_value(&self) -> &V {
+        &self.held_value
+    }
+
+    pub(crate) fn target_addr(&self) -> Option<usize> {
+        self.target.map(|target| target.as_addr())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.target = None;
+    }
+}
+
+impl<K, V> Trace for FinalizationCell<K, V>
+where
+    V: Trace,
+{
+    #[inline]
+    fn trace(&self, visits: &mut VisitList) {
+        // The target is held weakly.
+        self.held_value.trace(visits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size_of::<WeakHandle<u8>>(), size_of::<usize>());
+        assert_eq!(size_of::<Option<WeakHandle<u8>>>(), size_of::<usize>());
+    }
+
+    #[test]
+    fn test_ephemeron_clear() {
+        let mut v: u8 = 0;
+        let mut ephemeron = Ephemeron::new(HandleMut::from_mut(&mut v), 1u8);
+        assert!(ephemeron.key().is_some());
+        assert_eq!(ephemeron.value(), Some(&1));
+        ephemeron.clear();
+        assert!(ephemeron.key().is_none());
+        assert!(ephemeron.value().is_none());
+    }
+}
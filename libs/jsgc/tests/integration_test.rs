@@ -106,3 +106,197 @@ fn test_unmanaged_tracing_targets() {
 
     // TODO(feat): UAF... root.[car|cdr] are still accessible.
 }
+
+#[test]
+fn test_weak_handle() {
+    let mut heap = Heap::new();
+
+    let target = heap.alloc_mut(Cell::default());
+    let weak = heap.alloc_weak(target);
+    assert_eq!(heap.stats().num_objects, 2);
+
+    heap.collect_garbage(&[target.as_addr(), weak.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 2);
+    assert!(weak.get() == Some(target));
+
+    heap.collect_garbage(&[weak.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 1);
+    assert!(weak.get().is_none());
+
+    heap.collect_garbage(&[]);
+    assert_eq!(heap.stats().num_objects, 0);
+}
+
+#[test]
+fn test_ephemeron() {
+    let mut heap = Heap::new();
+
+    let key = heap.alloc_mut(Cell::default());
+    let value = heap.alloc_mut(Cell::default());
+    let ephemeron = heap.alloc_ephemeron(key, value);
+    assert_eq!(heap.stats().num_objects, 3);
+
+    // The value is reachable only while the key is reachable.
+    heap.collect_garbage(&[key.as_addr(), ephemeron.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 3);
+    assert!(ephemeron.value() == Some(&value));
+
+    heap.collect_garbage(&[ephemeron.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 1);
+    assert!(ephemeron.key().is_none());
+    assert!(ephemeron.value().is_none());
+
+    heap.collect_garbage(&[]);
+    assert_eq!(heap.stats().num_objects, 0);
+}
+
+#[test]
+fn test_ephemeron_chain() {
+    let mut heap = Heap::new();
+
+    let key = heap.alloc_mut(Cell::default());
+    let value = heap.alloc_mut(Cell::default());
+    let value2 = heap.alloc_mut(Cell::default());
+    // `second` is visited before its key becomes reachable through `first`.
+    let second = heap.alloc_ephemeron(value, value2);
+    let first = heap.alloc_ephemeron(key, value);
+    assert_eq!(heap.stats().num_objects, 5);
+
+    heap.collect_garbage(&[second.as_addr(), first.as_addr(), key.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 5);
+    assert!(second.value().is_some());
+
+    heap.collect_garbage(&[second.as_addr(), first.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 2);
+    assert!(second.value().is_none());
+
+    heap.collect_garbage(&[]);
+    assert_eq!(heap.stats().num_objects, 0);
+}
+
+#[test]
+fn test_finalization_cell() {
+    let mut heap = Heap::new();
+
+    let target = heap.alloc_mut(Cell::default());
+    let held_value = heap.alloc_mut(Cell::default());
+    let cell = heap.alloc_finalization_cell(target, held_value);
+    assert_eq!(heap.stats().num_objects, 3);
+
+    heap.collect_garbage(&[target.as_addr(), cell.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 3);
+    assert!(heap.pop_finalization::<Cell, HandleMut<Cell>>().is_none());
+
+    heap.collect_garbage(&[cell.as_addr()]);
+    assert_eq!(heap.stats().num_objects, 2);
+    assert!(cell.target().is_none());
+
+    // Cells in the finalization queue are kept alive until they are popped.
+    heap.collect_garbage(&[]);
+    assert_eq!(heap.stats().num_objects, 2);
+
+    let finalized = heap.pop_finalization::<Cell, HandleMut<Cell>>();
+    assert!(finalized == Some(cell));
+    assert!(*finalized.unwrap().held_value() == held_value);
+    assert!(heap.pop_finalization::<Cell, HandleMut<Cell>>().is_none());
+
+    heap.collect_garbage(&[]);
+    assert_eq!(heap.stats().num_objects, 0);
+}
+
+#[test]
+fn test_stats() {
+    let mut heap = Heap::new();
+
+    let cell = heap.alloc_mut(Cell::default());
+    let _ = heap.alloc_mut(Cell {
+        car: Some(cell),
+        cdr: None,
+    });
+    let _ = heap.alloc_seq(&[0u16; 4]);
+
+    let stats = heap.stats();
+    assert_eq!(stats.num_objects, 3);
+    assert_eq!(
+        stats.num_bytes,
+        size_of::<Cell>() * 2 + size_of::<u16>() * 4
+    );
+    assert_eq!(stats.types.len(), 2);
+    assert_eq!(stats.types[0].type_name, std::any::type_name::<Cell>());
+    assert_eq!(stats.types[0].num_objects, 2);
+    assert_eq!(stats.types[0].num_bytes, size_of::<Cell>() * 2);
+    assert_eq!(stats.types[1].type_name, std::any::type_name::<[u16]>());
+    assert_eq!(stats.types[1].num_objects, 1);
+    assert_eq!(stats.types[1].num_bytes, size_of::<u16>() * 4);
+}
+
+#[test]
+fn test_write_snapshot() {
+    let mut heap = Heap::new();
+
+    let car = heap.alloc_mut(Cell::default());
+    let cdr = heap.alloc_mut(Cell::default());
+    let cell = heap.alloc_mut(Cell {
+        car: Some(car),
+        cdr: Some(cdr),
+    });
+    let _ = heap.alloc_weak(cell);
+
+    let mut buf = vec![];
+    heap.write_snapshot(&[cell.as_addr()], &mut buf).unwrap();
+    let json = String::from_utf8(buf).unwrap();
+
+    // (GC roots), 3 cells and an ephemeron.
+    assert!(json.contains(r#""node_count":5,"#));
+    // (GC roots) -> cell, cell -> car, cell -> cdr and ephemeron -> cell (weak).
+    assert!(json.contains(r#""edge_count":4,"#));
+    assert!(json.contains(std::any::type_name::<Cell>()));
+}
+
+#[test]
+fn test_limit() {
+    use std::cell::Cell as StdCell;
+    use std::rc::Rc;
+
+    let mut heap = Heap::new();
+    heap.set_limit(size_of::<Cell>() * 2);
+
+    let called = Rc::new(StdCell::new(0));
+    {
+        let called = called.clone();
+        heap.set_near_limit_callback(move |limit, _num_bytes| {
+            called.set(called.get() + 1);
+            if called.get() == 1 {
+                // Grow the limit only once.
+                limit + size_of::<Cell>()
+            } else {
+                limit
+            }
+        });
+    }
+
+    let cell = heap.alloc_mut(Cell::default());
+    let _ = heap.alloc_mut(Cell::default());
+    assert!(!heap.is_exhausted());
+    assert_eq!(called.get(), 0);
+
+    let _ = heap.alloc_mut(Cell::default());
+    assert!(!heap.is_exhausted());
+    assert_eq!(called.get(), 1);
+    assert_eq!(heap.limit(), size_of::<Cell>() * 3);
+
+    let _ = heap.alloc_mut(Cell::default());
+    assert!(heap.is_exhausted());
+    assert_eq!(called.get(), 2);
+
+    // The callback is not called while the heap is exhausted.
+    let _ = heap.alloc_mut(Cell::default());
+    assert_eq!(called.get(), 2);
+
+    // SAFETY: the address is valid while the heap is alive.
+    assert!(unsafe { *(heap.exhausted_flag_addr() as *const bool) });
+
+    heap.collect_garbage(&[cell.as_addr()]);
+    assert!(!heap.is_exhausted());
+    assert_eq!(heap.stats().num_bytes, size_of::<Cell>());
+}
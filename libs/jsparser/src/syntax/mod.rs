@@ -3,7 +3,10 @@ mod actions;
 #[cfg(test)]
 mod tests;
 
+use std::borrow::Cow;
+use std::iter::Peekable;
 use std::ops::Range;
+use std::str::Chars;
 
 use bitflags::bitflags;
 use smallvec::SmallVec;
@@ -60,6 +63,49 @@ pub struct Processor<'s, H> {
     web_compat_mode: bool,
     strict_mode: bool,
     module: bool,
+
+    // `strict_mode` of the enclosing code is saved when a function body starts and restored when
+    // the function body ends.
+    strict_mode_stack: Vec<bool>,
+
+    // Available until a statement other than directives appears in the current function body or
+    // script.
+    directive_prologue: Option<DirectivePrologue>,
+
+    // Set when a function signature is processed and consumed when the next token is shifted.
+    // The next token is `{` if the function has a FunctionBody.
+    function_signature: Option<DirectivePrologue>,
+
+    // Template strings of template literals being processed.  The template strings of a template
+    // literal are popped when the template literal is reduced.
+    template_strings: Vec<TemplateString<'s>>,
+}
+
+// 11.2.1 Directive Prologues and the Use Strict Directive
+#[derive(Debug, Default)]
+struct DirectivePrologue {
+    /// The index of the first token of the next directive.
+    next_token_index: usize,
+
+    /// The bound names of the formal parameters and the name of the function.
+    ///
+    /// These must be checked again if the function body contains a Use Strict Directive.
+    bound_names: SmallVec<[Symbol; 4]>,
+
+    /// `IsSimpleParameterList` of the formal parameters.
+    simple_parameters: bool,
+
+    /// Enabled if a preceding directive contains a legacy octal escape sequence.
+    legacy_octal_escape: bool,
+}
+
+#[derive(Debug)]
+struct TemplateString<'s> {
+    /// The index of the `Node::String` for the template string.
+    node_index: usize,
+
+    /// The source text of the template string without the delimiters.
+    content: &'s str,
 }
 
 #[derive(Debug)]
@@ -111,6 +157,7 @@ enum Detail {
     ConstDeclaration(#[allow(unused)] SmallVec<[Symbol; 4]>), // TODO: SS
     SingleNameBinding(Symbol, bool),
     BindingElement(BindingElement),
+    BindingRestElement(Symbol),
     CaseBlock,
     CaseClause,
     CaseClauseList,
@@ -182,6 +229,15 @@ enum BindingElementKind {
 /// AST for a JavaScript program.
 ///
 /// A stack machine can reconstruct the AST of the Javascript program from the stream of the nodes.
+///
+/// Nodes for function calls hold the offset of the start of the expression in the source text.
+/// Nodes for function definitions hold the range of the definition.  Nodes for conditional
+/// expressions, `if` statements and iteration statements hold the ranges of their branches or
+/// bodies.
+///
+/// A tagged template is converted into a function call.  `TemplateObject` holds the cooked and
+/// raw strings of the template literal and is passed as the first argument of the call.  A cooked
+/// string is `None` if the template string contains an invalid escape sequence.
 #[derive(Debug)]
 pub enum Node<'s> {
     Null,
@@ -189,6 +245,7 @@ pub enum Node<'s> {
     Number(f64, &'s str),
     String(Vec<u16>, &'s str),
     TemplateLiteral(u16),
+    TemplateObject(Vec<Option<Vec<u16>>>, Vec<Vec<u16>>),
     Array,
     Object,
     LiteralPropertyName(LiteralPropertyName),
@@ -200,15 +257,16 @@ pub enum Node<'s> {
     ArgumentListHead(bool, bool),
     ArgumentListItem(bool),
     Arguments,
-    CallExpression,
-    NewExpression(bool),
+    CallExpression(usize),
+    DirectEval(usize),
+    NewExpression(bool, usize),
     NonNullish,
     OptionalChain(PropertyAccessKind),
     UpdateExpression(UpdateOperator),
     UnaryExpression(UnaryOperator),
     BinaryExpression(BinaryOperator),
     LogicalExpression(LogicalOperator),
-    ConditionalExpression,
+    ConditionalExpression(Range<usize>, Range<usize>),
     AssignmentExpression(AssignmentOperator),
     SequenceExpression,
     BlockStatement,
@@ -220,11 +278,11 @@ pub enum Node<'s> {
     BindingElement(bool),
     EmptyStatement,
     ExpressionStatement,
-    IfElseStatement,
-    IfStatement,
-    DoWhileStatement,
-    WhileStatement,
-    ForStatement(LoopFlags),
+    IfElseStatement(Range<usize>, Range<usize>),
+    IfStatement(Range<usize>),
+    DoWhileStatement(Range<usize>),
+    WhileStatement(Range<usize>),
+    ForStatement(LoopFlags, Range<usize>),
     ContinueStatement(Symbol),
     BreakStatement(Symbol),
     ReturnStatement(u32),
@@ -236,7 +294,7 @@ pub enum Node<'s> {
     DefaultClause(bool),
     LabelledStatement(Symbol, bool),
     Label(Symbol),
-    ThrowStatement,
+    ThrowStatement(usize),
     TryStatement,
     CatchClause(bool),
     FinallyClause,
@@ -244,21 +302,23 @@ pub enum Node<'s> {
     TryBlock,
     CatchBlock,
     FinallyBlock,
-    DebuggerStatement,
+    DebuggerStatement(usize),
     FormalParameter,
+    FunctionRestParameter,
     FormalParameters(u32),
     FunctionContext(Symbol),
     AsyncFunctionContext(Symbol),
     ArrowFunctionContext,
     AsyncArrowFunctionContext,
     FunctionSignature,
-    FunctionDeclaration,
-    AsyncFunctionDeclaration,
-    FunctionExpression(bool),
-    AsyncFunctionExpression(bool),
-    ArrowFunction,
-    AsyncArrowFunction,
-    Method,
+    UseStrict,
+    FunctionDeclaration(Range<usize>),
+    AsyncFunctionDeclaration(Range<usize>),
+    FunctionExpression(bool, Range<usize>),
+    AsyncFunctionExpression(bool, Range<usize>),
+    ArrowFunction(Range<usize>),
+    AsyncArrowFunction(Range<usize>),
+    Method(Range<usize>),
     AwaitExpression,
     Then(bool),
     Else(bool),
@@ -305,7 +365,7 @@ pub enum MemberExpressionKind {
 
 #[derive(Clone, Debug)]
 pub enum PropertyAccessKind {
-    Call,
+    Call(usize),
     IdentifierKey(Symbol),
 }
 
@@ -498,11 +558,26 @@ where
             iteration_statement_depth: 0,
             switch_statement_depth: 0,
             web_compat_mode: false,
-            strict_mode: false,
+            // 11.2.2 Strict Mode Code
+            strict_mode: module,
             module,
+            strict_mode_stack: vec![],
+            directive_prologue: Some(DirectivePrologue {
+                simple_parameters: true,
+                ..Default::default()
+            }),
+            function_signature: None,
+            template_strings: vec![],
         }
     }
 
+    /// Parses the source text as strict mode code.
+    ///
+    /// Used for eval code evaluated in strict mode code.
+    pub fn set_strict_mode(&mut self) {
+        self.strict_mode = true;
+    }
+
     fn top(&self) -> &Syntax {
         self.nth(0)
     }
@@ -554,6 +629,16 @@ where
         syntax.source_range.end = source_end;
     }
 
+    /// Returns the start offset in the source text of the top `n` syntaxes.
+    fn source_start(&self, n: usize) -> usize {
+        self.nth(n - 1).source_range.start
+    }
+
+    /// Returns the range in the source text of the top `n` syntaxes.
+    fn source_range(&self, n: usize) -> Range<usize> {
+        self.source_start(n)..self.top().source_range.end
+    }
+
     fn enqueue(&mut self, event: Node<'s>) -> usize {
         let index = self.nodes.len();
         self.nodes.push(event);
@@ -618,6 +703,8 @@ where
     // BindingIdentifier_Yield_Await : yield
     // BindingIdentifier_Await : await
     // BindingIdentifier_Yield_Await : await
+    // OptionalChain : ?. TemplateLiteral
+    // OptionalChain : OptionalChain TemplateLiteral
     fn syntax_error(&mut self) -> Result<(), Error> {
         Err(Error::SyntaxError)
     }
@@ -714,6 +801,35 @@ where
     // _FUNCTION_SIGNATURE_
     fn process_function_signature(&mut self) -> Result<(), Error> {
         self.enqueue(Node::FunctionSignature);
+
+        let mut prologue = DirectivePrologue {
+            simple_parameters: true,
+            ..Default::default()
+        };
+        // The top of the stack is `)` or `=>`.  Getters have no FormalParameters.
+        if let Detail::FormalParameters(ref bound_names) = self.nth(1).detail {
+            let nodes_range = self.nth(1).nodes_range.clone();
+            prologue.bound_names = bound_names.clone();
+            prologue.simple_parameters = !self.nodes[nodes_range].iter().any(|node| {
+                matches!(
+                    node,
+                    Node::BindingElement(true) | Node::FunctionRestParameter
+                )
+            });
+            // function BindingIdentifier ( FormalParameters )
+            let is_rparen = match self.top().detail {
+                Detail::Token(index) => self.tokens[index].kind == TokenKind::Rparen,
+                _ => false,
+            };
+            if is_rparen
+                && self.stack.len() > 3
+                && let Detail::BindingIdentifier(symbol) = self.nth(3).detail
+            {
+                prologue.bound_names.push(symbol);
+            }
+        }
+        self.function_signature = Some(prologue);
+
         Ok(())
     }
 
@@ -1020,6 +1136,20 @@ where
     // PrimaryExpression[Yield, Await] :
     //   TemplateLiteral[?Yield, ?Await, ~Tagged]
     fn process_primary_expression_template_literal(&mut self) -> Result<(), Error> {
+        let n = match self.top().detail {
+            Detail::TemplateLiteral(n) => n,
+            _ => unreachable!(),
+        };
+        // 13.2.8.1 Static Semantics: Early Errors
+        // It is a Syntax Error if the [Tagged] parameter was not set and NoSubstitutionTemplate,
+        // TemplateHead, TemplateMiddle or TemplateTail Contains NotEscapeSequence.
+        let strings = self.pop_template_strings(n);
+        if strings
+            .iter()
+            .any(|string| contains_not_escape_sequence(string.content))
+        {
+            return Err(Error::SyntaxError);
+        }
         self.top_mut().detail = Detail::Expression {
             assignment_target_type: AssignmentTargetType::Invalid,
         };
@@ -1139,7 +1269,6 @@ where
         };
         self.top_mut().detail = Detail::Expression {
             assignment_target_type: match symbol {
-                // TODO(feat): if strict
                 Symbol::ARGUMENTS | Symbol::EVAL if self.strict_mode => {
                     AssignmentTargetType::Invalid
                 }
@@ -1172,6 +1301,8 @@ where
             TokenKind::True => self.enqueue(Node::Boolean(true)),
             TokenKind::False => self.enqueue(Node::Boolean(false)),
             TokenKind::NumericLiteral => {
+                // 12.9.3.1 Static Semantics: Early Errors
+                ensure!(!(self.strict_mode && is_legacy_octal_like(token.lexeme)));
                 // TODO: perform `NumericValue`
                 let value = if token.lexeme.ends_with('n') {
                     // TODO: BigInt
@@ -1193,6 +1324,11 @@ where
                 self.enqueue(Node::Number(value, token.lexeme))
             }
             TokenKind::StringLiteral => {
+                // 12.9.4.1 Static Semantics: Early Errors
+                ensure!(
+                    !(self.strict_mode
+                        && has_legacy_octal_escape(&token.lexeme[1..(token.lexeme.len() - 1)]))
+                );
                 // TODO(perf): introduce a dedicated heap for strings.
                 let value = token.to_string_value()?;
                 self.enqueue(Node::String(value, token.lexeme))
@@ -1573,15 +1709,11 @@ where
     // TemplateLiteral[Yield, Await, Tagged] :
     //   NoSubstitutionTemplate
     fn process_template_literal_no_substitution(&mut self) -> Result<(), Error> {
-        // TODO(feat): 13.2.8.1 Static Semantics: Early Errors
-        // It is a Syntax Error if the [Tagged] parameter was not set and NoSubstitutionTemplate
-        // Contains NotEscapeSequence.
         let token = self.last_token();
         debug_assert!(matches!(token.kind, TokenKind::NoSubstitutionTemplate));
-        // TODO: perform `TV`
-        let content = &token.lexeme[1..(token.lexeme.len() - 1)];
-        let value = content.encode_utf16().collect();
-        let start_index = self.enqueue(Node::String(value, token.lexeme));
+        let lexeme = token.lexeme;
+        let content = &lexeme[1..(lexeme.len() - 1)];
+        let start_index = self.enqueue_template_string(content, lexeme);
         let end_index = self.enqueue(Node::TemplateLiteral(0));
         let syntax = self.top_mut();
         syntax.detail = Detail::TemplateLiteral(0);
@@ -1653,11 +1785,10 @@ where
     fn process_template_head(&mut self) -> Result<(), Error> {
         let token = self.last_token();
         debug_assert!(matches!(token.kind, TokenKind::TemplateHead));
-        // TODO: perform `TV`
-        let content = &token.lexeme[1..(token.lexeme.len() - 2)];
-        let value = content.encode_utf16().collect();
+        let lexeme = token.lexeme;
+        let content = &lexeme[1..(lexeme.len() - 2)];
         // The template string may be empty.
-        let start_index = self.enqueue(Node::String(value, token.lexeme));
+        let start_index = self.enqueue_template_string(content, lexeme);
         let syntax = self.top_mut();
         syntax.detail = Detail::TemplateString;
         syntax.nodes_range = start_index..(start_index + 1);
@@ -1667,11 +1798,10 @@ where
     fn process_template_middle(&mut self) -> Result<(), Error> {
         let token = self.last_token();
         debug_assert!(matches!(token.kind, TokenKind::TemplateMiddle));
-        // TODO: perform `TV`
-        let content = &token.lexeme[1..(token.lexeme.len() - 2)];
-        let value = content.encode_utf16().collect();
+        let lexeme = token.lexeme;
+        let content = &lexeme[1..(lexeme.len() - 2)];
         // The template string may be empty.
-        let start_index = self.enqueue(Node::String(value, token.lexeme));
+        let start_index = self.enqueue_template_string(content, lexeme);
         let syntax = self.top_mut();
         syntax.detail = Detail::TemplateString;
         syntax.nodes_range = start_index..(start_index + 1);
@@ -1681,17 +1811,35 @@ where
     fn process_template_tail(&mut self) -> Result<(), Error> {
         let token = self.last_token();
         debug_assert!(matches!(token.kind, TokenKind::TemplateTail));
-        // TODO: perform `TV`
-        let content = &token.lexeme[1..(token.lexeme.len() - 1)];
-        let value = content.encode_utf16().collect();
+        let lexeme = token.lexeme;
+        let content = &lexeme[1..(lexeme.len() - 1)];
         // The template string may be empty.
-        let start_index = self.enqueue(Node::String(value, token.lexeme));
+        let start_index = self.enqueue_template_string(content, lexeme);
         let syntax = self.top_mut();
         syntax.detail = Detail::TemplateString;
         syntax.nodes_range = start_index..(start_index + 1);
         Ok(())
     }
 
+    fn enqueue_template_string(&mut self, content: &'s str, lexeme: &'s str) -> usize {
+        // The template value of a template string containing NotEscapeSequence is undefined.  An
+        // empty string is used as a placeholder in this case and the template literal will be
+        // checked when it's reduced.
+        let value = template_value(content).unwrap_or_default();
+        let node_index = self.enqueue(Node::String(value, lexeme));
+        self.template_strings.push(TemplateString {
+            node_index,
+            content,
+        });
+        node_index
+    }
+
+    fn pop_template_strings(&mut self, n: u16) -> Vec<TemplateString<'s>> {
+        // n: The number of expressions interpolated into the template literal.
+        let len = self.template_strings.len() - (n as usize + 1);
+        self.template_strings.split_off(len)
+    }
+
     // 13.3 Left-Hand-Side Expressions
 
     // 13.3.2 Property Accessors
@@ -1765,7 +1913,11 @@ where
     // CallExpression[Yield, Await] :
     //   CoverCallExpressionAndAsyncArrowHead[?Yield, ?Await]
     fn process_call_expression(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::CallExpression);
+        if self.is_direct_eval() {
+            self.enqueue(Node::DirectEval(self.source_start(1)));
+        } else {
+            self.enqueue(Node::CallExpression(self.source_start(1)));
+        }
         self.replace(
             1,
             Detail::Expression {
@@ -1781,10 +1933,22 @@ where
         Ok(())
     }
 
+    // 19.2.1.1 PerformEval ( x, strictCaller, direct )
+    //
+    // A call is a direct eval if the callee is the identifier `eval` followed by arguments.  A
+    // parenthesized callee like `(eval)(x)` is treated as an indirect eval for simplicity.
+    fn is_direct_eval(&self) -> bool {
+        let top = self.top();
+        matches!(
+            self.nodes.get(top.nodes_range.start),
+            Some(Node::IdentifierReference(Symbol::EVAL))
+        ) && self.token(top.tokens_range.start + 1).kind == TokenKind::Lparen
+    }
+
     // CallExpression[Yield, Await] :
     //   CallExpression[?Yield, ?Await] Arguments[?Yield, ?Await]
     fn process_call_expression_call(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::CallExpression);
+        self.enqueue(Node::CallExpression(self.source_start(2)));
         self.replace(
             2,
             Detail::Expression {
@@ -1800,6 +1964,78 @@ where
         Ok(())
     }
 
+    // 13.3.11 Tagged Templates
+
+    // MemberExpression[Yield, Await] :
+    //   MemberExpression[?Yield, ?Await] TemplateLiteral[?Yield, ?Await, +Tagged]
+    fn process_member_expression_tagged_template(&mut self) -> Result<(), Error> {
+        self.process_tagged_template(true);
+        self.enqueue(Node::CallExpression(self.source_start(2)));
+        self.replace(
+            2,
+            Detail::Expression {
+                assignment_target_type: AssignmentTargetType::Invalid,
+            },
+        );
+        Ok(())
+    }
+
+    // CallExpression[Yield, Await] :
+    //   CallExpression[?Yield, ?Await] TemplateLiteral[?Yield, ?Await, +Tagged]
+    fn process_call_expression_tagged_template(&mut self) -> Result<(), Error> {
+        self.process_tagged_template(false);
+        self.enqueue(Node::CallExpression(self.source_start(2)));
+        self.replace(
+            2,
+            Detail::Expression {
+                assignment_target_type: AssignmentTargetType::Invalid,
+            },
+        );
+        Ok(())
+    }
+
+    // A tagged template is converted into a function call.  The template object is passed as
+    // the first argument and the substitutions follow it:
+    //
+    //   TemplateObject, ArgumentListHead, <expr>, ArgumentListItem, ..., Arguments
+    //
+    // The nodes for the template strings are replaced in place.  The `Node::Dereference` just
+    // before the template literal in a MemberExpression is replaced with the template object so
+    // that the member expression can be used as the `this` value of the call.
+    fn process_tagged_template(&mut self, dereferenced: bool) {
+        let n = match self.top().detail {
+            Detail::TemplateLiteral(n) => n,
+            _ => unreachable!(),
+        };
+        let strings = self.pop_template_strings(n);
+
+        let last = self.nodes.len() - 1;
+        debug_assert!(matches!(self.nodes[last], Node::TemplateLiteral(m) if m == n));
+        self.nodes[last] = Node::Arguments;
+        for string in strings[1..].iter() {
+            self.nodes[string.node_index] = Node::ArgumentListItem(false);
+        }
+
+        let cooked = strings
+            .iter()
+            .map(|string| template_value(string.content))
+            .collect();
+        let raw = strings
+            .iter()
+            .map(|string| template_raw_value(string.content))
+            .collect();
+        let template_object = Node::TemplateObject(cooked, raw);
+
+        let head = strings[0].node_index;
+        self.nodes[head] = Node::ArgumentListHead(false, false);
+        if dereferenced {
+            debug_assert!(matches!(self.nodes[head - 1], Node::Dereference));
+            self.nodes[head - 1] = template_object;
+        } else {
+            self.nodes.insert(head, template_object);
+        }
+    }
+
     // Arguments[Yield, Await] :
     //   ( )
     fn process_arguments_empty(&mut self) -> Result<(), Error> {
@@ -1846,7 +2082,7 @@ where
     // NewExpression[Yield, Await] :
     //   new NewExpression[?Yield, ?Await]
     fn process_new_expression(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::NewExpression(false));
+        self.enqueue(Node::NewExpression(false, self.source_start(2)));
         self.replace(
             2,
             Detail::Expression {
@@ -1859,7 +2095,7 @@ where
     // MemberExpression[Yield, Await] :
     //   new MemberExpression[?Yield, ?Await] Arguments[?Yield, ?Await]
     fn process_member_expression_new(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::NewExpression(true));
+        self.enqueue(Node::NewExpression(true, self.source_start(3)));
         self.replace(
             3,
             Detail::Expression {
@@ -1910,7 +2146,9 @@ where
     // OptionalChain[Yield, Await] :
     //   ?. Arguments[?Yield, ?Await]
     fn process_optional_chain_call(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::OptionalChain(PropertyAccessKind::Call));
+        self.enqueue(Node::OptionalChain(PropertyAccessKind::Call(
+            self.source_start(2),
+        )));
         self.replace(2, Detail::OptionalChain);
         Ok(())
     }
@@ -1928,7 +2166,7 @@ where
     // OptionalChain[Yield, Await] :
     //   OptionalChain[?Yield, ?Await] Arguments[?Yield, ?Await]
     fn process_optional_chain_call_chain(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::CallExpression);
+        self.enqueue(Node::CallExpression(self.source_start(2)));
         self.replace(2, Detail::OptionalChain);
         Ok(())
     }
@@ -2034,7 +2272,11 @@ where
     // UnaryExpression[Yield, Await] :
     //   delete UnaryExpression[?Yield, ?Await]
     fn process_delete(&mut self) -> Result<(), Error> {
-        // TODO: 13.5.1.1 Static Semantics: Early Errors
+        // 13.5.1.1 Static Semantics: Early Errors
+        if self.strict_mode {
+            let nodes = &self.nodes[self.top().nodes_range.clone()];
+            ensure!(!matches!(nodes, [Node::IdentifierReference(_)]));
+        }
         self.process_unary_expression(UnaryOperator::Delete)
     }
 
@@ -2271,7 +2513,10 @@ where
     //     ? AssignmentExpression[+In, ?Yield, ?Await]
     //     : AssignmentExpression[?In, ?Yield, ?Await]
     fn process_conditional_expression(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ConditionalExpression);
+        self.enqueue(Node::ConditionalExpression(
+            self.nth(2).source_range.clone(),
+            self.nth(0).source_range.clone(),
+        ));
         self.replace(
             5,
             Detail::Expression {
@@ -2750,8 +2995,18 @@ where
             _ => unreachable!(),
         };
         self.replace(2, Detail::SingleNameBinding(symbol, true));
-        // TODO(feat): implement jsruntime::semantics
-        Err(Error::NotYetImplemented)
+        Ok(())
+    }
+
+    // BindingRestElement[Yield, Await] :
+    //   ... BindingIdentifier[?Yield, ?Await]
+    fn process_binding_rest_element(&mut self) -> Result<(), Error> {
+        let symbol = match self.top().detail {
+            Detail::BindingIdentifier(symbol) => symbol,
+            _ => unreachable!(),
+        };
+        self.replace(2, Detail::BindingRestElement(symbol));
+        Ok(())
     }
 
     // 14.4 Empty Statement
@@ -2773,11 +3028,80 @@ where
     //   [lookahead ∉ { {, function, async [no LineTerminator here] function, class, let [ }]
     //   Expression[+In, ?Yield, ?Await] ;
     fn process_expression_statement(&mut self) -> Result<(), Error> {
+        self.process_directive()?;
         self.enqueue(Node::ExpressionStatement);
         self.replace(2, Detail::ExpressionStatement);
         Ok(())
     }
 
+    // 11.2.1 Directive Prologues and the Use Strict Directive
+    fn process_directive(&mut self) -> Result<(), Error> {
+        let tokens_range = self.nth(1).tokens_range.clone();
+
+        let prologue = match self.directive_prologue {
+            Some(ref mut prologue) => prologue,
+            None => return Ok(()),
+        };
+
+        if tokens_range.start != prologue.next_token_index {
+            // Not a statement in the directive prologue.
+            return Ok(());
+        }
+
+        let token = &self.tokens[tokens_range.start];
+        if tokens_range.len() != 1 || token.kind != TokenKind::StringLiteral {
+            // The directive prologue ends.
+            self.directive_prologue = None;
+            return Ok(());
+        }
+
+        prologue.next_token_index = self.tokens.len();
+
+        // The exact code points `use strict` without any escape sequences or line continuations.
+        let content = &token.lexeme[1..(token.lexeme.len() - 1)];
+        if content != "use strict" {
+            prologue.legacy_octal_escape |= has_legacy_octal_escape(content);
+            return Ok(());
+        }
+
+        // 15.2.1 Static Semantics: Early Errors
+        ensure!(prologue.simple_parameters);
+
+        if self.strict_mode {
+            return Ok(());
+        }
+
+        // 12.9.4.1 Static Semantics: Early Errors
+        ensure!(!prologue.legacy_octal_escape);
+
+        // 13.1.1 Static Semantics: Early Errors
+        // 15.2.1 Static Semantics: Early Errors
+        //
+        // The formal parameters and the name of the function have already been processed in
+        // non-strict mode code.
+        for symbol in prologue.bound_names.iter() {
+            ensure!(!matches!(
+                *symbol,
+                Symbol::ARGUMENTS
+                    | Symbol::EVAL
+                    | Symbol::KEYWORD_IMPLEMENTS
+                    | Symbol::KEYWORD_INTERFACE
+                    | Symbol::KEYWORD_LET
+                    | Symbol::KEYWORD_PACKAGE
+                    | Symbol::KEYWORD_PRIVATE
+                    | Symbol::KEYWORD_PROTECTED
+                    | Symbol::KEYWORD_PUBLIC
+                    | Symbol::KEYWORD_STATIC
+                    | Symbol::KEYWORD_YIELD
+            ));
+        }
+
+        self.strict_mode = true;
+        self.enqueue(Node::UseStrict);
+
+        Ok(())
+    }
+
     // 14.6 The if Statement
 
     // IfStatement[Yield, Await, Return] :
@@ -2785,7 +3109,10 @@ where
     //   else Statement[?Yield, ?Await, ?Return]
     fn process_if_else_statement(&mut self) -> Result<(), Error> {
         // TODO: 14.6.1 Static Semantics: Early Errors
-        self.enqueue(Node::IfElseStatement);
+        self.enqueue(Node::IfElseStatement(
+            self.nth(2).source_range.clone(),
+            self.nth(0).source_range.clone(),
+        ));
         self.replace(7, Detail::IfStatement);
         Ok(())
     }
@@ -2795,7 +3122,7 @@ where
     //   [lookahead ≠ else]
     fn process_if_statement(&mut self) -> Result<(), Error> {
         // TODO: 14.6.1 Static Semantics: Early Errors
-        self.enqueue(Node::IfStatement);
+        self.enqueue(Node::IfStatement(self.top().source_range.clone()));
         self.replace(5, Detail::IfStatement);
         Ok(())
     }
@@ -2868,7 +3195,7 @@ where
     // DoWhileStatement[Yield, Await, Return] :
     //   do Statement[?Yield, ?Await, ?Return] while ( Expression[+In, ?Yield, ?Await] ) ;
     fn process_do_while_statement(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::DoWhileStatement);
+        self.enqueue(Node::DoWhileStatement(self.nth(5).source_range.clone()));
         self.replace(7, Detail::DoWhileStatement);
         Ok(())
     }
@@ -2878,7 +3205,7 @@ where
     // WhileStatement[Yield, Await, Return] :
     //   while ( Expression[+In, ?Yield, ?Await] ) Statement[?Yield, ?Await, ?Return]
     fn process_while_statement(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::WhileStatement);
+        self.enqueue(Node::WhileStatement(self.top().source_range.clone()));
         self.replace(5, Detail::WhileStatement);
         Ok(())
     }
@@ -2893,19 +3220,28 @@ where
     //   Statement[?Yield, ?Await, ?Return]
 
     fn process_for_statement_no_init_test_next(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ForStatement(LoopFlags::empty()));
+        self.enqueue(Node::ForStatement(
+            LoopFlags::empty(),
+            self.top().source_range.clone(),
+        ));
         self.replace(6, Detail::ForStatement);
         Ok(())
     }
 
     fn process_for_statement_no_test_next(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ForStatement(LoopFlags::HAS_INIT));
+        self.enqueue(Node::ForStatement(
+            LoopFlags::HAS_INIT,
+            self.top().source_range.clone(),
+        ));
         self.replace(7, Detail::ForStatement);
         Ok(())
     }
 
     fn process_for_statement_no_init_next(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ForStatement(LoopFlags::HAS_TEST));
+        self.enqueue(Node::ForStatement(
+            LoopFlags::HAS_TEST,
+            self.top().source_range.clone(),
+        ));
         self.replace(7, Detail::ForStatement);
         Ok(())
     }
@@ -2913,13 +3249,17 @@ where
     fn process_for_statement_no_next(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_TEST,
+            self.top().source_range.clone(),
         ));
         self.replace(8, Detail::ForStatement);
         Ok(())
     }
 
     fn process_for_statement_no_init_test(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ForStatement(LoopFlags::HAS_NEXT));
+        self.enqueue(Node::ForStatement(
+            LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
+        ));
         self.replace(7, Detail::ForStatement);
         Ok(())
     }
@@ -2927,6 +3267,7 @@ where
     fn process_for_statement_no_test(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
         ));
         self.replace(8, Detail::ForStatement);
         Ok(())
@@ -2935,6 +3276,7 @@ where
     fn process_for_statement_no_init(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_TEST | LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
         ));
         self.replace(8, Detail::ForStatement);
         Ok(())
@@ -2943,6 +3285,7 @@ where
     fn process_for_statement(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_TEST | LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
         ));
         self.replace(9, Detail::ForStatement);
         Ok(())
@@ -2956,7 +3299,10 @@ where
     //   Statement[?Yield, ?Await, ?Return]
 
     fn process_for_statement_vars_no_test_next(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ForStatement(LoopFlags::HAS_INIT));
+        self.enqueue(Node::ForStatement(
+            LoopFlags::HAS_INIT,
+            self.top().source_range.clone(),
+        ));
         self.replace(8, Detail::ForStatement);
         Ok(())
     }
@@ -2964,6 +3310,7 @@ where
     fn process_for_statement_vars_no_next(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_TEST,
+            self.top().source_range.clone(),
         ));
         self.replace(9, Detail::ForStatement);
         Ok(())
@@ -2972,6 +3319,7 @@ where
     fn process_for_statement_vars_no_test(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
         ));
         self.replace(9, Detail::ForStatement);
         Ok(())
@@ -2980,6 +3328,7 @@ where
     fn process_for_statement_vars(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_TEST | LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
         ));
         self.replace(10, Detail::ForStatement);
         Ok(())
@@ -2993,7 +3342,10 @@ where
     //   Statement[?Yield, ?Await, ?Return]
 
     fn process_for_statement_decl_no_test_next(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ForStatement(LoopFlags::HAS_INIT));
+        self.enqueue(Node::ForStatement(
+            LoopFlags::HAS_INIT,
+            self.top().source_range.clone(),
+        ));
         self.replace(6, Detail::ForStatement);
         Ok(())
     }
@@ -3001,6 +3353,7 @@ where
     fn process_for_statement_decl_no_next(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_TEST,
+            self.top().source_range.clone(),
         ));
         self.replace(7, Detail::ForStatement);
         Ok(())
@@ -3009,6 +3362,7 @@ where
     fn process_for_statement_decl_no_test(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
         ));
         self.replace(7, Detail::ForStatement);
         Ok(())
@@ -3017,6 +3371,7 @@ where
     fn process_for_statement_decl(&mut self) -> Result<(), Error> {
         self.enqueue(Node::ForStatement(
             LoopFlags::HAS_INIT | LoopFlags::HAS_TEST | LoopFlags::HAS_NEXT,
+            self.top().source_range.clone(),
         ));
         self.replace(8, Detail::ForStatement);
         Ok(())
@@ -3153,6 +3508,17 @@ where
         Ok(())
     }
 
+    // 14.11 The with Statement
+
+    // WithStatement[Yield, Await, Return] :
+    //   with ( Expression[+In, ?Yield, ?Await] ) Statement[?Yield, ?Await, ?Return]
+    fn process_with_statement(&mut self) -> Result<(), Error> {
+        // 14.11.1 Static Semantics: Early Errors
+        ensure!(!self.strict_mode);
+        // TODO(feat): with statement
+        Err(Error::NotYetImplemented)
+    }
+
     // 14.12 The switch Statement
 
     // SwitchStatement[Yield, Await, Return] :
@@ -3325,7 +3691,7 @@ where
     // ThrowStatement[Yield, Await] :
     //   throw [no LineTerminator here] Expression[+In, ?Yield, ?Await] ;
     fn process_throw_statement(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::ThrowStatement);
+        self.enqueue(Node::ThrowStatement(self.source_start(3)));
         self.replace(3, Detail::ThrowStatement);
         Ok(())
     }
@@ -3414,7 +3780,7 @@ where
     // DebuggerStatement :
     //   debugger ;
     fn process_debugger_statement(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::DebuggerStatement);
+        self.enqueue(Node::DebuggerStatement(self.source_start(2)));
         self.replace(2, Detail::DebuggerStatement);
         Ok(())
     }
@@ -3460,6 +3826,33 @@ where
         Ok(())
     }
 
+    // FormalParameters[Yield, Await] :
+    //   FunctionRestParameter[?Yield, ?Await]
+    fn process_formal_parameters_rest(&mut self) -> Result<(), Error> {
+        self.enqueue(Node::FormalParameters(1));
+        self.update_ends();
+        Ok(())
+    }
+
+    // FormalParameters[Yield, Await] :
+    //   FormalParameterList[?Yield, ?Await] , FunctionRestParameter[?Yield, ?Await]
+    fn process_formal_parameters_list_with_rest(&mut self) -> Result<(), Error> {
+        let rest = match self.top().detail {
+            Detail::FormalParameters(ref bound_names) => bound_names[0],
+            _ => unreachable!(),
+        };
+        let mut bound_names = match self.nth_mut(2).detail {
+            Detail::FormalParameters(ref mut bound_names) => std::mem::take(bound_names),
+            _ => unreachable!(),
+        };
+        // 15.1.1 Static Semantics: Early Errors
+        ensure!(!bound_names.contains(&rest));
+        bound_names.push(rest);
+        self.enqueue(Node::FormalParameters(bound_names.len() as u32));
+        self.replace(3, Detail::FormalParameters(bound_names));
+        Ok(())
+    }
+
     // FormalParameterList[Yield, Await] :
     //   FormalParameterList[?Yield, ?Await] , FormalParameter[?Yield, ?Await]
     fn process_formal_parameter_list(&mut self) -> Result<(), Error> {
@@ -3496,13 +3889,25 @@ where
         Ok(())
     }
 
+    // FunctionRestParameter[Yield, Await] :
+    //   BindingRestElement[?Yield, ?Await]
+    fn process_function_rest_parameter(&mut self) -> Result<(), Error> {
+        self.enqueue(Node::FunctionRestParameter);
+        let symbol = match self.top().detail {
+            Detail::BindingRestElement(symbol) => symbol,
+            _ => unreachable!(),
+        };
+        self.replace(1, Detail::FormalParameters(smallvec![symbol]));
+        Ok(())
+    }
+
     // 15.2 Function Definitions
 
     // FunctionDeclaration[Yield, Await, Default] :
     //   function BindingIdentifier[?Yield, ?Await] ( FormalParameters[~Yield, ~Await] )
     //   { FunctionBody[~Yield, ~Await] }
     fn process_function_declaration(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::FunctionDeclaration);
+        self.enqueue(Node::FunctionDeclaration(self.source_range(8)));
         self.replace(8, Detail::Declaration);
         Ok(())
     }
@@ -3510,7 +3915,7 @@ where
     // FunctionExpression :
     //   function ( FormalParameters[~Yield, ~Await] ) { FunctionBody[~Yield, ~Await] }
     fn process_anonymous_function_expression(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::FunctionExpression(false));
+        self.enqueue(Node::FunctionExpression(false, self.source_range(7)));
         self.replace(
             7,
             Detail::Expression {
@@ -3524,7 +3929,7 @@ where
     //   function BindingIdentifier[~Yield, ~Await] ( FormalParameters[~Yield, ~Await] )
     //   { FunctionBody[~Yield, ~Await] }
     fn process_function_expression(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::FunctionExpression(true));
+        self.enqueue(Node::FunctionExpression(true, self.source_range(8)));
         self.replace(
             8,
             Detail::Expression {
@@ -3534,6 +3939,17 @@ where
         Ok(())
     }
 
+    // FunctionBody[Yield, Await] :
+    //   FunctionStatementList[?Yield, ?Await]
+    fn process_function_body(&mut self) -> Result<(), Error> {
+        // Generators and async methods have no signature at this point.
+        if let Some(strict_mode) = self.strict_mode_stack.pop() {
+            self.strict_mode = strict_mode;
+        }
+        self.directive_prologue = None;
+        Ok(())
+    }
+
     // FunctionStatementList[Yield, Await] :
     //   [empty]
     fn process_function_statement_list_empty(&mut self) -> Result<(), Error> {
@@ -3555,7 +3971,7 @@ where
     //   ArrowParameters[?Yield, ?Await] [no LineTerminator here] => ConciseBody[?In]
     fn process_arrow_function(&mut self) -> Result<(), Error> {
         // TODO: 15.3.1 Static Semantics: Early Errors
-        self.enqueue(Node::ArrowFunction);
+        self.enqueue(Node::ArrowFunction(self.source_range(3)));
         self.replace(
             3,
             Detail::Expression {
@@ -3652,7 +4068,7 @@ where
             Detail::ClassElementName(name, private) => (name, private),
             ref detail => unreachable!("{detail:?}"),
         };
-        self.enqueue(Node::Method);
+        self.enqueue(Node::Method(self.source_range(7)));
         self.replace(7, Detail::MethodDefinition(name, private));
         Ok(())
     }
@@ -3692,7 +4108,7 @@ where
     //   async [no LineTerminator here] function BindingIdentifier[?Yield, ?Await]
     //   ( FormalParameters[~Yield, +Await] ) { AsyncFunctionBody }
     fn process_async_function_declaration(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::AsyncFunctionDeclaration);
+        self.enqueue(Node::AsyncFunctionDeclaration(self.source_range(9)));
         self.replace(9, Detail::Declaration);
         Ok(())
     }
@@ -3701,7 +4117,7 @@ where
     //   async [no LineTerminator here] function BindingIdentifier[~Yield, +Await]
     //   ( FormalParameters[~Yield, +Await] ) { AsyncFunctionBody }
     fn process_async_function_expression(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::AsyncFunctionExpression(true));
+        self.enqueue(Node::AsyncFunctionExpression(true, self.source_range(9)));
         self.replace(
             9,
             Detail::Expression {
@@ -3715,7 +4131,7 @@ where
     //   async [no LineTerminator here] function
     //   ( FormalParameters[~Yield, +Await] ) { AsyncFunctionBody }
     fn process_anonymous_async_function_expression(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::AsyncFunctionExpression(false));
+        self.enqueue(Node::AsyncFunctionExpression(false, self.source_range(8)));
         self.replace(
             8,
             Detail::Expression {
@@ -3744,7 +4160,7 @@ where
     //   async [no LineTerminator here] AsyncArrowBindingIdentifier[?Yield]
     //   [no LineTerminator here] => AsyncConciseBody[?In]
     fn process_async_arrow_function(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::AsyncArrowFunction);
+        self.enqueue(Node::AsyncArrowFunction(self.source_range(4)));
         self.replace(
             4,
             Detail::Expression {
@@ -3758,7 +4174,7 @@ where
     //   AsyncArrowHeadCCEAAAH[?Yield, ?Await] [no LineTerminator here] =>
     //   AsyncConciseBody[?In]
     fn process_async_arrow_function_cceaaah(&mut self) -> Result<(), Error> {
-        self.enqueue(Node::AsyncArrowFunction);
+        self.enqueue(Node::AsyncArrowFunction(self.source_range(3)));
         self.replace(
             3,
             Detail::Expression {
@@ -3905,6 +4321,15 @@ where
         let node_index = self.nodes.len();
         let token_index = self.tokens.len();
 
+        if let Some(mut prologue) = self.function_signature.take()
+            && token.kind == TokenKind::Lbrace
+        {
+            // A function body starts.
+            self.strict_mode_stack.push(self.strict_mode);
+            prologue.next_token_index = token_index + 1;
+            self.directive_prologue = Some(prologue);
+        }
+
         // Tokens coming from the `parser` module are held until refinements of permissive
         // production rules in a statement are processed.
         self.tokens.push(token.clone());
@@ -4012,6 +4437,69 @@ fn to_string_value(content: &str) -> Result<Vec<u16>, Error> {
     Ok(builder.build())
 }
 
+// 13.2.8.3 Static Semantics: TV
+//
+// Returns `None` if the template string contains NotEscapeSequence.
+fn template_value(content: &str) -> Option<Vec<u16>> {
+    if contains_not_escape_sequence(content) {
+        return None;
+    }
+    to_string_value(&normalize_line_terminators(content)).ok()
+}
+
+// 13.2.8.3 Static Semantics: TRV
+fn template_raw_value(content: &str) -> Vec<u16> {
+    normalize_line_terminators(content).encode_utf16().collect()
+}
+
+// <CR><LF> and <CR> in a template string are normalized to <LF> in both TV and TRV.
+fn normalize_line_terminators(content: &str) -> Cow<'_, str> {
+    if content.contains('\r') {
+        Cow::Owned(content.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+// NotEscapeSequence is allowed only in tagged templates.
+fn contains_not_escape_sequence(content: &str) -> bool {
+    fn skip_hex_digits(chars: &mut Peekable<Chars>, n: usize) -> bool {
+        (0..n).all(|_| chars.next().is_some_and(|ch| ch.is_ascii_hexdigit()))
+    }
+
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            continue;
+        }
+        match chars.next() {
+            Some('0') if chars.peek().is_some_and(|ch| ch.is_ascii_digit()) => return true,
+            Some('1'..='9') => return true,
+            Some('x') if !skip_hex_digits(&mut chars, 2) => return true,
+            Some('u') if chars.next_if_eq(&'{').is_some() => {
+                let mut cp = 0;
+                let mut has_digits = false;
+                loop {
+                    match chars.next() {
+                        Some('}') if has_digits => break,
+                        Some(ch) if ch.is_ascii_hexdigit() => {
+                            cp = (cp << 4) | ch.to_digit(16).unwrap();
+                            if cp > 0x10FFFF {
+                                return true;
+                            }
+                            has_digits = true;
+                        }
+                        _ => return true,
+                    }
+                }
+            }
+            Some('u') if !skip_hex_digits(&mut chars, 4) => return true,
+            _ => (),
+        }
+    }
+    false
+}
+
 #[derive(Default)]
 struct StringBuilder {
     state: StringBuilderState,
@@ -4184,6 +4672,30 @@ enum StringBuilderState {
     UnicodeEscapeSequenceCodePoint(u32),
 }
 
+// LegacyOctalIntegerLiteral or NonOctalDecimalIntegerLiteral.
+fn is_legacy_octal_like(lexeme: &str) -> bool {
+    let bytes = lexeme.as_bytes();
+    bytes.len() > 1 && bytes[0] == b'0' && bytes[1].is_ascii_digit()
+}
+
+// LegacyOctalEscapeSequence or NonOctalDecimalEscapeSequence.
+fn has_legacy_octal_escape(content: &str) -> bool {
+    let mut chars = content.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            continue;
+        }
+        match chars.next() {
+            Some('1'..='9') => return true,
+            Some('0') if chars.clone().next().is_some_and(|ch| ch.is_ascii_digit()) => {
+                return true;
+            }
+            _ => (),
+        }
+    }
+    false
+}
+
 fn is_single_code_unit(cp: u32) -> bool {
     cp < 0x10000
 }
@@ -19,3 +19,29 @@ fn test_to_string_value_unicode_hex4() {
 fn test_to_string_value_unicode_code_point() {
     assert!(matches!(to_string_value("\\u{2F804}"), Ok(v) if v == vec![0xD87E, 0xDC04]));
 }
+
+#[test]
+fn test_template_value() {
+    assert!(matches!(template_value("a\\nb"), Some(v) if v == vec![0x0061, 0x000A, 0x0062]));
+    assert!(matches!(template_value("a\r\nb"), Some(v) if v == vec![0x0061, 0x000A, 0x0062]));
+    assert!(matches!(template_value("\\u{2F804}"), Some(v) if v == vec![0xD87E, 0xDC04]));
+}
+
+#[test]
+fn test_template_value_not_escape_sequence() {
+    assert!(template_value("\\01").is_none());
+    assert!(template_value("\\1").is_none());
+    assert!(template_value("\\xG").is_none());
+    assert!(template_value("\\u12").is_none());
+    assert!(template_value("\\u{}").is_none());
+    assert!(template_value("\\u{110000}").is_none());
+}
+
+#[test]
+fn test_template_raw_value() {
+    assert_eq!(
+        template_raw_value("\\n\r\n\r"),
+        vec![0x005C, 0x006E, 0x000A, 0x000A]
+    );
+    assert_eq!(template_raw_value("\\u{"), vec![0x005C, 0x0075, 0x007B]);
+}
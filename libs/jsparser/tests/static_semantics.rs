@@ -25,6 +25,31 @@ macro_rules! parse_fail {
     };
 }
 
+#[test]
+fn test_12_9_3_1_legacy_octal_literal() {
+    parse_fail!(script: "static_semantics_12_9_3_1_legacy_octal_literal.js");
+}
+
+#[test]
+fn test_12_9_3_1_legacy_octal_literal_module() {
+    parse_fail!(module: "static_semantics_12_9_3_1_legacy_octal_literal_module.js");
+}
+
+#[test]
+fn test_12_9_4_1_legacy_octal_escape() {
+    parse_fail!(script: "static_semantics_12_9_4_1_legacy_octal_escape.js");
+}
+
+#[test]
+fn test_12_9_4_1_legacy_octal_escape_in_directive() {
+    parse_fail!(script: "static_semantics_12_9_4_1_legacy_octal_escape_in_directive.js");
+}
+
+#[test]
+fn test_13_1_1_strict_reserved_word() {
+    parse_fail!(script: "static_semantics_13_1_1_strict_reserved_word.js");
+}
+
 #[test]
 fn test_13_2_5_1_cover_initialized_name() {
     // TODO
@@ -61,6 +86,11 @@ fn test_13_4_1_prefix_decrement() {
     parse_fail!(script: "static_semantics_13_4_1_prefix_decrement.js");
 }
 
+#[test]
+fn test_13_5_1_1_delete_identifier() {
+    parse_fail!(script: "static_semantics_13_5_1_1_delete_identifier.js");
+}
+
 #[test]
 fn test_13_15_1_assignment() {
     parse_fail!(script: "static_semantics_13_15_1_assignment.js");
@@ -126,11 +156,41 @@ fn test_14_9_1_label() {
     parse_fail!(script: "static_semantics_14_9_1_label.js");
 }
 
+#[test]
+fn test_14_11_1_with() {
+    parse_fail!(script: "static_semantics_14_11_1_with.js");
+}
+
 #[test]
 fn test_15_1_1() {
     parse_fail!(script: "static_semantics_15_1_1.js");
 }
 
+#[test]
+fn test_15_1_1_rest() {
+    parse_fail!(script: "static_semantics_15_1_1_rest.js");
+}
+
+#[test]
+fn test_15_2_1_use_strict_non_simple_parameters() {
+    parse_fail!(script: "static_semantics_15_2_1_use_strict_non_simple_parameters.js");
+}
+
+#[test]
+fn test_15_2_1_use_strict_eval_parameter() {
+    parse_fail!(script: "static_semantics_15_2_1_use_strict_eval_parameter.js");
+}
+
+#[test]
+fn test_15_2_1_use_strict_arguments_name() {
+    parse_fail!(script: "static_semantics_15_2_1_use_strict_arguments_name.js");
+}
+
+#[test]
+fn test_15_3_1_use_strict_non_simple_parameters() {
+    parse_fail!(script: "static_semantics_15_3_1_use_strict_non_simple_parameters.js");
+}
+
 #[test]
 fn test_15_7_1_class_element_name() {
     parse_fail!(script: "static_semantics_15_7_1_class_element_name.js");
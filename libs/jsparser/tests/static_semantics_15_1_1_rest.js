@@ -0,0 +1 @@
+function x(a, ...a) {}
@@ -0,0 +1,3 @@
+function arguments() {
+  'use strict';
+}
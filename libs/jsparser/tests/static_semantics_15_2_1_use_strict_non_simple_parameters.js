@@ -0,0 +1,3 @@
+function f(a = 0) {
+  'use strict';
+}
@@ -9,7 +9,18 @@ use jsruntime::BasicRuntime;
 
 const SAMPLE_SIZE: usize = 200;
 
-const DATA_SET: &[(&str, &str)] = &[("fib16.js", include_str!("dataset/fib16.js"))];
+const DATA_SET: &[(&str, &str)] = &[
+    ("fib16.js", include_str!("dataset/fib16.js")),
+    ("inline_call.js", include_str!("dataset/inline_call.js")),
+    (
+        "inline_closure.js",
+        include_str!("dataset/inline_closure.js"),
+    ),
+    (
+        "scalar_replacement.js",
+        include_str!("dataset/scalar_replacement.js"),
+    ),
+];
 
 macro_rules! elapsed {
     ($target:expr) => {{
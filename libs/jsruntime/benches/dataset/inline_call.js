@@ -0,0 +1,15 @@
+for (let i = 0; i < 2000; i++) {
+  sumOfSquares(100);
+}
+
+function square(x) {
+  return x * x;
+}
+
+function sumOfSquares(n) {
+  let sum = 0;
+  for (let i = 0; i < n; i++) {
+    sum += square(i);
+  }
+  return sum;
+}
@@ -0,0 +1,16 @@
+const scale = makeScale(3);
+for (let i = 0; i < 2000; i++) {
+  sum(scale, 100);
+}
+
+function makeScale(k) {
+  return (x) => x * k;
+}
+
+function sum(f, n) {
+  let sum = 0;
+  for (let i = 0; i < n; i++) {
+    sum += f(i);
+  }
+  return sum;
+}
@@ -0,0 +1,12 @@
+for (let i = 0; i < 2000; i++) {
+  sumOfScaled(100, i);
+}
+
+function sumOfScaled(n, k) {
+  const scale = (x) => x * k;
+  let sum = 0;
+  for (let i = 0; i < n; i++) {
+    sum += scale(i);
+  }
+  return sum;
+}
@@ -0,0 +1,272 @@
+use jsgc::HandleMut;
+use jsparser::Symbol;
+
+use crate::Error;
+use crate::Rooted;
+use crate::Runtime;
+use crate::types::CallContext;
+use crate::types::CallContextFlags;
+use crate::types::Object;
+use crate::types::PropertyKey;
+use crate::types::Value;
+
+// Public API for embedders to manipulate JavaScript values.
+//
+// Values returned from the methods, including thrown values, are rooted so that they stay valid
+// across garbage collections.
+impl<X> Runtime<X> {
+    /// Returns the global object of the current realm.
+    pub fn global_object(&self) -> Rooted {
+        self.root(&Value::Object(self.builtins.global_object))
+    }
+
+    /// Returns the value of a global variable, or `Value::Undefined` if it's not defined.
+    pub fn get_global(&mut self, name: &str) -> Rooted {
+        let key = self.symbol_registry.intern_str(name).into();
+        let value = self
+            .builtins
+            .global_object
+            .get_value(&key)
+            .cloned()
+            .unwrap_or(Value::Undefined);
+        self.root(&value)
+    }
+
+    /// Sets the value of a global variable.
+    pub fn set_global(&mut self, name: &str, value: &Value) {
+        let key = self.symbol_registry.intern_str(name).into();
+        self.builtins.global_object.set_value(&key, value);
+    }
+
+    /// Calls a function with a `this` value and arguments.
+    ///
+    /// Returns the thrown value as an error if the function throws an exception.
+    pub fn call(&mut self, func: &Value, this: &Value, args: &[Value]) -> Result<Rooted, Rooted> {
+        let result = match func {
+            Value::Object(object) if object.is_callable() => {
+                self.call_from_host(*object, this, args, CallContextFlags::empty())
+            }
+            _ => self.throw(type_error!("not a function")),
+        };
+        self.root_result(result)
+    }
+
+    /// Creates an object by calling a constructor in the same way as the `new` operator.
+    pub fn construct(&mut self, constructor: &Value, args: &[Value]) -> Result<Rooted, Rooted> {
+        let result = match constructor {
+            Value::Object(object) if object.is_constructor() => {
+                self.construct_from_host(*object, args)
+            }
+            _ => self.throw(type_error!("not a constructor")),
+        };
+        self.root_result(result)
+    }
+
+    /// Returns the value of a property of an object.
+    ///
+    /// The prototype chain is searched if the object doesn't have the property.
+    pub fn get(&mut self, object: &Value, key: &str) -> Result<Rooted, Rooted> {
+        let key = self.symbol_registry.intern_str(key).into();
+        let result = self.get_by_key(object, &key);
+        self.root_result(result)
+    }
+
+    /// Returns the value of an indexed property of an object such as an array element.
+    pub fn get_index(&mut self, object: &Value, index: u32) -> Result<Rooted, Rooted> {
+        let result = self.get_by_key(object, &PropertyKey::from(index as f64));
+        self.root_result(result)
+    }
+
+    /// Sets the value of a property of an object.
+    pub fn set(&mut self, object: &Value, key: &str, value: &Value) -> Result<(), Rooted> {
+        let key = self.symbol_registry.intern_str(key).into();
+        self.set_by_key(object, &key, value)
+            .map_err(|err| self.root(&err))
+    }
+
+    /// Sets the value of an indexed property of an object such as an array element.
+    pub fn set_index(&mut self, object: &Value, index: u32, value: &Value) -> Result<(), Rooted> {
+        self.set_by_key(object, &PropertyKey::from(index as f64), value)
+            .map_err(|err| self.root(&err))
+    }
+
+    /// Returns the names of the own enumerable properties of an object.
+    ///
+    /// The order of the names is unspecified.
+    pub fn keys(&mut self, object: &Value) -> Result<Vec<std::string::String>, Rooted> {
+        let object = self.expect_object(object).map_err(|err| self.root(&err))?;
+        let keys = object
+            .iter_own_properties()
+            .filter(|(_, prop)| prop.is_enumerable())
+            .map(|(key, _)| self.property_key_to_string(key))
+            .collect();
+        Ok(keys)
+    }
+
+    /// Creates an empty object.
+    pub fn new_object(&mut self) -> Rooted {
+        let object = self.create_ordinary_object();
+        self.root(&Value::Object(object))
+    }
+
+    /// Creates an array containing elements.
+    pub fn new_array(&mut self, elements: &[Value]) -> Rooted {
+        let array = self.create_array(elements);
+        self.root(&Value::Object(array))
+    }
+
+    /// Creates a string.
+    pub fn new_string(&mut self, value: &str) -> Rooted {
+        let string = self.create_string_from_utf8(value);
+        self.root(&Value::String(string))
+    }
+
+    /// Converts a value into a Rust string in the same way as `String(value)`.
+    pub fn to_rust_string(&mut self, value: &Value) -> Result<std::string::String, Rooted> {
+        match self.value_to_string(value) {
+            Ok(string) => Ok(std::string::String::from_utf16_lossy(string.as_slice())),
+            Err(err) => {
+                let err = self.create_exception(err);
+                Err(self.root(&err))
+            }
+        }
+    }
+
+    // 10.1.12 OrdinaryObjectCreate ( proto [ , additionalInternalSlotsList ] )
+    //
+    // Creates an empty object inheriting `Object.prototype`.
+    pub(crate) fn create_ordinary_object(&mut self) -> HandleMut<Object> {
+        let mut object = self.create_object();
+        object.set_prototype(self.builtins.object_prototype);
+        object
+    }
+
+    // 7.3.18 CreateArrayFromList ( elements )
+    pub(crate) fn create_array(&mut self, elements: &[Value]) -> HandleMut<Object> {
+        let mut object = self.create_ordinary_object();
+        // TODO: error handling
+        let _ = self.create_data_property(&mut object, &Symbol::LENGTH.into(), &Value::from(0));
+        for element in elements.iter() {
+            let _ = self.push_value(&mut object, element);
+        }
+        object
+    }
+
+    fn root_result(&self, result: Result<Value, Value>) -> Result<Rooted, Rooted> {
+        match result {
+            Ok(value) => Ok(self.root(&value)),
+            Err(err) => Err(self.root(&err)),
+        }
+    }
+
+    pub(crate) fn property_key_to_string(&self, key: &PropertyKey) -> std::string::String {
+        match key {
+            PropertyKey::Symbol(symbol) => self
+                .symbol_registry
+                .resolve(*symbol)
+                .map(std::string::String::from_utf16_lossy)
+                .unwrap_or_default(),
+            PropertyKey::Number(index) => format!("{index}"),
+        }
+    }
+
+    pub(crate) fn get_by_key(&mut self, object: &Value, key: &PropertyKey) -> Result<Value, Value> {
+        let object = self.expect_object(object)?;
+        self.enter_from_host(|runtime, caller| {
+            let value = runtime.get_property_value(caller, object, key)?;
+            Ok(value.unwrap_or(Value::Undefined))
+        })
+    }
+
+    pub(crate) fn set_by_key(
+        &mut self,
+        object: &Value,
+        key: &PropertyKey,
+        value: &Value,
+    ) -> Result<(), Value> {
+        let object = self.expect_object(object)?;
+        self.enter_from_host(|runtime, caller| {
+            runtime.set_property_value(caller, object, key, value, false)?;
+            Ok(Value::Undefined)
+        })?;
+        Ok(())
+    }
+
+    fn expect_object(&mut self, value: &Value) -> Result<HandleMut<Object>, Value> {
+        match value {
+            Value::Object(object) => Ok(*object),
+            _ => self.throw(type_error!("not an object")),
+        }
+    }
+
+    pub(crate) fn throw<T>(&mut self, result: Result<T, Error>) -> Result<T, Value> {
+        result.map_err(|err| self.create_exception(err))
+    }
+
+    pub(crate) fn call_from_host(
+        &mut self,
+        callable: HandleMut<Object>,
+        this: &Value,
+        args: &[Value],
+        flags: CallContextFlags,
+    ) -> Result<Value, Value> {
+        self.enter_from_host(|runtime, caller| {
+            let mut retv = Value::Undefined;
+            let status = runtime.call_with_flags(caller, callable, this, args, flags, &mut retv);
+            retv.into_result(status)
+        })
+    }
+
+    // Performs an operation which may run JavaScript code such as a function call and a getter
+    // call requested from the host.
+    fn enter_from_host<F>(&mut self, op: F) -> Result<Value, Value>
+    where
+        F: FnOnce(&mut Self, &CallContext) -> Result<Value, Value>,
+    {
+        let entry_args: [Value; 0] = [];
+        let entry = CallContext::new_for_entry(&entry_args);
+        // SAFETY: `host_call_context` is null or a valid pointer to the call context of the host
+        // function being called.
+        let caller = unsafe { self.host_call_context.as_ref() }.unwrap_or(&entry);
+        let outermost = self.enter_stack();
+        let result = op(self, caller);
+        self.leave_stack(outermost);
+        if outermost {
+            self.finish_execution(result)
+        } else {
+            result
+        }
+    }
+
+    fn construct_from_host(
+        &mut self,
+        constructor: HandleMut<Object>,
+        args: &[Value],
+    ) -> Result<Value, Value> {
+        self.enter_from_host(|runtime, caller| {
+            // 10.1.14 GetPrototypeFromConstructor ( constructor, intrinsicDefaultProto )
+            let key = Symbol::PROTOTYPE.into();
+            let prototype = match runtime.get_property_value(caller, constructor, &key)? {
+                Some(Value::Object(prototype)) => prototype,
+                _ => runtime.builtins.object_prototype,
+            };
+            let mut object = runtime.create_object();
+            object.set_prototype(prototype);
+            let this = Value::Object(object);
+            let mut retv = Value::Undefined;
+            let status = runtime.call_with_flags(
+                caller,
+                constructor,
+                &this,
+                args,
+                CallContextFlags::NEW,
+                &mut retv,
+            );
+            // 10.2.2 [[Construct]] ( argumentsList, newTarget ), step 10
+            match retv.into_result(status)? {
+                result @ Value::Object(_) => Ok(result),
+                _ => Ok(this),
+            }
+        })
+    }
+}
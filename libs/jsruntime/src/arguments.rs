@@ -0,0 +1,75 @@
+use jsgc::HandleMut;
+use jsparser::Symbol;
+
+use crate::Runtime;
+use crate::types::CallContext;
+use crate::types::Capture;
+use crate::types::Object;
+use crate::types::ParameterMap;
+use crate::types::Property;
+use crate::types::Value;
+
+impl<X> Runtime<X> {
+    // 10.4.4.6 CreateUnmappedArgumentsObject ( argumentsList )
+    // 10.4.4.7 CreateMappedArgumentsObject ( func, formals, argumentsList, env )
+    //
+    // The properties of a mapped arguments object are mapped to the formal parameters in
+    // `map_argument()` after the object is created.
+    pub(crate) fn create_arguments_object(
+        &mut self,
+        context: &CallContext,
+        mapped: bool,
+    ) -> HandleMut<Object> {
+        let args = context.args();
+        let mut object = self.create_object();
+        object.set_prototype(self.builtins.object_prototype);
+        // TODO: error handling
+        let _ = object.define_own_property(
+            Symbol::LENGTH.into(),
+            Property::data_wxc(Value::from(args.len() as u32)),
+        );
+        for (index, value) in args.iter().enumerate() {
+            let _ = object
+                .define_own_property((index as f64).into(), Property::data_wec(value.clone()));
+        }
+        // TODO(feat): %Array.prototype.values%
+        if mapped {
+            if let Some(func) = context.func() {
+                let _ = object.define_own_property(
+                    Symbol::CALLEE.into(),
+                    Property::data_wxc(Value::Object(func)),
+                );
+            }
+            let map = self.heap.alloc_mut(ParameterMap::new(args.len()));
+            object.set_parameter_map(map);
+        } else {
+            // TODO(feat): an accessor property throwing a TypeError.
+        }
+        object
+    }
+
+    /// Maps the property of a mapped arguments object at `index` to a formal parameter.
+    pub(crate) fn map_argument(
+        &mut self,
+        arguments: &mut Object,
+        index: u16,
+        capture: HandleMut<Capture>,
+    ) {
+        debug_assert!(arguments.is_arguments());
+        arguments.parameter_map().map(index as usize, capture);
+    }
+
+    // Step#28 in 10.2.11 FunctionDeclarationInstantiation ( func, argumentsList )
+    //
+    // Creates an array containing the arguments from the `start`-th position for the rest
+    // parameter.
+    pub(crate) fn create_rest_parameter(
+        &mut self,
+        context: &CallContext,
+        start: u16,
+    ) -> HandleMut<Object> {
+        let args = context.args();
+        let rest = args.get(start as usize..).unwrap_or(&[]);
+        self.create_array(rest)
+    }
+}
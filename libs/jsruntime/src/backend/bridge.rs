@@ -5,6 +5,8 @@ use crate::Error;
 use crate::Runtime;
 use crate::lambda::LambdaKind;
 use crate::logger;
+use crate::semantics::ScopeRef;
+use crate::semantics::TemplateStrings;
 use crate::types::CallContext;
 use crate::types::Capture;
 use crate::types::Closure;
@@ -48,7 +50,11 @@ pub(crate) extern "C" fn runtime_lazy_compile_normal<X>(
     logger::debug!(event = "runtime_lazy_compile_normal");
 
     let lambda_id = context.closure().lambda_id;
-    let lambda = if let Some(lambda) = runtime.code_registry.get_lambda(lambda_id) {
+    // Use the optimized code if the function has already been optimized with the type feedback
+    // collected through other closures.
+    let lambda = if let Some(lambda) = runtime.code_registry.get_optimized_lambda(lambda_id) {
+        lambda
+    } else if let Some(lambda) = runtime.code_registry.get_lambda(lambda_id) {
         lambda
     } else {
         let lambda_info = runtime.lambda_registry.get(lambda_id);
@@ -236,8 +242,12 @@ impl<X> Runtime<X> {
     }
 }
 
-// 7.1.6 ToInt32 ( argument )
 pub(crate) extern "C" fn runtime_to_int32<X>(_runtime: &mut Runtime<X>, value: f64) -> i32 {
+    to_int32(value)
+}
+
+// 7.1.6 ToInt32 ( argument )
+pub(crate) fn to_int32(value: f64) -> i32 {
     const EXP2_31: f64 = (2u64 << 31) as f64;
     const EXP2_32: f64 = (2u64 << 32) as f64;
 
@@ -261,8 +271,12 @@ pub(crate) extern "C" fn runtime_to_int32<X>(_runtime: &mut Runtime<X>, value: f
     }
 }
 
-// 7.1.7 ToUint32 ( argument )
 pub(crate) extern "C" fn runtime_to_uint32<X>(_runtime: &mut Runtime<X>, value: f64) -> u32 {
+    to_uint32(value)
+}
+
+// 7.1.7 ToUint32 ( argument )
+pub(crate) fn to_uint32(value: f64) -> u32 {
     const EXP2_31: f64 = (2u64 << 31) as f64;
     const EXP2_32: f64 = (2u64 << 32) as f64;
 
@@ -365,6 +379,14 @@ pub(crate) extern "C" fn runtime_create_string<X>(
     runtime.create_string(utf16)
 }
 
+pub(crate) extern "C" fn runtime_get_template_object<X>(
+    runtime: &mut Runtime<X>,
+    strings: &TemplateStrings,
+) -> HandleMut<Object> {
+    logger::debug!(event = "runtime_get_template_object");
+    runtime.get_template_object(strings)
+}
+
 pub(crate) extern "C" fn runtime_create_capture<X>(
     runtime: &mut Runtime<X>,
     target: *mut Value,
@@ -388,6 +410,15 @@ pub(crate) extern "C" fn runtime_create_closure<X>(
     runtime.create_closure(lambda, lambda_id.into(), num_captures)
 }
 
+pub(crate) extern "C" fn runtime_call_in_realm<X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    retv: &mut Value,
+) -> Status {
+    logger::debug!(event = "runtime_call_in_realm");
+    runtime.call_in_realm(context, retv)
+}
+
 pub(crate) extern "C" fn runtime_create_coroutine<X>(
     runtime: &mut Runtime<X>,
     closure: *mut Closure,
@@ -442,14 +473,53 @@ pub(crate) extern "C" fn runtime_create_object<X>(
 
 pub(crate) extern "C" fn runtime_create_reference_error<X>(
     runtime: &mut Runtime<X>,
+    context: &CallContext,
+    lambda_id: u32,
+    offset: u32,
 ) -> HandleMut<Object> {
-    runtime.create_reference_error(None)
+    let error = runtime.create_reference_error(None);
+    runtime.set_error_stack_at(error, context, lambda_id.into(), offset.into());
+    error
 }
 
 pub(crate) extern "C" fn runtime_create_type_error<X>(
     runtime: &mut Runtime<X>,
+    context: &CallContext,
+    lambda_id: u32,
+    offset: u32,
+) -> HandleMut<Object> {
+    let error = runtime.create_type_error(None);
+    runtime.set_error_stack_at(error, context, lambda_id.into(), offset.into());
+    error
+}
+
+pub(crate) extern "C" fn runtime_handle_interrupt<X>(
+    runtime: &mut Runtime<X>,
+) -> HandleMut<Object> {
+    runtime.handle_interrupt()
+}
+
+pub(crate) extern "C" fn runtime_handle_heap_exhausted<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    lambda_id: u32,
+    offset: u32,
+) -> HandleMut<Object> {
+    let error = runtime.handle_heap_exhausted();
+    runtime.set_error_stack_at(error, context, lambda_id.into(), offset.into());
+    error
+}
+
+pub(crate) extern "C" fn runtime_create_range_error<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    lambda_id: u32,
+    offset: u32,
+    message: Handle<String>,
 ) -> HandleMut<Object> {
-    runtime.create_type_error(None)
+    let error = runtime.create_range_error(Some(message));
+    runtime.set_error_stack_at(error, context, lambda_id.into(), offset.into());
+    error
 }
 
 pub(crate) extern "C" fn runtime_create_internal_error<X>(
@@ -461,6 +531,7 @@ pub(crate) extern "C" fn runtime_create_internal_error<X>(
 
 pub(crate) extern "C" fn runtime_get_value_by_symbol<X>(
     runtime: &mut Runtime<X>,
+    context: &CallContext,
     object: *mut Object,
     key: u32,
     strict: bool,
@@ -471,24 +542,12 @@ pub(crate) extern "C" fn runtime_get_value_by_symbol<X>(
     debug_assert_ne!(key, 0);
     let key = PropertyKey::from(key);
 
-    match object.get_value(&key) {
-        Some(v) => {
-            *retv = v.clone();
-            Status::Normal
-        }
-        None if strict => {
-            *retv = Value::Object(runtime.create_reference_error(None));
-            Status::Exception
-        }
-        None => {
-            *retv = Value::Undefined;
-            Status::Normal
-        }
-    }
+    get_value(runtime, context, object, &key, strict, retv)
 }
 
 pub(crate) extern "C" fn runtime_get_value_by_number<X>(
     runtime: &mut Runtime<X>,
+    context: &CallContext,
     object: *mut Object,
     key: f64,
     strict: bool,
@@ -499,24 +558,12 @@ pub(crate) extern "C" fn runtime_get_value_by_number<X>(
     debug_assert!(f64::is_finite(key));
     let key = PropertyKey::from(key);
 
-    match object.get_value(&key) {
-        Some(v) => {
-            *retv = v.clone();
-            Status::Normal
-        }
-        None if strict => {
-            *retv = Value::Object(runtime.create_reference_error(None));
-            Status::Exception
-        }
-        None => {
-            *retv = Value::Undefined;
-            Status::Normal
-        }
-    }
+    get_value(runtime, context, object, &key, strict, retv)
 }
 
 pub(crate) extern "C" fn runtime_get_value_by_value<X>(
     runtime: &mut Runtime<X>,
+    context: &CallContext,
     object: *mut Object,
     key: &Value,
     strict: bool,
@@ -531,55 +578,46 @@ pub(crate) extern "C" fn runtime_get_value_by_value<X>(
         }
     };
 
-    match object.get_value(&key) {
-        Some(v) => {
-            *retv = v.clone();
-            Status::Normal
-        }
-        None if strict => {
-            *retv = Value::Object(runtime.create_reference_error(None));
-            Status::Exception
-        }
-        None => {
-            *retv = Value::Undefined;
-            Status::Normal
-        }
-    }
+    get_value(runtime, context, object, &key, strict, retv)
 }
 
 pub(crate) extern "C" fn runtime_set_value_by_symbol<X>(
-    _runtime: &mut Runtime<X>,
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
     object: *mut Object,
     key: u32,
     value: &Value,
-    _retv: &mut Value,
+    strict: bool,
+    retv: &mut Value,
 ) -> Status {
     let object = into_object!(object);
     debug_assert_ne!(key, 0);
     let key = PropertyKey::from(key);
-    object.set_value(&key, value);
-    Status::Normal
+    set_value(runtime, context, object, &key, value, strict, retv)
 }
 
 pub(crate) extern "C" fn runtime_set_value_by_number<X>(
-    _runtime: &mut Runtime<X>,
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
     object: *mut Object,
     key: f64,
     value: &Value,
-    _retv: &mut Value,
+    strict: bool,
+    retv: &mut Value,
 ) -> Status {
     let object = into_object!(object);
     debug_assert!(f64::is_finite(key));
     let key = PropertyKey::from(key);
-    object.set_value(&key, value);
-    Status::Normal
+    set_value(runtime, context, object, &key, value, strict, retv)
 }
 
 pub(crate) extern "C" fn runtime_set_value_by_value<X>(
     runtime: &mut Runtime<X>,
+    context: &CallContext,
     object: *mut Object,
     key: &Value,
     value: &Value,
+    strict: bool,
     retv: &mut Value,
 ) -> Status {
     let object = into_object!(object);
@@ -590,8 +628,72 @@ pub(crate) extern "C" fn runtime_set_value_by_value<X>(
             return Status::Exception;
         }
     };
-    object.set_value(&key, value);
-    Status::Normal
+    set_value(runtime, context, object, &key, value, strict, retv)
+}
+
+fn get_value<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    object: &mut Object,
+    key: &PropertyKey,
+    strict: bool,
+    retv: &mut Value,
+) -> Status {
+    match runtime.get_property_value(context, object.as_handle(), key) {
+        Ok(Some(v)) => {
+            *retv = v;
+            Status::Normal
+        }
+        Ok(None) if strict => {
+            *retv = Value::Object(runtime.create_reference_error(None));
+            Status::Exception
+        }
+        Ok(None) => {
+            *retv = Value::Undefined;
+            Status::Normal
+        }
+        Err(err) => {
+            *retv = err;
+            Status::Exception
+        }
+    }
+}
+
+fn set_value<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    object: &mut Object,
+    key: &PropertyKey,
+    value: &Value,
+    strict: bool,
+    retv: &mut Value,
+) -> Status {
+    match runtime.set_property_value(context, object.as_handle(), key, value, strict) {
+        Ok(()) => Status::Normal,
+        Err(err) => {
+            *retv = err;
+            Status::Exception
+        }
+    }
+}
+
+pub(crate) extern "C" fn runtime_set_global_binding_value<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    key: u32,
+    value: &Value,
+    strict: bool,
+    retv: &mut Value,
+) -> Status {
+    debug_assert_ne!(key, 0);
+    let key = PropertyKey::from(key);
+    match runtime.set_global_binding_value(context, &key, value, strict) {
+        Ok(()) => Status::Normal,
+        Err(err) => {
+            *retv = err;
+            Status::Exception
+        }
+    }
 }
 
 pub(crate) extern "C" fn runtime_concat_strings<X>(
@@ -689,6 +791,7 @@ pub(crate) extern "C" fn runtime_create_data_property_by_value<X>(
 // 7.3.25 CopyDataProperties ( target, source, excludedItems )
 pub(crate) extern "C" fn runtime_copy_data_properties<X>(
     runtime: &mut Runtime<X>,
+    context: &CallContext,
     target: *mut Object,
     source: &Value,
     retv: &mut Value,
@@ -696,13 +799,13 @@ pub(crate) extern "C" fn runtime_copy_data_properties<X>(
     // TODO(refactor): generate ffi-conversion code by script
     let target = into_object!(target);
 
-    match runtime.copy_data_properties(target, source) {
+    match runtime.copy_data_properties(context, target, source) {
         Ok(()) => {
             *retv = Value::None;
             Status::Normal
         }
         Err(err) => {
-            *retv = runtime.create_exception(err);
+            *retv = err;
             Status::Exception
         }
     }
@@ -730,6 +833,48 @@ pub(crate) extern "C" fn runtime_push_value<X>(
     }
 }
 
+pub(crate) extern "C" fn runtime_create_arguments<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    mapped: bool,
+) -> HandleMut<Object> {
+    logger::debug!(event = "runtime_create_arguments", mapped);
+    runtime.create_arguments_object(context, mapped)
+}
+
+pub(crate) extern "C" fn runtime_map_argument<X>(
+    runtime: &mut Runtime<X>,
+    arguments: *mut Object,
+    index: u16,
+    capture: *mut Capture,
+) {
+    logger::debug!(event = "runtime_map_argument", index);
+    let arguments = into_object!(arguments);
+    let capture = HandleMut::from_ptr(capture).unwrap();
+    runtime.map_argument(arguments, index, capture);
+}
+
+pub(crate) extern "C" fn runtime_create_rest_parameter<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    start: u16,
+) -> HandleMut<Object> {
+    logger::debug!(event = "runtime_create_rest_parameter", start);
+    runtime.create_rest_parameter(context, start)
+}
+
+pub(crate) extern "C" fn runtime_direct_eval<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    scope_id: u16,
+    captures: *const HandleMut<Capture>,
+    strict: bool,
+    retv: &mut Value,
+) -> Status {
+    logger::debug!(event = "runtime_direct_eval", scope_id, strict);
+    runtime.perform_direct_eval(context, ScopeRef::new(scope_id), captures, strict, retv)
+}
+
 pub(crate) extern "C" fn runtime_panic<X>(
     _runtime: &mut Runtime<X>,
     msg: *const std::os::raw::c_char,
@@ -857,7 +1002,60 @@ pub(crate) extern "C" fn runtime_print_message<X>(
     logger::debug!("runtime_print_value: {msg:?}");
 }
 
-pub(crate) extern "C" fn runtime_launch_debugger<X>(_runtime: &mut Runtime<X>) {
-    logger::debug!("runtime_launch_debugger");
-    // TODO(feat): Support debuggers such as Chrome DevTools.
+pub(crate) extern "C" fn runtime_launch_debugger<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    scope_id: u16,
+    variables: *const &Value,
+) {
+    logger::debug!(event = "runtime_launch_debugger", scope_id);
+    runtime.launch_debugger(context, ScopeRef::new(scope_id), variables);
+}
+
+pub(crate) extern "C" fn runtime_handle_pause_point<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    scope_id: u16,
+    variables: *const &Value,
+) {
+    logger::debug!(event = "runtime_handle_pause_point", scope_id);
+    runtime.handle_pause_point(context, ScopeRef::new(scope_id), variables);
+}
+
+pub(crate) extern "C" fn runtime_pause_on_exception<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    scope_id: u16,
+    variables: *const &Value,
+    exception: &Value,
+) {
+    logger::debug!(event = "runtime_pause_on_exception", scope_id, ?exception);
+    runtime.pause_on_exception(context, ScopeRef::new(scope_id), variables, exception);
+}
+
+pub(crate) extern "C" fn runtime_take_profile_sample<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    lambda_id: u32,
+) {
+    logger::debug!(event = "runtime_take_profile_sample", lambda_id);
+    runtime.take_profile_sample(context, lambda_id.into());
+}
+
+pub(crate) extern "C" fn runtime_optimize_lambda<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    lambda_id: u32,
+) {
+    logger::debug!(event = "runtime_optimize_lambda", lambda_id);
+    runtime.optimize_lambda(context, lambda_id.into());
+}
+
+pub(crate) extern "C" fn runtime_deoptimize_lambda<X>(
+    runtime: &mut Runtime<X>,
+    context: &CallContext,
+    lambda_id: u32,
+) {
+    logger::debug!(event = "runtime_deoptimize_lambda", lambda_id);
+    runtime.deoptimize_lambda(context, lambda_id.into());
 }
@@ -9,8 +9,12 @@ use cranelift::frontend::Switch;
 use base::static_assert_eq;
 use jsgc::Handle;
 
+use crate::RealmId;
 use crate::lambda::LambdaKind;
 use crate::logger;
+use crate::semantics::SourceOffset;
+use crate::semantics::TemplateStrings;
+use crate::type_feedback::TypeFeedback;
 use crate::types::CallContext;
 use crate::types::CallContextFlags;
 use crate::types::Capture;
@@ -29,6 +33,7 @@ use super::ClosureIr;
 use super::CoroutineIr;
 use super::EditorSupport;
 use super::FlowSelector;
+use super::Int32Ir;
 use super::LambdaId;
 use super::LambdaIr;
 use super::NumberIr;
@@ -63,6 +68,9 @@ pub struct Editor<'a> {
 
     /// Enable the runtime assertions.
     runtime_assert_enabled: bool,
+
+    /// Enabled if the function is optimized with the type feedback.
+    optimized: bool,
 }
 
 impl<'a> Editor<'a> {
@@ -107,9 +115,14 @@ impl<'a> Editor<'a> {
             block_terminated: false,
             coroutine_mode: false,
             runtime_assert_enabled,
+            optimized: false,
         }
     }
 
+    pub fn set_optimized(&mut self) {
+        self.optimized = true;
+    }
+
     pub fn put_declare_lazy_compile(
         &mut self,
         support: &mut impl EditorSupport,
@@ -319,6 +332,13 @@ impl<'a> Editor<'a> {
         block
     }
 
+    pub fn create_block_with_f64(&mut self) -> ir::Block {
+        logger::debug!(event = "create_block_with_f64");
+        let block = self.builder.create_block();
+        self.builder.append_block_param(block, ir::types::F64);
+        block
+    }
+
     pub fn create_block_with_addr(&mut self) -> ir::Block {
         logger::debug!(event = "create_block_with_addr");
         let block = self.builder.create_block();
@@ -658,6 +678,22 @@ impl<'a> Editor<'a> {
         AnyIr(self.put_load_addr(capture, Capture::TARGET_OFFSET))
     }
 
+    pub fn put_load_captured_value_from_closure(
+        &mut self,
+        closure: ClosureIr,
+        index: u16,
+    ) -> AnyIr {
+        logger::debug!(
+            event = "put_load_captured_value_from_closure",
+            ?closure,
+            index
+        );
+        let offset =
+            Closure::CAPTURES_OFFSET + (self.addr_type.bytes() as usize) * (index as usize);
+        let capture = self.put_load_addr(closure.0, offset);
+        AnyIr(self.put_load_addr(capture, Capture::TARGET_OFFSET))
+    }
+
     pub fn put_escape_value(&mut self, capture: CaptureIr, value: AnyIr) {
         logger::debug!(event = "put_escape_value", ?capture, ?value);
         let escaped = self
@@ -681,6 +717,14 @@ impl<'a> Editor<'a> {
         LambdaIr(self.put_load_addr(closure.0, Closure::LAMBDA_OFFSET))
     }
 
+    pub fn put_is_closure_of(&mut self, closure: ClosureIr, lambda_id: LambdaId) -> BooleanIr {
+        use ir::condcodes::IntCC::Equal;
+        logger::debug!(event = "put_is_closure_of", ?closure, ?lambda_id);
+        let id = self.put_load_i32(closure.0, Closure::LAMBDA_ID_OFFSET);
+        let expected = u32::from(lambda_id) as i32 as i64;
+        BooleanIr(self.builder.ins().icmp_imm(Equal, id, expected))
+    }
+
     pub fn put_store_capture_to_closure(
         &mut self,
         capture: CaptureIr,
@@ -700,28 +744,62 @@ impl<'a> Editor<'a> {
 
     pub fn put_call(
         &mut self,
+        support: &mut impl EditorSupport,
         function: ObjectIr,
         closure: ClosureIr,
         flags: CallContextFlags,
+        realm: RealmId,
         retv: AnyIr,
     ) -> StatusIr {
-        logger::debug!(event = "put_call", ?function, ?closure, ?flags, ?retv);
+        logger::debug!(
+            event = "put_call",
+            ?function,
+            ?closure,
+            ?flags,
+            ?realm,
+            ?retv
+        );
+        use ir::condcodes::IntCC::Equal;
+        let then_block = self.create_block();
+        let cross_realm_block = self.create_block();
+        self.builder.set_cold_block(cross_realm_block);
+        let merge_block = self.create_block();
+        self.builder.append_block_param(merge_block, ir::types::I32);
+
         self.put_store_closure_to_call_context(closure);
         self.put_store_function_to_call_context(function);
         self.put_store_flags_to_call_context(flags);
+        let context = self
+            .builder
+            .ins()
+            .stack_addr(self.addr_type, self.call_context, 0);
+
+        // The realm of the caller is the realm of the program being compiled.  A closure created
+        // in another realm is called via the runtime function which switches the current realm.
+        let callee_realm = self.put_load_i16(closure.0, Closure::REALM_OFFSET);
+        let same_realm = BooleanIr(self.builder.ins().icmp_imm(
+            Equal,
+            callee_realm,
+            realm.index() as i64,
+        ));
+        self.put_branch(same_realm, then_block, &[], cross_realm_block, &[]);
+
+        self.switch_to_block(then_block);
         let lambda = self.put_load_lambda_from_closure(closure);
-        let args = &[
-            self.runtime(),
-            self.builder
-                .ins()
-                .stack_addr(self.addr_type, self.call_context, 0),
-            retv.0,
-        ];
+        let args = &[self.runtime(), context, retv.0];
         let call = self
             .builder
             .ins()
             .call_indirect(self.lambda_sig, lambda.0, args);
-        StatusIr(self.builder.inst_results(call)[0])
+        let status = self.builder.inst_results(call)[0];
+        self.put_jump(merge_block, &[status.into()]);
+
+        self.switch_to_block(cross_realm_block);
+        let status = self.put_runtime_call_in_realm(support, context, retv);
+        self.put_jump(merge_block, &[status.0.into()]);
+
+        self.switch_to_block(merge_block);
+        StatusIr(self.get_block_param(merge_block, 0))
     }
 
     // coroutine
@@ -858,6 +936,32 @@ impl<'a> Editor<'a> {
             .stack_store(self.addr_type, caller, self.call_context, OFFSET);
     }
 
+    pub fn put_store_call_site_to_call_context(
+        &mut self,
+        lambda_id: LambdaId,
+        offset: SourceOffset,
+    ) {
+        const LAMBDA_ID_OFFSET: i32 = CallContext::CALL_SITE_LAMBDA_ID_OFFSET as i32;
+        const OFFSET_OFFSET: i32 = CallContext::CALL_SITE_OFFSET_OFFSET as i32;
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        self.builder.ins().stack_store(
+            self.addr_type,
+            lambda_id,
+            self.call_context,
+            LAMBDA_ID_OFFSET,
+        );
+        let offset = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(offset) as i64);
+        self.builder
+            .ins()
+            .stack_store(self.addr_type, offset, self.call_context, OFFSET_OFFSET);
+    }
+
     pub fn put_store_flags_to_call_context(&mut self, flags: CallContextFlags) {
         const OFFSET: i32 = CallContext::FLAGS_OFFSET as i32;
         let flags = self
@@ -939,6 +1043,18 @@ impl<'a> Editor<'a> {
             .stack_store(self.addr_type, function.0, self.call_context, OFFSET);
     }
 
+    /// Returns `true` if the stack pointer is below the stack limit stored at `stack_limit_addr`.
+    pub fn put_stack_overflow(&mut self, stack_limit_addr: usize) -> BooleanIr {
+        use ir::condcodes::IntCC::UnsignedLessThan;
+        let addr = self
+            .builder
+            .ins()
+            .iconst(self.addr_type, stack_limit_addr as i64);
+        let limit = self.put_load_addr(addr, 0);
+        let sp = self.builder.ins().get_stack_pointer(self.addr_type);
+        BooleanIr(self.builder.ins().icmp(UnsignedLessThan, sp, limit))
+    }
+
     pub fn put_call_stack_too_deep(&mut self, max: u16) -> BooleanIr {
         use ir::condcodes::IntCC::UnsignedGreaterThan;
         const OFFSET: i32 = CallContext::DEPTH_OFFSET as i32;
@@ -955,6 +1071,128 @@ impl<'a> Editor<'a> {
         )
     }
 
+    pub fn put_is_heap_exhausted(&mut self, flag_addr: usize) -> BooleanIr {
+        use ir::condcodes::IntCC::NotEqual;
+        let addr = self.builder.ins().iconst(self.addr_type, flag_addr as i64);
+        let flag = self.put_load_i8(addr, 0);
+        BooleanIr(self.builder.ins().icmp_imm_u(NotEqual, flag, 0))
+    }
+
+    pub fn put_is_interrupted(&mut self, flags_addr: usize) -> BooleanIr {
+        use ir::condcodes::IntCC::NotEqual;
+        let addr = self.builder.ins().iconst(self.addr_type, flags_addr as i64);
+        let flags = self.put_load_i8(addr, 0);
+        BooleanIr(self.builder.ins().icmp_imm_u(NotEqual, flags, 0))
+    }
+
+    pub fn put_is_pause_point_active(&mut self, flag_addr: usize) -> BooleanIr {
+        use ir::condcodes::IntCC::NotEqual;
+        let addr = self.builder.ins().iconst(self.addr_type, flag_addr as i64);
+        let active = self.put_load_i8(addr, 0);
+        BooleanIr(self.builder.ins().icmp_imm_u(NotEqual, active, 0))
+    }
+
+    pub fn put_is_profiler_ticked(&mut self, tick_addr: usize) -> BooleanIr {
+        use ir::condcodes::IntCC::NotEqual;
+        let addr = self.builder.ins().iconst(self.addr_type, tick_addr as i64);
+        let tick = self.put_load_i8(addr, 0);
+        BooleanIr(self.builder.ins().icmp_imm_u(NotEqual, tick, 0))
+    }
+
+    /// Consumes the fuel by one and returns `true` if the fuel has been exhausted.
+    pub fn put_consume_fuel(&mut self, fuel_addr: usize) -> BooleanIr {
+        use ir::condcodes::IntCC::SignedLessThan;
+        let addr = self.builder.ins().iconst(self.addr_type, fuel_addr as i64);
+        let fuel = self.put_load_i64(addr, 0);
+        let fuel = self.builder.ins().iadd_imm(fuel, -1);
+        self.put_store(fuel, addr, 0);
+        BooleanIr(self.builder.ins().icmp_imm(SignedLessThan, fuel, 0))
+    }
+
+    /// Increments the `index`-th counter in an array of `u64` counters.
+    pub fn put_increment_counter(&mut self, counters_addr: usize, index: u32) {
+        let addr = self
+            .builder
+            .ins()
+            .iconst(self.addr_type, counters_addr as i64);
+        let offset = index as usize * size_of::<u64>();
+        let count = self.put_load_i64(addr, offset);
+        let count = self.builder.ins().iadd_imm(count, 1);
+        self.put_store(count, addr, offset);
+    }
+
+    /// Increments the call counter in the type feedback slots and returns `true` if the count
+    /// reaches `threshold`.
+    pub fn put_count_call(&mut self, slots_addr: usize, index: usize, threshold: u32) -> BooleanIr {
+        use ir::condcodes::IntCC::Equal;
+        let addr = self.builder.ins().iconst(self.addr_type, slots_addr as i64);
+        let offset = index * size_of::<u32>();
+        let count = self.put_load_i32(addr, offset);
+        let count = self.builder.ins().iadd_imm(count, 1);
+        self.put_store(count, addr, offset);
+        BooleanIr(
+            self.builder
+                .ins()
+                .icmp_imm(Equal, count, threshold as i32 as i64),
+        )
+    }
+
+    /// Records the kind of a value into the `index`-th slot in the type feedback slots.
+    ///
+    /// `TypeFeedback::NON_INT32` is also recorded if the value is a number which is not an int32
+    /// value.
+    pub fn put_record_kind(&mut self, slots_addr: usize, index: usize, any: AnyIr) {
+        use ir::condcodes::IntCC::Equal;
+        let kind = self.put_load_kind(any);
+        let is_number = self
+            .builder
+            .ins()
+            .icmp_imm(Equal, kind, Value::KIND_NUMBER as i64);
+        let kind = self.builder.ins().uextend(ir::types::I32, kind);
+        let one = self.builder.ins().iconst(ir::types::I32, 1);
+        let bit = self.builder.ins().ishl(one, kind);
+        // The payload is loaded regardless of the kind but used only for numbers.
+        let number = self.put_load_number(any);
+        let (_, is_int32) = self.put_number_to_exact_int32(number);
+        let is_non_int32 = self.builder.ins().band_not(is_number, is_int32.0);
+        let non_int32 = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, TypeFeedback::NON_INT32 as i32 as i64);
+        let zero = self.builder.ins().iconst(ir::types::I32, 0);
+        let non_int32 = self.builder.ins().select(is_non_int32, non_int32, zero);
+        let bit = self.builder.ins().bor(bit, non_int32);
+        let addr = self.builder.ins().iconst(self.addr_type, slots_addr as i64);
+        let offset = index * size_of::<u32>();
+        let kinds = self.put_load_i32(addr, offset);
+        let kinds = self.builder.ins().bor(kinds, bit);
+        self.put_store(kinds, addr, offset);
+    }
+
+    /// Records the lambda function of a closure called at a call site into the `index`-th slot in
+    /// the type feedback slots.
+    ///
+    /// The slot holds `0` until a call is recorded, `lambda_id + 1` while only a single lambda
+    /// function is called and `TypeFeedback::MEGAMORPHIC` once different lambda functions are
+    /// called.
+    pub fn put_record_call_target(&mut self, slots_addr: usize, index: usize, closure: ClosureIr) {
+        use ir::condcodes::IntCC::Equal;
+        let lambda_id = self.put_load_i32(closure.0, Closure::LAMBDA_ID_OFFSET);
+        let target = self.builder.ins().iadd_imm(lambda_id, 1);
+        let addr = self.builder.ins().iconst(self.addr_type, slots_addr as i64);
+        let offset = index * size_of::<u32>();
+        let recorded = self.put_load_i32(addr, offset);
+        let megamorphic = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, TypeFeedback::MEGAMORPHIC as i32 as i64);
+        let is_same = self.builder.ins().icmp(Equal, recorded, target);
+        let merged = self.builder.ins().select(is_same, target, megamorphic);
+        let is_empty = self.builder.ins().icmp_imm(Equal, recorded, 0);
+        let recorded = self.builder.ins().select(is_empty, target, merged);
+        self.put_store(recorded, addr, offset);
+    }
+
     // argv
 
     pub fn put_alloc_argv(&mut self, argc: u16) -> ArgvIr {
@@ -1122,7 +1360,7 @@ impl<'a> Editor<'a> {
         value: NumberIr,
     ) -> NumberIr {
         logger::debug!(event = "put_bitwise_not", ?value);
-        let int32 = self.put_runtime_to_int32(support, value);
+        let int32 = self.put_to_int32(support, value);
         let bnot = self.builder.ins().bnot(int32);
         self.put_i32_to_f64(bnot)
     }
@@ -1132,6 +1370,11 @@ impl<'a> Editor<'a> {
         BooleanIr(self.builder.ins().bxor_imm_u(value.0, 1))
     }
 
+    pub fn put_logical_and(&mut self, lhs: BooleanIr, rhs: BooleanIr) -> BooleanIr {
+        logger::debug!(event = "put_logical_and", ?lhs, ?rhs);
+        BooleanIr(self.builder.ins().band(lhs.0, rhs.0))
+    }
+
     pub fn put_logical_or(&mut self, lhs: BooleanIr, rhs: BooleanIr) -> BooleanIr {
         logger::debug!(event = "put_logical_or", ?lhs, ?rhs);
         BooleanIr(self.builder.ins().bor(lhs.0, rhs.0))
@@ -1197,8 +1440,8 @@ impl<'a> Editor<'a> {
         y: NumberIr,
     ) -> NumberIr {
         logger::debug!(event = "put_left_shift", ?x, ?y);
-        let lnum = self.put_runtime_to_int32(support, x);
-        let rnum = self.put_runtime_to_uint32(support, y);
+        let lnum = self.put_to_int32(support, x);
+        let rnum = self.put_to_uint32(support, y);
         let shift_count = self.builder.ins().urem_imm_u(rnum, 32);
         let shifted = self.builder.ins().ishl(lnum, shift_count);
         self.put_i32_to_f64(shifted)
@@ -1212,8 +1455,8 @@ impl<'a> Editor<'a> {
         y: NumberIr,
     ) -> NumberIr {
         logger::debug!(event = "put_signed_right_shift", ?x, ?y);
-        let lnum = self.put_runtime_to_int32(support, x);
-        let rnum = self.put_runtime_to_uint32(support, y);
+        let lnum = self.put_to_int32(support, x);
+        let rnum = self.put_to_uint32(support, y);
         let shift_count = self.builder.ins().urem_imm_s(rnum, 32);
         let shifted = self.builder.ins().sshr(lnum, shift_count);
         self.put_i32_to_f64(shifted)
@@ -1227,17 +1470,127 @@ impl<'a> Editor<'a> {
         y: NumberIr,
     ) -> NumberIr {
         logger::debug!(event = "put_unsigned_right_shift", ?x, ?y);
-        let lnum = self.put_runtime_to_uint32(support, x);
-        let rnum = self.put_runtime_to_uint32(support, y);
+        let lnum = self.put_to_uint32(support, x);
+        let rnum = self.put_to_uint32(support, y);
         let shift_count = self.builder.ins().urem_imm_u(rnum, 32);
         let shifted = self.builder.ins().ushr(lnum, shift_count);
-        self.put_i32_to_f64(shifted)
+        self.put_u32_to_f64(shifted)
+    }
+
+    /// Converts a number into an int32 value.
+    ///
+    /// The returned boolean value is `true` if the number is an int32 value.  Otherwise, the
+    /// returned int32 value is meaningless.  -0 is not an int32 value.
+    pub fn put_number_to_exact_int32(&mut self, value: NumberIr) -> (Int32Ir, BooleanIr) {
+        logger::debug!(event = "put_number_to_exact_int32", ?value);
+        use ir::condcodes::IntCC::Equal;
+        let int32 = self.builder.ins().fcvt_to_sint_sat(ir::types::I32, value.0);
+        let number = self.put_i32_to_f64(int32);
+        // Comparing the bit patterns excludes NaN and -0.
+        let flags = ir::MemFlagsData::new();
+        let expected = self.builder.ins().bitcast(ir::types::I64, flags, value.0);
+        let actual = self.builder.ins().bitcast(ir::types::I64, flags, number.0);
+        let exact = self.builder.ins().icmp(Equal, expected, actual);
+        (Int32Ir(int32), BooleanIr(exact))
+    }
+
+    pub fn put_int32_to_number(&mut self, value: Int32Ir) -> NumberIr {
+        logger::debug!(event = "put_int32_to_number", ?value);
+        self.put_i32_to_f64(value.0)
+    }
+
+    /// Adds int32 values.
+    ///
+    /// The returned boolean value is `true` if the addition overflows.
+    pub fn put_int32_add(&mut self, lhs: Int32Ir, rhs: Int32Ir) -> (Int32Ir, BooleanIr) {
+        logger::debug!(event = "put_int32_add", ?lhs, ?rhs);
+        let (result, overflow) = self.builder.ins().sadd_overflow(lhs.0, rhs.0);
+        (Int32Ir(result), BooleanIr(overflow))
+    }
+
+    /// Subtracts int32 values.
+    ///
+    /// The returned boolean value is `true` if the subtraction overflows.
+    pub fn put_int32_sub(&mut self, lhs: Int32Ir, rhs: Int32Ir) -> (Int32Ir, BooleanIr) {
+        logger::debug!(event = "put_int32_sub", ?lhs, ?rhs);
+        let (result, overflow) = self.builder.ins().ssub_overflow(lhs.0, rhs.0);
+        (Int32Ir(result), BooleanIr(overflow))
+    }
+
+    /// Multiplies int32 values.
+    ///
+    /// The returned boolean value is `true` if the multiplication overflows or the result has to
+    /// be -0 which is not an int32 value.
+    pub fn put_int32_mul(&mut self, lhs: Int32Ir, rhs: Int32Ir) -> (Int32Ir, BooleanIr) {
+        logger::debug!(event = "put_int32_mul", ?lhs, ?rhs);
+        use ir::condcodes::IntCC::Equal;
+        use ir::condcodes::IntCC::SignedLessThan;
+        let (result, overflow) = self.builder.ins().smul_overflow(lhs.0, rhs.0);
+        // The result is -0 if it's zero and one of the operands is negative.
+        let is_zero = self.builder.ins().icmp_imm(Equal, result, 0);
+        let sign = self.builder.ins().bor(lhs.0, rhs.0);
+        let is_negative = self.builder.ins().icmp_imm(SignedLessThan, sign, 0);
+        let is_negative_zero = self.builder.ins().band(is_zero, is_negative);
+        let overflow = self.builder.ins().bor(overflow, is_negative_zero);
+        (Int32Ir(result), BooleanIr(overflow))
     }
 
     fn put_i32_to_f64(&mut self, value: ir::Value) -> NumberIr {
         NumberIr(self.builder.ins().fcvt_from_sint(ir::types::F64, value))
     }
 
+    fn put_u32_to_f64(&mut self, value: ir::Value) -> NumberIr {
+        NumberIr(self.builder.ins().fcvt_from_uint(ir::types::F64, value))
+    }
+
+    fn put_to_int32(&mut self, support: &mut impl EditorSupport, value: NumberIr) -> ir::Value {
+        if self.optimized {
+            self.put_to_int32_with_fast_path(support, value, false)
+        } else {
+            self.put_runtime_to_int32(support, value)
+        }
+    }
+
+    fn put_to_uint32(&mut self, support: &mut impl EditorSupport, value: NumberIr) -> ir::Value {
+        if self.optimized {
+            self.put_to_int32_with_fast_path(support, value, true)
+        } else {
+            self.put_runtime_to_uint32(support, value)
+        }
+    }
+
+    // Converts a number into a 32-bit integer without calling the runtime function if the number
+    // is representable as an int32 value.  In this case, ToInt32() and ToUint32() return the same
+    // 32-bit pattern.
+    fn put_to_int32_with_fast_path(
+        &mut self,
+        support: &mut impl EditorSupport,
+        value: NumberIr,
+        unsigned: bool,
+    ) -> ir::Value {
+        logger::debug!(event = "put_to_int32_with_fast_path", ?value, unsigned);
+        use ir::condcodes::FloatCC::Equal;
+        let slow_block = self.create_block();
+        let merge_block = self.create_block();
+        self.builder.append_block_param(merge_block, ir::types::I32);
+
+        let int32 = self.builder.ins().fcvt_to_sint_sat(ir::types::I32, value.0);
+        let number = self.put_i32_to_f64(int32);
+        let exact = BooleanIr(self.builder.ins().fcmp(Equal, number.0, value.0));
+        self.put_branch(exact, merge_block, &[int32.into()], slow_block, &[]);
+
+        self.switch_to_block(slow_block);
+        let int32 = if unsigned {
+            self.put_runtime_to_uint32(support, value)
+        } else {
+            self.put_runtime_to_int32(support, value)
+        };
+        self.put_jump(merge_block, &[int32.into()]);
+
+        self.switch_to_block(merge_block);
+        self.get_block_param(merge_block, 0)
+    }
+
     // relational operators
 
     pub fn put_less_than(&mut self, lhs: NumberIr, rhs: NumberIr) -> BooleanIr {
@@ -1369,8 +1722,8 @@ impl<'a> Editor<'a> {
         y: NumberIr,
     ) -> NumberIr {
         logger::debug!(event = "put_bitwise_and", ?x, ?y);
-        let lnum = self.put_runtime_to_int32(support, x);
-        let rnum = self.put_runtime_to_int32(support, y);
+        let lnum = self.put_to_int32(support, x);
+        let rnum = self.put_to_int32(support, y);
         let result = self.builder.ins().band(lnum, rnum);
         self.put_i32_to_f64(result)
     }
@@ -1383,8 +1736,8 @@ impl<'a> Editor<'a> {
         y: NumberIr,
     ) -> NumberIr {
         logger::debug!(event = "put_bitwise_xor", ?x, ?y);
-        let lnum = self.put_runtime_to_int32(support, x);
-        let rnum = self.put_runtime_to_int32(support, y);
+        let lnum = self.put_to_int32(support, x);
+        let rnum = self.put_to_int32(support, y);
         let result = self.builder.ins().bxor(lnum, rnum);
         self.put_i32_to_f64(result)
     }
@@ -1397,8 +1750,8 @@ impl<'a> Editor<'a> {
         y: NumberIr,
     ) -> NumberIr {
         logger::debug!(event = "put_bitwise_or", ?x, ?y);
-        let lnum = self.put_runtime_to_int32(support, x);
-        let rnum = self.put_runtime_to_int32(support, y);
+        let lnum = self.put_to_int32(support, x);
+        let rnum = self.put_to_int32(support, y);
         let result = self.builder.ins().bor(lnum, rnum);
         self.put_i32_to_f64(result)
     }
@@ -1923,6 +2276,24 @@ impl<'a> Editor<'a> {
         StringIr(self.builder.inst_results(call)[0])
     }
 
+    pub fn put_runtime_get_template_object(
+        &mut self,
+        support: &mut impl EditorSupport,
+        strings: &TemplateStrings,
+    ) -> ObjectIr {
+        logger::debug!(event = "put_runtime_get_template_object", ?strings);
+        let func = self
+            .runtime_func_cache
+            .import_runtime_get_template_object(support, self.builder.func);
+        let strings = self
+            .builder
+            .ins()
+            .iconst(self.addr_type, strings as *const TemplateStrings as i64);
+        let args = [self.runtime(), strings];
+        let call = self.builder.ins().call(func, &args);
+        ObjectIr(self.builder.inst_results(call)[0])
+    }
+
     pub fn put_runtime_create_capture(
         &mut self,
         support: &mut impl EditorSupport,
@@ -1964,6 +2335,21 @@ impl<'a> Editor<'a> {
         ClosureIr(self.builder.inst_results(call)[0])
     }
 
+    fn put_runtime_call_in_realm(
+        &mut self,
+        support: &mut impl EditorSupport,
+        context: ir::Value,
+        retv: AnyIr,
+    ) -> StatusIr {
+        logger::debug!(event = "put_runtime_call_in_realm", ?context, ?retv);
+        let func = self
+            .runtime_func_cache
+            .import_runtime_call_in_realm(support, self.builder.func);
+        let args = [self.runtime(), context, retv.0];
+        let call = self.builder.ins().call(func, &args);
+        StatusIr(self.builder.inst_results(call)[0])
+    }
+
     pub fn put_runtime_create_coroutine(
         &mut self,
         support: &mut impl EditorSupport,
@@ -2067,26 +2453,123 @@ impl<'a> Editor<'a> {
     pub fn put_runtime_create_reference_error(
         &mut self,
         support: &mut impl EditorSupport,
+        lambda_id: LambdaId,
+        offset: SourceOffset,
     ) -> ObjectIr {
-        logger::debug!(event = "put_runtime_create_reference_error");
+        logger::debug!(
+            event = "put_runtime_create_reference_error",
+            ?lambda_id,
+            ?offset,
+        );
         let func = self
             .runtime_func_cache
             .import_runtime_create_reference_error(support, self.builder.func);
-        let args = [self.runtime()];
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        let offset = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(offset) as i64);
+        let args = [self.runtime(), self.context(), lambda_id, offset];
         let call = self.builder.ins().call(func, &args);
         ObjectIr(self.builder.inst_results(call)[0])
     }
 
-    pub fn put_runtime_create_type_error(&mut self, support: &mut impl EditorSupport) -> ObjectIr {
-        logger::debug!(event = "put_runtime_create_type_error");
+    pub fn put_runtime_create_type_error(
+        &mut self,
+        support: &mut impl EditorSupport,
+        lambda_id: LambdaId,
+        offset: SourceOffset,
+    ) -> ObjectIr {
+        logger::debug!(event = "put_runtime_create_type_error", ?lambda_id, ?offset);
         let func = self
             .runtime_func_cache
             .import_runtime_create_type_error(support, self.builder.func);
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        let offset = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(offset) as i64);
+        let args = [self.runtime(), self.context(), lambda_id, offset];
+        let call = self.builder.ins().call(func, &args);
+        ObjectIr(self.builder.inst_results(call)[0])
+    }
+
+    pub fn put_runtime_handle_interrupt(&mut self, support: &mut impl EditorSupport) -> ObjectIr {
+        logger::debug!(event = "put_runtime_handle_interrupt");
+        let func = self
+            .runtime_func_cache
+            .import_runtime_handle_interrupt(support, self.builder.func);
         let args = [self.runtime()];
         let call = self.builder.ins().call(func, &args);
         ObjectIr(self.builder.inst_results(call)[0])
     }
 
+    pub fn put_runtime_handle_heap_exhausted(
+        &mut self,
+        support: &mut impl EditorSupport,
+        lambda_id: LambdaId,
+        offset: SourceOffset,
+    ) -> ObjectIr {
+        logger::debug!(
+            event = "put_runtime_handle_heap_exhausted",
+            ?lambda_id,
+            ?offset,
+        );
+        let func = self
+            .runtime_func_cache
+            .import_runtime_handle_heap_exhausted(support, self.builder.func);
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        let offset = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(offset) as i64);
+        let args = [self.runtime(), self.context(), lambda_id, offset];
+        let call = self.builder.ins().call(func, &args);
+        ObjectIr(self.builder.inst_results(call)[0])
+    }
+
+    pub fn put_runtime_create_range_error(
+        &mut self,
+        support: &mut impl EditorSupport,
+        lambda_id: LambdaId,
+        offset: SourceOffset,
+        message: Handle<String>,
+    ) -> ObjectIr {
+        logger::debug!(
+            event = "put_runtime_create_range_error",
+            ?lambda_id,
+            ?offset,
+            ?message,
+        );
+        let func = self
+            .runtime_func_cache
+            .import_runtime_create_range_error(support, self.builder.func);
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        let offset = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(offset) as i64);
+        let message = self
+            .builder
+            .ins()
+            .iconst(self.addr_type, message.as_addr() as i64);
+        let args = [self.runtime(), self.context(), lambda_id, offset, message];
+        let call = self.builder.ins().call(func, &args);
+        ObjectIr(self.builder.inst_results(call)[0])
+    }
+
     pub fn put_runtime_create_internal_error(
         &mut self,
         support: &mut impl EditorSupport,
@@ -2132,7 +2615,14 @@ impl<'a> Editor<'a> {
             .import_runtime_get_value_by_symbol(support, self.builder.func);
         let key = self.builder.ins().iconst(ir::types::I32, key.id() as i64);
         let strict = self.put_boolean(strict);
-        let args = [self.runtime(), object.0, key, strict.0, retv.0];
+        let args = [
+            self.runtime(),
+            self.context(),
+            object.0,
+            key,
+            strict.0,
+            retv.0,
+        ];
         let call = self.builder.ins().call(func, &args);
         StatusIr(self.builder.inst_results(call)[0])
     }
@@ -2164,7 +2654,14 @@ impl<'a> Editor<'a> {
             .import_runtime_get_value_by_number(support, self.builder.func);
         let key = self.put_number(key);
         let strict = self.put_boolean(strict);
-        let args = [self.runtime(), object.0, key.0, strict.0, retv.0];
+        let args = [
+            self.runtime(),
+            self.context(),
+            object.0,
+            key.0,
+            strict.0,
+            retv.0,
+        ];
         let call = self.builder.ins().call(func, &args);
         StatusIr(self.builder.inst_results(call)[0])
     }
@@ -2200,7 +2697,14 @@ impl<'a> Editor<'a> {
             .runtime_func_cache
             .import_runtime_get_value_by_value(support, self.builder.func);
         let strict = self.put_boolean(strict);
-        let args = [self.runtime(), object.0, key.0, strict.0, retv.0];
+        let args = [
+            self.runtime(),
+            self.context(),
+            object.0,
+            key.0,
+            strict.0,
+            retv.0,
+        ];
         let call = self.builder.ins().call(func, &args);
         StatusIr(self.builder.inst_results(call)[0])
     }
@@ -2211,6 +2715,7 @@ impl<'a> Editor<'a> {
         object: ObjectIr,
         key: Symbol,
         value: AnyIr,
+        strict: bool,
         retv: AnyIr,
     ) -> StatusIr {
         logger::debug!(
@@ -2218,6 +2723,7 @@ impl<'a> Editor<'a> {
             ?object,
             ?key,
             ?value,
+            strict,
             ?retv,
         );
         if self.runtime_assert_enabled {
@@ -2236,7 +2742,16 @@ impl<'a> Editor<'a> {
             .runtime_func_cache
             .import_runtime_set_value_by_symbol(support, self.builder.func);
         let key = self.builder.ins().iconst(ir::types::I32, key.id() as i64);
-        let args = [self.runtime(), object.0, key, value.0, retv.0];
+        let strict = self.put_boolean(strict);
+        let args = [
+            self.runtime(),
+            self.context(),
+            object.0,
+            key,
+            value.0,
+            strict.0,
+            retv.0,
+        ];
         let call = self.builder.ins().call(func, &args);
         StatusIr(self.builder.inst_results(call)[0])
     }
@@ -2247,6 +2762,7 @@ impl<'a> Editor<'a> {
         object: ObjectIr,
         key: f64,
         value: AnyIr,
+        strict: bool,
         retv: AnyIr,
     ) -> StatusIr {
         logger::debug!(
@@ -2254,6 +2770,7 @@ impl<'a> Editor<'a> {
             ?object,
             key,
             ?value,
+            strict,
             ?retv,
         );
         if self.runtime_assert_enabled {
@@ -2272,7 +2789,16 @@ impl<'a> Editor<'a> {
             .runtime_func_cache
             .import_runtime_set_value_by_number(support, self.builder.func);
         let key = self.builder.ins().f64const(key);
-        let args = [self.runtime(), object.0, key, value.0, retv.0];
+        let strict = self.put_boolean(strict);
+        let args = [
+            self.runtime(),
+            self.context(),
+            object.0,
+            key,
+            value.0,
+            strict.0,
+            retv.0,
+        ];
         let call = self.builder.ins().call(func, &args);
         StatusIr(self.builder.inst_results(call)[0])
     }
@@ -2283,6 +2809,7 @@ impl<'a> Editor<'a> {
         object: ObjectIr,
         key: AnyIr,
         value: AnyIr,
+        strict: bool,
         retv: AnyIr,
     ) -> StatusIr {
         logger::debug!(
@@ -2290,6 +2817,7 @@ impl<'a> Editor<'a> {
             ?object,
             ?key,
             ?value,
+            strict,
             ?retv,
         );
         if self.runtime_assert_enabled {
@@ -2312,7 +2840,55 @@ impl<'a> Editor<'a> {
         let func = self
             .runtime_func_cache
             .import_runtime_set_value_by_value(support, self.builder.func);
-        let args = [self.runtime(), object.0, key.0, value.0, retv.0];
+        let strict = self.put_boolean(strict);
+        let args = [
+            self.runtime(),
+            self.context(),
+            object.0,
+            key.0,
+            value.0,
+            strict.0,
+            retv.0,
+        ];
+        let call = self.builder.ins().call(func, &args);
+        StatusIr(self.builder.inst_results(call)[0])
+    }
+
+    pub fn put_runtime_set_global_binding_value(
+        &mut self,
+        support: &mut impl EditorSupport,
+        key: Symbol,
+        value: AnyIr,
+        strict: bool,
+        retv: AnyIr,
+    ) -> StatusIr {
+        logger::debug!(
+            event = "put_runtime_set_global_binding_value",
+            ?key,
+            ?value,
+            strict,
+            ?retv,
+        );
+        if self.runtime_assert_enabled {
+            self.put_assert_non_null(
+                support,
+                value.0,
+                c"value passed to runtime_set_global_binding_value() must be non-null",
+            );
+        }
+        let func = self
+            .runtime_func_cache
+            .import_runtime_set_global_binding_value(support, self.builder.func);
+        let key = self.builder.ins().iconst(ir::types::I32, key.id() as i64);
+        let strict = self.put_boolean(strict);
+        let args = [
+            self.runtime(),
+            self.context(),
+            key,
+            value.0,
+            strict.0,
+            retv.0,
+        ];
         let call = self.builder.ins().call(func, &args);
         StatusIr(self.builder.inst_results(call)[0])
     }
@@ -2504,7 +3080,7 @@ impl<'a> Editor<'a> {
         let func = self
             .runtime_func_cache
             .import_runtime_copy_data_properties(support, self.builder.func);
-        let args = [self.runtime(), target.0, source.0, retv.0];
+        let args = [self.runtime(), self.context(), target.0, source.0, retv.0];
         let call = self.builder.ins().call(func, &args);
         StatusIr(self.builder.inst_results(call)[0])
     }
@@ -2547,6 +3123,97 @@ impl<'a> Editor<'a> {
         StatusIr(self.builder.inst_results(call)[0])
     }
 
+    pub fn put_runtime_create_arguments(
+        &mut self,
+        support: &mut impl EditorSupport,
+        mapped: bool,
+    ) -> ObjectIr {
+        logger::debug!(event = "put_runtime_create_arguments", mapped);
+        let func = self
+            .runtime_func_cache
+            .import_runtime_create_arguments(support, self.builder.func);
+        let mapped = self.put_boolean(mapped);
+        let args = [self.runtime(), self.context(), mapped.0];
+        let call = self.builder.ins().call(func, &args);
+        ObjectIr(self.builder.inst_results(call)[0])
+    }
+
+    pub fn put_runtime_map_argument(
+        &mut self,
+        support: &mut impl EditorSupport,
+        arguments: ObjectIr,
+        index: u16,
+        capture: CaptureIr,
+    ) {
+        logger::debug!(
+            event = "put_runtime_map_argument",
+            ?arguments,
+            index,
+            ?capture
+        );
+        let func = self
+            .runtime_func_cache
+            .import_runtime_map_argument(support, self.builder.func);
+        let index = self.builder.ins().iconst(ir::types::I16, index as i64);
+        let args = [self.runtime(), arguments.0, index, capture.0];
+        self.builder.ins().call(func, &args);
+    }
+
+    pub fn put_runtime_create_rest_parameter(
+        &mut self,
+        support: &mut impl EditorSupport,
+        start: u16,
+    ) -> ObjectIr {
+        logger::debug!(event = "put_runtime_create_rest_parameter", start);
+        let func = self
+            .runtime_func_cache
+            .import_runtime_create_rest_parameter(support, self.builder.func);
+        let start = self.builder.ins().iconst(ir::types::I16, start as i64);
+        let args = [self.runtime(), self.context(), start];
+        let call = self.builder.ins().call(func, &args);
+        ObjectIr(self.builder.inst_results(call)[0])
+    }
+
+    pub fn put_runtime_direct_eval(
+        &mut self,
+        support: &mut impl EditorSupport,
+        scope_ref: ScopeRef,
+        captures: &[CaptureIr],
+        strict: bool,
+        retv: AnyIr,
+    ) -> StatusIr {
+        logger::debug!(
+            event = "put_runtime_direct_eval",
+            ?scope_ref,
+            ?captures,
+            strict,
+            ?retv
+        );
+        let func = self
+            .runtime_func_cache
+            .import_runtime_direct_eval(support, self.builder.func);
+        let context = self
+            .builder
+            .ins()
+            .stack_addr(self.addr_type, self.call_context, 0);
+        let scope_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I16, scope_ref.id() as i64);
+        let captures = self.put_addr_array(captures.iter().map(|capture| capture.0));
+        let strict = self.put_boolean(strict);
+        let args = [
+            self.runtime(),
+            context,
+            scope_id,
+            captures,
+            strict.0,
+            retv.0,
+        ];
+        let call = self.builder.ins().call(func, &args);
+        StatusIr(self.builder.inst_results(call)[0])
+    }
+
     pub fn put_runtime_panic(&mut self, support: &mut impl EditorSupport, msg: &'static CStr) {
         logger::debug!(event = "put_runtime_panic", ?msg);
         let func = self
@@ -2668,15 +3335,166 @@ impl<'a> Editor<'a> {
         self.builder.ins().call(func, &args);
     }
 
-    pub fn put_runtime_launch_debugger(&mut self, support: &mut impl EditorSupport) {
-        logger::debug!(event = "put_runtime_launch_debugger");
+    pub fn put_runtime_launch_debugger(
+        &mut self,
+        support: &mut impl EditorSupport,
+        scope_ref: ScopeRef,
+        variables: &[AnyIr],
+    ) {
+        logger::debug!(
+            event = "put_runtime_launch_debugger",
+            ?scope_ref,
+            ?variables
+        );
         let func = self
             .runtime_func_cache
             .import_runtime_launch_debugger(support, self.builder.func);
-        let args = [self.runtime()];
+        let context = self
+            .builder
+            .ins()
+            .stack_addr(self.addr_type, self.call_context, 0);
+        let scope_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I16, scope_ref.id() as i64);
+        let variables = self.put_debug_variables(variables);
+        let args = [self.runtime(), context, scope_id, variables];
+        self.builder.ins().call(func, &args);
+    }
+
+    pub fn put_runtime_handle_pause_point(
+        &mut self,
+        support: &mut impl EditorSupport,
+        scope_ref: ScopeRef,
+        variables: &[AnyIr],
+    ) {
+        logger::debug!(
+            event = "put_runtime_handle_pause_point",
+            ?scope_ref,
+            ?variables
+        );
+        let func = self
+            .runtime_func_cache
+            .import_runtime_handle_pause_point(support, self.builder.func);
+        let context = self
+            .builder
+            .ins()
+            .stack_addr(self.addr_type, self.call_context, 0);
+        let scope_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I16, scope_ref.id() as i64);
+        let variables = self.put_debug_variables(variables);
+        let args = [self.runtime(), context, scope_id, variables];
         self.builder.ins().call(func, &args);
     }
 
+    pub fn put_runtime_pause_on_exception(
+        &mut self,
+        support: &mut impl EditorSupport,
+        scope_ref: ScopeRef,
+        variables: &[AnyIr],
+        exception: AnyIr,
+    ) {
+        logger::debug!(
+            event = "put_runtime_pause_on_exception",
+            ?scope_ref,
+            ?variables,
+            ?exception
+        );
+        let func = self
+            .runtime_func_cache
+            .import_runtime_pause_on_exception(support, self.builder.func);
+        let context = self
+            .builder
+            .ins()
+            .stack_addr(self.addr_type, self.call_context, 0);
+        let scope_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I16, scope_ref.id() as i64);
+        let variables = self.put_debug_variables(variables);
+        let args = [self.runtime(), context, scope_id, variables, exception.0];
+        self.builder.ins().call(func, &args);
+    }
+
+    pub fn put_runtime_take_profile_sample(
+        &mut self,
+        support: &mut impl EditorSupport,
+        lambda_id: LambdaId,
+    ) {
+        logger::debug!(event = "put_runtime_take_profile_sample", ?lambda_id);
+        let func = self
+            .runtime_func_cache
+            .import_runtime_take_profile_sample(support, self.builder.func);
+        let context = self
+            .builder
+            .ins()
+            .stack_addr(self.addr_type, self.call_context, 0);
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        let args = [self.runtime(), context, lambda_id];
+        self.builder.ins().call(func, &args);
+    }
+
+    pub fn put_runtime_optimize_lambda(
+        &mut self,
+        support: &mut impl EditorSupport,
+        lambda_id: LambdaId,
+    ) {
+        logger::debug!(event = "put_runtime_optimize_lambda", ?lambda_id);
+        let func = self
+            .runtime_func_cache
+            .import_runtime_optimize_lambda(support, self.builder.func);
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        let args = [self.runtime(), self.context(), lambda_id];
+        self.builder.ins().call(func, &args);
+    }
+
+    pub fn put_runtime_deoptimize_lambda(
+        &mut self,
+        support: &mut impl EditorSupport,
+        lambda_id: LambdaId,
+    ) {
+        logger::debug!(event = "put_runtime_deoptimize_lambda", ?lambda_id);
+        let func = self
+            .runtime_func_cache
+            .import_runtime_deoptimize_lambda(support, self.builder.func);
+        let lambda_id = self
+            .builder
+            .ins()
+            .iconst(ir::types::I32, u32::from(lambda_id) as i64);
+        let args = [self.runtime(), self.context(), lambda_id];
+        self.builder.ins().call(func, &args);
+    }
+
+    // Stores the addresses of the variables into an array allocated in the stack frame.
+    fn put_debug_variables(&mut self, variables: &[AnyIr]) -> ir::Value {
+        self.put_addr_array(variables.iter().map(|variable| variable.0))
+    }
+
+    // Stores addresses into an array allocated on the stack and returns the address of the array.
+    fn put_addr_array(&mut self, addrs: impl ExactSizeIterator<Item = ir::Value>) -> ir::Value {
+        let addr_size = self.addr_type.bytes();
+        let slot = self.builder.create_sized_stack_slot(ir::StackSlotData::new(
+            ir::StackSlotKind::ExplicitSlot,
+            addr_size * (addrs.len().max(1) as u32),
+            addr_size.ilog2() as u8,
+        ));
+        for (i, addr) in addrs.enumerate() {
+            let offset = (addr_size as i32) * (i as i32);
+            self.builder
+                .ins()
+                .stack_store(self.addr_type, addr, slot, offset);
+        }
+        self.builder.ins().stack_addr(self.addr_type, slot, 0)
+    }
+
     // scope cleanup checker
 
     pub fn put_init_scope_cleanup_checker(&mut self) {
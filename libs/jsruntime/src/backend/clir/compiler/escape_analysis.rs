@@ -0,0 +1,266 @@
+use rustc_hash::FxHashMap;
+
+use jsparser::Symbol;
+
+use crate::semantics::CompileCommand;
+use crate::semantics::Function;
+use crate::semantics::Locator;
+use crate::semantics::ScopeRef;
+use crate::semantics::ScopeTree;
+use crate::semantics::Variable;
+
+use super::LambdaId;
+
+/// Closures replaced with scalars in a function.
+///
+/// A closure is replaced with scalars if it's stored in a `const` variable that is not captured
+/// and the variable is used only as the callee of calls.  Such a closure never escapes from the
+/// function and it's inlined at every call site.  So, neither the closure nor its function object
+/// is created.  Variables captured only by such closures are not captured at all.
+#[derive(Default)]
+pub struct ScalarReplacement {
+    /// The lambda function of a replaced closure and the scope where the closure is created,
+    /// keyed by the index of the local variable holding the closure.
+    closures: FxHashMap<u16, (LambdaId, ScopeRef)>,
+
+    /// Variables captured only by the replaced closures.
+    captured_variables: Vec<Locator>,
+}
+
+impl ScalarReplacement {
+    /// Analyzes the commands of a function.
+    ///
+    /// `is_inlinable` is called for each closure that never escapes from the function.
+    pub fn analyze<F>(func: &Function, scope_tree: &ScopeTree, is_inlinable: F) -> Self
+    where
+        F: Fn(LambdaId) -> bool,
+    {
+        let commands = &func.commands[..];
+
+        let mut scopes = vec![];
+        let mut lambda_id = None;
+        let mut candidates: FxHashMap<u16, Candidate> = Default::default();
+        let mut capturers: FxHashMap<Locator, Vec<LambdaId>> = Default::default();
+        let mut references = vec![];
+        for (i, command) in commands.iter().enumerate() {
+            match command {
+                CompileCommand::PushScope(scope_ref) => scopes.push(*scope_ref),
+                CompileCommand::PopScope(_) => {
+                    scopes.pop();
+                }
+                // All variables visible from eval code are captured.
+                CompileCommand::DirectEval(..) => return Default::default(),
+                CompileCommand::Lambda(id) => lambda_id = Some(*id),
+                CompileCommand::Closure(_, closure_scope_ref) => {
+                    let (Some(&scope_ref), Some(lambda_id)) = (scopes.last(), lambda_id.take())
+                    else {
+                        return Default::default();
+                    };
+                    for variable in scope_tree
+                        .scope(*closure_scope_ref)
+                        .variables
+                        .iter()
+                        .filter(|variable| variable.is_capture())
+                    {
+                        match find_variable(scope_tree, scope_ref, variable.symbol)
+                            .map(Variable::locator)
+                        {
+                            // The elements of the mapped arguments object are bound to the
+                            // captures of the formal parameters.
+                            Some(locator @ Locator::Param(_)) if !func.has_mapped_arguments() => {
+                                capturers.entry(locator).or_default().push(lambda_id);
+                            }
+                            Some(locator @ Locator::Local(_)) => {
+                                capturers.entry(locator).or_default().push(lambda_id);
+                            }
+                            _ => (),
+                        }
+                    }
+                    // const <symbol> = <closure>;
+                    let [
+                        CompileCommand::Function(_),
+                        CompileCommand::VariableReference(symbol),
+                        CompileCommand::ImmutableVariable,
+                        ..,
+                    ] = &commands[i + 1..]
+                    else {
+                        continue;
+                    };
+                    match find_variable(scope_tree, scope_ref, *symbol) {
+                        Some(variable) if variable.is_local() && !variable.is_captured() => {
+                            candidates.insert(
+                                variable.index,
+                                Candidate {
+                                    lambda_id,
+                                    scope_ref,
+                                    declaration: i + 2,
+                                    escaped: false,
+                                },
+                            );
+                        }
+                        _ => (),
+                    }
+                }
+                CompileCommand::VariableReference(symbol) => {
+                    let Some(&scope_ref) = scopes.last() else {
+                        continue;
+                    };
+                    if let Some(Locator::Local(index)) =
+                        find_variable(scope_tree, scope_ref, *symbol).map(Variable::locator)
+                    {
+                        references.push((index, i));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for (index, position) in references {
+            let Some(candidate) = candidates.get_mut(&index) else {
+                continue;
+            };
+            if position == candidate.declaration {
+                continue;
+            }
+            if position < candidate.declaration || !is_callee(&commands[position + 1..]) {
+                candidate.escaped = true;
+            }
+        }
+
+        let closures: FxHashMap<u16, (LambdaId, ScopeRef)> = candidates
+            .into_iter()
+            .filter(|(_, candidate)| !candidate.escaped && is_inlinable(candidate.lambda_id))
+            .map(|(index, candidate)| (index, (candidate.lambda_id, candidate.scope_ref)))
+            .collect();
+        let captured_variables = capturers
+            .into_iter()
+            .filter(|(_, lambda_ids)| {
+                lambda_ids
+                    .iter()
+                    .all(|lambda_id| closures.values().any(|(id, _)| id == lambda_id))
+            })
+            .map(|(locator, _)| locator)
+            .collect();
+
+        Self {
+            closures,
+            captured_variables,
+        }
+    }
+
+    /// Returns `true` if the closure of the lambda function is replaced with scalars.
+    pub fn is_replaced(&self, lambda_id: LambdaId) -> bool {
+        self.closures.values().any(|(id, _)| *id == lambda_id)
+    }
+
+    /// Returns the lambda function of the replaced closure held in a variable and the scope
+    /// where the closure is created.
+    pub fn closure(&self, locator: Locator) -> Option<(LambdaId, ScopeRef)> {
+        match locator {
+            Locator::Local(index) => self.closures.get(&index).copied(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if a variable is captured only by the replaced closures.
+    pub fn is_capture_elided(&self, locator: Locator) -> bool {
+        self.captured_variables.contains(&locator)
+    }
+}
+
+struct Candidate {
+    lambda_id: LambdaId,
+    scope_ref: ScopeRef,
+    /// The position of the `VariableReference` command in the declaration.
+    declaration: usize,
+    escaped: bool,
+}
+
+// Unlike `ScopeTree::find_variable()`, variables in outer functions are not found.
+fn find_variable(scope_tree: &ScopeTree, scope_ref: ScopeRef, symbol: Symbol) -> Option<&Variable> {
+    scope_tree
+        .iter_function_scopes(scope_ref)
+        .find_map(|(_, scope)| {
+            scope
+                .variables
+                .iter()
+                .find(|variable| variable.symbol == symbol)
+        })
+}
+
+// Returns `true` if the value pushed by the command just before `commands` is used only as the
+// callee of a call.
+//
+// The operand stack is simulated until the call consuming the value.  Commands other than simple
+// expressions are conservatively treated as escaping the value.
+fn is_callee(commands: &[CompileCommand]) -> bool {
+    // The value is at the bottom.
+    let mut depth = 1;
+    for command in commands {
+        if let CompileCommand::Call(argc, _) = command {
+            if depth == *argc as usize + 1 {
+                return true;
+            }
+        }
+        let Some((pops, pushes)) = stack_effect(command) else {
+            return false;
+        };
+        if pops >= depth {
+            return false;
+        }
+        depth = depth - pops + pushes;
+    }
+    false
+}
+
+// Returns the numbers of operands popped from and pushed onto the operand stack by a command.
+fn stack_effect(command: &CompileCommand) -> Option<(usize, usize)> {
+    match command {
+        CompileCommand::Nop | CompileCommand::Coverage(_) => Some((0, 0)),
+        CompileCommand::Undefined
+        | CompileCommand::Null
+        | CompileCommand::Boolean(_)
+        | CompileCommand::Number(_)
+        | CompileCommand::String(_)
+        | CompileCommand::This
+        | CompileCommand::VariableReference(_) => Some((0, 1)),
+        CompileCommand::PropertyReference(_)
+        | CompileCommand::ToPropertyKey
+        | CompileCommand::Dereference
+        | CompileCommand::ToNumeric
+        | CompileCommand::ToString
+        | CompileCommand::Void
+        | CompileCommand::Typeof
+        | CompileCommand::UnaryPlus
+        | CompileCommand::UnaryMinus
+        | CompileCommand::BitwiseNot
+        | CompileCommand::LogicalNot => Some((1, 1)),
+        CompileCommand::Exponentiation
+        | CompileCommand::Multiplication
+        | CompileCommand::Division
+        | CompileCommand::Remainder
+        | CompileCommand::Addition
+        | CompileCommand::Subtraction
+        | CompileCommand::LeftShift
+        | CompileCommand::SignedRightShift
+        | CompileCommand::UnsignedRightShift
+        | CompileCommand::LessThan
+        | CompileCommand::GreaterThan
+        | CompileCommand::LessThanOrEqual
+        | CompileCommand::GreaterThanOrEqual
+        | CompileCommand::Instanceof
+        | CompileCommand::In
+        | CompileCommand::Equality
+        | CompileCommand::Inequality
+        | CompileCommand::StrictEquality
+        | CompileCommand::StrictInequality
+        | CompileCommand::BitwiseAnd
+        | CompileCommand::BitwiseXor
+        | CompileCommand::BitwiseOr => Some((2, 1)),
+        CompileCommand::Swap => Some((2, 2)),
+        CompileCommand::Duplicate(offset) => Some((*offset as usize + 1, *offset as usize + 2)),
+        CompileCommand::Discard => Some((1, 0)),
+        CompileCommand::Call(argc, _) => Some((*argc as usize + 1, 1)),
+        _ => None,
+    }
+}
@@ -1,5 +1,6 @@
 mod control_flow;
 mod editor;
+mod escape_analysis;
 
 use std::ops::Deref;
 use std::ops::DerefMut;
@@ -19,8 +20,11 @@ use jsparser::SymbolRegistry;
 use jsparser::syntax::LoopFlags;
 
 use crate::ProgramId;
+use crate::RealmId;
 use crate::Runtime;
 use crate::RuntimePref;
+use crate::backend::bridge::to_int32;
+use crate::backend::bridge::to_uint32;
 use crate::lambda::LambdaInfo;
 use crate::lambda::LambdaKind;
 use crate::lambda::LambdaRegistry;
@@ -31,8 +35,11 @@ use crate::semantics::Locator;
 use crate::semantics::Program;
 use crate::semantics::ScopeRef;
 use crate::semantics::ScopeTree;
+use crate::semantics::SourceOffset;
+use crate::semantics::TemplateStrings;
 use crate::semantics::ThisBinding;
 use crate::semantics::VariableRef;
+use crate::type_feedback::TypeFeedback;
 use crate::types::CallContextFlags;
 use crate::types::Object;
 use crate::types::String;
@@ -46,6 +53,7 @@ use super::RuntimeFunctionCache;
 
 use control_flow::ControlFlowStack;
 use editor::Editor;
+use escape_analysis::ScalarReplacement;
 
 pub struct Session<'r, X> {
     pref: &'r RuntimePref,
@@ -57,6 +65,16 @@ pub struct Session<'r, X> {
     object_prototype: HandleMut<Object>,
     function_prototype: HandleMut<Object>,
     promise_prototype: HandleMut<Object>,
+    eval_function: HandleMut<Object>,
+    heap_exhausted_flag_addr: usize,
+    interrupt_flags_addr: usize,
+    fuel_addr: usize,
+    profiler_tick_addr: usize,
+    pause_point_active_addr: usize,
+    coverage_counters_addr: usize,
+    type_feedback_addr: usize,
+    type_feedback: Option<&'r [u32]>,
+    stack_limit_addr: usize,
 }
 
 trait CompilerSupport {
@@ -64,6 +82,10 @@ trait CompilerSupport {
     fn max_call_stack_depth(&self) -> u16;
     fn is_scope_cleanup_checker_enabled(&self) -> bool;
     fn is_runtime_assert_enabled(&self) -> bool;
+    fn is_fuel_metering_enabled(&self) -> bool;
+    fn is_debugger_enabled(&self) -> bool;
+    fn is_profiler_enabled(&self) -> bool;
+    fn is_coverage_enabled(&self) -> bool;
 
     // SymbolRegistry
     fn get_symbol_name(&self, symbol: Symbol) -> &[u16];
@@ -75,6 +97,7 @@ trait CompilerSupport {
 
     // Program
     fn get_function(&self, lambda_id: LambdaId) -> &Function;
+    fn realm(&self) -> RealmId;
 
     // CodeRegistry
     fn target_config(&self) -> isa::TargetFrontendConfig;
@@ -86,6 +109,34 @@ trait CompilerSupport {
     fn object_prototype(&self) -> HandleMut<Object>;
     fn function_prototype(&self) -> HandleMut<Object>;
     fn promise_prototype(&self) -> HandleMut<Object>;
+    fn eval_function(&self) -> HandleMut<Object>;
+
+    // Heap
+    fn heap_exhausted_flag_addr(&self) -> usize;
+
+    // Interrupter
+    fn interrupt_flags_addr(&self) -> usize;
+    fn fuel_addr(&self) -> usize;
+
+    // Profiler
+    fn profiler_tick_addr(&self) -> usize;
+
+    // Debugger
+    fn pause_point_active_addr(&self) -> usize;
+
+    // Coverage
+    fn coverage_counters_addr(&self) -> usize;
+
+    // TypeFeedback
+    fn optimization_threshold(&self) -> u32;
+    fn type_feedback_addr(&self) -> usize;
+    fn is_optimizing(&self) -> bool;
+    fn is_inlining_enabled(&self) -> bool;
+    fn observed_kinds(&self, index: usize) -> u32;
+    fn observed_call_target(&self, index: usize) -> Option<LambdaId>;
+
+    // Native stack
+    fn stack_limit_addr(&self) -> usize;
 }
 
 impl<X> CompilerSupport for Session<'_, X> {
@@ -101,6 +152,22 @@ impl<X> CompilerSupport for Session<'_, X> {
         self.pref.enable_runtime_assert
     }
 
+    fn is_fuel_metering_enabled(&self) -> bool {
+        self.pref.enable_fuel_metering
+    }
+
+    fn is_debugger_enabled(&self) -> bool {
+        self.pref.enable_debugger
+    }
+
+    fn is_profiler_enabled(&self) -> bool {
+        self.pref.enable_profiler
+    }
+
+    fn is_coverage_enabled(&self) -> bool {
+        self.pref.enable_coverage
+    }
+
     fn get_symbol_name(&self, symbol: Symbol) -> &[u16] {
         self.symbol_registry.resolve(symbol).unwrap()
     }
@@ -122,6 +189,10 @@ impl<X> CompilerSupport for Session<'_, X> {
         &self.program.functions[index]
     }
 
+    fn realm(&self) -> RealmId {
+        self.program.realm
+    }
+
     fn target_config(&self) -> isa::TargetFrontendConfig {
         self.code_registry.target_config()
     }
@@ -141,6 +212,72 @@ impl<X> CompilerSupport for Session<'_, X> {
     fn promise_prototype(&self) -> HandleMut<Object> {
         self.promise_prototype
     }
+
+    fn eval_function(&self) -> HandleMut<Object> {
+        self.eval_function
+    }
+
+    fn heap_exhausted_flag_addr(&self) -> usize {
+        self.heap_exhausted_flag_addr
+    }
+
+    fn interrupt_flags_addr(&self) -> usize {
+        self.interrupt_flags_addr
+    }
+
+    fn fuel_addr(&self) -> usize {
+        self.fuel_addr
+    }
+
+    fn profiler_tick_addr(&self) -> usize {
+        self.profiler_tick_addr
+    }
+
+    fn pause_point_active_addr(&self) -> usize {
+        self.pause_point_active_addr
+    }
+
+    fn coverage_counters_addr(&self) -> usize {
+        self.coverage_counters_addr
+    }
+
+    fn optimization_threshold(&self) -> u32 {
+        self.pref.optimization_threshold
+    }
+
+    fn type_feedback_addr(&self) -> usize {
+        self.type_feedback_addr
+    }
+
+    fn is_optimizing(&self) -> bool {
+        self.type_feedback.is_some()
+    }
+
+    fn is_inlining_enabled(&self) -> bool {
+        // Inlined calls are invisible to the instrumentation.
+        self.is_optimizing()
+            && !self.pref.enable_fuel_metering
+            && !self.pref.enable_debugger
+            && !self.pref.enable_profiler
+            && !self.pref.enable_coverage
+    }
+
+    fn observed_kinds(&self, index: usize) -> u32 {
+        self.type_feedback
+            .and_then(|slots| slots.get(index).copied())
+            .unwrap_or_default()
+    }
+
+    fn observed_call_target(&self, index: usize) -> Option<LambdaId> {
+        match self.type_feedback?.get(index).copied()? {
+            0 | TypeFeedback::MEGAMORPHIC => None,
+            target => Some((target - 1).into()),
+        }
+    }
+
+    fn stack_limit_addr(&self) -> usize {
+        self.stack_limit_addr
+    }
 }
 
 // TODO: Deferring the compilation until it's actually called improves the performance.
@@ -148,7 +285,7 @@ impl<X> CompilerSupport for Session<'_, X> {
 pub fn compile<X>(
     runtime: &mut Runtime<X>,
     program_id: ProgramId,
-    _optimize: bool,
+    optimize: bool,
 ) -> Result<(), CompileError> {
     // Allocate large data on the heap memory.
     //
@@ -169,18 +306,42 @@ pub fn compile<X>(
     // TODO: We should manage dependencies between functions in a more general way.
     let program = &runtime.programs[program_id.index()];
     for func in program.functions.iter() {
+        let coverage_counters_addr = if runtime.pref.enable_coverage {
+            runtime.coverage.counters_addr(func)
+        } else {
+            0
+        };
+        let type_feedback_addr =
+            if optimize && is_optimizable(&runtime.pref, &runtime.lambda_registry, func) {
+                runtime
+                    .type_feedback
+                    .slots_addr(func.id, num_type_feedback_slots(func))
+            } else {
+                0
+            };
         let mut session = {
-            let global_object = runtime.builtins.global_object;
+            // The intrinsics of the realm of the program are embedded in the compiled code.
+            let builtins = &runtime.realms[program.realm.index()];
             Session {
                 pref: &runtime.pref,
                 program,
                 symbol_registry: &mut runtime.symbol_registry,
                 lambda_registry: &mut runtime.lambda_registry,
                 code_registry: &mut runtime.code_registry,
-                global_object,
-                object_prototype: runtime.builtins.object_prototype,
-                function_prototype: runtime.builtins.function_prototype,
-                promise_prototype: runtime.builtins.promise_prototype,
+                global_object: builtins.global_object,
+                object_prototype: builtins.object_prototype,
+                function_prototype: builtins.function_prototype,
+                promise_prototype: builtins.promise_prototype,
+                eval_function: builtins.eval_function,
+                heap_exhausted_flag_addr: runtime.heap.exhausted_flag_addr(),
+                interrupt_flags_addr: runtime.interrupter.flags_addr(),
+                fuel_addr: runtime.interrupter.fuel_addr(),
+                profiler_tick_addr: runtime.profiler.tick_addr(),
+                pause_point_active_addr: runtime.pause_points.active_addr(),
+                coverage_counters_addr,
+                type_feedback_addr,
+                type_feedback: None,
+                stack_limit_addr: runtime.stack_limit_addr(),
             }
         };
         context.compile_function(func, &mut session, &program.scope_tree);
@@ -198,7 +359,7 @@ pub fn compile_function<X>(
     runtime: &mut Runtime<X>,
     program_id: ProgramId,
     function_index: usize,
-    _optimize: bool,
+    optimize: bool,
 ) -> Result<(), CompileError> {
     logger::debug!(event = "compile_function", ?program_id, function_index);
 
@@ -214,18 +375,44 @@ pub fn compile_function<X>(
     let program = &runtime.programs[program_id.index()];
     let func = &program.functions[function_index];
 
+    let coverage_counters_addr = if runtime.pref.enable_coverage {
+        runtime.coverage.counters_addr(func)
+    } else {
+        0
+    };
+
+    let type_feedback_addr =
+        if optimize && is_optimizable(&runtime.pref, &runtime.lambda_registry, func) {
+            runtime
+                .type_feedback
+                .slots_addr(func.id, num_type_feedback_slots(func))
+        } else {
+            0
+        };
+
     let mut session = {
-        let global_object = runtime.builtins.global_object;
+        // The intrinsics of the realm of the program are embedded in the compiled code.
+        let builtins = &runtime.realms[program.realm.index()];
         Session {
             pref: &runtime.pref,
             program,
             symbol_registry: &mut runtime.symbol_registry,
             lambda_registry: &mut runtime.lambda_registry,
             code_registry: &mut runtime.code_registry,
-            global_object,
-            object_prototype: runtime.builtins.object_prototype,
-            function_prototype: runtime.builtins.function_prototype,
-            promise_prototype: runtime.builtins.promise_prototype,
+            global_object: builtins.global_object,
+            object_prototype: builtins.object_prototype,
+            function_prototype: builtins.function_prototype,
+            promise_prototype: builtins.promise_prototype,
+            eval_function: builtins.eval_function,
+            heap_exhausted_flag_addr: runtime.heap.exhausted_flag_addr(),
+            interrupt_flags_addr: runtime.interrupter.flags_addr(),
+            fuel_addr: runtime.interrupter.fuel_addr(),
+            profiler_tick_addr: runtime.profiler.tick_addr(),
+            pause_point_active_addr: runtime.pause_points.active_addr(),
+            coverage_counters_addr,
+            type_feedback_addr,
+            type_feedback: None,
+            stack_limit_addr: runtime.stack_limit_addr(),
         }
     };
 
@@ -239,6 +426,182 @@ pub fn compile_function<X>(
     Ok(())
 }
 
+/// Recompiles a function with the type feedback collected by the code compiled with the
+/// optimization.
+pub fn optimize_function<X>(
+    runtime: &mut Runtime<X>,
+    program_id: ProgramId,
+    function_index: usize,
+) -> Result<(), CompileError> {
+    logger::debug!(event = "optimize_function", ?program_id, function_index);
+
+    // See the comment in `compile_function()`.
+    let mut context = Box::new(CraneliftContext::new());
+
+    let program = &runtime.programs[program_id.index()];
+    let func = &program.functions[function_index];
+    debug_assert!(runtime.type_feedback.slots(func.id).is_some());
+
+    let coverage_counters_addr = if runtime.pref.enable_coverage {
+        runtime.coverage.counters_addr(func)
+    } else {
+        0
+    };
+
+    let mut session = {
+        // The intrinsics of the realm of the program are embedded in the compiled code.
+        let builtins = &runtime.realms[program.realm.index()];
+        Session {
+            pref: &runtime.pref,
+            program,
+            symbol_registry: &mut runtime.symbol_registry,
+            lambda_registry: &mut runtime.lambda_registry,
+            code_registry: &mut runtime.code_registry,
+            global_object: builtins.global_object,
+            object_prototype: builtins.object_prototype,
+            function_prototype: builtins.function_prototype,
+            promise_prototype: builtins.promise_prototype,
+            eval_function: builtins.eval_function,
+            heap_exhausted_flag_addr: runtime.heap.exhausted_flag_addr(),
+            interrupt_flags_addr: runtime.interrupter.flags_addr(),
+            fuel_addr: runtime.interrupter.fuel_addr(),
+            profiler_tick_addr: runtime.profiler.tick_addr(),
+            pause_point_active_addr: runtime.pause_points.active_addr(),
+            coverage_counters_addr,
+            type_feedback_addr: 0,
+            type_feedback: runtime.type_feedback.slots(func.id),
+            stack_limit_addr: runtime.stack_limit_addr(),
+        }
+    };
+
+    context.compile_function(func, &mut session, &program.scope_tree);
+    if let Some(ref mut monitor) = runtime.monitor {
+        monitor.print_function_ir(func.id, &context.context.func);
+    }
+
+    runtime
+        .code_registry
+        .codegen_optimized(func, &mut context.context);
+
+    Ok(())
+}
+
+// Only normal functions are optimized.  The top-level code runs only once and coroutines are
+// called through their ramp functions.
+fn is_optimizable(pref: &RuntimePref, lambda_registry: &LambdaRegistry, func: &Function) -> bool {
+    pref.optimization_threshold > 0
+        && !func.is_entry_function()
+        && matches!(lambda_registry.get(func.id).kind, LambdaKind::Normal)
+}
+
+// The first slot counts calls and each site has two slots for its operands.
+fn num_type_feedback_slots(func: &Function) -> usize {
+    let num_sites = func
+        .commands
+        .iter()
+        .filter(|command| is_type_feedback_site(command))
+        .count();
+    1 + num_sites * 2
+}
+
+// Commands converting their operands into numbers and calls.
+fn is_type_feedback_site(command: &CompileCommand) -> bool {
+    matches!(
+        command,
+        CompileCommand::Call(..)
+            | CompileCommand::ToNumeric
+            | CompileCommand::UnaryPlus
+            | CompileCommand::UnaryMinus
+            | CompileCommand::BitwiseNot
+            | CompileCommand::Exponentiation
+            | CompileCommand::Multiplication
+            | CompileCommand::Division
+            | CompileCommand::Remainder
+            | CompileCommand::Addition
+            | CompileCommand::Subtraction
+            | CompileCommand::LeftShift
+            | CompileCommand::SignedRightShift
+            | CompileCommand::UnsignedRightShift
+            | CompileCommand::LessThan
+            | CompileCommand::GreaterThan
+            | CompileCommand::LessThanOrEqual
+            | CompileCommand::GreaterThanOrEqual
+            | CompileCommand::BitwiseAnd
+            | CompileCommand::BitwiseXor
+            | CompileCommand::BitwiseOr
+    )
+}
+
+// The maximum number of commands in a function to be inlined.
+const MAX_INLINE_COMMANDS: usize = 32;
+
+// Only small leaf functions are inlined.  The body of such a function consists of expressions
+// built from literals, formal parameters, captured variables and global variables with
+// operators that never call other functions.  The function has no local variables and does not
+// use the `this` binding.
+fn is_inlinable(callee: &Function, scope_tree: &ScopeTree) -> bool {
+    if callee.commands.len() > MAX_INLINE_COMMANDS
+        || callee.num_locals > 0
+        || !matches!(callee.this_binding, ThisBinding::None)
+    {
+        return false;
+    }
+    let mut returned = false;
+    callee.commands.iter().all(|command| match command {
+        CompileCommand::Nop
+        | CompileCommand::LoadFormalParameters(_)
+        | CompileCommand::AllocateLocals(_)
+        | CompileCommand::Coverage(_) => true,
+        CompileCommand::PushScope(scope_ref)
+        | CompileCommand::DeclareVariables(scope_ref)
+        | CompileCommand::DeclareFunctions(scope_ref)
+        | CompileCommand::PopScope(scope_ref) => *scope_ref == callee.scope_ref,
+        // Commands following a `return` statement are never performed.
+        _ if returned => false,
+        CompileCommand::Return(_) => {
+            returned = true;
+            true
+        }
+        CompileCommand::VariableReference(symbol) => {
+            let variable_ref = scope_tree.find_variable(callee.scope_ref, *symbol);
+            !matches!(scope_tree.compute_locator(variable_ref), Locator::Local(_))
+        }
+        CompileCommand::Undefined
+        | CompileCommand::Null
+        | CompileCommand::Boolean(_)
+        | CompileCommand::Number(_)
+        | CompileCommand::ToNumeric
+        | CompileCommand::UnaryPlus
+        | CompileCommand::UnaryMinus
+        | CompileCommand::BitwiseNot
+        | CompileCommand::LogicalNot
+        | CompileCommand::Exponentiation
+        | CompileCommand::Multiplication
+        | CompileCommand::Division
+        | CompileCommand::Remainder
+        | CompileCommand::Addition
+        | CompileCommand::Subtraction
+        | CompileCommand::LeftShift
+        | CompileCommand::SignedRightShift
+        | CompileCommand::UnsignedRightShift
+        | CompileCommand::LessThan
+        | CompileCommand::GreaterThan
+        | CompileCommand::LessThanOrEqual
+        | CompileCommand::GreaterThanOrEqual
+        | CompileCommand::Equality
+        | CompileCommand::Inequality
+        | CompileCommand::StrictEquality
+        | CompileCommand::StrictInequality
+        | CompileCommand::BitwiseAnd
+        | CompileCommand::BitwiseXor
+        | CompileCommand::BitwiseOr
+        | CompileCommand::Swap
+        | CompileCommand::Dereference
+        | CompileCommand::Discard => true,
+        _ => false,
+    })
+}
+
 struct CraneliftContext {
     builder_context: FunctionBuilderContext,
     context: codegen::Context,
@@ -297,6 +660,36 @@ struct Compiler<'a, R> {
     max_capture_buffer_len: u16,
 
     skip_count: u16,
+
+    /// The lambda function to compile.
+    lambda_id: LambdaId,
+
+    /// The number of type feedback slots of the function.
+    num_type_feedback_slots: usize,
+
+    /// The index of the type feedback site of the command being processed.
+    type_feedback_site: usize,
+
+    /// The number of type feedback sites processed so far.
+    num_type_feedback_sites: usize,
+
+    /// Types of local variables inferred in the current basic block.
+    local_types: LocalTypes,
+
+    /// Enabled while processing the commands of an inlined function.
+    inlining: bool,
+
+    /// Closures replaced with scalars in the function.
+    scalar_replacement: ScalarReplacement,
+
+    /// Enabled if the function being compiled is strict mode code.
+    strict: bool,
+
+    /// The source offset of the command being processed.
+    ///
+    /// Used for the `stack` property of errors thrown in the generated code.  The offset is known
+    /// only for calls.
+    source_offset: SourceOffset,
 }
 
 impl<'a, R> Compiler<'a, R>
@@ -338,6 +731,17 @@ where
 
         let builder = FunctionBuilder::new(func_ir, builder_context);
 
+        let scalar_replacement = if support.is_inlining_enabled()
+            && matches!(support.get_lambda_info(func.id).kind, LambdaKind::Normal)
+        {
+            ScalarReplacement::analyze(func, scope_tree, |lambda_id| {
+                matches!(support.get_lambda_info(lambda_id).kind, LambdaKind::Normal)
+                    && is_inlinable(support.get_function(lambda_id), scope_tree)
+            })
+        } else {
+            Default::default()
+        };
+
         Self {
             support,
             scope_tree,
@@ -353,6 +757,15 @@ where
             max_scratch_buffer_len: 0,
             max_capture_buffer_len: 0,
             skip_count: 0,
+            lambda_id: func.id,
+            num_type_feedback_slots: num_type_feedback_slots(func),
+            type_feedback_site: 0,
+            num_type_feedback_sites: 0,
+            local_types: Default::default(),
+            inlining: false,
+            scalar_replacement,
+            strict: func.is_strict(),
+            source_offset: SourceOffset::UNKNOWN,
         }
     }
 
@@ -368,6 +781,10 @@ where
         let entry_block = self.editor.entry_block();
         self.editor.switch_to_block(entry_block);
 
+        if self.support.is_optimizing() {
+            self.editor.set_optimized();
+        }
+
         self.editor
             .put_assert_lambda_params(self.support, func.is_entry_function());
 
@@ -426,10 +843,17 @@ where
         // `self.control_flow_stack.push_function_flow()`.
 
         self.check_call_depth();
+        self.check_interrupt();
+        self.check_heap_limit();
+        self.check_profiler_tick(func);
+        self.count_function_call(func);
+        self.count_call_for_optimization();
 
         self.resolve_this_binding(func);
     }
 
+    // Throws a RangeError if the call stack depth exceeds the limit or the native stack is about to
+    // overflow.
     fn check_call_depth(&mut self) {
         logger::debug!(event = "check_call_depth");
         let then_block = self.editor.create_block();
@@ -437,10 +861,125 @@ where
         let too_deep = self
             .editor
             .put_call_stack_too_deep(self.support.max_call_stack_depth());
+        let overflow = self
+            .editor
+            .put_stack_overflow(self.support.stack_limit_addr());
+        let too_deep = self.editor.put_logical_or(too_deep, overflow);
         self.editor
             .put_branch(too_deep, then_block, &[], merge_block, &[]);
         self.editor.switch_to_block(then_block);
-        self.emit_throw_internal_error(const_string_handle!("Call stack too deep"));
+        self.emit_throw_range_error(const_string_handle!("Maximum call stack size exceeded"));
+        self.editor.put_jump(merge_block, &[]);
+        self.editor.switch_to_block(merge_block);
+    }
+
+    // Terminates the execution if an interrupt has been requested or the fuel has been exhausted.
+    //
+    // Like `check_heap_limit()`, this is performed in the prologue of each function and at each
+    // loop back-edge.
+    fn check_interrupt(&mut self) {
+        logger::debug!(event = "check_interrupt");
+        let then_block = self.editor.create_block();
+        let merge_block = self.editor.create_block();
+        let mut interrupted = self
+            .editor
+            .put_is_interrupted(self.support.interrupt_flags_addr());
+        if self.support.is_fuel_metering_enabled() {
+            let out_of_fuel = self.editor.put_consume_fuel(self.support.fuel_addr());
+            interrupted = self.editor.put_logical_or(interrupted, out_of_fuel);
+        }
+        self.editor
+            .put_branch(interrupted, then_block, &[], merge_block, &[]);
+        self.editor.switch_to_block(then_block);
+        let error = self.editor.put_runtime_handle_interrupt(self.support);
+        self.operand_stack.push(Operand::Object(error));
+        self.process_throw();
+        self.editor.put_jump(merge_block, &[]);
+        self.editor.switch_to_block(merge_block);
+    }
+
+    // Takes a sample if the profiler requests.
+    //
+    // Like `check_interrupt()`, this is performed in the prologue of each function and at each
+    // loop back-edge.
+    fn check_profiler_tick(&mut self, func: &Function) {
+        if !self.support.is_profiler_enabled() {
+            return;
+        }
+        logger::debug!(event = "check_profiler_tick");
+        let then_block = self.editor.create_block();
+        let merge_block = self.editor.create_block();
+        let ticked = self
+            .editor
+            .put_is_profiler_ticked(self.support.profiler_tick_addr());
+        self.editor
+            .put_branch(ticked, then_block, &[], merge_block, &[]);
+        self.editor.switch_to_block(then_block);
+        self.editor
+            .put_runtime_take_profile_sample(self.support, func.id);
+        self.editor.put_jump(merge_block, &[]);
+        self.editor.switch_to_block(merge_block);
+    }
+
+    // Counts calls of the function if the code coverage is collected.
+    //
+    // A coroutine function is called each time the coroutine resumes.  Calls of an async function
+    // are counted in its ramp function.
+    fn count_function_call(&mut self, func: &Function) {
+        if !self.support.is_coverage_enabled() || self.is_coroutine(func) {
+            return;
+        }
+        logger::debug!(event = "count_function_call");
+        self.editor
+            .put_increment_counter(self.support.coverage_counters_addr(), 0);
+    }
+
+    // Counts calls of the function for the type feedback and recompiles the function with the
+    // type feedback when the count reaches the threshold.
+    fn count_call_for_optimization(&mut self) {
+        let slots_addr = self.support.type_feedback_addr();
+        if slots_addr == 0 {
+            return;
+        }
+        logger::debug!(event = "count_call_for_optimization");
+        let then_block = self.editor.create_block();
+        let merge_block = self.editor.create_block();
+        let reached = self.editor.put_count_call(
+            slots_addr,
+            TypeFeedback::CALL_COUNT_INDEX,
+            self.support.optimization_threshold(),
+        );
+        self.editor
+            .put_branch(reached, then_block, &[], merge_block, &[]);
+        self.editor.switch_to_block(then_block);
+        self.editor
+            .put_runtime_optimize_lambda(self.support, self.lambda_id);
+        self.editor.put_jump(merge_block, &[]);
+        self.editor.switch_to_block(merge_block);
+    }
+
+    // Terminates the execution if the heap limit has been exceeded.
+    //
+    // This is performed in the prologue of each function and at each loop back-edge so that a
+    // runaway script allocating objects in a loop can be stopped.  Like interrupts, the
+    // termination cannot be caught.  Garbage is collected after the stack has been unwound.
+    fn check_heap_limit(&mut self) {
+        logger::debug!(event = "check_heap_limit");
+        let then_block = self.editor.create_block();
+        let merge_block = self.editor.create_block();
+        let exhausted = self
+            .editor
+            .put_is_heap_exhausted(self.support.heap_exhausted_flag_addr());
+        self.editor
+            .put_branch(exhausted, then_block, &[], merge_block, &[]);
+        self.editor.switch_to_block(then_block);
+        let error = self.editor.put_runtime_handle_heap_exhausted(
+            self.support,
+            self.lambda_id,
+            self.source_offset,
+        );
+        self.operand_stack.push(Operand::Object(error));
+        self.process_throw();
         self.editor.put_jump(merge_block, &[]);
         self.editor.switch_to_block(merge_block);
     }
@@ -554,6 +1093,11 @@ where
 
     fn process_command(&mut self, func: &Function, command: &CompileCommand) {
         logger::debug!(event = "process_command", ?command);
+        if !self.inlining && is_type_feedback_site(command) {
+            self.type_feedback_site = self.num_type_feedback_sites;
+            self.num_type_feedback_sites += 1;
+        }
+        self.source_offset = SourceOffset::UNKNOWN;
         match command {
             CompileCommand::Nop => (),
             CompileCommand::Batch(n) => self.process_batch(*n),
@@ -562,6 +1106,7 @@ where
             CompileCommand::Boolean(value) => self.process_boolean(*value),
             CompileCommand::Number(value) => self.process_number(*value),
             CompileCommand::String(value) => self.process_string(value),
+            CompileCommand::TemplateObject(strings) => self.process_template_object(strings),
             CompileCommand::Object => self.process_object(),
             CompileCommand::Function(name) => self.process_function(*name),
             CompileCommand::Lambda(lambda_id) => self.process_lambda(*lambda_id),
@@ -578,18 +1123,22 @@ where
             CompileCommand::PropertyReference(symbol) => self.process_property_reference(*symbol),
             CompileCommand::ToPropertyKey => self.process_to_property_key(),
             CompileCommand::LoadFormalParameters(n) => self.process_load_formal_parameters(*n),
+            CompileCommand::RestParameter(index) => self.process_rest_parameter(*index),
             CompileCommand::AllocateLocals(n) => self.process_allocate_locals(*n),
             CompileCommand::MutableVariable => self.process_mutable_variable(),
             CompileCommand::ImmutableVariable => self.process_immutable_variable(),
             CompileCommand::DeclareVariables(scope_ref) => {
-                self.process_declare_variables(*scope_ref)
+                self.process_declare_variables(func, *scope_ref)
             }
             CompileCommand::DeclareFunctions(scope_ref) => {
                 self.process_declare_functions(func, *scope_ref)
             }
             CompileCommand::DeclareFunction => self.process_declare_function(),
-            CompileCommand::Call(nargs) => self.process_call(*nargs),
-            CompileCommand::New(nargs) => self.process_new(*nargs),
+            CompileCommand::Call(nargs, offset) => self.process_call(func, *nargs, *offset),
+            CompileCommand::DirectEval(nargs, offset) => {
+                self.process_direct_eval(func, *nargs, *offset)
+            }
+            CompileCommand::New(nargs, offset) => self.process_new(func, *nargs, *offset),
             CompileCommand::PushScope(scope_ref) => self.process_push_scope(func, *scope_ref),
             CompileCommand::PopScope(scope_ref) => self.process_pop_scope(*scope_ref),
             CompileCommand::ToNumeric => self.process_to_numeric(),
@@ -638,9 +1187,9 @@ where
             CompileCommand::Else(expr) => self.process_else(*expr),
             CompileCommand::IfElseStatement => self.process_if_else_statement(),
             CompileCommand::IfStatement => self.process_if_statement(),
-            CompileCommand::DoWhileLoop(id) => self.process_do_while_loop(*id),
-            CompileCommand::WhileLoop(id) => self.process_while_loop(*id),
-            CompileCommand::ForLoop(id, flags) => self.process_for_loop(*id, *flags),
+            CompileCommand::DoWhileLoop(id) => self.process_do_while_loop(func, *id),
+            CompileCommand::WhileLoop(id) => self.process_while_loop(func, *id),
+            CompileCommand::ForLoop(id, flags) => self.process_for_loop(func, *id, *flags),
             CompileCommand::LoopInit => self.process_loop_init(),
             CompileCommand::LoopTest => self.process_loop_test(),
             CompileCommand::LoopNext => self.process_loop_next(),
@@ -666,16 +1215,18 @@ where
             CompileCommand::Continue(symbol) => self.process_continue(*symbol),
             CompileCommand::Break(symbol) => self.process_break(*symbol),
             CompileCommand::Return(n) => self.process_return(*n),
-            CompileCommand::Throw => self.process_throw(),
+            CompileCommand::Throw(offset) => self.process_throw_statement(func, *offset),
             CompileCommand::Environment(num_locals) => self.process_environment(*num_locals),
             CompileCommand::JumpTable(num_states) => self.process_jump_table(*num_states),
             CompileCommand::Await(next_state) => self.process_await(*next_state),
             CompileCommand::Resume => self.process_resume(),
             CompileCommand::Discard => self.process_discard(),
+            CompileCommand::CompletionValue => self.process_completion_value(),
             CompileCommand::Swap => self.process_swap(),
             CompileCommand::Duplicate(offset) => self.process_duplicate(*offset),
             CompileCommand::Dereference => self.process_dereference(),
-            CompileCommand::Debugger => self.process_debugger(),
+            CompileCommand::Debugger(offset) => self.process_debugger(func, *offset),
+            CompileCommand::Coverage(index) => self.process_coverage(*index),
             CompileCommand::PlaceHolder => unreachable!(),
         }
 
@@ -732,6 +1283,14 @@ where
         self.operand_stack.push(Operand::String(string_ir, None));
     }
 
+    fn process_template_object(&mut self, strings: &TemplateStrings) {
+        // Like `process_string()`, the generated code refers to `strings` held in the command.
+        let object = self
+            .editor
+            .put_runtime_get_template_object(self.support, strings);
+        self.operand_stack.push(Operand::Object(object));
+    }
+
     fn process_object(&mut self) {
         let prototype = self.object_prototype();
         let object = self
@@ -741,18 +1300,28 @@ where
     }
 
     fn process_function(&mut self, name: Symbol) {
-        let closure = self.pop_closure();
+        let (closure, lambda_id) = self.pop_closure();
         let prototype = self.function_prototype();
         let function = self
             .editor
             .put_runtime_create_object(self.support, prototype);
         self.editor.put_store_closure_to_object(closure, function);
+        let length = self.support.get_function(lambda_id).length;
+        self.perform_set_function_length(function, length);
         self.perform_set_function_name(function, name);
         self.perform_make_constructor(function);
         self.operand_stack.push(Operand::Object(function));
     }
 
     fn process_lambda(&mut self, lambda_id: LambdaId) {
+        if self.scalar_replacement.is_replaced(lambda_id) {
+            // Skip the following `Closure` and `Function` commands.  `undefined` is stored into
+            // the variable instead of the function object so that the TDZ check works.
+            self.skip_count = 2;
+            self.operand_stack.push(Operand::Undefined);
+            return;
+        }
+
         let lambda_kind = self.support.get_lambda_info(lambda_id).kind;
         // Perform lazy compilation by default.
         let lambda = self
@@ -811,11 +1380,12 @@ where
                 .put_store_capture_to_closure(capture, closure, variable.index);
         }
 
-        self.operand_stack.push(Operand::Closure(closure));
+        self.operand_stack
+            .push(Operand::Closure(closure, lambda_id));
     }
 
     fn process_coroutine(&mut self, lambda_id: LambdaId, num_locals: u16) {
-        let closure = self.pop_closure();
+        let (closure, _) = self.pop_closure();
         let lambda_info = self.support.get_lambda_info(lambda_id);
         let coroutine = self.editor.put_runtime_create_coroutine(
             self.support,
@@ -911,7 +1481,7 @@ where
             Operand::Any(value, None) => value.into(),
             Operand::Any(..) => todo!(),
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::PropertyReference(..)
             | Operand::VariableReference(..) => {
@@ -1001,6 +1571,7 @@ where
         };
 
         self.emit_store_operand_to_any(&operand, local);
+        self.update_local_type(locator, &operand);
     }
 
     fn process_immutable_variable(&mut self) {
@@ -1013,9 +1584,22 @@ where
         };
 
         self.emit_store_operand_to_any(&operand, local);
+        self.update_local_type(locator, &operand);
+    }
+
+    // 10.2.11 FunctionDeclarationInstantiation ( func, argumentsList )
+    //
+    // Steps 24-25: the rest parameter is created from the actual arguments which are not bound
+    // to the preceding formal parameters.
+    fn process_rest_parameter(&mut self, index: u16) {
+        let rest = self
+            .editor
+            .put_runtime_create_rest_parameter(self.support, index);
+        let param = self.get_param(index);
+        self.editor.put_store_object_to_any(rest, param);
     }
 
-    fn process_declare_variables(&mut self, scope_ref: ScopeRef) {
+    fn process_declare_variables(&mut self, func: &Function, scope_ref: ScopeRef) {
         debug_assert!(self.scope_tree.scope(scope_ref).is_function());
 
         // In the specification, function-scoped variables defined by "VariableStatement"s are
@@ -1028,11 +1612,21 @@ where
         // functions defined by "FunctionDeclaration"s are created.
 
         // TODO(refactor): inefficient
-        for (variable_ref, variable) in self.scope_tree.iter_variables(scope_ref) {
+        let scope_tree = self.scope_tree;
+        for (variable_ref, variable) in scope_tree.iter_variables(scope_ref) {
+            if variable.is_arguments() {
+                let local = match scope_tree.compute_locator(variable_ref) {
+                    Locator::Local(index) => self.get_local(index),
+                    locator => unreachable!("{locator:?}"),
+                };
+                let arguments = self.perform_create_arguments(func);
+                self.editor.put_store_object_to_any(arguments, local);
+                continue;
+            }
             if !variable.is_function_scoped() {
                 continue;
             }
-            let local = match self.scope_tree.compute_locator(variable_ref) {
+            let local = match scope_tree.compute_locator(variable_ref) {
                 Locator::Local(index) => self.get_local(index),
                 locator => unreachable!("{locator:?}"),
             };
@@ -1040,6 +1634,27 @@ where
         }
     }
 
+    // 10.2.11 FunctionDeclarationInstantiation ( func, argumentsList )
+    //
+    // Steps 22.a-22.f: the arguments object is created before the function body is performed.
+    // Each element of a mapped arguments object is bound to the capture of the corresponding
+    // formal parameter.
+    fn perform_create_arguments(&mut self, func: &Function) -> ObjectIr {
+        let mapped = func.has_mapped_arguments();
+        let arguments = self
+            .editor
+            .put_runtime_create_arguments(self.support, mapped);
+        if mapped {
+            for index in 0..func.num_params {
+                let capture_addr = self.captures[&Locator::Param(index)];
+                let capture = self.perform_load_capture(capture_addr);
+                self.editor
+                    .put_runtime_map_argument(self.support, arguments, index, capture);
+            }
+        }
+        arguments
+    }
+
     // NOTE: This function may call `process_command()`.
     fn process_declare_functions(&mut self, func: &Function, scope_ref: ScopeRef) {
         for batch_index in self
@@ -1078,6 +1693,7 @@ where
             Locator::Local(index) => {
                 let local = self.get_local(index);
                 self.editor.put_store_object_to_any(object, local);
+                self.local_types.forget(index);
             }
             Locator::Global => {
                 let global_object = self.support.global_object();
@@ -1090,6 +1706,7 @@ where
                     global_object,
                     symbol,
                     value,
+                    false,
                     retv,
                 );
                 self.emit_check_status_for_exception(status, retv);
@@ -1137,6 +1754,23 @@ where
         );
     }
 
+    // 10.2.10 SetFunctionLength ( F, length )
+    fn perform_set_function_length(&mut self, function: ObjectIr, length: u16) {
+        let length = self.editor.put_number(length as f64);
+        let value = self.editor.put_alloc_any();
+        self.editor.put_store_number_to_any(length, value);
+        let retv = self.editor.put_alloc_any();
+        // TODO(feat): [[Writable]]: false, [[Enumerable]]: false, [[Configurable]]: true
+        // TODO(feat): retv
+        self.editor.put_runtime_create_data_property_by_symbol(
+            self.support,
+            function,
+            Symbol::LENGTH,
+            value,
+            retv,
+        );
+    }
+
     // 10.2.9 SetFunctionName ( F, name [ , prefix ] )
     fn perform_set_function_name(&mut self, function: ObjectIr, name: Symbol) {
         logger::debug!(event = "set_function_name", ?function, ?name);
@@ -1165,7 +1799,9 @@ where
         );
     }
 
-    fn process_call(&mut self, argc: u16) {
+    fn process_call(&mut self, func: &Function, argc: u16, offset: SourceOffset) {
+        self.source_offset = offset;
+        self.emit_pause_point(func, offset);
         // TODO: dynamic allocation
         if argc > 8 {
             self.emit_throw_internal_error(const_string_handle!("TODO: too many arguments"));
@@ -1175,55 +1811,44 @@ where
             return;
         }
 
+        let callee = &self.operand_stack[self.operand_stack.len() - argc as usize - 1];
+        if let Operand::VariableReference(_, locator) = callee {
+            if let Some((lambda_id, scope_ref)) = self.scalar_replacement.closure(*locator) {
+                self.perform_call_with_scalar_replacement(func, argc, lambda_id, scope_ref);
+                return;
+            }
+        }
+
+        if let Some(lambda_id) = self.find_inline_target() {
+            self.perform_call_with_inlining(func, argc, offset, lambda_id);
+            return;
+        }
+
         self.emit_fill_args(argc);
 
         let (operand, owner) = self.dereference();
-
-        let object = match operand {
-            Operand::Closure(_) => unreachable!(),
-            Operand::Object(object) => object, // IIFE
-            Operand::Any(value, ..) => self.emit_load_object_or_throw_type_error(value),
-            _ => {
-                self.emit_throw_type_error();
-                self.operand_stack.push(Operand::Undefined); // TODO: dummy
-                return;
-            }
+        let Some(object) = self.emit_load_callee(operand) else {
+            self.operand_stack.push(Operand::Undefined); // TODO: dummy
+            return;
         };
 
         let closure = self.emit_load_closure_or_throw_type_error(object);
-
-        if let Some(owner) = owner {
-            let dst = self.editor.put_get_this_from_call_context();
-            self.emit_store_property_owner_to_any(owner, dst);
-        } else {
-            let this = self.editor.this_argument();
-            let dst = self.editor.put_get_this_from_call_context();
-            self.editor.put_store_any_to_any(this, dst);
-        }
+        self.record_call_target(closure);
 
         let retv = self.emit_create_any();
-        let status = self
-            .editor
-            .put_call(object, closure, CallContextFlags::empty(), retv);
-        self.emit_check_status_for_exception(status, retv);
+        self.emit_call(func, offset, object, closure, owner, retv);
 
         // TODO(pref): compile-time evaluation
         self.operand_stack.push(Operand::Any(retv, None));
     }
 
-    fn emit_store_property_owner_to_any(&mut self, owner: PropertyOwner, any: AnyIr) {
-        match owner {
-            PropertyOwner::Undefined => self.editor.put_store_undefined_to_any(any),
-            PropertyOwner::Null => self.editor.put_store_null_to_any(any),
-            PropertyOwner::Boolean(value) => self.editor.put_store_boolean_to_any(value, any),
-            PropertyOwner::Number(value) => self.editor.put_store_number_to_any(value, any),
-            PropertyOwner::String(value) => self.editor.put_store_string_to_any(value, any),
-            PropertyOwner::Object(value) => self.editor.put_store_object_to_any(value, any),
-            PropertyOwner::Any(value) => self.editor.put_store_any_to_any(value, any),
-        }
-    }
-
-    fn process_new(&mut self, argc: u16) {
+    // 19.2.1.1 PerformEval ( x, strictCaller, direct )
+    //
+    // Whether the call is a direct eval or not is determined at runtime.  If the callee is not
+    // %eval%, it's called in the same way as `process_call()`.
+    fn process_direct_eval(&mut self, func: &Function, argc: u16, offset: SourceOffset) {
+        self.source_offset = offset;
+        self.emit_pause_point(func, offset);
         // TODO: dynamic allocation
         if argc > 8 {
             self.emit_throw_internal_error(const_string_handle!("TODO: too many arguments"));
@@ -1235,13 +1860,392 @@ where
 
         self.emit_fill_args(argc);
 
-        let (operand, ..) = self.dereference();
-        let constructor = match operand {
-            Operand::Closure(_) => unreachable!(),
-            Operand::Object(object) => object,
-            Operand::Any(value, ..) => self.emit_load_object_or_throw_type_error(value),
-            _ => {
-                self.emit_throw_type_error();
+        let (operand, owner) = self.dereference();
+        let Some(object) = self.emit_load_callee(operand) else {
+            self.operand_stack.push(Operand::Undefined); // TODO: dummy
+            return;
+        };
+
+        let retv = self.emit_create_any();
+
+        let eval_block = self.editor.create_block();
+        let call_block = self.editor.create_block();
+        let end_block = self.editor.create_block();
+
+        // if object === %eval%
+        let eval_function = self.eval_function();
+        let is_eval = self.editor.put_is_same_object(object, eval_function);
+        self.editor
+            .put_branch(is_eval, eval_block, &[], call_block, &[]);
+        // {
+        self.editor.switch_to_block(eval_block);
+        self.emit_direct_eval(func, offset, object, retv);
+        self.editor.put_jump(end_block, &[]);
+        // } else {
+        self.editor.switch_to_block(call_block);
+        let closure = self.emit_load_closure_or_throw_type_error(object);
+        self.emit_call(func, offset, object, closure, owner, retv);
+        self.editor.put_jump(end_block, &[]);
+        // }
+
+        self.editor.switch_to_block(end_block);
+        self.operand_stack.push(Operand::Any(retv, None));
+    }
+
+    fn emit_direct_eval(
+        &mut self,
+        func: &Function,
+        offset: SourceOffset,
+        eval_function: ObjectIr,
+        retv: AnyIr,
+    ) {
+        // The outgoing call context is used as the call context of %eval%.  The eval code is
+        // evaluated with the `this` binding of the caller.
+        let this = self.this.unwrap();
+        let dst = self.editor.put_get_this_from_call_context();
+        self.editor.put_store_any_to_any(this, dst);
+        self.editor
+            .put_store_call_site_to_call_context(func.id, offset);
+        self.editor
+            .put_store_function_to_call_context(eval_function);
+        self.editor
+            .put_store_flags_to_call_context(CallContextFlags::empty());
+
+        let (scope_ref, captures) = self.collect_eval_captures();
+        let status = self.editor.put_runtime_direct_eval(
+            self.support,
+            scope_ref,
+            &captures,
+            func.is_strict(),
+            retv,
+        );
+        self.emit_check_status_for_exception(status, retv);
+    }
+
+    // Collects the captures of the variables visible in the current scope.
+    //
+    // The order of the captures is the same as `ScopeTree::visible_variables()`.  All of the
+    // variables have been captured in the semantic analysis.
+    fn collect_eval_captures(&mut self) -> (ScopeRef, Vec<CaptureIr>) {
+        let scope_ref = self.control_flow_stack.scope_flow().scope_ref;
+        let scope_tree = self.scope_tree;
+        let mut captures = vec![];
+        for (_, variable) in scope_tree.visible_variables(scope_ref) {
+            let locator = variable.locator();
+            let capture = match locator {
+                Locator::Param(_) | Locator::Local(_) => {
+                    debug_assert!(self.captures.contains_key(&locator));
+                    self.perform_load_capture(*self.captures.get(&locator).unwrap())
+                }
+                Locator::Capture(index) => self.editor.put_load_capture(index),
+                Locator::Global => unreachable!(),
+            };
+            captures.push(capture);
+        }
+        (scope_ref, captures)
+    }
+
+    fn emit_load_callee(&mut self, operand: Operand) -> Option<ObjectIr> {
+        match operand {
+            Operand::Closure(..) => unreachable!(),
+            Operand::Object(object) => Some(object), // IIFE
+            Operand::Any(value, ..) => Some(self.emit_load_object_or_throw_type_error(value)),
+            _ => {
+                self.emit_throw_type_error();
+                None
+            }
+        }
+    }
+
+    fn emit_call(
+        &mut self,
+        func: &Function,
+        offset: SourceOffset,
+        object: ObjectIr,
+        closure: ClosureIr,
+        owner: Option<PropertyOwner>,
+        retv: AnyIr,
+    ) {
+        if let Some(owner) = owner {
+            let dst = self.editor.put_get_this_from_call_context();
+            self.emit_store_property_owner_to_any(owner, dst);
+        } else {
+            let this = self.editor.this_argument();
+            let dst = self.editor.put_get_this_from_call_context();
+            self.editor.put_store_any_to_any(this, dst);
+        }
+
+        self.editor
+            .put_store_call_site_to_call_context(func.id, offset);
+
+        let realm = self.support.realm();
+        let status = self.editor.put_call(
+            self.support,
+            object,
+            closure,
+            CallContextFlags::empty(),
+            realm,
+            retv,
+        );
+        self.emit_check_status_for_exception(status, retv);
+    }
+
+    // Records the lambda function called at the current call site into the type feedback.
+    fn record_call_target(&mut self, closure: ClosureIr) {
+        let slots_addr = self.support.type_feedback_addr();
+        if slots_addr == 0 {
+            return;
+        }
+        if let Some(slot) = self.type_feedback_slot(0) {
+            self.editor
+                .put_record_call_target(slots_addr, slot, closure);
+        }
+    }
+
+    // Returns the lambda function to be inlined at the current call site.
+    //
+    // A lambda function is inlined if it's the only one called at the call site so far and it's
+    // a small leaf function defined in the same program.
+    fn find_inline_target(&self) -> Option<LambdaId> {
+        if !self.support.is_inlining_enabled() {
+            return None;
+        }
+        let lambda_id = self
+            .support
+            .observed_call_target(self.type_feedback_slot(0)?)?;
+        let caller_info = self.support.get_lambda_info(self.lambda_id);
+        let callee_info = self.support.get_lambda_info(lambda_id);
+        if callee_info.program_id != caller_info.program_id
+            || !matches!(callee_info.kind, LambdaKind::Normal)
+        {
+            return None;
+        }
+        let callee = self.support.get_function(lambda_id);
+        is_inlinable(callee, self.scope_tree).then_some(lambda_id)
+    }
+
+    // Performs a call speculating that the inlined lambda function is called.
+    //
+    // The arguments are passed to the inlined code as operands.  So, neither `argv` nor the call
+    // context is filled on the inlined path.  When the speculation fails, the closure falls back
+    // to the generic code and the call is performed in the generic way.
+    fn perform_call_with_inlining(
+        &mut self,
+        func: &Function,
+        argc: u16,
+        offset: SourceOffset,
+        lambda_id: LambdaId,
+    ) {
+        logger::debug!(event = "perform_call_with_inlining", ?lambda_id);
+
+        let args = self.pop_inlined_args(argc);
+
+        let (operand, owner) = self.dereference();
+        let Some(object) = self.emit_load_callee(operand) else {
+            self.operand_stack.push(Operand::Undefined); // TODO: dummy
+            return;
+        };
+
+        let closure = self.emit_load_closure_or_throw_type_error(object);
+
+        let then_block = self.editor.create_block();
+        let else_block = self.editor.create_block();
+        let merge_block = self.editor.create_block();
+        let retv = self.emit_create_any();
+
+        let is_target = self.editor.put_is_closure_of(closure, lambda_id);
+        self.editor
+            .put_branch(is_target, then_block, &[], else_block, &[]);
+        // then
+        {
+            self.editor.switch_to_block(then_block);
+            let captures = InlinedCaptures::Closure(closure);
+            let result = self.emit_inlined_code(func, lambda_id, captures, &args);
+            self.emit_store_operand_to_any(&result, retv);
+            self.editor.put_jump(merge_block, &[]);
+        }
+        // else
+        {
+            self.editor.switch_to_block(else_block);
+            self.editor
+                .put_runtime_deoptimize_lambda(self.support, self.lambda_id);
+            self.emit_store_args(&args);
+            self.emit_call(func, offset, object, closure, owner, retv);
+            self.editor.put_jump(merge_block, &[]);
+        }
+        // end
+
+        self.editor.switch_to_block(merge_block);
+        self.operand_stack.push(Operand::Any(retv, None));
+    }
+
+    // Performs a call of a closure replaced with scalars.
+    //
+    // The lambda function is always inlined.  Unlike `perform_call_with_inlining()`, no guard is
+    // needed because the callee is known at compile time.
+    fn perform_call_with_scalar_replacement(
+        &mut self,
+        func: &Function,
+        argc: u16,
+        lambda_id: LambdaId,
+        scope_ref: ScopeRef,
+    ) {
+        logger::debug!(event = "perform_call_with_scalar_replacement", ?lambda_id);
+
+        let args = self.pop_inlined_args(argc);
+
+        // The variable holds `undefined`.  This throws a ReferenceError if the variable is in the
+        // TDZ.
+        self.dereference();
+
+        let retv = self.emit_create_any();
+        let captures = InlinedCaptures::Scope(scope_ref);
+        let result = self.emit_inlined_code(func, lambda_id, captures, &args);
+        self.emit_store_operand_to_any(&result, retv);
+        self.operand_stack.push(Operand::Any(retv, None));
+    }
+
+    // Pops the arguments of a call to an inlined lambda function.
+    fn pop_inlined_args(&mut self, argc: u16) -> Vec<Operand> {
+        let mut args = Vec::with_capacity(argc as usize);
+        for _ in 0..argc {
+            let (operand, ..) = self.dereference();
+            // Take a copy in the same way as `emit_fill_args()` so that the value is not affected
+            // by the evaluation of the callee.
+            let operand = match operand {
+                Operand::Any(..) => Operand::Any(self.perform_to_any(&operand), None),
+                _ => operand,
+            };
+            args.push(operand);
+        }
+        args.reverse();
+        args
+    }
+
+    // Emits the code of an inlined lambda function and returns the operand of the return value.
+    //
+    // The commands of the lambda function are processed in the current function except for
+    // commands accessing its formal parameters and captured variables.
+    fn emit_inlined_code(
+        &mut self,
+        func: &Function,
+        lambda_id: LambdaId,
+        captures: InlinedCaptures,
+        args: &[Operand],
+    ) -> Operand {
+        logger::debug!(event = "emit_inlined_code", ?lambda_id);
+
+        let callee = self.support.get_function(lambda_id);
+        let scope_ref = callee.scope_ref;
+        let commands = callee.commands.clone();
+        let callee_strict = callee.is_strict();
+
+        debug_assert!(!self.inlining);
+        self.inlining = true;
+        let strict = std::mem::replace(&mut self.strict, callee_strict);
+
+        let depth = self.operand_stack.len();
+        let mut result = Operand::Undefined;
+        for command in commands.iter() {
+            match command {
+                CompileCommand::Nop
+                | CompileCommand::LoadFormalParameters(_)
+                | CompileCommand::AllocateLocals(_)
+                | CompileCommand::PushScope(_)
+                | CompileCommand::DeclareVariables(_)
+                | CompileCommand::DeclareFunctions(_)
+                | CompileCommand::PopScope(_)
+                | CompileCommand::Coverage(_) => (),
+                CompileCommand::VariableReference(symbol) => {
+                    let operand = self.resolve_inlined_variable(scope_ref, captures, args, *symbol);
+                    self.operand_stack.push(operand);
+                }
+                CompileCommand::Return(n) => {
+                    if *n > 0 {
+                        let (operand, ..) = self.dereference();
+                        result = operand;
+                    }
+                    break;
+                }
+                _ => self.process_command(func, command),
+            }
+        }
+        debug_assert_eq!(self.operand_stack.len(), depth);
+
+        self.strict = strict;
+        self.inlining = false;
+        result
+    }
+
+    fn resolve_inlined_variable(
+        &mut self,
+        scope_ref: ScopeRef,
+        captures: InlinedCaptures,
+        args: &[Operand],
+        symbol: Symbol,
+    ) -> Operand {
+        let variable_ref = self.scope_tree.find_variable(scope_ref, symbol);
+        debug_assert_ne!(variable_ref, VariableRef::NONE);
+        match self.scope_tree.compute_locator(variable_ref) {
+            Locator::Param(index) => args
+                .get(index as usize)
+                .cloned()
+                .unwrap_or(Operand::Undefined),
+            Locator::Capture(index) => match captures {
+                InlinedCaptures::Closure(closure) => {
+                    // The captured value is loaded directly from the closure.
+                    let value = self
+                        .editor
+                        .put_load_captured_value_from_closure(closure, index);
+                    self.emit_check_none(value);
+                    Operand::Any(value, None)
+                }
+                InlinedCaptures::Scope(outer_scope_ref) => {
+                    // The closure is replaced with scalars.  The variable is accessed directly.
+                    let variable_ref = self.scope_tree.find_variable(outer_scope_ref, symbol);
+                    debug_assert_ne!(variable_ref, VariableRef::NONE);
+                    let locator = self.scope_tree.compute_locator(variable_ref);
+                    Operand::VariableReference(symbol, locator)
+                }
+            },
+            Locator::Global => Operand::VariableReference(symbol, Locator::Global),
+            Locator::Local(_) => unreachable!(),
+        }
+    }
+
+    fn emit_store_property_owner_to_any(&mut self, owner: PropertyOwner, any: AnyIr) {
+        match owner {
+            PropertyOwner::Undefined => self.editor.put_store_undefined_to_any(any),
+            PropertyOwner::Null => self.editor.put_store_null_to_any(any),
+            PropertyOwner::Boolean(value) => self.editor.put_store_boolean_to_any(value, any),
+            PropertyOwner::Number(value) => self.editor.put_store_number_to_any(value, any),
+            PropertyOwner::String(value) => self.editor.put_store_string_to_any(value, any),
+            PropertyOwner::Object(value) => self.editor.put_store_object_to_any(value, any),
+            PropertyOwner::Any(value) => self.editor.put_store_any_to_any(value, any),
+        }
+    }
+
+    fn process_new(&mut self, func: &Function, argc: u16, offset: SourceOffset) {
+        self.source_offset = offset;
+        self.emit_pause_point(func, offset);
+        // TODO: dynamic allocation
+        if argc > 8 {
+            self.emit_throw_internal_error(const_string_handle!("TODO: too many arguments"));
+            let len = self.operand_stack.len() - (argc as usize) - 1;
+            self.operand_stack.truncate(len);
+            self.operand_stack.push(Operand::Undefined); // TODO: dummy
+            return;
+        }
+
+        self.emit_fill_args(argc);
+
+        let (operand, ..) = self.dereference();
+        let constructor = match operand {
+            Operand::Closure(..) => unreachable!(),
+            Operand::Object(object) => object,
+            Operand::Any(value, ..) => self.emit_load_object_or_throw_type_error(value),
+            _ => {
+                self.emit_throw_type_error();
                 self.operand_stack.push(Operand::Undefined); // TODO: dummy
                 return;
             }
@@ -1272,14 +2276,46 @@ where
             object
         };
 
+        self.editor
+            .put_store_call_site_to_call_context(func.id, offset);
+
         let retv = self.emit_create_any();
-        let status = self
-            .editor
-            .put_call(constructor, closure, CallContextFlags::NEW, retv);
+        let realm = self.support.realm();
+        let status = self.editor.put_call(
+            self.support,
+            constructor,
+            closure,
+            CallContextFlags::NEW,
+            realm,
+            retv,
+        );
         self.emit_check_status_for_exception(status, retv);
 
+        // 10.2.2 [[Construct]] ( argumentsList, newTarget ), step 10
+        //
+        // The return value of the constructor is the result if it's an object.
+        let then_block = self.editor.create_block();
+        let else_block = self.editor.create_block();
+        let end_block = self.editor.create_block_with_addr();
+
+        // if retv.is_object()
+        let is_object = self.editor.put_is_object(retv);
+        self.editor
+            .put_branch(is_object, then_block, &[], else_block, &[]);
+        // {
+        self.editor.switch_to_block(then_block);
+        let object = self.editor.put_load_object(retv);
+        self.editor.put_jump(end_block, &[object.0.into()]);
+        // } else {
+        self.editor.switch_to_block(else_block);
+        self.editor.put_jump(end_block, &[this.0.into()]);
+        // }
+
+        self.editor.switch_to_block(end_block);
+        let result = ObjectIr(self.editor.get_block_param(end_block, 0));
+
         // TODO(pref): compile-time evaluation
-        self.operand_stack.push(Operand::Object(this));
+        self.operand_stack.push(Operand::Object(result));
     }
 
     fn process_push_scope(&mut self, func: &Function, scope_ref: ScopeRef) {
@@ -1306,7 +2342,7 @@ where
 
         for variable in scope.variables.iter() {
             let locator = variable.locator();
-            if variable.is_captured() {
+            if variable.is_captured() && !self.scalar_replacement.is_capture_elided(locator) {
                 let target = match locator {
                     Locator::Param(index) => self.get_param(index),
                     Locator::Local(index) => self.get_local(index),
@@ -1347,8 +2383,9 @@ where
         self.editor.switch_to_block(flow.cleanup_block);
         let scope = self.scope_tree.scope(scope_ref);
         for variable in scope.variables.iter() {
-            if variable.is_captured() {
-                self.perform_escape_value(variable.locator());
+            let locator = variable.locator();
+            if variable.is_captured() && !self.scalar_replacement.is_capture_elided(locator) {
+                self.perform_escape_value(locator);
             }
             if variable.is_local() {
                 // tidy local value
@@ -1390,7 +2427,11 @@ where
     // 7.1.3 ToNumeric ( value )
     fn process_to_numeric(&mut self) {
         let (operand, ..) = self.dereference();
-        let result = self.perform_to_numeric(&operand);
+        if let Some(value) = constant_to_number(&operand) {
+            self.process_number(value);
+            return;
+        }
+        let result = self.perform_to_numeric_with_feedback(&operand, 0);
         self.operand_stack.push(Operand::Number(result, None));
     }
 
@@ -1429,7 +2470,7 @@ where
             }
             Operand::Any(value, _) => self.editor.put_runtime_to_string(self.support, *value),
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!(),
@@ -1665,7 +2706,7 @@ where
                 self.operand_stack.push(Operand::String(string, None));
             }
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!("{operand:?}"),
@@ -1675,100 +2716,318 @@ where
     // 13.5.4.1 Runtime Semantics: Evaluation
     fn process_unary_plus(&mut self) {
         let (operand, ..) = self.dereference();
-        let value = self.perform_to_numeric(&operand);
-        // TODO(perf): compile-time evaluation
+        if let Some(value) = constant_to_number(&operand) {
+            self.process_number(value);
+            return;
+        }
+        let value = self.perform_to_numeric_with_feedback(&operand, 0);
         self.operand_stack.push(Operand::Number(value, None));
     }
 
     // 13.5.5.1 Runtime Semantics: Evaluation
     fn process_unary_minus(&mut self) {
         let (operand, ..) = self.dereference();
-        let value = self.perform_to_numeric(&operand);
         // TODO: BigInt
         // 6.1.6.1.1 Number::unaryMinus ( x )
+        if let Some(value) = constant_to_number(&operand) {
+            self.process_number(-value);
+            return;
+        }
+        let value = self.perform_to_numeric_with_feedback(&operand, 0);
         let value = self.editor.put_negate(value);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(value, None));
     }
 
     // 13.5.6.1 Runtime Semantics: Evaluation
     fn process_bitwise_not(&mut self) {
         let (operand, ..) = self.dereference();
-        let number = self.perform_to_numeric(&operand);
         // TODO: BigInt
+        if let Some(value) = constant_to_number(&operand) {
+            self.process_number(!to_int32(value) as f64);
+            return;
+        }
+        let number = self.perform_to_numeric_with_feedback(&operand, 0);
         let number = self.editor.put_bitwise_not(self.support, number);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
     // 13.5.7.1 Runtime Semantics: Evaluation
     fn process_logical_not(&mut self) {
         let (operand, ..) = self.dereference();
+        if let Some(value) = constant_to_boolean(&operand) {
+            self.process_boolean(!value);
+            return;
+        }
         let boolean = self.perform_to_boolean(&operand);
         let boolean = self.editor.put_logical_not(boolean);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
     // 13.6.1 Runtime Semantics: Evaluation
     fn process_exponentiation(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(lhs.powf(rhs));
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let number = self.editor.put_exp(self.support, lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
     // 13.7.1 Runtime Semantics: Evaluation
     fn process_multiplication(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(lhs * rhs);
+            return;
+        }
+
+        if self.perform_speculative_int32_operation(Int32Operation::Mul, &lhs, &rhs) {
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let number = self.editor.put_mul(lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
     // 13.7.1 Runtime Semantics: Evaluation
     fn process_division(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(lhs / rhs);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let number = self.editor.put_div(lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
     // 13.7.1 Runtime Semantics: Evaluation
     fn process_remainder(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
+        let (rhs, ..) = self.dereference();
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(lhs % rhs);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
+        let number = self.editor.put_rem(self.support, lhs, rhs);
+        self.operand_stack.push(Operand::Number(number, None));
+    }
+
+    // 13.8.1.1 Runtime Semantics: Evaluation
+    fn process_addition(&mut self) {
+        let (lhs, ..) = self.dereference();
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
-        let number = self.editor.put_rem(self.support, lhs, rhs);
-        // TODO(perf): compile-time evaluation
-        self.operand_stack.push(Operand::Number(number, None));
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(lhs + rhs);
+            return;
+        }
+
+        if self.perform_speculative_int32_operation(Int32Operation::Add, &lhs, &rhs) {
+            return;
+        }
+
+        let lhs_kinds = self.observe_operand_kinds(&lhs, 0);
+        let rhs_kinds = self.observe_operand_kinds(&rhs, 1);
+        let is_any = matches!(lhs, Operand::Any(..)) || matches!(rhs, Operand::Any(..));
+        if is_any && is_speculated_number(&lhs, lhs_kinds) && is_speculated_number(&rhs, rhs_kinds)
+        {
+            self.perform_speculative_addition(lhs, rhs);
+        } else {
+            self.perform_addition(lhs, rhs);
+        }
+    }
+
+    // Performs the addition speculating that `Any` operands are numbers.
+    //
+    // When the speculation fails, the closure falls back to the generic code and the addition is
+    // performed in the generic way.
+    fn perform_speculative_addition(&mut self, lhs: Operand, rhs: Operand) {
+        let then_block = self.editor.create_block();
+        let else_block = self.editor.create_block();
+        let merge_block = self.editor.create_block();
+        let result = self.emit_create_any();
+
+        let is_number = match (&lhs, &rhs) {
+            (Operand::Any(lhs, _), Operand::Any(rhs, _)) => {
+                let lhs_is_number = self.editor.put_is_number(*lhs);
+                let rhs_is_number = self.editor.put_is_number(*rhs);
+                self.editor.put_logical_and(lhs_is_number, rhs_is_number)
+            }
+            (Operand::Any(value, _), _) | (_, Operand::Any(value, _)) => {
+                self.editor.put_is_number(*value)
+            }
+            _ => unreachable!(),
+        };
+        self.editor
+            .put_branch(is_number, then_block, &[], else_block, &[]);
+        // then
+        {
+            self.editor.switch_to_block(then_block);
+            let lhs_number = self.load_speculated_number(&lhs);
+            let rhs_number = self.load_speculated_number(&rhs);
+            let number = self.editor.put_add(lhs_number, rhs_number);
+            self.editor.put_store_number_to_any(number, result);
+            self.editor.put_jump(merge_block, &[]);
+        }
+        // else
+        {
+            self.editor.switch_to_block(else_block);
+            self.editor
+                .put_runtime_deoptimize_lambda(self.support, self.lambda_id);
+            self.perform_addition(lhs, rhs);
+            let operand = self.operand_stack.pop().unwrap();
+            self.emit_store_operand_to_any(&operand, result);
+            self.editor.put_jump(merge_block, &[]);
+        }
+        // end
+
+        self.editor.switch_to_block(merge_block);
+        self.operand_stack.push(Operand::Any(result, None));
+    }
+
+    // Performs an addition, a subtraction or a multiplication with 32-bit integers if `Any`
+    // operands are speculated to be int32 values.  Returns `false` without emitting any code if
+    // the operands are not speculated to be int32 values.
+    //
+    // When the speculation fails or the operation overflows, the closure falls back to the generic
+    // code and the operation is performed in the generic way.
+    fn perform_speculative_int32_operation(
+        &mut self,
+        op: Int32Operation,
+        lhs: &Operand,
+        rhs: &Operand,
+    ) -> bool {
+        let lhs_kinds = self.observed_operand_kinds(lhs, 0);
+        let rhs_kinds = self.observed_operand_kinds(rhs, 1);
+        let is_any = matches!(lhs, Operand::Any(..)) || matches!(rhs, Operand::Any(..));
+        if !is_any || !is_speculated_int32(lhs, lhs_kinds) || !is_speculated_int32(rhs, rhs_kinds) {
+            return false;
+        }
+
+        let then_block = self.editor.create_block();
+        let fallback_block = self.editor.create_block();
+        // The result of the addition may be a string in the generic way.
+        let (merge_block, result) = match op {
+            Int32Operation::Add => (self.editor.create_block(), Some(self.emit_create_any())),
+            Int32Operation::Sub | Int32Operation::Mul => {
+                (self.editor.create_block_with_f64(), None)
+            }
+        };
+
+        let lhs_int32 = self.emit_speculative_load_int32(lhs, fallback_block);
+        let rhs_int32 = self.emit_speculative_load_int32(rhs, fallback_block);
+        let (int32, overflow) = match op {
+            Int32Operation::Add => self.editor.put_int32_add(lhs_int32, rhs_int32),
+            Int32Operation::Sub => self.editor.put_int32_sub(lhs_int32, rhs_int32),
+            Int32Operation::Mul => self.editor.put_int32_mul(lhs_int32, rhs_int32),
+        };
+        self.editor
+            .put_branch(overflow, fallback_block, &[], then_block, &[]);
+        // then
+        {
+            self.editor.switch_to_block(then_block);
+            let number = self.editor.put_int32_to_number(int32);
+            match result {
+                Some(result) => {
+                    self.editor.put_store_number_to_any(number, result);
+                    self.editor.put_jump(merge_block, &[]);
+                }
+                None => self.editor.put_jump(merge_block, &[number.0.into()]),
+            }
+        }
+        // fallback
+        {
+            self.editor.switch_to_block(fallback_block);
+            self.editor
+                .put_runtime_deoptimize_lambda(self.support, self.lambda_id);
+            match result {
+                Some(result) => {
+                    self.perform_addition(lhs.clone(), rhs.clone());
+                    let operand = self.operand_stack.pop().unwrap();
+                    self.emit_store_operand_to_any(&operand, result);
+                    self.editor.put_jump(merge_block, &[]);
+                }
+                None => {
+                    let lhs = self.perform_to_numeric(lhs);
+                    let rhs = self.perform_to_numeric(rhs);
+                    let number = match op {
+                        Int32Operation::Sub => self.editor.put_sub(lhs, rhs),
+                        _ => self.editor.put_mul(lhs, rhs),
+                    };
+                    self.editor.put_jump(merge_block, &[number.0.into()]);
+                }
+            }
+        }
+        // end
+
+        self.editor.switch_to_block(merge_block);
+        match result {
+            Some(result) => self.operand_stack.push(Operand::Any(result, None)),
+            None => {
+                let number = NumberIr(self.editor.get_block_param(merge_block, 0));
+                self.operand_stack.push(Operand::Number(number, None));
+            }
+        }
+        true
+    }
+
+    // Loads an int32 value from an operand speculated to be an int32 value.
+    //
+    // Jumps to `fallback_block` if the speculation fails.
+    fn emit_speculative_load_int32(
+        &mut self,
+        operand: &Operand,
+        fallback_block: ir::Block,
+    ) -> Int32Ir {
+        let number = match operand {
+            Operand::Any(value, _) => {
+                let then_block = self.editor.create_block();
+                let is_number = self.editor.put_is_number(*value);
+                self.editor
+                    .put_branch(is_number, then_block, &[], fallback_block, &[]);
+                self.editor.switch_to_block(then_block);
+                self.editor.put_load_number(*value)
+            }
+            Operand::Number(value, _) => *value,
+            _ => unreachable!("{operand:?}"),
+        };
+        let then_block = self.editor.create_block();
+        let (int32, is_int32) = self.editor.put_number_to_exact_int32(number);
+        self.editor
+            .put_branch(is_int32, then_block, &[], fallback_block, &[]);
+        self.editor.switch_to_block(then_block);
+        int32
+    }
+
+    fn load_speculated_number(&mut self, operand: &Operand) -> NumberIr {
+        match operand {
+            Operand::Any(value, _) => self.editor.put_load_number(*value),
+            _ => self.perform_to_numeric(operand),
+        }
     }
 
-    // 13.8.1.1 Runtime Semantics: Evaluation
-    // TODO(perf): compile-time evaluation
+    // TODO(feat): ToPrimitive()
     // TODO(refactor): refactoring
-    fn process_addition(&mut self) {
-        let (lhs, ..) = self.dereference();
-        let (rhs, ..) = self.dereference();
-
+    fn perform_addition(&mut self, lhs: Operand, rhs: Operand) {
         match (&lhs, &rhs) {
             (Operand::String(lhs, _), Operand::String(rhs, _)) => {
                 let string = self
@@ -1943,13 +3202,20 @@ where
     // 13.8.2.1 Runtime Semantics: Evaluation
     fn process_subtraction(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(lhs - rhs);
+            return;
+        }
+
+        if self.perform_speculative_int32_operation(Int32Operation::Sub, &lhs, &rhs) {
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let number = self.editor.put_sub(lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
@@ -1957,15 +3223,18 @@ where
     fn process_left_shift(&mut self) {
         // 13.15.4 EvaluateStringOrNumericBinaryExpression ( leftOperand, opText, rightOperand )
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
         // 13.15.3 ApplyStringOrNumericBinaryOperator ( lval, opText, rval )
         // TODO: BigInt
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(to_int32(lhs).wrapping_shl(to_uint32(rhs)) as f64);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let number = self.editor.put_left_shift(self.support, lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
@@ -1973,15 +3242,18 @@ where
     fn process_signed_right_shift(&mut self) {
         // 13.15.4 EvaluateStringOrNumericBinaryExpression ( leftOperand, opText, rightOperand )
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
         // 13.15.3 ApplyStringOrNumericBinaryOperator ( lval, opText, rval )
         // TODO: BigInt
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(to_int32(lhs).wrapping_shr(to_uint32(rhs)) as f64);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let number = self.editor.put_signed_right_shift(self.support, lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
@@ -1989,67 +3261,82 @@ where
     fn process_unsigned_right_shift(&mut self) {
         // 13.15.4 EvaluateStringOrNumericBinaryExpression ( leftOperand, opText, rightOperand )
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
         // 13.15.3 ApplyStringOrNumericBinaryOperator ( lval, opText, rval )
         // TODO: BigInt
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number(to_uint32(lhs).wrapping_shr(to_uint32(rhs)) as f64);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let number = self.editor.put_unsigned_right_shift(self.support, lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Number(number, None));
     }
 
     // 13.10.1 Runtime Semantics: Evaluation
     fn process_less_than(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_boolean(lhs < rhs);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let boolean = self.editor.put_less_than(lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
     // 13.10.1 Runtime Semantics: Evaluation
     fn process_greater_than(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_boolean(lhs > rhs);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let boolean = self.editor.put_greater_than(lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
     // 13.10.1 Runtime Semantics: Evaluation
     fn process_less_than_or_equal(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_boolean(lhs <= rhs);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let boolean = self.editor.put_less_than_or_equal(lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
     // 13.10.1 Runtime Semantics: Evaluation
     fn process_greater_than_or_equal(&mut self) {
         let (lhs, ..) = self.dereference();
-        let lhs = self.perform_to_numeric(&lhs);
-
         let (rhs, ..) = self.dereference();
-        let rhs = self.perform_to_numeric(&rhs);
 
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_boolean(lhs >= rhs);
+            return;
+        }
+
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
         let boolean = self.editor.put_greater_than_or_equal(lhs, rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
@@ -2075,8 +3362,12 @@ where
         let (lhs, ..) = self.dereference();
         let (rhs, ..) = self.dereference();
 
+        if let Some(value) = constant_is_loosely_equal(&lhs, &rhs) {
+            self.process_boolean(value);
+            return;
+        }
+
         let boolean = self.perform_is_loosely_equal(&lhs, &rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
@@ -2086,9 +3377,13 @@ where
         let (lhs, ..) = self.dereference();
         let (rhs, ..) = self.dereference();
 
+        if let Some(value) = constant_is_loosely_equal(&lhs, &rhs) {
+            self.process_boolean(!value);
+            return;
+        }
+
         let eq = self.perform_is_loosely_equal(&lhs, &rhs);
         let boolean = self.editor.put_logical_not(eq);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
@@ -2098,8 +3393,12 @@ where
         let (lhs, ..) = self.dereference();
         let (rhs, ..) = self.dereference();
 
+        if let Some(value) = constant_is_strictly_equal(&lhs, &rhs) {
+            self.process_boolean(value);
+            return;
+        }
+
         let boolean = self.perform_is_strictly_equal(&lhs, &rhs);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
@@ -2109,57 +3408,70 @@ where
         let (lhs, ..) = self.dereference();
         let (rhs, ..) = self.dereference();
 
+        if let Some(value) = constant_is_strictly_equal(&lhs, &rhs) {
+            self.process_boolean(!value);
+            return;
+        }
+
         let eq = self.perform_is_strictly_equal(&lhs, &rhs);
         let boolean = self.editor.put_logical_not(eq);
-        // TODO(perf): compile-time evaluation
         self.operand_stack.push(Operand::Boolean(boolean, None));
     }
 
     // 13.12.1 Runtime Semantics: Evaluation
     fn process_bitwise_and(&mut self) {
         // 13.15.4 EvaluateStringOrNumericBinaryExpression ( leftOperand, opText, rightOperand )
-        let (lval, ..) = self.dereference();
-        let (rval, ..) = self.dereference();
+        let (lhs, ..) = self.dereference();
+        let (rhs, ..) = self.dereference();
 
         // 13.15.3 ApplyStringOrNumericBinaryOperator ( lval, opText, rval )
-        let lnum = self.perform_to_numeric(&lval);
-        let rnum = self.perform_to_numeric(&rval);
         // TODO: BigInt
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number((to_int32(lhs) & to_int32(rhs)) as f64);
+            return;
+        }
 
-        let number = self.editor.put_bitwise_and(self.support, lnum, rnum);
-        // TODO(perf): compile-time evaluation
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
+        let number = self.editor.put_bitwise_and(self.support, lhs, rhs);
         self.operand_stack.push(Operand::Number(number, None));
     }
 
     // 13.12.1 Runtime Semantics: Evaluation
     fn process_bitwise_xor(&mut self) {
         // 13.15.4 EvaluateStringOrNumericBinaryExpression ( leftOperand, opText, rightOperand )
-        let (lval, ..) = self.dereference();
-        let (rval, ..) = self.dereference();
+        let (lhs, ..) = self.dereference();
+        let (rhs, ..) = self.dereference();
 
         // 13.15.3 ApplyStringOrNumericBinaryOperator ( lval, opText, rval )
-        let lnum = self.perform_to_numeric(&lval);
-        let rnum = self.perform_to_numeric(&rval);
         // TODO: BigInt
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number((to_int32(lhs) ^ to_int32(rhs)) as f64);
+            return;
+        }
 
-        let number = self.editor.put_bitwise_xor(self.support, lnum, rnum);
-        // TODO(perf): compile-time evaluation
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
+        let number = self.editor.put_bitwise_xor(self.support, lhs, rhs);
         self.operand_stack.push(Operand::Number(number, None));
     }
 
     // 13.12.1 Runtime Semantics: Evaluation
     fn process_bitwise_or(&mut self) {
         // 13.15.4 EvaluateStringOrNumericBinaryExpression ( leftOperand, opText, rightOperand )
-        let (lval, ..) = self.dereference();
-        let (rval, ..) = self.dereference();
+        let (lhs, ..) = self.dereference();
+        let (rhs, ..) = self.dereference();
 
         // 13.15.3 ApplyStringOrNumericBinaryOperator ( lval, opText, rval )
-        let lnum = self.perform_to_numeric(&lval);
-        let rnum = self.perform_to_numeric(&rval);
         // TODO: BigInt
+        if let (Some(lhs), Some(rhs)) = (constant_to_number(&lhs), constant_to_number(&rhs)) {
+            self.process_number((to_int32(lhs) | to_int32(rhs)) as f64);
+            return;
+        }
 
-        let number = self.editor.put_bitwise_or(self.support, lnum, rnum);
-        // TODO(perf): compile-time evaluation
+        let lhs = self.perform_to_numeric_with_feedback(&lhs, 0);
+        let rhs = self.perform_to_numeric_with_feedback(&rhs, 1);
+        let number = self.editor.put_bitwise_or(self.support, lhs, rhs);
         self.operand_stack.push(Operand::Number(number, None));
     }
 
@@ -2181,17 +3493,14 @@ where
 
         match self.operand_stack.pop().unwrap() {
             Operand::VariableReference(symbol, Locator::Global) => {
-                let global_object = self.support.global_object();
-                let object = self.editor.put_object(global_object.as_addr());
                 let value = self.editor.put_alloc_any();
                 self.emit_store_operand_to_any(&rhs, value);
-                // TODO(feat): ReferenceError, TypeError
                 let retv = self.emit_create_any();
-                let status = self.editor.put_runtime_set_value_by_symbol(
+                let status = self.editor.put_runtime_set_global_binding_value(
                     self.support,
-                    object,
                     symbol,
                     value,
+                    self.strict,
                     retv,
                 );
                 self.emit_check_status_for_exception(status, retv);
@@ -2201,6 +3510,7 @@ where
                 // TODO: throw a TypeError in the strict mode.
                 // auto* flags_ptr = CreateGetFlagsPtr(value_ptr);
                 self.emit_store_operand_to_any(&rhs, var);
+                self.update_local_type(locator, &rhs);
             }
             Operand::PropertyReference(owner, key) => {
                 // TODO(refactor): reduce code clone
@@ -2220,6 +3530,7 @@ where
                         object,
                         key,
                         value,
+                        self.strict,
                         retv,
                     ),
                     PropertyKey::Number(key) => self.editor.put_runtime_set_value_by_number(
@@ -2227,6 +3538,7 @@ where
                         object,
                         key,
                         value,
+                        self.strict,
                         retv,
                     ),
                     PropertyKey::Any(key) => self.editor.put_runtime_set_value_by_any(
@@ -2234,6 +3546,7 @@ where
                         object,
                         key,
                         value,
+                        self.strict,
                         retv,
                     ),
                 };
@@ -2269,7 +3582,7 @@ where
                 self.operand_stack.push(Operand::Any(retv, None));
             }
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!("{operand:?}"),
@@ -2412,7 +3725,7 @@ where
         self.editor.switch_to_block(flow.merge_block);
     }
 
-    fn process_do_while_loop(&mut self, _id: u16) {
+    fn process_do_while_loop(&mut self, func: &Function, _id: u16) {
         let loop_body = self.editor.create_block();
         let loop_ctrl = self.editor.create_block();
         let loop_test = self.editor.create_block();
@@ -2429,12 +3742,12 @@ where
 
         self.editor.put_jump(loop_start, &[]);
 
-        self.build_loop_ctrl_block(loop_ctrl, loop_continue, loop_break);
+        self.build_loop_ctrl_block(func, loop_ctrl, loop_continue, loop_break);
 
         self.editor.switch_to_block(loop_start);
     }
 
-    fn process_while_loop(&mut self, _id: u16) {
+    fn process_while_loop(&mut self, func: &Function, _id: u16) {
         let loop_test = self.editor.create_block();
         let loop_body = self.editor.create_block();
         let loop_ctrl = self.editor.create_block();
@@ -2451,13 +3764,13 @@ where
 
         self.editor.put_jump(loop_start, &[]);
 
-        self.build_loop_ctrl_block(loop_ctrl, loop_continue, loop_break);
+        self.build_loop_ctrl_block(func, loop_ctrl, loop_continue, loop_break);
 
         self.editor.switch_to_block(loop_start);
     }
 
     // TODO: rewrite using if and break
-    fn process_for_loop(&mut self, _id: u16, flags: LoopFlags) {
+    fn process_for_loop(&mut self, func: &Function, _id: u16, flags: LoopFlags) {
         let has_init = flags.contains(LoopFlags::HAS_INIT);
         let has_test = flags.contains(LoopFlags::HAS_TEST);
         let has_next = flags.contains(LoopFlags::HAS_NEXT);
@@ -2521,13 +3834,14 @@ where
 
         self.editor.put_jump(loop_start, &[]);
 
-        self.build_loop_ctrl_block(loop_ctrl, loop_continue, loop_break);
+        self.build_loop_ctrl_block(func, loop_ctrl, loop_continue, loop_break);
 
         self.editor.switch_to_block(insert_point);
     }
 
     fn build_loop_ctrl_block(
         &mut self,
+        func: &Function,
         loop_ctrl: ir::Block,
         loop_continue: ir::Block,
         loop_break: ir::Block,
@@ -2561,8 +3875,15 @@ where
         self.editor.put_jump(break_or_continue_block, &[]);
 
         self.editor.switch_to_block(break_or_continue_block);
+        let back_edge_block = self.editor.create_block();
         self.editor
-            .put_branch(is_normal_or_continue, loop_continue, &[], loop_break, &[]);
+            .put_branch(is_normal_or_continue, back_edge_block, &[], loop_break, &[]);
+
+        self.editor.switch_to_block(back_edge_block);
+        self.check_interrupt();
+        self.check_heap_limit();
+        self.check_profiler_tick(func);
+        self.editor.put_jump(loop_continue, &[]);
     }
 
     fn process_loop_init(&mut self) {
@@ -2722,6 +4043,12 @@ where
         // TODO(perf): Directly jump to the finally block if the flow selector is not THROW.
         let block = self.editor.create_block();
         let is_throw = self.editor.put_is_flow_selector_throw();
+        // The termination of the execution cannot be caught.
+        let is_terminating = self
+            .editor
+            .put_is_interrupted(self.support.interrupt_flags_addr());
+        let is_not_terminating = self.editor.put_logical_not(is_terminating);
+        let is_throw = self.editor.put_logical_and(is_throw, is_not_terminating);
         self.editor
             .put_branch(is_throw, block, &[], finally_block, &[]);
 
@@ -2950,7 +4277,7 @@ where
                     self.editor
                         .put_write_string_to_scratch_buffer(*value, &mut scratch_buffer);
                 }
-                Operand::Closure(value) => {
+                Operand::Closure(value, _) => {
                     self.editor
                         .put_write_closure_to_scratch_buffer(*value, &mut scratch_buffer);
                 }
@@ -3007,7 +4334,7 @@ where
                         .editor
                         .put_read_string_from_scratch_buffer(&mut scratch_buffer);
                 }
-                Operand::Closure(value) => {
+                Operand::Closure(value, _) => {
                     *value = self
                         .editor
                         .put_read_closure_from_scratch_buffer(&mut scratch_buffer);
@@ -3090,7 +4417,7 @@ where
                     .put_runtime_emit_promise_resolved(self.support, promise, value);
             }
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!("{operand:?}"),
@@ -3102,10 +4429,41 @@ where
         self.operand_stack.pop();
     }
 
+    // The completion value of eval code is returned in `retv` when the evaluation completes
+    // normally.
+    fn process_completion_value(&mut self) {
+        let (operand, ..) = self.dereference();
+        self.store_operand_to_retv(&operand);
+    }
+
+    fn process_throw_statement(&mut self, func: &Function, offset: SourceOffset) {
+        if !self.support.is_debugger_enabled() {
+            self.process_throw();
+            return;
+        }
+
+        let (operand, ..) = self.dereference();
+        self.store_operand_to_retv(&operand);
+
+        // Give the debugger a chance to inspect the frame before the stack is unwound.
+        let (scope_ref, variables) = self.collect_debug_variables();
+        let exception = self.editor.retv();
+        self.editor
+            .put_store_call_site_to_call_context(func.id, offset);
+        self.editor
+            .put_runtime_pause_on_exception(self.support, scope_ref, &variables, exception);
+
+        self.emit_throw_retv();
+    }
+
     fn process_throw(&mut self) {
         let (operand, ..) = self.dereference();
         self.store_operand_to_retv(&operand);
+        self.emit_throw_retv();
+    }
 
+    // Throws the value stored in the return value.
+    fn emit_throw_retv(&mut self) {
         self.editor.put_store_status(Status::EXCEPTION);
         self.editor.put_store_flow_selector(FlowSelector::THROW);
 
@@ -3129,8 +4487,72 @@ where
         self.operand_stack.push(operand);
     }
 
-    fn process_debugger(&mut self) {
-        self.editor.put_runtime_launch_debugger(self.support);
+    fn process_debugger(&mut self, func: &Function, offset: SourceOffset) {
+        let (scope_ref, variables) = self.collect_debug_variables();
+        // The location of the `debugger` statement is passed to the debugger as a call site.
+        self.editor
+            .put_store_call_site_to_call_context(func.id, offset);
+        self.editor
+            .put_runtime_launch_debugger(self.support, scope_ref, &variables);
+    }
+
+    // Gives the debugger a chance to pause the execution before a call if a breakpoint is set or
+    // a step is being performed.
+    fn emit_pause_point(&mut self, func: &Function, offset: SourceOffset) {
+        if !self.support.is_debugger_enabled() || offset == SourceOffset::UNKNOWN {
+            return;
+        }
+
+        let then_block = self.editor.create_block();
+        let merge_block = self.editor.create_block();
+        let active = self
+            .editor
+            .put_is_pause_point_active(self.support.pause_point_active_addr());
+        self.editor
+            .put_branch(active, then_block, &[], merge_block, &[]);
+        self.editor.switch_to_block(then_block);
+        let (scope_ref, variables) = self.collect_debug_variables();
+        // The location of the pause point is passed to the debugger as a call site.
+        self.editor
+            .put_store_call_site_to_call_context(func.id, offset);
+        self.editor
+            .put_runtime_handle_pause_point(self.support, scope_ref, &variables);
+        self.editor.put_jump(merge_block, &[]);
+        self.editor.switch_to_block(merge_block);
+    }
+
+    fn process_coverage(&mut self, index: u32) {
+        if !self.support.is_coverage_enabled() {
+            return;
+        }
+        // The counters of the blocks follow the counter of the function calls.
+        self.editor
+            .put_increment_counter(self.support.coverage_counters_addr(), index + 1);
+    }
+
+    // Collects the addresses of the variables visible in the current scope.
+    //
+    // The order of the variables is the same as `ScopeTree::iter_function_scopes()`.  Variables
+    // in the global scope are not included.
+    fn collect_debug_variables(&mut self) -> (ScopeRef, Vec<AnyIr>) {
+        if !self.control_flow_stack.has_scope_flow() {
+            return (ScopeRef::NONE, vec![]);
+        }
+        let scope_ref = self.control_flow_stack.scope_flow().scope_ref;
+        let scope_tree = self.scope_tree;
+        let mut variables = vec![];
+        for (_, scope) in scope_tree.iter_function_scopes(scope_ref) {
+            for variable in scope.variables.iter() {
+                let value = match variable.locator() {
+                    Locator::Param(index) => self.get_param(index),
+                    Locator::Local(index) => self.get_local(index),
+                    Locator::Capture(index) => self.editor.put_load_captured_value(index),
+                    Locator::Global => continue,
+                };
+                variables.push(value);
+            }
+        }
+        (scope_ref, variables)
     }
 
     // commonly used functions
@@ -3144,7 +4566,7 @@ where
             | Operand::Object(_) => self.editor.put_boolean(true),
             Operand::Any(value, ..) => self.editor.put_is_non_nullish(*value),
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!("{operand:?}"),
@@ -3158,9 +4580,9 @@ where
         }
     }
 
-    fn pop_closure(&mut self) -> ClosureIr {
+    fn pop_closure(&mut self) -> (ClosureIr, LambdaId) {
         match self.operand_stack.pop().unwrap() {
-            Operand::Closure(value) => value,
+            Operand::Closure(value, lambda_id) => (value, lambda_id),
             _ => unreachable!(),
         }
     }
@@ -3198,7 +4620,7 @@ where
             Operand::Object(_) => self.editor.put_boolean(true),
             Operand::Any(value, ..) => self.editor.put_runtime_to_boolean(self.support, *value),
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => {
@@ -3225,13 +4647,99 @@ where
             }
             Operand::Any(value, ..) => self.editor.put_runtime_to_numeric(self.support, *value),
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!("{operand:?}"),
         }
     }
 
+    // Converts an operand of a numeric operator into a number with the type feedback.
+    //
+    // `index` is the index of the operand at the current site.  An `Any` operand is speculated to
+    // be a number if only numbers have been observed at the operand.
+    fn perform_to_numeric_with_feedback(&mut self, operand: &Operand, index: usize) -> NumberIr {
+        let kinds = self.observe_operand_kinds(operand, index);
+        match operand {
+            Operand::Any(value, _) if kinds.is_some_and(TypeFeedback::is_number_only) => {
+                self.emit_speculative_load_number(*value)
+            }
+            _ => self.perform_to_numeric(operand),
+        }
+    }
+
+    // Emits code to record the kind of an `Any` operand at the current site if the function
+    // collects the type feedback.  Returns the kinds observed before the optimization if the
+    // function is being optimized.
+    fn observe_operand_kinds(&mut self, operand: &Operand, index: usize) -> Option<u32> {
+        let Operand::Any(value, _) = operand else {
+            return None;
+        };
+        let slot = self.type_feedback_slot(index)?;
+        let slots_addr = self.support.type_feedback_addr();
+        if slots_addr != 0 {
+            self.editor.put_record_kind(slots_addr, slot, *value);
+        }
+        if self.support.is_optimizing() {
+            Some(self.support.observed_kinds(slot))
+        } else {
+            None
+        }
+    }
+
+    // Returns the kinds observed at an `Any` operand at the current site before the optimization
+    // if the function is being optimized.  Unlike `observe_operand_kinds()`, no code is emitted.
+    fn observed_operand_kinds(&self, operand: &Operand, index: usize) -> Option<u32> {
+        if !matches!(operand, Operand::Any(..)) || !self.support.is_optimizing() {
+            return None;
+        }
+        let slot = self.type_feedback_slot(index)?;
+        Some(self.support.observed_kinds(slot))
+    }
+
+    // Returns the index of the type feedback slot for the `index`-th operand at the current site.
+    //
+    // The commands of an inlined function have no type feedback slots.
+    fn type_feedback_slot(&self, index: usize) -> Option<usize> {
+        if self.inlining {
+            return None;
+        }
+        let slot = 1 + self.type_feedback_site * 2 + index;
+        (slot < self.num_type_feedback_slots).then_some(slot)
+    }
+
+    // Loads a number from a value speculated to be a number.
+    //
+    // When the speculation fails, the closure falls back to the generic code and the value is
+    // converted in the generic way.
+    fn emit_speculative_load_number(&mut self, value: AnyIr) -> NumberIr {
+        let then_block = self.editor.create_block();
+        let else_block = self.editor.create_block();
+        let merge_block = self.editor.create_block_with_f64();
+
+        let is_number = self.editor.put_is_number(value);
+        self.editor
+            .put_branch(is_number, then_block, &[], else_block, &[]);
+        // then
+        {
+            self.editor.switch_to_block(then_block);
+            let number = self.editor.put_load_number(value);
+            self.editor.put_jump(merge_block, &[number.0.into()]);
+        }
+        // else
+        {
+            self.editor.switch_to_block(else_block);
+            self.editor
+                .put_runtime_deoptimize_lambda(self.support, self.lambda_id);
+            let number = self.editor.put_runtime_to_numeric(self.support, value);
+            self.editor.put_jump(merge_block, &[number.0.into()]);
+        }
+        // end
+
+        self.editor.switch_to_block(merge_block);
+        NumberIr(self.editor.get_block_param(merge_block, 0))
+    }
+
     fn perform_to_any(&mut self, operand: &Operand) -> AnyIr {
         let any = self.editor.put_alloc_any();
         self.emit_store_operand_to_any(operand, any);
@@ -3331,7 +4839,7 @@ where
                     .put_runtime_is_strictly_equal(self.support, lhs, *rhs)
             }
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!("{rhs:?}"),
@@ -3449,6 +4957,39 @@ where
     }
 
     // TODO(refactor): need rethink, especially the return value.
+    // Updates the inferred type of a local variable after an operand is stored into it.
+    fn update_local_type(&mut self, locator: Locator, operand: &Operand) {
+        let Locator::Local(index) = locator else {
+            return;
+        };
+        match operand {
+            // A captured variable may be updated in a closure.
+            Operand::Number(_, value) if !self.captures.contains_key(&locator) => {
+                let block = self.editor.current_block();
+                self.local_types.set_number(block, index, *value);
+            }
+            _ => self.local_types.forget(index),
+        }
+    }
+
+    // Returns the value of a local variable as a number if the variable is known to hold a number
+    // in the current basic block.
+    fn get_local_number(&mut self, locator: Locator) -> Option<Operand> {
+        let Locator::Local(index) = locator else {
+            return None;
+        };
+        let block = self.editor.current_block();
+        let value = self.local_types.get_number(block, index)?;
+        let number = match value {
+            Some(value) => self.editor.put_number(value),
+            None => {
+                let local = self.get_local(index);
+                self.editor.put_load_number(local)
+            }
+        };
+        Some(Operand::Number(number, value))
+    }
+
     fn dereference(&mut self) -> (Operand, Option<PropertyOwner>) {
         logger::debug!(event = "dereference", operand_stack.top = ?self.operand_stack.last());
 
@@ -3459,9 +5000,11 @@ where
                 (Operand::Undefined, None)
             }
             Operand::VariableReference(symbol, locator) => {
+                if let Some(operand) = self.get_local_number(locator) {
+                    return (operand, None);
+                }
                 let value = self.emit_get_variable(symbol, locator);
                 self.emit_check_none(value);
-                // TODO(pref): compile-time evaluation
                 (Operand::Any(value, None), None)
             }
             Operand::PropertyReference(owner, key) => {
@@ -3609,6 +5152,17 @@ where
         self.editor.put_store_argc_to_call_context(argc);
     }
 
+    fn emit_store_args(&mut self, args: &[Operand]) {
+        logger::debug!(event = "emit_store_args", argc = args.len());
+        let argv = self.editor.put_get_argv_from_call_context();
+        for (i, operand) in args.iter().enumerate() {
+            let arg = self.editor.put_get_arg(argv, i as u16);
+            self.emit_store_operand_to_any(operand, arg);
+        }
+        self.editor
+            .put_store_argc_to_call_context(args.len() as u16);
+    }
+
     fn emit_store_operand_to_any(&mut self, operand: &Operand, any: AnyIr) {
         match operand {
             Operand::Undefined => self.editor.put_store_undefined_to_any(any),
@@ -3619,7 +5173,7 @@ where
             Operand::Object(value) => self.editor.put_store_object_to_any(*value, any),
             Operand::Any(value, _) => self.editor.put_store_any_to_any(*value, any),
             Operand::Lambda(..)
-            | Operand::Closure(_)
+            | Operand::Closure(..)
             | Operand::Coroutine(_)
             | Operand::VariableReference(..)
             | Operand::PropertyReference(..) => unreachable!("{operand:?}"),
@@ -3832,14 +5386,34 @@ where
 
     fn emit_throw_reference_error(&mut self) {
         logger::debug!(event = "emit_throw_reference_error");
-        let error = self.editor.put_runtime_create_reference_error(self.support);
+        let error = self.editor.put_runtime_create_reference_error(
+            self.support,
+            self.lambda_id,
+            self.source_offset,
+        );
         self.operand_stack.push(Operand::Object(error));
         self.process_throw();
     }
 
     fn emit_throw_type_error(&mut self) {
         logger::debug!(event = "emit_throw_type_error");
-        let error = self.editor.put_runtime_create_type_error(self.support);
+        let error = self.editor.put_runtime_create_type_error(
+            self.support,
+            self.lambda_id,
+            self.source_offset,
+        );
+        self.operand_stack.push(Operand::Object(error));
+        self.process_throw();
+    }
+
+    fn emit_throw_range_error(&mut self, message: Handle<String>) {
+        logger::debug!(event = "emit_throw_range_error", ?message);
+        let error = self.editor.put_runtime_create_range_error(
+            self.support,
+            self.lambda_id,
+            self.source_offset,
+            message,
+        );
         self.operand_stack.push(Operand::Object(error));
         self.process_throw();
     }
@@ -3870,6 +5444,11 @@ where
         self.editor.put_object(prototype.as_addr())
     }
 
+    fn eval_function(&mut self) -> ObjectIr {
+        let eval_function = self.support.eval_function();
+        self.editor.put_object(eval_function.as_addr())
+    }
+
     fn is_coroutine(&self, func: &Function) -> bool {
         matches!(
             self.support.get_lambda_info(func.id).kind,
@@ -3922,6 +5501,38 @@ impl DerefMut for OperandStack {
 
 /// Values pushed on to the operand stack.
 // TODO(feat): add variant for BigInt
+/// Types of local variables inferred in a basic block.
+///
+/// A local variable is known to hold a number after a number is stored into it until the end of
+/// the basic block.  Calls of functions which may update the variable always end the basic block
+/// because the compiler checks the status of each call.
+#[derive(Default)]
+struct LocalTypes {
+    block: Option<ir::Block>,
+    numbers: FxHashMap<u16, Option<f64>>,
+}
+
+impl LocalTypes {
+    fn get_number(&self, block: ir::Block, index: u16) -> Option<Option<f64>> {
+        if self.block != Some(block) {
+            return None;
+        }
+        self.numbers.get(&index).copied()
+    }
+
+    fn set_number(&mut self, block: ir::Block, index: u16, value: Option<f64>) {
+        if self.block != Some(block) {
+            self.block = Some(block);
+            self.numbers.clear();
+        }
+        self.numbers.insert(index, value);
+    }
+
+    fn forget(&mut self, index: u16) {
+        self.numbers.remove(&index);
+    }
+}
+
 #[derive(Clone, Debug)]
 enum Operand {
     // Values that can be store into a `Value`.
@@ -3932,19 +5543,17 @@ enum Operand {
     Null,
 
     /// Runtime value and optional compile-time constant value of boolean type.
-    // TODO(perf): compile-time evaluation
-    Boolean(BooleanIr, #[allow(unused)] Option<bool>),
+    Boolean(BooleanIr, Option<bool>),
 
     /// Runtime value and optional compile-time constant value of number type.
-    // TODO(perf): compile-time evaluation
-    Number(NumberIr, #[allow(unused)] Option<f64>),
+    Number(NumberIr, Option<f64>),
 
     /// Runtime value and optional compile-time constant value of number type.
     // TODO(perf): compile-time evaluation
     String(StringIr, #[allow(unused)] Option<Handle<String>>),
 
     /// Runtime value of closure type.
-    Closure(ClosureIr),
+    Closure(ClosureIr, LambdaId),
 
     /// Runtime value of object type.
     Object(ObjectIr),
@@ -3965,6 +5574,87 @@ enum Operand {
     PropertyReference(PropertyOwner, PropertyKey),
 }
 
+// compile-time evaluation
+
+// Returns `true` if an operand is a number or speculated to be a number.  Strings are excluded
+// because the addition operator concatenates them.
+fn is_speculated_number(operand: &Operand, kinds: Option<u32>) -> bool {
+    match operand {
+        Operand::Undefined | Operand::Null | Operand::Boolean(..) | Operand::Number(..) => true,
+        Operand::Any(..) => kinds.is_some_and(TypeFeedback::is_number_only),
+        _ => false,
+    }
+}
+
+// Returns `true` if an operand is an int32 constant or speculated to be an int32 value.
+fn is_speculated_int32(operand: &Operand, kinds: Option<u32>) -> bool {
+    match operand {
+        // -0 is not an int32 value.
+        Operand::Number(_, Some(value)) => (*value as i32 as f64).to_bits() == value.to_bits(),
+        Operand::Any(..) => kinds.is_some_and(TypeFeedback::is_int32_only),
+        _ => false,
+    }
+}
+
+// 7.1.4 ToNumber ( argument )
+fn constant_to_number(operand: &Operand) -> Option<f64> {
+    match operand {
+        Operand::Undefined => Some(f64::NAN),
+        Operand::Null => Some(0.0),
+        Operand::Boolean(_, Some(value)) => Some(if *value { 1.0 } else { 0.0 }),
+        Operand::Number(_, value) => *value,
+        _ => None,
+    }
+}
+
+// 7.1.2 ToBoolean ( argument )
+fn constant_to_boolean(operand: &Operand) -> Option<bool> {
+    match operand {
+        Operand::Undefined | Operand::Null => Some(false),
+        Operand::Boolean(_, value) => *value,
+        Operand::Number(_, Some(value)) => Some(!(*value == 0.0 || value.is_nan())),
+        _ => None,
+    }
+}
+
+// 7.2.14 IsStrictlyEqual ( x, y )
+fn constant_is_strictly_equal(lhs: &Operand, rhs: &Operand) -> Option<bool> {
+    match (lhs, rhs) {
+        (Operand::Undefined, Operand::Undefined) | (Operand::Null, Operand::Null) => Some(true),
+        (Operand::Boolean(_, Some(lhs)), Operand::Boolean(_, Some(rhs))) => Some(lhs == rhs),
+        (Operand::Number(_, Some(lhs)), Operand::Number(_, Some(rhs))) => Some(lhs == rhs),
+        _ if is_constant_primitive(lhs) && is_constant_primitive(rhs) => Some(false),
+        _ => None,
+    }
+}
+
+// 7.2.13 IsLooselyEqual ( x, y )
+fn constant_is_loosely_equal(lhs: &Operand, rhs: &Operand) -> Option<bool> {
+    match (lhs, rhs) {
+        (Operand::Undefined | Operand::Null, Operand::Undefined | Operand::Null) => Some(true),
+        (Operand::Undefined | Operand::Null, _) | (_, Operand::Undefined | Operand::Null)
+            if is_constant_primitive(lhs) && is_constant_primitive(rhs) =>
+        {
+            Some(false)
+        }
+        (
+            Operand::Boolean(..) | Operand::Number(..),
+            Operand::Boolean(..) | Operand::Number(..),
+        ) => Some(constant_to_number(lhs)? == constant_to_number(rhs)?),
+        _ => None,
+    }
+}
+
+fn is_constant_primitive(operand: &Operand) -> bool {
+    matches!(
+        operand,
+        Operand::Undefined
+            | Operand::Null
+            | Operand::Boolean(_, Some(_))
+            | Operand::Number(_, Some(_))
+    )
+}
+
 #[derive(Clone, Copy)]
 enum CaptureAddr {
     Direct(CaptureIr),
@@ -4038,6 +5728,29 @@ struct BooleanIr(ir::Value);
 #[derive(Clone, Copy, Debug)]
 struct NumberIr(ir::Value);
 
+/// A runtime int32 value in `ir::types::I32`.
+#[derive(Clone, Copy, Debug)]
+struct Int32Ir(ir::Value);
+
+/// Where an inlined lambda function loads captured values from.
+#[derive(Clone, Copy, Debug)]
+enum InlinedCaptures {
+    /// The captures of the closure.
+    Closure(ClosureIr),
+
+    /// The variables visible from the scope where the closure replaced with scalars would be
+    /// created.
+    Scope(ScopeRef),
+}
+
+/// An arithmetic operation performed with 32-bit integers.
+#[derive(Clone, Copy, Debug)]
+enum Int32Operation {
+    Add,
+    Sub,
+    Mul,
+}
+
 #[derive(Clone, Copy, Debug)]
 struct StringIr(ir::Value);
 
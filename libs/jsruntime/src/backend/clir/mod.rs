@@ -28,6 +28,7 @@ use support::RuntimeFunctionIds;
 
 pub use compiler::compile;
 pub use compiler::compile_function;
+pub use compiler::optimize_function;
 
 pub fn initialize() {
     // Nothing to do.
@@ -45,6 +46,9 @@ pub struct CodeRegistry<X> {
     lambda_sig: ir::Signature,
     runtime_func_ids: RuntimeFunctionIds,
     id_map: FxHashMap<LambdaId, FuncId>,
+    // Functions recompiled with the type feedback.  An entry is removed when the function is
+    // deoptimized but the compiled code is kept because closures may still refer to it.
+    optimized_id_map: FxHashMap<LambdaId, FuncId>,
     _phantom: PhantomData<X>,
 }
 
@@ -74,12 +78,24 @@ impl<X> CodeRegistry<X> {
             lambda_sig,
             runtime_func_ids,
             id_map: Default::default(),
+            optimized_id_map: Default::default(),
             _phantom: PhantomData,
         }
     }
 
     pub fn get_lambda(&self, lambda_id: LambdaId) -> Option<Lambda<X>> {
-        let func_id = *self.id_map.get(&lambda_id)?;
+        self.get_lambda_by_func_id(*self.id_map.get(&lambda_id)?)
+    }
+
+    pub fn get_optimized_lambda(&self, lambda_id: LambdaId) -> Option<Lambda<X>> {
+        self.get_lambda_by_func_id(*self.optimized_id_map.get(&lambda_id)?)
+    }
+
+    pub fn remove_optimized_lambda(&mut self, lambda_id: LambdaId) {
+        self.optimized_id_map.remove(&lambda_id);
+    }
+
+    fn get_lambda_by_func_id(&self, func_id: FuncId) -> Option<Lambda<X>> {
         let ptr = self
             .module
             .as_ref()
@@ -99,17 +115,29 @@ impl<X> CodeRegistry<X> {
 
     fn codegen(&mut self, func: &Function, ctx: &mut codegen::Context) {
         logger::debug!(event = "codegen");
+        let func_id = self.define_function(func.id.make_name(), ctx);
+        self.id_map.insert(func.id, func_id);
+    }
+
+    fn codegen_optimized(&mut self, func: &Function, ctx: &mut codegen::Context) {
+        logger::debug!(event = "codegen_optimized");
+        // A function is optimized at most once.
+        let name = format!("{}_opt", func.id.make_name());
+        let func_id = self.define_function(name, ctx);
+        self.optimized_id_map.insert(func.id, func_id);
+    }
+
+    fn define_function(&mut self, name: String, ctx: &mut codegen::Context) -> FuncId {
         // It's unnecessary to declare JavaScript functions called in a JavaScript function before
         // the JIT compilation.  Because every JavaScript function will be called indirectly.
-        let name = func.id.make_name();
         let module = self.module.as_mut().unwrap();
         let func_id = module
             .declare_function(&name, Linkage::Local, &self.lambda_sig)
             .unwrap();
-        self.id_map.insert(func.id, func_id);
         module.define_function(func_id, ctx).unwrap();
         module.clear_context(ctx);
         module.finalize_definitions().unwrap();
+        func_id
     }
 }
 
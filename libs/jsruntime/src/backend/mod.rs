@@ -5,6 +5,7 @@ pub use clir::CodeRegistry;
 pub use clir::compile;
 pub use clir::compile_function;
 pub use clir::initialize;
+pub use clir::optimize_function;
 
 #[derive(Debug, thiserror::Error)]
 pub enum CompileError {
@@ -70,11 +70,16 @@ function makeCraneliftIRType(type) {
     case '&std::ffi::CStr':
     case '&Capture':
     case '&mut Capture':
+    case '&CallContext':
+    case '&mut CallContext':
     case '&mut Closure':
     case '&mut Coroutine':
     case '&mut Object':
     case '&Value':
     case '&mut Value':
+    case '*const &Value':
+    case '*const HandleMut<Capture>':
+    case '&TemplateStrings':
     case 'Lambda':
     case '&mut Runtime<X>':
     case 'Handle<String>':
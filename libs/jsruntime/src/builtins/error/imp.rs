@@ -55,6 +55,8 @@ pub fn constructor<X>(runtime: &mut Runtime<X>, context: &mut CallContext) -> Re
         }
     }
 
+    runtime.set_error_stack(object, context);
+
     Ok(Value::Object(object))
 }
 
@@ -74,7 +76,7 @@ pub fn error_is_error<X>(
 pub fn error_prototype_message<X>(_runtime: &mut Runtime<X>, mut prototype: HandleMut<Object>) {
     let _ = prototype.define_own_property(
         Symbol::MESSAGE.into(),
-        Property::data_xxx(Value::String(crate::types::string::EMPTY)),
+        Property::data_wxc(Value::String(crate::types::string::EMPTY)),
     );
 }
 
@@ -82,7 +84,7 @@ pub fn error_prototype_message<X>(_runtime: &mut Runtime<X>, mut prototype: Hand
 pub fn error_prototype_name<X>(_runtime: &mut Runtime<X>, mut prototype: HandleMut<Object>) {
     let _ = prototype.define_own_property(
         Symbol::NAME.into(),
-        Property::data_xxx(Value::String(crate::types::string::EMPTY)),
+        Property::data_wxc(Value::String(crate::types::string::EMPTY)),
     );
 }
 
@@ -93,30 +95,38 @@ pub fn error_prototype_to_string<X>(
 ) -> Result<Value, Error> {
     logger::debug!(event = "error_prototype_to_string");
     let object = match context.this() {
-        Value::Object(object) => object,
+        Value::Object(object) => *object,
         _ => return type_error!(),
     };
+    runtime.error_to_string(object).map(Value::String)
+}
 
-    let name = match object.get_value(&Symbol::NAME.into()) {
-        None | Some(Value::Undefined) => NAME,
-        Some(value) => runtime.value_to_string(value)?,
-    };
-
-    let message = match object.get_value(&Symbol::MESSAGE.into()) {
-        None | Some(Value::Undefined) => crate::types::string::EMPTY,
-        Some(value) => runtime.value_to_string(value)?,
-    };
-
-    let result = if name.is_empty() {
-        message
-    } else if message.is_empty() {
-        name
-    } else {
-        let result = runtime.concat_strings(const_string_handle!(&[0x003A, 0x0020]), message);
-        runtime.concat_strings(name, result)
-    };
-
-    Ok(Value::String(result))
+impl<X> Runtime<X> {
+    pub(crate) fn error_to_string(
+        &mut self,
+        object: HandleMut<Object>,
+    ) -> Result<Handle<String>, Error> {
+        let name = match object.get_value(&Symbol::NAME.into()) {
+            None | Some(Value::Undefined) => NAME,
+            Some(value) => self.value_to_string(value)?,
+        };
+
+        let message = match object.get_value(&Symbol::MESSAGE.into()) {
+            None | Some(Value::Undefined) => crate::types::string::EMPTY,
+            Some(value) => self.value_to_string(value)?,
+        };
+
+        let result = if name.is_empty() {
+            message
+        } else if message.is_empty() {
+            name
+        } else {
+            let result = self.concat_strings(const_string_handle!(&[0x003A, 0x0020]), message);
+            self.concat_strings(name, result)
+        };
+
+        Ok(result)
+    }
 }
 
 const NAME: Handle<String> = const_string_handle!(jsparser::symbol::builtin::names::ERROR);
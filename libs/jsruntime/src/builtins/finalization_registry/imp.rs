@@ -0,0 +1,127 @@
+//$id finalization_registry
+//$class FinalizationRegistry
+//$inherits object
+
+use crate::Error;
+use crate::Runtime;
+use crate::types::CallContext;
+use crate::types::FinalizationRegistry;
+use crate::types::HeldValue;
+use crate::types::Object;
+use crate::types::Value;
+
+use super::logger;
+
+//#sec-finalization-registry-cleanup-callback constructor
+pub fn constructor<X>(runtime: &mut Runtime<X>, context: &mut CallContext) -> Result<Value, Error> {
+    logger::debug!(event = "finalization_registry");
+
+    if !context.is_new() {
+        return type_error!();
+    }
+
+    let cleanup_callback = match context.arg(0) {
+        Value::Object(callback) if callback.is_callable() => *callback,
+        _ => return type_error!(),
+    };
+
+    let mut object = if let Value::Object(this) = context.this() {
+        *this
+    } else {
+        let mut object = runtime.create_object();
+        object.set_prototype(runtime.builtins.finalization_registry_prototype);
+        object
+    };
+
+    let registry = runtime
+        .heap
+        .alloc_mut(FinalizationRegistry::new(cleanup_callback));
+    object.set_finalization_registry(registry);
+
+    Ok(Value::Object(object))
+}
+
+//#sec-finalization-registry.prototype.register prototype.function
+pub fn finalization_registry_prototype_register<X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+) -> Result<Value, Error> {
+    logger::debug!(event = "finalization_registry_prototype_register");
+
+    let object = match context.this() {
+        Value::Object(this) if this.is_finalization_registry() => *this,
+        _ => return type_error!(),
+    };
+
+    // TODO(feat): CanBeHeldWeakly() returns true for non-registered symbols.
+    let target = match context.arg(0) {
+        Value::Object(target) => *target,
+        _ => return type_error!(),
+    };
+
+    let held_value = context.arg(1);
+    if matches!(held_value, Value::Object(value) if *value == target) {
+        return type_error!();
+    }
+
+    let unregister_token = match context.arg(2) {
+        Value::Object(token) => Some(*token),
+        Value::Undefined => None,
+        _ => return type_error!(),
+    };
+
+    let cell = runtime.heap.alloc_finalization_cell(
+        target,
+        HeldValue {
+            registry: object,
+            value: held_value.clone(),
+        },
+    );
+    let unregister_token = unregister_token.map(|token| runtime.heap.alloc_weak(token));
+    object
+        .finalization_registry()
+        .register(cell, unregister_token);
+
+    Ok(Value::Undefined)
+}
+
+//#sec-finalization-registry.prototype.unregister prototype.function
+pub fn finalization_registry_prototype_unregister<X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+) -> Result<Value, Error> {
+    logger::debug!(event = "finalization_registry_prototype_unregister");
+
+    let object = match context.this() {
+        Value::Object(this) if this.is_finalization_registry() => *this,
+        _ => return type_error!(),
+    };
+
+    // TODO(feat): CanBeHeldWeakly() returns true for non-registered symbols.
+    let unregister_token = match context.arg(0) {
+        Value::Object(token) => *token,
+        _ => return type_error!(),
+    };
+
+    let removed = object.finalization_registry().unregister(unregister_token);
+    Ok(Value::Boolean(removed))
+}
+
+impl<X> Runtime<X> {
+    /// Schedules cleanup jobs for cells whose targets have been reclaimed.
+    ///
+    /// This must be called after each garbage collection.
+    pub(crate) fn schedule_finalization_cleanup_jobs(&mut self) {
+        while let Some(cell) = self.heap.pop_finalization::<Object, HeldValue>() {
+            let held_value = cell.held_value();
+            let mut registry = held_value.registry.finalization_registry();
+            // 9.13 CleanupFinalizationRegistry ( finalizationRegistry )
+            if registry.remove_cell(cell) {
+                self.job_runner.emit_finalization_cleanup(
+                    registry.cleanup_callback(),
+                    held_value.value.clone(),
+                );
+            }
+        }
+    }
+}
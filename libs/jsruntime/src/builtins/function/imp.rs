@@ -28,13 +28,15 @@ macro_rules! catch {
     };
 }
 
-//#sec-function-p1-p2-pn-body constructor
+//#sec-function-p1-p2-pn-body constructor { "no_adapter": true }
 pub fn constructor<X>(
-    _runtime: &mut Runtime<X>,
-    _context: &mut CallContext,
-) -> Result<Value, Error> {
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    retv: &mut Value,
+) -> Status {
     logger::debug!(event = "function");
-    runtime_todo!("TODO: Function constructor")
+    // TODO(feat): NewTarget
+    runtime.create_dynamic_function(context, retv)
 }
 
 //#sec-function.prototype.apply prototype.function { "no_adapter": true }
@@ -50,12 +52,12 @@ pub fn function_prototype_apply<X>(
         Value::None => unreachable!(),
         Value::Undefined | Value::Null => {
             // TODO: PrepareForTailCall()
-            runtime.call(context, func, this, &[], retv)
+            runtime.call_with_context(context, func, this, &[], retv)
         }
         args => {
             let args = catch!(runtime.create_vec_from_array_like(args); runtime, retv);
             // TODO: PrepareForTailCall()
-            runtime.call(context, func, this, &args, retv)
+            runtime.call_with_context(context, func, this, &args, retv)
         }
     }
 }
@@ -111,19 +113,44 @@ pub fn function_prototype_call<X>(
         &[]
     };
     // TODO: PrepareForTailCall()
-    runtime.call(context, func, this, args, retv)
+    runtime.call_with_context(context, func, this, args, retv)
 }
 
 //#sec-function.prototype.tostring prototype.function
 pub fn function_prototype_to_string<X>(
-    _runtime: &mut Runtime<X>,
-    _context: &mut CallContext,
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
 ) -> Result<Value, Error> {
     logger::debug!(event = "function_prototype_to_string");
-    runtime_todo!("TODO: Function.prototype.toString()")
+    let func = runtime.this_func(context.this())?;
+    if let Some(source_text) = runtime.function_source_text(func) {
+        return Ok(Value::String(runtime.create_string(&source_text)));
+    }
+    // NativeFunction syntax for built-in functions, host functions and bound functions.
+    let name = match func.get_value(&Symbol::NAME.into()) {
+        Some(Value::String(name)) => std::string::String::from_utf16_lossy(&name.make_utf16()),
+        _ => std::string::String::new(),
+    };
+    let source_text = format!("function {name}() {{ [native code] }}");
+    Ok(Value::String(runtime.create_string_from_utf8(&source_text)))
 }
 
 impl<X> Runtime<X> {
+    // Returns the source text of a function defined in a program.  `None` is returned for
+    // functions implemented in Rust.
+    fn function_source_text(&self, func: HandleMut<Object>) -> Option<Vec<u16>> {
+        let lambda_id = func.closure().lambda_id;
+        if lambda_id == LambdaId::HOST {
+            return None;
+        }
+        let lambda_info = self.lambda_registry.get(lambda_id);
+        let program = &self.programs[lambda_info.program_id.index()];
+        let function = &program.functions[lambda_info.function_index as usize];
+        let range = &function.source_range;
+        let source_text = &program.source[range.start as usize..range.end as usize];
+        Some(source_text.encode_utf16().collect())
+    }
+
     fn this_func(&mut self, this: &Value) -> Result<HandleMut<Object>, Error> {
         match this {
             Value::None => unreachable!(),
@@ -148,7 +175,7 @@ impl<X> Runtime<X> {
             let (func, this, mut args) = closure.get_bound_function_params();
             args.extend_from_slice(context.args());
             // TODO(feat): [[Construct]], newTarget
-            runtime.call(context, func, this, &args, retv)
+            runtime.call_with_context(context, func, this, &args, retv)
         }
 
         let prototype = func.prototype();
@@ -5,6 +5,7 @@ use jsparser::Symbol;
 use crate::Error;
 use crate::Runtime;
 use crate::types::CallContext;
+use crate::types::Status;
 use crate::types::Value;
 use crate::types::object::Property;
 
@@ -32,9 +33,10 @@ pub fn global_undefined<X>(runtime: &mut Runtime<X>) {
     runtime.define_global_property(Symbol::KEYWORD_UNDEFINED, prop);
 }
 
-//#sec-eval-x global.function
-pub fn eval<X>(_runtime: &mut Runtime<X>, _context: &mut CallContext) -> Result<Value, Error> {
-    runtime_todo!("eval: not implemented")
+//#sec-eval-x global.function { "no_adapter": true }
+pub fn eval<X>(runtime: &mut Runtime<X>, context: &mut CallContext, retv: &mut Value) -> Status {
+    // A direct eval is performed in the generated code without calling this function.
+    runtime.perform_indirect_eval(context, retv)
 }
 
 //#sec-isfinite-number global.function
@@ -105,6 +107,12 @@ pub fn define_eval_error_constructor<X>(runtime: &mut Runtime<X>) {
     runtime.define_constructor(Symbol::EVAL_ERROR, constructor);
 }
 
+//#sec-constructor-properties-of-the-global-object-finalization-registry global.constructor
+pub fn define_finalization_registry_constructor<X>(runtime: &mut Runtime<X>) {
+    let constructor = runtime.builtins.finalization_registry_constructor;
+    runtime.define_constructor(Symbol::FINALIZATION_REGISTRY, constructor);
+}
+
 //#sec-constructor-properties-of-the-global-object-function global.constructor
 pub fn define_function_constructor<X>(runtime: &mut Runtime<X>) {
     let constructor = runtime.builtins.function_constructor;
@@ -164,3 +172,9 @@ pub fn define_uri_error_constructor<X>(runtime: &mut Runtime<X>) {
     let constructor = runtime.builtins.uri_error_constructor;
     runtime.define_constructor(Symbol::URI_ERROR, constructor);
 }
+
+//#sec-constructor-properties-of-the-global-object-weakref global.constructor
+pub fn define_weak_ref_constructor<X>(runtime: &mut Runtime<X>) {
+    let constructor = runtime.builtins.weak_ref_constructor;
+    runtime.define_constructor(Symbol::WEAK_REF, constructor);
+}
@@ -1,6 +1,7 @@
 mod aggregate_error;
 mod error;
 mod eval_error;
+mod finalization_registry;
 mod function;
 mod global;
 mod internal_error;
@@ -12,6 +13,7 @@ mod string;
 mod syntax_error;
 mod type_error;
 mod uri_error;
+mod weak_ref;
 
 use jsgc::Handle;
 use jsgc::HandleMut;
@@ -36,10 +38,12 @@ logging::define_logger! {}
 // 1. Create an empty object for each built-in objects.
 // 2. Initialize built-in objects.
 //
-#[derive(jsgc_derive::Trace)]
+#[derive(Clone, jsgc_derive::Trace)]
 pub(crate) struct Builtins {
     // [[GlobalObject]]
     pub(crate) global_object: HandleMut<Object>,
+    // %eval%
+    pub(crate) eval_function: HandleMut<Object>,
     // %Object%
     pub(crate) object_constructor: HandleMut<Object>,
     // %Object.prototype%
@@ -56,6 +60,14 @@ pub(crate) struct Builtins {
     pub(crate) promise_constructor: HandleMut<Object>,
     // %Promise.prototype%
     pub(crate) promise_prototype: HandleMut<Object>,
+    // %WeakRef%
+    pub(crate) weak_ref_constructor: HandleMut<Object>,
+    // %WeakRef.prototype%
+    pub(crate) weak_ref_prototype: HandleMut<Object>,
+    // %FinalizationRegistry%
+    pub(crate) finalization_registry_constructor: HandleMut<Object>,
+    // %FinalizationRegistry.prototype%
+    pub(crate) finalization_registry_prototype: HandleMut<Object>,
     // %Error%
     pub(crate) error_constructor: HandleMut<Object>,
     // %Error.prototype%
@@ -99,6 +111,8 @@ impl Builtins {
     pub(crate) fn new(heap: &mut Heap) -> Self {
         Self {
             global_object: heap.alloc_mut(Object::new()),
+            // Replaced with the `eval` property of the global object.
+            eval_function: heap.alloc_mut(Object::new()),
             object_constructor: heap.alloc_mut(Object::new()),
             object_prototype: heap.alloc_mut(Object::new()),
             function_constructor: heap.alloc_mut(Object::new()),
@@ -107,6 +121,10 @@ impl Builtins {
             string_prototype: heap.alloc_mut(Object::new()),
             promise_constructor: heap.alloc_mut(Object::new()),
             promise_prototype: heap.alloc_mut(Object::new()),
+            weak_ref_constructor: heap.alloc_mut(Object::new()),
+            weak_ref_prototype: heap.alloc_mut(Object::new()),
+            finalization_registry_constructor: heap.alloc_mut(Object::new()),
+            finalization_registry_prototype: heap.alloc_mut(Object::new()),
             error_constructor: heap.alloc_mut(Object::new()),
             error_prototype: heap.alloc_mut(Object::new()),
             aggregate_error_constructor: heap.alloc_mut(Object::new()),
@@ -134,6 +152,15 @@ impl<X> Runtime<X> {
     pub(crate) fn init_builtin_objects(&mut self) {
         self.init_intrinsic_objects();
         self.init_global_object();
+        self.init_eval_function();
+    }
+
+    // %eval% is compared with the callee of a direct eval in compiled code.
+    fn init_eval_function(&mut self) {
+        match self.builtins.global_object.get_value(&Symbol::EVAL.into()) {
+            Some(Value::Object(func)) => self.builtins.eval_function = *func,
+            _ => unreachable!(),
+        }
     }
 
     fn init_intrinsic_objects(&mut self) {
@@ -145,6 +172,10 @@ impl<X> Runtime<X> {
         self.init_string_prototype();
         self.init_promise_constructor();
         self.init_promise_prototype();
+        self.init_weak_ref_constructor();
+        self.init_weak_ref_prototype();
+        self.init_finalization_registry_constructor();
+        self.init_finalization_registry_prototype();
         self.init_error_constructor();
         self.init_error_prototype();
         self.init_aggregate_error_constructor();
@@ -165,7 +196,10 @@ impl<X> Runtime<X> {
         self.init_uri_error_prototype();
     }
 
-    fn create_builtin_function(&mut self, params: &BuiltinFunctionParams<X>) -> HandleMut<Object> {
+    pub(crate) fn create_builtin_function(
+        &mut self,
+        params: &BuiltinFunctionParams<X>,
+    ) -> HandleMut<Object> {
         logger::debug!(
             event = "create_builtin_function",
             ?params.lambda,
@@ -210,7 +244,7 @@ impl<X> Runtime<X> {
 
     // 7.1.4 ToNumber ( argument )
     // TODO: code clone, see backend::bridge::runtime_to_numeric
-    fn value_to_number(&mut self, value: &Value) -> Result<f64, Error> {
+    pub(crate) fn value_to_number(&mut self, value: &Value) -> Result<f64, Error> {
         logger::debug!(event = "runtime.value_to_numeric", ?value);
         match value {
             Value::None => unreachable!("Value::None"),
@@ -283,9 +317,11 @@ impl<X> Runtime<X> {
     pub(crate) fn create_exception(&mut self, err: Error) -> Value {
         let msg = err.message.map(Handle::from_ref);
         Value::Object(match err.kind {
+            ErrorKind::EvalError => self.create_eval_error(msg),
             ErrorKind::SyntaxError => self.create_syntax_error(msg),
             ErrorKind::TypeError => self.create_type_error(msg),
             ErrorKind::RangeError => self.create_range_error(msg),
+            ErrorKind::ReferenceError => self.create_reference_error(msg),
             ErrorKind::InternalError => self.create_internal_error(msg),
         })
     }
@@ -359,10 +395,10 @@ fn require_object_coercible(value: &Value) -> Result<(), Error> {
     }
 }
 
-struct BuiltinFunctionParams<'a, X> {
-    lambda: Lambda<X>,
+pub(crate) struct BuiltinFunctionParams<'a, X> {
+    pub(crate) lambda: Lambda<X>,
     #[allow(unused)]
-    name: Handle<String>,
-    length: u16,
-    slots: &'a [Value],
+    pub(crate) name: Handle<String>,
+    pub(crate) length: u16,
+    pub(crate) slots: &'a [Value],
 }
@@ -105,10 +105,39 @@ pub fn object_define_property<X>(
     };
     let key = runtime.value_to_property_key(context.arg(1))?;
     let prop = runtime.value_to_property(context.arg(2))?;
-    obj.define_own_property(key, prop)?;
+    if !obj.define_own_property(key, prop)? {
+        return type_error!("cannot define property");
+    }
     Ok(Value::Object(obj))
 }
 
+//#sec-object.isextensible constructor.function
+pub fn object_is_extensible<X>(
+    _runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+) -> Result<Value, Error> {
+    logger::debug!(event = "object_is_extensible");
+    match context.arg(0) {
+        Value::None => unreachable!(),
+        Value::Object(object) => Ok(Value::Boolean(object.is_extensible())),
+        _ => Ok(Value::FALSE),
+    }
+}
+
+//#sec-object.preventextensions constructor.function
+pub fn object_prevent_extensions<X>(
+    _runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+) -> Result<Value, Error> {
+    logger::debug!(event = "object_prevent_extensions");
+    let arg = context.arg(0);
+    if let Value::Object(object) = arg {
+        let mut object = *object;
+        object.prevent_extensions();
+    }
+    Ok(arg.clone())
+}
+
 //#sec-object.prototype.hasownproperty prototype.function
 pub fn object_prototype_has_own_property<X>(
     runtime: &mut Runtime<X>,
@@ -213,7 +242,9 @@ impl<X> Runtime<X> {
             let desc_obj = prop.value();
             if !matches!(desc_obj, Value::Undefined) && prop.is_enumerable() {
                 let new_prop = self.value_to_property(desc_obj)?;
-                obj.define_own_property(key.clone(), new_prop)?;
+                if !obj.define_own_property(key.clone(), new_prop)? {
+                    return type_error!("cannot define property");
+                }
             }
         }
         Ok(obj)
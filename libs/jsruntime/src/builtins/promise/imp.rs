@@ -47,7 +47,7 @@ pub fn constructor<X>(runtime: &mut Runtime<X>, context: &mut CallContext) -> Re
 
     let (resolve, reject) = runtime.create_resolving_functions(object);
     let mut retv = Value::None;
-    if let Status::Exception = runtime.call(
+    if let Status::Exception = runtime.call_with_context(
         context,
         executor,
         &Value::Undefined,
@@ -64,6 +64,43 @@ pub fn string_from_code_point<X>(
     Ok(Value::String(string))
 }
 
+//#sec-string.raw constructor.function
+pub fn string_raw<X>(runtime: &mut Runtime<X>, context: &mut CallContext) -> Result<Value, Error> {
+    logger::debug!(event = "string_raw");
+
+    let args = context.args();
+    let template = args.first().unwrap_or(&Value::Undefined);
+    let substitutions = args.get(1..).unwrap_or(&[]);
+
+    let cooked = runtime.value_to_object(template)?;
+    let literals = cooked
+        .get_value(&Symbol::RAW.into())
+        .unwrap_or(&Value::Undefined)
+        .clone();
+    let literals = runtime.value_to_object(&literals)?;
+    let literal_count = runtime.length_of_array_like(literals)?;
+
+    let mut r = EMPTY;
+    let mut next_index = 0.0;
+    while next_index < literal_count {
+        let next_literal = literals
+            .get_value(&next_index.into())
+            .unwrap_or(&Value::Undefined)
+            .clone();
+        let next_literal = runtime.value_to_string(&next_literal)?;
+        r = runtime.concat_strings(r, next_literal);
+        if next_index + 1.0 == literal_count {
+            break;
+        }
+        if let Some(next_sub) = substitutions.get(next_index as usize) {
+            let next_sub = runtime.value_to_string(next_sub)?;
+            r = runtime.concat_strings(r, next_sub);
+        }
+        next_index += 1.0;
+    }
+    Ok(Value::String(r))
+}
+
 // 11.1.1 Static Semantics: UTF16EncodeCodePoint ( cp )
 fn encode_code_point(cp: i64, buf: &mut [u16; 2]) -> &[u16] {
     debug_assert!((0..=0x10FFFF).contains(&cp));
@@ -0,0 +1,74 @@
+//$id weak_ref
+//$class WeakRef
+//$inherits object
+
+use jsgc::HandleMut;
+
+use crate::Error;
+use crate::Runtime;
+use crate::types::CallContext;
+use crate::types::Object;
+use crate::types::Value;
+
+use super::logger;
+
+//#sec-weak-ref-target constructor
+pub fn constructor<X>(runtime: &mut Runtime<X>, context: &mut CallContext) -> Result<Value, Error> {
+    logger::debug!(event = "weak_ref");
+
+    if !context.is_new() {
+        return type_error!();
+    }
+
+    // TODO(feat): CanBeHeldWeakly() returns true for non-registered symbols.
+    let target = match context.arg(0) {
+        Value::Object(target) => *target,
+        _ => return type_error!(),
+    };
+
+    let mut object = if let Value::Object(this) = context.this() {
+        *this
+    } else {
+        let mut object = runtime.create_object();
+        object.set_prototype(runtime.builtins.weak_ref_prototype);
+        object
+    };
+
+    runtime.add_to_kept_objects(target);
+    let weak_ref = runtime.heap.alloc_weak(target);
+    object.set_weak_ref(weak_ref);
+
+    Ok(Value::Object(object))
+}
+
+//#sec-weak-ref.prototype.deref prototype.function
+pub fn weak_ref_prototype_deref<X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+) -> Result<Value, Error> {
+    logger::debug!(event = "weak_ref_prototype_deref");
+    let weak_ref = match context.this() {
+        Value::Object(this) if this.is_weak_ref() => *this,
+        _ => return type_error!(),
+    };
+    // 26.1.4.1 WeakRefDeref ( weakRef )
+    match weak_ref.weak_ref().get() {
+        Some(target) => {
+            runtime.add_to_kept_objects(target);
+            Ok(Value::Object(target))
+        }
+        None => Ok(Value::Undefined),
+    }
+}
+
+impl<X> Runtime<X> {
+    // 9.10 AddToKeptObjects ( value )
+    pub(crate) fn add_to_kept_objects(&mut self, value: HandleMut<Object>) {
+        self.kept_objects.push(value);
+    }
+
+    // 9.9 ClearKeptObjects ( )
+    pub(crate) fn clear_kept_objects(&mut self) {
+        self.kept_objects.clear();
+    }
+}
@@ -0,0 +1,571 @@
+//! A code cache storing analyzed programs.
+//!
+//! A code cache contains a `semantics::Program` in a compact binary format so that a program
+//! can be loaded without parsing the source text again.  Symbols and lambda IDs depend on the
+//! state of a runtime.  They are stored as indexes into tables in the code cache and remapped
+//! when the code cache is loaded into another runtime.
+//!
+//! The format is:
+//!
+//! ```text
+//! magic: b"BJSC"
+//! format version: u32
+//! runtime version: string
+//! source length: u64
+//! source text: [u8; source length]
+//! module: bool
+//! symbol table: [UTF-16 string]
+//! lambda table: [LambdaKind]
+//! program
+//! ```
+//!
+//! The whole source text and whether it was parsed as a module are stored so that a code cache
+//! is never loaded together with another source text or for another parse mode.  Native code is
+//! not contained.  Functions in a loaded program are compiled in the same way as a program
+//! parsed from the source text.
+
+use std::rc::Rc;
+
+use jsparser::Symbol;
+use jsparser::syntax::LoopFlags;
+use rustc_hash::FxHashMap;
+
+use crate::ProgramId;
+use crate::Runtime;
+use crate::lambda::LambdaId;
+use crate::lambda::LambdaKind;
+use crate::logger;
+use crate::semantics::Program;
+use crate::semantics::ScopeRef;
+use crate::semantics::SourceOffset;
+use crate::semantics::SourceRange;
+
+const MAGIC: &[u8; 4] = b"BJSC";
+
+// Increment this value when the format of a program is changed.
+const FORMAT_VERSION: u32 = 8;
+
+const RUNTIME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// An error occurred while loading a code cache.
+#[derive(Debug, thiserror::Error)]
+pub enum CodeCacheError {
+    #[error("Invalid code cache")]
+    InvalidFormat,
+    #[error("Code cache created by another version of the runtime")]
+    VersionMismatch,
+    #[error("Code cache created from another source text")]
+    SourceMismatch,
+    #[error("Code cache created for another parse mode")]
+    ParseModeMismatch,
+}
+
+impl<X> Runtime<X> {
+    /// Creates a code cache of a program.
+    ///
+    /// The code cache can be loaded by `load_code_cache()` together with the same source text
+    /// and the same parse mode in order to skip parsing.
+    pub fn create_code_cache(&self, program_id: ProgramId) -> Vec<u8> {
+        logger::debug!(event = "create_code_cache", ?program_id);
+        let program = &self.programs[program_id.index()];
+
+        let mut writer = Writer::new(&self.symbol_registry);
+        for (index, func) in program.functions.iter().enumerate() {
+            writer.lambdas.insert(func.id, index as u32);
+        }
+        program.encode(&mut writer);
+
+        let mut header = Writer::new(&self.symbol_registry);
+        header.put_bytes(MAGIC);
+        header.put_u32(FORMAT_VERSION);
+        header.put_utf8(RUNTIME_VERSION);
+        header.put_u64(program.source.len() as u64);
+        header.put_bytes(program.source.as_bytes());
+        program.module.encode(&mut header);
+        header.put_u32(writer.symbol_names.len() as u32);
+        for name in writer.symbol_names.iter() {
+            name.encode(&mut header);
+        }
+        header.put_u32(program.functions.len() as u32);
+        for func in program.functions.iter() {
+            let kind = match self.lambda_registry.get(func.id).kind {
+                LambdaKind::Normal => 0,
+                LambdaKind::Ramp => 1,
+                LambdaKind::Coroutine => 2,
+            };
+            header.put_u8(kind);
+        }
+
+        let mut data = header.buf;
+        data.extend(writer.buf);
+        data
+    }
+
+    /// Loads a program from a code cache created by `create_code_cache()`.
+    ///
+    /// `source` must be the source text of the program and `module` must be `true` if it's
+    /// parsed as a module.  An error is returned if the code cache was created from another
+    /// source text, for another parse mode or by another version of the runtime.  In this case,
+    /// the program has to be parsed from the source text.
+    ///
+    /// Like `parse_script()`, global declarations in the program are defined in the current
+    /// realm.
+    pub fn load_code_cache(
+        &mut self,
+        source: &str,
+        module: bool,
+        data: &[u8],
+    ) -> Result<ProgramId, CodeCacheError> {
+        logger::debug!(event = "load_code_cache", len = data.len());
+        let mut reader = Reader::new(data);
+
+        if reader.get_bytes(MAGIC.len())? != MAGIC {
+            return Err(CodeCacheError::InvalidFormat);
+        }
+        if reader.get_u32()? != FORMAT_VERSION || reader.get_utf8()? != RUNTIME_VERSION {
+            return Err(CodeCacheError::VersionMismatch);
+        }
+        if reader.get_u64()? != source.len() as u64
+            || reader.get_bytes(source.len())? != source.as_bytes()
+        {
+            return Err(CodeCacheError::SourceMismatch);
+        }
+        if bool::decode(&mut reader)? != module {
+            return Err(CodeCacheError::ParseModeMismatch);
+        }
+
+        let num_symbols = reader.get_u32()?;
+        for _ in 0..num_symbols {
+            let name = Vec::<u16>::decode(&mut reader)?;
+            reader.symbols.push(self.symbol_registry.intern_utf16(name));
+        }
+
+        // NOTE: Lambdas registered here are never used if the program cannot be decoded.
+        let num_lambdas = reader.get_u32()?;
+        for _ in 0..num_lambdas {
+            let kind = match reader.get_u8()? {
+                0 => LambdaKind::Normal,
+                1 => LambdaKind::Ramp,
+                2 => LambdaKind::Coroutine,
+                _ => return Err(CodeCacheError::InvalidFormat),
+            };
+            reader.lambdas.push(self.lambda_registry.register(kind));
+        }
+
+        let mut program = Program::decode(&mut reader)?;
+        if !reader.is_empty() || program.module != module {
+            return Err(CodeCacheError::InvalidFormat);
+        }
+
+        // Perform the global declarations in the same way as the semantic analysis.
+        for &symbol in program.global_declarations.iter() {
+            self.define_global_declaration(symbol);
+        }
+        program.realm = self.realm;
+
+        Ok(self.register_program(program, source))
+    }
+}
+
+/// Encodes values in the code cache format.
+pub(crate) struct Writer<'a> {
+    buf: Vec<u8>,
+    symbol_registry: &'a jsparser::SymbolRegistry,
+    symbols: FxHashMap<Symbol, u32>,
+    symbol_names: Vec<Vec<u16>>,
+    lambdas: FxHashMap<LambdaId, u32>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(symbol_registry: &'a jsparser::SymbolRegistry) -> Self {
+        Self {
+            buf: vec![],
+            symbol_registry,
+            symbols: Default::default(),
+            symbol_names: vec![],
+            lambdas: Default::default(),
+        }
+    }
+
+    pub(crate) fn put_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub(crate) fn put_u16(&mut self, value: u16) {
+        self.buf.extend(value.to_le_bytes());
+    }
+
+    pub(crate) fn put_u32(&mut self, value: u32) {
+        self.buf.extend(value.to_le_bytes());
+    }
+
+    pub(crate) fn put_u64(&mut self, value: u64) {
+        self.buf.extend(value.to_le_bytes());
+    }
+
+    fn put_bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn put_utf8(&mut self, value: &str) {
+        self.put_u32(value.len() as u32);
+        self.put_bytes(value.as_bytes());
+    }
+
+    fn put_symbol(&mut self, symbol: Symbol) {
+        let next_index = self.symbol_names.len() as u32;
+        let index = *self.symbols.entry(symbol).or_insert(next_index);
+        if index == next_index {
+            let name = self.symbol_registry.resolve(symbol).unwrap();
+            self.symbol_names.push(name.to_vec());
+        }
+        self.put_u32(index);
+    }
+
+    fn put_lambda_id(&mut self, lambda_id: LambdaId) {
+        // Lambdas in a program refer only to lambdas in the same program.
+        let index = self.lambdas[&lambda_id];
+        self.put_u32(index);
+    }
+}
+
+/// Decodes values in the code cache format.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    symbols: Vec<Symbol>,
+    lambdas: Vec<LambdaId>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            symbols: vec![],
+            lambdas: vec![],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn get_bytes(&mut self, len: usize) -> Result<&'a [u8], CodeCacheError> {
+        if self.data.len() < len {
+            return Err(CodeCacheError::InvalidFormat);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn get_u8(&mut self) -> Result<u8, CodeCacheError> {
+        Ok(self.get_bytes(1)?[0])
+    }
+
+    pub(crate) fn get_u16(&mut self) -> Result<u16, CodeCacheError> {
+        Ok(u16::from_le_bytes(self.get_bytes(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn get_u32(&mut self) -> Result<u32, CodeCacheError> {
+        Ok(u32::from_le_bytes(self.get_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn get_u64(&mut self) -> Result<u64, CodeCacheError> {
+        Ok(u64::from_le_bytes(self.get_bytes(8)?.try_into().unwrap()))
+    }
+
+    fn get_utf8(&mut self) -> Result<&'a str, CodeCacheError> {
+        let len = self.get_u32()? as usize;
+        std::str::from_utf8(self.get_bytes(len)?).map_err(|_| CodeCacheError::InvalidFormat)
+    }
+
+    fn get_symbol(&mut self) -> Result<Symbol, CodeCacheError> {
+        let index = self.get_u32()? as usize;
+        self.symbols
+            .get(index)
+            .copied()
+            .ok_or(CodeCacheError::InvalidFormat)
+    }
+
+    fn get_lambda_id(&mut self) -> Result<LambdaId, CodeCacheError> {
+        let index = self.get_u32()? as usize;
+        self.lambdas
+            .get(index)
+            .copied()
+            .ok_or(CodeCacheError::InvalidFormat)
+    }
+
+    /// Reads the length of a sequence.
+    ///
+    /// An error is returned if the length exceeds the remaining data so that a broken code cache
+    /// does not cause a huge allocation.
+    pub(crate) fn get_len(&mut self) -> Result<usize, CodeCacheError> {
+        let len = self.get_u32()? as usize;
+        if len > self.data.len() {
+            return Err(CodeCacheError::InvalidFormat);
+        }
+        Ok(len)
+    }
+}
+
+/// A type which can be stored in a code cache.
+pub(crate) trait Encode {
+    fn encode(&self, writer: &mut Writer);
+}
+
+/// A type which can be loaded from a code cache.
+pub(crate) trait Decode: Sized {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError>;
+}
+
+macro_rules! impl_codec_for_integer {
+    ($($ty:ty => $put:ident, $get:ident;)*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, writer: &mut Writer) {
+                    writer.$put(*self);
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+                    reader.$get()
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_integer! {
+    u8 => put_u8, get_u8;
+    u16 => put_u16, get_u16;
+    u32 => put_u32, get_u32;
+}
+
+impl Encode for usize {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u64(*self as u64);
+    }
+}
+
+impl Decode for usize {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        usize::try_from(reader.get_u64()?).map_err(|_| CodeCacheError::InvalidFormat)
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u8(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        match reader.get_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CodeCacheError::InvalidFormat),
+        }
+    }
+}
+
+impl Encode for f64 {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u64(self.to_bits());
+    }
+}
+
+impl Decode for f64 {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(f64::from_bits(reader.get_u64()?))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u32(self.len() as u32);
+        for item in self.iter() {
+            item.encode(writer);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        let len = reader.get_len()?;
+        (0..len).map(|_| T::decode(reader)).collect()
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Some(value) => {
+                writer.put_u8(1);
+                value.encode(writer);
+            }
+            None => writer.put_u8(0),
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        match reader.get_u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(reader)?)),
+            _ => Err(CodeCacheError::InvalidFormat),
+        }
+    }
+}
+
+impl<T: Encode> Encode for Rc<T> {
+    fn encode(&self, writer: &mut Writer) {
+        self.as_ref().encode(writer);
+    }
+}
+
+impl<T: Decode> Decode for Rc<T> {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(Rc::new(T::decode(reader)?))
+    }
+}
+
+impl Encode for Symbol {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_symbol(*self);
+    }
+}
+
+impl Decode for Symbol {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        reader.get_symbol()
+    }
+}
+
+impl Encode for LambdaId {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_lambda_id(*self);
+    }
+}
+
+impl Decode for LambdaId {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        reader.get_lambda_id()
+    }
+}
+
+impl Encode for ScopeRef {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u16(self.id());
+    }
+}
+
+impl Decode for ScopeRef {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(ScopeRef::new(reader.get_u16()?))
+    }
+}
+
+impl Encode for SourceOffset {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u32((*self).into());
+    }
+}
+
+impl Decode for SourceOffset {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(reader.get_u32()?.into())
+    }
+}
+
+impl Encode for SourceRange {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u32(self.start);
+        writer.put_u32(self.end);
+    }
+}
+
+impl Decode for SourceRange {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(SourceRange {
+            start: reader.get_u32()?,
+            end: reader.get_u32()?,
+        })
+    }
+}
+
+impl Encode for LoopFlags {
+    fn encode(&self, writer: &mut Writer) {
+        writer.put_u16(self.bits());
+    }
+}
+
+impl Decode for LoopFlags {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        LoopFlags::from_bits(reader.get_u16()?).ok_or(CodeCacheError::InvalidFormat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicRuntime;
+
+    #[test]
+    fn test_primitives() {
+        let registry = jsparser::SymbolRegistry::default();
+        let mut writer = Writer::new(&registry);
+        42u16.encode(&mut writer);
+        vec![Some(1usize), None].encode(&mut writer);
+        f64::NAN.encode(&mut writer);
+        true.encode(&mut writer);
+
+        let mut reader = Reader::new(&writer.buf);
+        assert_eq!(u16::decode(&mut reader).unwrap(), 42);
+        assert_eq!(
+            Vec::<Option<usize>>::decode(&mut reader).unwrap(),
+            [Some(1), None]
+        );
+        assert!(f64::decode(&mut reader).unwrap().is_nan());
+        assert!(bool::decode(&mut reader).unwrap());
+        assert!(reader.is_empty());
+        assert!(matches!(
+            u8::decode(&mut reader),
+            Err(CodeCacheError::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_code_cache() {
+        const SOURCE: &str = "var a = 1;";
+
+        let mut runtime = BasicRuntime::new();
+        let program_id = runtime.parse_script(SOURCE).unwrap();
+        let data = runtime.create_code_cache(program_id);
+
+        let mut runtime = BasicRuntime::new();
+        assert!(matches!(
+            runtime.load_code_cache("var a = 2;", false, &data),
+            Err(CodeCacheError::SourceMismatch)
+        ));
+        assert!(matches!(
+            runtime.load_code_cache("var a = 10;", false, &data),
+            Err(CodeCacheError::SourceMismatch)
+        ));
+        assert!(matches!(
+            runtime.load_code_cache(SOURCE, true, &data),
+            Err(CodeCacheError::ParseModeMismatch)
+        ));
+        assert!(matches!(
+            runtime.load_code_cache(SOURCE, false, &data[..data.len() - 1]),
+            Err(CodeCacheError::InvalidFormat)
+        ));
+        assert!(matches!(
+            runtime.load_code_cache(SOURCE, false, b"JSON"),
+            Err(CodeCacheError::InvalidFormat)
+        ));
+        assert!(runtime.load_code_cache(SOURCE, false, &data).is_ok());
+    }
+}
@@ -0,0 +1,250 @@
+use jsparser::Symbol;
+
+use crate::Rooted;
+use crate::Runtime;
+use crate::types::PropertyKey;
+use crate::types::Value;
+
+/// Conversion from a JavaScript value into a Rust value.
+///
+/// No implicit type coercion is performed.  A `TypeError` is returned if the value has an
+/// unexpected type.
+pub trait FromJsValue: Sized {
+    fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value>;
+}
+
+/// Conversion from a Rust value into a JavaScript value.
+///
+/// The conversion fails if the Rust value represents an error.
+pub trait IntoJsValue {
+    fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value>;
+}
+
+impl FromJsValue for Value {
+    fn from_js_value<X>(_runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoJsValue for Value {
+    fn into_js_value<X>(self, _runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        Ok(self)
+    }
+}
+
+impl IntoJsValue for Rooted {
+    fn into_js_value<X>(self, _runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        Ok(self.get())
+    }
+}
+
+impl IntoJsValue for () {
+    fn into_js_value<X>(self, _runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        Ok(Value::Undefined)
+    }
+}
+
+impl FromJsValue for bool {
+    fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+        match value {
+            Value::Boolean(value) => Ok(*value),
+            _ => runtime.throw(type_error!("expected a boolean")),
+        }
+    }
+}
+
+impl IntoJsValue for bool {
+    fn into_js_value<X>(self, _runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        Ok(Value::Boolean(self))
+    }
+}
+
+// Integers are converted with `as` and out-of-range values are saturated.
+macro_rules! impl_number {
+    ($($ty:ty),*) => {
+        $(
+            impl FromJsValue for $ty {
+                #[allow(clippy::unnecessary_cast)]
+                fn from_js_value<X>(
+                    runtime: &mut Runtime<X>,
+                    value: &Value,
+                ) -> Result<Self, Value> {
+                    match value {
+                        Value::Number(value) => Ok(*value as $ty),
+                        _ => runtime.throw(type_error!("expected a number")),
+                    }
+                }
+            }
+
+            impl IntoJsValue for $ty {
+                #[allow(clippy::unnecessary_cast)]
+                fn into_js_value<X>(self, _runtime: &mut Runtime<X>) -> Result<Value, Value> {
+                    Ok(Value::Number(self as f64))
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(f64, f32, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromJsValue for std::string::String {
+    fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+        match value {
+            Value::String(value) => Ok(std::string::String::from_utf16_lossy(value.as_slice())),
+            _ => runtime.throw(type_error!("expected a string")),
+        }
+    }
+}
+
+impl IntoJsValue for std::string::String {
+    fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        Ok(Value::String(runtime.create_string_from_utf8(&self)))
+    }
+}
+
+impl IntoJsValue for &str {
+    fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        Ok(Value::String(runtime.create_string_from_utf8(self)))
+    }
+}
+
+// `undefined` and `null` are mapped to `None`.
+impl<T: FromJsValue> FromJsValue for Option<T> {
+    fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+        match value {
+            Value::Undefined | Value::Null => Ok(None),
+            _ => T::from_js_value(runtime, value).map(Some),
+        }
+    }
+}
+
+// `None` is mapped to `undefined`.
+impl<T: IntoJsValue> IntoJsValue for Option<T> {
+    fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        match self {
+            Some(value) => value.into_js_value(runtime),
+            None => Ok(Value::Undefined),
+        }
+    }
+}
+
+// An array-like object is converted into a `Vec`.
+impl<T: FromJsValue> FromJsValue for Vec<T> {
+    fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+        let elements = get_elements(runtime, value)?;
+        elements
+            .iter()
+            .map(|element| T::from_js_value(runtime, element))
+            .collect()
+    }
+}
+
+impl<T: IntoJsValue> IntoJsValue for Vec<T> {
+    fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        let elements = self
+            .into_iter()
+            .map(|element| element.into_js_value(runtime))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Value::Object(runtime.create_array(&elements)))
+    }
+}
+
+// Tuples are converted from and into arrays.
+macro_rules! impl_tuple {
+    ($len:literal; $($name:ident: $index:tt),+) => {
+        impl<$($name: FromJsValue),+> FromJsValue for ($($name,)+) {
+            fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+                let elements = get_elements(runtime, value)?;
+                if elements.len() != $len {
+                    return runtime.throw(type_error!("unexpected array length"));
+                }
+                Ok(($($name::from_js_value(runtime, &elements[$index])?,)+))
+            }
+        }
+
+        impl<$($name: IntoJsValue),+> IntoJsValue for ($($name,)+) {
+            fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value> {
+                let elements = [$(self.$index.into_js_value(runtime)?),+];
+                Ok(Value::Object(runtime.create_array(&elements)))
+            }
+        }
+    };
+}
+
+impl_tuple!(1; A: 0);
+impl_tuple!(2; A: 0, B: 1);
+impl_tuple!(3; A: 0, B: 1, C: 2);
+impl_tuple!(4; A: 0, B: 1, C: 2, D: 3);
+
+// An error is thrown as a `TypeError` with the error message.
+impl<T, E> IntoJsValue for Result<T, E>
+where
+    T: IntoJsValue,
+    E: std::fmt::Display,
+{
+    fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        match self {
+            Ok(value) => value.into_js_value(runtime),
+            Err(err) => {
+                let message = runtime.create_string_from_utf8(&err.to_string());
+                Err(Value::Object(runtime.create_type_error(Some(message))))
+            }
+        }
+    }
+}
+
+fn get_elements<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Vec<Value>, Value> {
+    let object = match value {
+        Value::Object(object) => *object,
+        _ => return runtime.throw(type_error!("expected an array")),
+    };
+    let len = match object.get_value(&Symbol::LENGTH.into()) {
+        Some(Value::Number(len)) => *len as u32,
+        _ => return runtime.throw(type_error!("expected an array")),
+    };
+    let elements = (0..len)
+        .map(|i| {
+            object
+                .get_value(&PropertyKey::from(i as f64))
+                .cloned()
+                .unwrap_or(Value::Undefined)
+        })
+        .collect();
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicRuntime;
+
+    #[test]
+    fn test_from_js_value() {
+        let mut runtime = BasicRuntime::new();
+        assert_eq!(f64::from_js_value(&mut runtime, &Value::from(1.5)), Ok(1.5));
+        assert_eq!(i32::from_js_value(&mut runtime, &Value::from(-2)), Ok(-2));
+        assert_eq!(bool::from_js_value(&mut runtime, &Value::TRUE), Ok(true));
+        assert_eq!(
+            Option::<f64>::from_js_value(&mut runtime, &Value::Null),
+            Ok(None)
+        );
+        assert!(f64::from_js_value(&mut runtime, &Value::TRUE).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut runtime = BasicRuntime::new();
+        let value = "abc".into_js_value(&mut runtime).unwrap();
+        let result = std::string::String::from_js_value(&mut runtime, &value);
+        assert_eq!(result.as_deref(), Ok("abc"));
+        let value = vec![1, 2, 3].into_js_value(&mut runtime).unwrap();
+        let result = Vec::<i32>::from_js_value(&mut runtime, &value);
+        assert_eq!(result, Ok(vec![1, 2, 3]));
+        let value = (1, true).into_js_value(&mut runtime).unwrap();
+        let result = <(u8, bool)>::from_js_value(&mut runtime, &value);
+        assert_eq!(result, Ok((1, true)));
+        let result = Err::<(), _>("boom").into_js_value(&mut runtime);
+        assert!(matches!(result, Err(Value::Object(_))));
+    }
+}
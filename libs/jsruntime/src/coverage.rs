@@ -0,0 +1,364 @@
+use std::fmt::Write;
+
+use jsparser::Symbol;
+use rustc_hash::FxHashMap;
+
+use crate::ProgramId;
+use crate::Runtime;
+use crate::lambda::LambdaId;
+use crate::lambda::LambdaKind;
+use crate::logger;
+use crate::semantics::Function;
+use crate::semantics::Program;
+use crate::semantics::SourceRange;
+
+/// The coverage data of a program.
+///
+/// The structure follows the `ScriptCoverage` type in the Chrome DevTools Protocol which is
+/// returned from `Profiler.takePreciseCoverage`.
+#[derive(Debug)]
+pub struct ScriptCoverage {
+    /// The program.
+    pub program_id: ProgramId,
+
+    /// The name of the program set by `Runtime::set_program_name()`.
+    pub url: Option<std::string::String>,
+
+    /// The functions in the program.  The first function is the top-level code.
+    pub functions: Vec<FunctionCoverage>,
+}
+
+/// The coverage data of a function.
+#[derive(Debug)]
+pub struct FunctionCoverage {
+    /// The name of the function, or `None` for the top-level code and anonymous functions.
+    pub function_name: Option<std::string::String>,
+
+    /// The ranges in the function.
+    ///
+    /// The first range covers the whole function and its count is the number of calls of the
+    /// function.  The other ranges cover blocks in the function and are nested in the first range.
+    pub ranges: Vec<CoverageRange>,
+
+    /// `true` if `ranges` contains ranges of blocks in addition to the function range.
+    ///
+    /// `false` if the function has never been compiled.
+    pub is_block_coverage: bool,
+}
+
+/// A range in the source text with its execution count.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CoverageRange {
+    /// The start offset of the range in UTF-16 code units.
+    pub start_offset: u32,
+
+    /// The end offset of the range in UTF-16 code units.
+    pub end_offset: u32,
+
+    /// The number of times the range was executed.
+    pub count: u64,
+}
+
+/// Execution counters for the code coverage.
+pub(crate) struct Coverage {
+    // The counters for each lambda function.  The first counter counts calls of the function and
+    // the others count executions of the blocks in `Function::coverage_blocks`.  The addresses of
+    // the counters are embedded in generated code.  So, the counters are never freed.
+    counters: FxHashMap<LambdaId, Box<[u64]>>,
+}
+
+impl Coverage {
+    pub(crate) fn new() -> Self {
+        Self {
+            counters: Default::default(),
+        }
+    }
+
+    /// Returns the address of the counters for a function.
+    ///
+    /// The counters are allocated when this method is called for the function at the first time.
+    pub(crate) fn counters_addr(&mut self, func: &Function) -> usize {
+        self.counters
+            .entry(func.id)
+            .or_insert_with(|| vec![0; func.coverage_blocks.len() + 1].into_boxed_slice())
+            .as_ptr() as usize
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Starts collecting the code coverage.
+    ///
+    /// Generated code counts calls of functions and executions of branches and loop bodies.
+    /// Programs compiled before the first call to this method are not covered.
+    pub fn start_coverage(&mut self) {
+        logger::debug!(event = "start_coverage");
+        self.pref.enable_coverage = true;
+    }
+
+    /// Returns the code coverage collected so far and resets the counters.
+    ///
+    /// Programs which have never been compiled since `Runtime::start_coverage()` was called are
+    /// not included.
+    pub fn take_coverage(&mut self) -> Vec<ScriptCoverage> {
+        logger::debug!(event = "take_coverage");
+        let mut result = vec![];
+        for (index, program) in self.programs.iter().enumerate() {
+            if !self
+                .coverage
+                .counters
+                .contains_key(&program.entry_lambda_id())
+            {
+                continue;
+            }
+            // The top-level code comes first.
+            let mut functions = vec![];
+            for (function_index, func) in program.functions.iter().enumerate().rev() {
+                if let LambdaKind::Coroutine = self.lambda_registry.get(func.id).kind {
+                    continue;
+                }
+                functions.push(self.make_function_coverage(program, function_index));
+            }
+            result.push(ScriptCoverage {
+                program_id: ProgramId::new(index),
+                url: program.name.clone(),
+                functions,
+            });
+        }
+        for counters in self.coverage.counters.values_mut() {
+            counters.fill(0);
+        }
+        result
+    }
+
+    fn make_function_coverage(&self, program: &Program, function_index: usize) -> FunctionCoverage {
+        let func = &program.functions[function_index];
+        let function_name = if func.name == Symbol::NONE {
+            None
+        } else {
+            self.symbol_registry
+                .resolve(func.name)
+                .map(std::string::String::from_utf16_lossy)
+        };
+
+        let source_range = if func.is_entry_function() {
+            SourceRange {
+                start: 0,
+                end: program.source.len() as u32,
+            }
+        } else {
+            func.source_range
+        };
+        let Some(counters) = self.coverage.counters.get(&func.id) else {
+            return FunctionCoverage {
+                function_name,
+                ranges: vec![make_coverage_range(&program.source, source_range, 0)],
+                is_block_coverage: false,
+            };
+        };
+        let mut ranges = vec![make_coverage_range(
+            &program.source,
+            source_range,
+            counters[0],
+        )];
+
+        // The blocks in the body of an async function are in its coroutine function placed just
+        // before it.
+        let mut blocks = vec![(func, counters)];
+        if let Some(coroutine) = function_index
+            .checked_sub(1)
+            .map(|index| &program.functions[index])
+            .filter(|coroutine| self.is_coroutine_of(coroutine, func))
+            && let Some(coroutine_counters) = self.coverage.counters.get(&coroutine.id)
+        {
+            blocks.push((coroutine, coroutine_counters));
+        }
+        let start = ranges.len();
+        for (func, counters) in blocks {
+            for (&range, &count) in func.coverage_blocks.iter().zip(counters[1..].iter()) {
+                ranges.push(make_coverage_range(&program.source, range, count));
+            }
+        }
+        // Outer ranges come first.
+        ranges[start..].sort_by_key(|range| (range.start_offset, u32::MAX - range.end_offset));
+
+        FunctionCoverage {
+            function_name,
+            ranges,
+            is_block_coverage: true,
+        }
+    }
+
+    fn is_coroutine_of(&self, coroutine: &Function, ramp: &Function) -> bool {
+        matches!(
+            self.lambda_registry.get(coroutine.id).kind,
+            LambdaKind::Coroutine
+        ) && matches!(self.lambda_registry.get(ramp.id).kind, LambdaKind::Ramp)
+    }
+
+    /// Converts the code coverage into the LCOV tracefile format.
+    ///
+    /// A line is counted by the innermost range containing the first non-whitespace character of
+    /// the line.
+    pub fn coverage_to_lcov(&self, coverage: &[ScriptCoverage]) -> std::string::String {
+        let mut lcov = std::string::String::new();
+        for script in coverage {
+            let source = &self.programs[script.program_id.index()].source;
+            let lines = Lines::new(source);
+            let _ = writeln!(lcov, "TN:");
+            let _ = writeln!(lcov, "SF:{}", script.url.as_deref().unwrap_or_default());
+
+            // The top-level code is not reported as a function.
+            let functions = script.functions.get(1..).unwrap_or_default();
+            for (index, function) in functions.iter().enumerate() {
+                let line = lines.line_of(function.ranges[0].start_offset);
+                let name = lcov_function_name(function, index);
+                let _ = writeln!(lcov, "FN:{line},{name}");
+            }
+            for (index, function) in functions.iter().enumerate() {
+                let name = lcov_function_name(function, index);
+                let _ = writeln!(lcov, "FNDA:{},{name}", function.ranges[0].count);
+            }
+            let num_hit = functions
+                .iter()
+                .filter(|function| function.ranges[0].count > 0)
+                .count();
+            let _ = writeln!(lcov, "FNF:{}", functions.len());
+            let _ = writeln!(lcov, "FNH:{num_hit}");
+
+            let mut num_branches = 0;
+            let mut num_hit = 0;
+            for (index, function) in script.functions.iter().enumerate() {
+                for (branch, range) in function.ranges.iter().skip(1).enumerate() {
+                    let line = lines.line_of(range.start_offset);
+                    let _ = writeln!(lcov, "BRDA:{line},{index},{branch},{}", range.count);
+                    num_branches += 1;
+                    if range.count > 0 {
+                        num_hit += 1;
+                    }
+                }
+            }
+            let _ = writeln!(lcov, "BRF:{num_branches}");
+            let _ = writeln!(lcov, "BRH:{num_hit}");
+
+            let mut num_lines = 0;
+            let mut num_hit = 0;
+            for (line, offset) in lines.iter_line_starts() {
+                let innermost = script
+                    .functions
+                    .iter()
+                    .flat_map(|function| function.ranges.iter())
+                    .filter(|range| range.start_offset <= offset && offset < range.end_offset)
+                    .min_by_key(|range| range.end_offset - range.start_offset);
+                let Some(range) = innermost else {
+                    continue;
+                };
+                let _ = writeln!(lcov, "DA:{line},{}", range.count);
+                num_lines += 1;
+                if range.count > 0 {
+                    num_hit += 1;
+                }
+            }
+            let _ = writeln!(lcov, "LF:{num_lines}");
+            let _ = writeln!(lcov, "LH:{num_hit}");
+            let _ = writeln!(lcov, "end_of_record");
+        }
+        lcov
+    }
+}
+
+fn make_coverage_range(source: &str, range: SourceRange, count: u64) -> CoverageRange {
+    CoverageRange {
+        start_offset: utf16_offset(source, range.start),
+        end_offset: utf16_offset(source, range.end),
+        count,
+    }
+}
+
+// Converts a byte offset into an offset in UTF-16 code units.
+fn utf16_offset(source: &str, offset: u32) -> u32 {
+    match source.get(..offset as usize) {
+        Some(text) => text.encode_utf16().count() as u32,
+        None => source.encode_utf16().count() as u32,
+    }
+}
+
+fn lcov_function_name(function: &FunctionCoverage, index: usize) -> std::string::String {
+    match function.function_name {
+        Some(ref name) => name.clone(),
+        None => format!("(anonymous_{index})"),
+    }
+}
+
+// Lines in a source text indexed by UTF-16 offsets.
+struct Lines {
+    // The UTF-16 offsets of the first non-whitespace characters of non-blank lines paired with
+    // their 1-based line numbers.
+    line_starts: Vec<(u32, u32)>,
+
+    // The UTF-16 offsets of the beginnings of the lines.
+    line_offsets: Vec<u32>,
+}
+
+impl Lines {
+    fn new(source: &str) -> Self {
+        let mut line_starts = vec![];
+        let mut line_offsets = vec![0];
+        let mut blank = true;
+        let mut offset = 0;
+        let mut chars = source.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\r' if matches!(chars.peek(), Some('\n')) => (),
+                '\n' | '\r' | '\u{2028}' | '\u{2029}' => {
+                    line_offsets.push(offset + 1);
+                    blank = true;
+                }
+                _ if ch.is_whitespace() => (),
+                _ => {
+                    if blank {
+                        line_starts.push((line_offsets.len() as u32, offset));
+                        blank = false;
+                    }
+                }
+            }
+            offset += ch.len_utf16() as u32;
+        }
+        Self {
+            line_starts,
+            line_offsets,
+        }
+    }
+
+    // Returns the 1-based line number of a UTF-16 offset.
+    fn line_of(&self, offset: u32) -> u32 {
+        self.line_offsets.partition_point(|&start| start <= offset) as u32
+    }
+
+    fn iter_line_starts(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.line_starts.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let lines = Lines::new("a;\n\n  b;\r\nc;");
+        assert_eq!(
+            lines.iter_line_starts().collect::<Vec<_>>(),
+            [(1, 0), (3, 6), (4, 10)]
+        );
+        assert_eq!(lines.line_of(0), 1);
+        assert_eq!(lines.line_of(3), 2);
+        assert_eq!(lines.line_of(6), 3);
+        assert_eq!(lines.line_of(10), 4);
+    }
+
+    #[test]
+    fn test_utf16_offset() {
+        assert_eq!(utf16_offset("a\u{3042}b", 4), 2);
+        assert_eq!(utf16_offset("\u{1F600}b", 4), 2);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::ProgramId;
+use crate::Runtime;
+use crate::backend;
+use crate::logger;
+use crate::semantics::CompileCommand;
+use crate::semantics::Locator;
+use crate::semantics::ScopeRef;
+use crate::semantics::SourceOffset;
+use crate::stack_trace::StackFrame;
+use crate::stack_trace::line_and_column;
+use crate::types::CallContext;
+use crate::types::CallContextFlags;
+use crate::types::Value;
+
+pub(crate) type DebuggerCallback<X> = Box<dyn FnMut(&mut Runtime<X>, &Pause)>;
+
+/// The reason why the execution was paused.
+#[derive(Debug)]
+pub enum PauseReason {
+    /// A `debugger` statement was executed.
+    DebuggerStatement,
+
+    /// A `throw` statement is throwing the exception.
+    ///
+    /// The execution is paused before the exception is caught by a `try` statement if any.
+    Exception(Value),
+
+    /// A breakpoint set by `Runtime::set_breakpoint()` was hit.
+    Breakpoint(BreakpointId),
+
+    /// A step requested by `Runtime::step()` was completed.
+    Step,
+}
+
+/// An identifier of a breakpoint.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BreakpointId(u32);
+
+impl BreakpointId {
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A location in a program where the execution can pause.
+///
+/// Pause points are placed at calls of functions including `new` expressions.  The execution
+/// pauses before the function is called and after the arguments are evaluated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PausePoint {
+    /// The 1-based line number.
+    pub line: u32,
+
+    /// The 1-based column number in UTF-16 code units.
+    pub column: u32,
+}
+
+/// How to resume a paused execution.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StepAction {
+    /// Pauses at the next pause point including ones in called functions.
+    Into,
+
+    /// Pauses at the next pause point in the current function or its callers.
+    Over,
+
+    /// Pauses at the next pause point in a caller of the current function.
+    Out,
+}
+
+/// Breakpoints and the step being performed.
+pub(crate) struct PausePoints {
+    /// Non-zero if the generated code has to check the pause points.
+    ///
+    /// The address of the value is embedded in generated code.  `Box` provides a stable address.
+    active: Box<u8>,
+
+    breakpoints: Vec<Breakpoint>,
+    next_breakpoint_id: u32,
+
+    /// The step action and the depth of the call context where the step was requested.
+    step: Option<(StepAction, u16)>,
+
+    /// The step action requested by `Runtime::step()` in the debugger callback.
+    requested_step: Option<StepAction>,
+}
+
+struct Breakpoint {
+    id: BreakpointId,
+    program_id: ProgramId,
+    offset: SourceOffset,
+}
+
+impl PausePoints {
+    pub(crate) fn new() -> Self {
+        Self {
+            active: Box::new(0),
+            breakpoints: vec![],
+            next_breakpoint_id: 1,
+            step: None,
+            requested_step: None,
+        }
+    }
+
+    pub(crate) fn active_addr(&self) -> usize {
+        &*self.active as *const u8 as usize
+    }
+
+    fn update_active(&mut self) {
+        *self.active = (!self.breakpoints.is_empty() || self.step.is_some()) as u8;
+    }
+
+    fn find_breakpoint(&self, program_id: ProgramId, offset: SourceOffset) -> Option<BreakpointId> {
+        self.breakpoints
+            .iter()
+            .find(|bp| bp.program_id == program_id && bp.offset == offset)
+            .map(|bp| bp.id)
+    }
+
+    fn is_step_completed(&self, depth: u16) -> bool {
+        match self.step {
+            Some((StepAction::Into, _)) => true,
+            Some((StepAction::Over, step_depth)) => depth <= step_depth,
+            Some((StepAction::Out, step_depth)) => depth < step_depth,
+            None => false,
+        }
+    }
+}
+
+/// A scope in the innermost frame of a paused execution.
+#[derive(Debug)]
+pub struct DebugScope {
+    /// `true` if the scope is a function scope.  Otherwise, it's a block scope.
+    pub function: bool,
+
+    /// Names and values of variables declared in the scope.
+    ///
+    /// Variables in the temporal dead zone are not included.
+    pub variables: Vec<(std::string::String, Value)>,
+}
+
+/// The state of a paused execution.
+///
+/// See `Runtime::set_debugger()`.
+#[derive(Debug)]
+pub struct Pause {
+    /// The reason of the pause.
+    pub reason: PauseReason,
+
+    /// The program containing the paused location.
+    pub program_id: ProgramId,
+
+    /// The stack frames.  The innermost frame comes first and points to the paused location.
+    pub frames: Vec<StackFrame>,
+
+    /// The scopes in the innermost frame from the innermost block scope to the function scope.
+    ///
+    /// The global scope is not included.  Use `Runtime::global_object()` to inspect it.
+    pub scopes: Vec<DebugScope>,
+}
+
+impl<X> Runtime<X> {
+    /// Sets a callback function called when the execution is paused.
+    ///
+    /// The execution is paused at `debugger` statements, `throw` statements and pause points
+    /// where a breakpoint is set or a step is completed.  The execution resumes when the
+    /// callback function returns.  The callback function is not called
+    /// recursively while it's running.
+    ///
+    /// `throw` statements and pause points in programs compiled before this call do not pause
+    /// the execution.
+    pub fn set_debugger<F>(&mut self, callback: F)
+    where
+        F: FnMut(&mut Self, &Pause) + 'static,
+    {
+        self.pref.enable_debugger = true;
+        self.debugger = Some(Box::new(callback));
+    }
+
+    /// Returns the pause points in a program in the order of the source text.
+    pub fn pause_points(&self, program_id: ProgramId) -> Vec<PausePoint> {
+        let program = &self.programs[program_id.index()];
+        self.pause_point_offsets(program_id)
+            .into_iter()
+            .filter_map(|offset| line_and_column(&program.source, offset.get()?))
+            .map(|(line, column)| PausePoint { line, column })
+            .collect()
+    }
+
+    /// Sets a breakpoint at the first pause point on a line at or after a column.
+    ///
+    /// `line` and `column` are 1-based.  Returns `None` if there is no such pause point.
+    /// Otherwise, returns the identifier of the breakpoint and the pause point where it's set.
+    pub fn set_breakpoint(
+        &mut self,
+        program_id: ProgramId,
+        line: u32,
+        column: u32,
+    ) -> Option<(BreakpointId, PausePoint)> {
+        logger::debug!(event = "set_breakpoint", ?program_id, line, column);
+        let source = &self.programs[program_id.index()].source;
+        let (offset, point) = self
+            .pause_point_offsets(program_id)
+            .into_iter()
+            .filter_map(|offset| {
+                let (line, column) = line_and_column(source, offset.get()?)?;
+                Some((offset, PausePoint { line, column }))
+            })
+            .find(|(_, point)| point.line == line && point.column >= column)?;
+        let id = BreakpointId(self.pause_points.next_breakpoint_id);
+        self.pause_points.next_breakpoint_id += 1;
+        self.pause_points.breakpoints.push(Breakpoint {
+            id,
+            program_id,
+            offset,
+        });
+        self.pause_points.update_active();
+        Some((id, point))
+    }
+
+    /// Removes a breakpoint set by `set_breakpoint()`.
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) {
+        logger::debug!(event = "remove_breakpoint", ?id);
+        self.pause_points.breakpoints.retain(|bp| bp.id != id);
+        self.pause_points.update_active();
+    }
+
+    /// Requests a step performed when the paused execution resumes.
+    ///
+    /// This method has to be called in the callback function set by `set_debugger()`.
+    /// Otherwise, the request is ignored.  The step is canceled when the execution pauses at a
+    /// breakpoint or a `debugger` statement before the step is completed.
+    pub fn step(&mut self, action: StepAction) {
+        logger::debug!(event = "step", ?action);
+        self.pause_points.requested_step = Some(action);
+    }
+
+    fn pause_point_offsets(&self, program_id: ProgramId) -> Vec<SourceOffset> {
+        let mut offsets = self.programs[program_id.index()]
+            .functions
+            .iter()
+            .flat_map(|func| func.commands.iter())
+            .filter_map(|command| match command {
+                CompileCommand::Call(_, offset)
+                | CompileCommand::DirectEval(_, offset)
+                | CompileCommand::New(_, offset)
+                    if offset.get().is_some() =>
+                {
+                    Some(*offset)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        offsets.sort_unstable_by_key(|offset| offset.get());
+        offsets.dedup();
+        offsets
+    }
+
+    /// Evaluates an expression in the innermost frame of a paused execution.
+    ///
+    /// The expression can refer to the variables in `pause.scopes` in addition to global
+    /// variables.  Assignments to the variables do not affect the paused frame.
+    pub fn evaluate_on_pause(&mut self, pause: &Pause, expression: &str) -> Result<Value, Value> {
+        logger::debug!(event = "evaluate_on_pause", expression);
+
+        // Inner variables shadow outer variables having the same name.
+        let mut names = vec![];
+        let mut args = vec![];
+        for scope in pause.scopes.iter() {
+            for (name, value) in scope.variables.iter() {
+                if !names.contains(&name.as_str()) {
+                    names.push(name.as_str());
+                    args.push(value.clone());
+                }
+            }
+        }
+
+        // The variables are passed as arguments of a wrapper function.  The line terminators
+        // around the expression prevent a comment in the expression from breaking the wrapper.
+        let source = format!(
+            "(function ({}) {{ return (\n{expression}\n); }});",
+            names.join(", ")
+        );
+        let program_id = match self.parse_script(&source) {
+            Ok(program_id) => program_id,
+            Err(_) => return self.throw(syntax_error!("invalid expression")),
+        };
+
+        // The wrapper function is placed just before the entry function.
+        let function_index = self.programs[program_id.index()].functions.len() - 2;
+        let lambda_id = self.programs[program_id.index()].functions[function_index].id;
+        // TODO(fix): handle compilation errors
+        backend::compile_function(self, program_id, function_index, true).unwrap();
+        let lambda = self.code_registry.get_lambda(lambda_id).unwrap();
+        let closure = self.create_closure(lambda, lambda_id, 0);
+        let mut func = self.create_object();
+        func.set_prototype(self.builtins.function_prototype);
+        func.set_closure(closure);
+
+        self.call_from_host(func, &Value::Undefined, &args, CallContextFlags::empty())
+    }
+
+    /// Called from generated code at a `debugger` statement.
+    pub(crate) fn launch_debugger(
+        &mut self,
+        context: &CallContext,
+        scope_ref: ScopeRef,
+        variables: *const &Value,
+    ) {
+        self.pause(
+            PauseReason::DebuggerStatement,
+            context,
+            scope_ref,
+            variables,
+        );
+    }
+
+    /// Called from generated code at a `throw` statement if the debugger is enabled.
+    pub(crate) fn pause_on_exception(
+        &mut self,
+        context: &CallContext,
+        scope_ref: ScopeRef,
+        variables: *const &Value,
+        exception: &Value,
+    ) {
+        self.pause(
+            PauseReason::Exception(exception.clone()),
+            context,
+            scope_ref,
+            variables,
+        );
+    }
+
+    /// Called from generated code at a pause point if a breakpoint is set or a step is being
+    /// performed.
+    pub(crate) fn handle_pause_point(
+        &mut self,
+        context: &CallContext,
+        scope_ref: ScopeRef,
+        variables: *const &Value,
+    ) {
+        let (lambda_id, offset) = context.call_site().expect("the call site must be recorded");
+        let program_id = self.lambda_registry.get(lambda_id).program_id;
+        let reason = match self.pause_points.find_breakpoint(program_id, offset) {
+            Some(id) => PauseReason::Breakpoint(id),
+            None if self.pause_points.is_step_completed(context.depth()) => PauseReason::Step,
+            None => return,
+        };
+        self.pause(reason, context, scope_ref, variables);
+    }
+
+    fn pause(
+        &mut self,
+        reason: PauseReason,
+        context: &CallContext,
+        scope_ref: ScopeRef,
+        variables: *const &Value,
+    ) {
+        logger::debug!(event = "pause", ?reason, ?scope_ref);
+
+        // The callback is taken temporarily so that it can mutably borrow the runtime.  This also
+        // prevents the callback from being called recursively.
+        let Some(mut callback) = self.debugger.take() else {
+            return;
+        };
+
+        let (lambda_id, _) = context.call_site().expect("the call site must be recorded");
+        let program_id = self.lambda_registry.get(lambda_id).program_id;
+        let pause = Pause {
+            reason,
+            program_id,
+            frames: self.collect_stack_frames(context, usize::MAX),
+            scopes: self.collect_debug_scopes(program_id, scope_ref, variables),
+        };
+        // A pause completes the step being performed unless it's caused by an exception.
+        let step = self.pause_points.step.take();
+        self.pause_points.requested_step = None;
+        callback(self, &pause);
+        self.pause_points.step = match self.pause_points.requested_step.take() {
+            Some(action) => Some((action, context.depth())),
+            None if matches!(pause.reason, PauseReason::Exception(_)) => step,
+            None => None,
+        };
+        self.pause_points.update_active();
+
+        // Keep a callback newly set in the callback.
+        if self.debugger.is_none() {
+            self.debugger = Some(callback);
+        }
+    }
+
+    fn collect_debug_scopes(
+        &self,
+        program_id: ProgramId,
+        scope_ref: ScopeRef,
+        variables: *const &Value,
+    ) -> Vec<DebugScope> {
+        let mut scopes = vec![];
+        if scope_ref == ScopeRef::NONE {
+            return scopes;
+        }
+        let scope_tree = &self.programs[program_id.index()].scope_tree;
+        let mut index = 0;
+        for (_, scope) in scope_tree.iter_function_scopes(scope_ref) {
+            let mut debug_scope = DebugScope {
+                function: scope.is_function(),
+                variables: vec![],
+            };
+            for variable in scope.variables.iter() {
+                if let Locator::Global = variable.locator() {
+                    continue;
+                }
+                // SAFETY: The generated code passes the addresses of the variables in the same
+                // order as this loop.
+                let value = unsafe { *variables.add(index) };
+                index += 1;
+                if let Value::None = value {
+                    continue;
+                }
+                let name = self
+                    .symbol_registry
+                    .resolve(variable.symbol)
+                    .map(std::string::String::from_utf16_lossy)
+                    .unwrap_or_default();
+                debug_scope.variables.push((name, value.clone()));
+            }
+            scopes.push(debug_scope);
+        }
+        scopes
+    }
+}
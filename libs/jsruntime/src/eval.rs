@@ -0,0 +1,289 @@
+use std::ops::Range;
+
+use jsgc::HandleMut;
+use jsparser::Error as ParserError;
+use jsparser::Node;
+use jsparser::NodeHandler;
+use jsparser::Parser;
+use jsparser::Processor;
+use jsparser::Symbol;
+use jsparser::SymbolRegistry;
+
+use crate::Error;
+use crate::ErrorKind;
+use crate::ProgramId;
+use crate::Runtime;
+use crate::backend;
+use crate::logger;
+use crate::semantics::EvalParams;
+use crate::semantics::ScopeRef;
+use crate::types::CallContext;
+use crate::types::Capture;
+use crate::types::Status;
+use crate::types::Value;
+
+impl<X> Runtime<X> {
+    // 19.2.1.1 PerformEval ( x, strictCaller, direct )
+    //
+    // Called from generated code at a direct eval.  `captures` holds the captures of the
+    // variables visible from the call site in the order of `ScopeTree::visible_variables()`.
+    pub(crate) fn perform_direct_eval(
+        &mut self,
+        context: &CallContext,
+        scope_ref: ScopeRef,
+        captures: *const HandleMut<Capture>,
+        strict: bool,
+        retv: &mut Value,
+    ) -> Status {
+        logger::debug!(event = "perform_direct_eval", ?scope_ref, strict);
+
+        let source = match context.arg(0) {
+            Value::String(source) => std::string::String::from_utf16_lossy(&source.make_utf16()),
+            value => {
+                *retv = value.clone();
+                return Status::Normal;
+            }
+        };
+
+        let (lambda_id, _) = context.call_site().expect("the call site must be recorded");
+        let program_id = self.lambda_registry.get(lambda_id).program_id;
+        let symbols: Vec<Symbol> = self.programs[program_id.index()]
+            .scope_tree
+            .visible_variables(scope_ref)
+            .map(|(_, variable)| variable.symbol)
+            .collect();
+        // SAFETY: The generated code passes the captures of all the visible variables.
+        let captures = unsafe { std::slice::from_raw_parts(captures, symbols.len()) };
+
+        let params = EvalParams {
+            direct: true,
+            strict,
+            captures: symbols,
+        };
+        let program_id = match self.parse_eval_code(&source, params) {
+            Ok(program_id) => program_id,
+            Err(err) => {
+                *retv = self.create_exception_with_stack(err, context);
+                return Status::Exception;
+            }
+        };
+
+        let this = context.this().clone();
+        self.call_eval_code(context, program_id, captures, &this, retv)
+    }
+
+    // 19.2.1.1 PerformEval ( x, strictCaller, direct )
+    //
+    // Called from %eval% when it's not called directly.  The eval code is evaluated in the
+    // global scope.
+    pub(crate) fn perform_indirect_eval(
+        &mut self,
+        context: &CallContext,
+        retv: &mut Value,
+    ) -> Status {
+        logger::debug!(event = "perform_indirect_eval");
+
+        let source = match context.arg(0) {
+            Value::String(source) => std::string::String::from_utf16_lossy(&source.make_utf16()),
+            value => {
+                *retv = value.clone();
+                return Status::Normal;
+            }
+        };
+
+        let params = EvalParams {
+            direct: false,
+            strict: false,
+            captures: vec![],
+        };
+        let program_id = match self.parse_eval_code(&source, params) {
+            Ok(program_id) => program_id,
+            Err(err) => {
+                *retv = self.create_exception_with_stack(err, context);
+                return Status::Exception;
+            }
+        };
+
+        let this = Value::Object(self.builtins.global_object);
+        self.call_eval_code(context, program_id, &[], &this, retv)
+    }
+
+    // The source text is parsed after checking whether the code generation from strings is
+    // allowed.
+    fn parse_eval_code(&mut self, source: &str, params: EvalParams) -> Result<ProgramId, Error> {
+        self.ensure_can_compile_strings()?;
+        self.parse_eval(source, params).map_err(to_syntax_error)
+    }
+
+    // 19.2.1.2 HostEnsureCanCompileStrings ( calleeRealm, parameterStrings, bodyString, direct )
+    fn ensure_can_compile_strings(&self) -> Result<(), Error> {
+        if !self.pref.allow_code_generation_from_strings {
+            return eval_error!("code generation from strings disallowed");
+        }
+        Ok(())
+    }
+
+    // 20.2.1.1.1 CreateDynamicFunction ( constructor, newTarget, kind, parameterArgs, bodyArg )
+    //
+    // Only normal functions are supported.  The source text of the function is evaluated as
+    // an indirect eval code and the function object is obtained as its completion value.
+    pub(crate) fn create_dynamic_function(
+        &mut self,
+        context: &CallContext,
+        retv: &mut Value,
+    ) -> Status {
+        logger::debug!(event = "create_dynamic_function");
+
+        let program_id = match self.parse_dynamic_function(context.args()) {
+            Ok(program_id) => program_id,
+            Err(err) => {
+                *retv = self.create_exception_with_stack(err, context);
+                return Status::Exception;
+            }
+        };
+
+        let this = Value::Object(self.builtins.global_object);
+        self.call_eval_code(context, program_id, &[], &this, retv)
+    }
+
+    fn parse_dynamic_function(&mut self, args: &[Value]) -> Result<ProgramId, Error> {
+        let (body, params) = match args.split_last() {
+            Some((body, params)) => (Some(body), params),
+            None => (None, args),
+        };
+        let mut param_list = vec![];
+        for param in params.iter() {
+            let param = self.value_to_string(param)?;
+            param_list.push(std::string::String::from_utf16_lossy(&param.make_utf16()));
+        }
+        let body = match body {
+            Some(body) => {
+                let body = self.value_to_string(body)?;
+                std::string::String::from_utf16_lossy(&body.make_utf16())
+            }
+            None => std::string::String::new(),
+        };
+
+        self.ensure_can_compile_strings()?;
+
+        // The line terminators prevent a single-line comment in the parameters or the body from
+        // breaking the source text.
+        let param_list = param_list.join(",");
+        let source = format!("(function anonymous({param_list}\n) {{\n{body}\n}})");
+
+        // The parameters and the body must be parsed separately in the specification.  Each of
+        // them is checked in a function expression without the other so that a comment or
+        // a literal cannot span both of them.  In addition, the whole source text must be
+        // a single function expression so that they cannot break out of the function.
+        //
+        // These checks must be done before the analysis because the analysis has side effects
+        // such as defining global variables.
+        let sources = [
+            format!("(function anonymous({param_list}\n) {{\n}})"),
+            format!("(function anonymous(\n) {{\n{body}\n}})"),
+        ];
+        for source in sources.iter().chain(std::iter::once(&source)) {
+            if !self.is_function_expression(source)? {
+                return syntax_error!("invalid function source text");
+            }
+        }
+
+        let params = EvalParams {
+            direct: false,
+            strict: false,
+            captures: vec![],
+        };
+        self.parse_eval(&source, params).map_err(to_syntax_error)
+    }
+
+    // Parses a source text without analyzing it and returns `true` if the whole source text is
+    // a single function expression enclosed in parentheses.
+    fn is_function_expression(&mut self, source: &str) -> Result<bool, Error> {
+        let checker = SyntaxChecker {
+            symbol_registry: &mut self.symbol_registry,
+            function_ranges: vec![],
+        };
+        let function_ranges = Parser::for_script(source, Processor::new(checker, false))
+            .parse()
+            .map_err(to_syntax_error)?;
+        let range = 1..source.len() - 1;
+        Ok(function_ranges.contains(&range))
+    }
+
+    fn call_eval_code(
+        &mut self,
+        caller: &CallContext,
+        program_id: ProgramId,
+        captures: &[HandleMut<Capture>],
+        this: &Value,
+        retv: &mut Value,
+    ) -> Status {
+        // The entry function of the eval code is called as a closure capturing the variables
+        // of the caller.
+        let function_index = self.programs[program_id.index()].functions.len() - 1;
+        let lambda_id = self.programs[program_id.index()].functions[function_index].id;
+        debug_assert_eq!(
+            self.programs[program_id.index()].functions[function_index].num_captures as usize,
+            captures.len()
+        );
+        if backend::compile_function(self, program_id, function_index, true).is_err() {
+            let err = Error::new(
+                ErrorKind::InternalError,
+                Some(const_string!("failed to compile eval code")),
+            );
+            *retv = self.create_exception_with_stack(err, caller);
+            return Status::Exception;
+        }
+        let lambda = self.code_registry.get_lambda(lambda_id).unwrap();
+        let mut closure = self.create_closure(lambda, lambda_id, captures.len() as u16);
+        for (index, capture) in captures.iter().enumerate() {
+            closure.put_capture(index, *capture);
+        }
+        let mut func = self.create_object();
+        func.set_prototype(self.builtins.function_prototype);
+        func.set_closure(closure);
+
+        *retv = Value::Undefined;
+        self.call_with_context(caller, func, this, &[], retv)
+    }
+}
+
+// The message of the parser error is used as the message of the SyntaxError.
+fn to_syntax_error(err: ParserError) -> Error {
+    let message = match err {
+        ParserError::UnexpectedCharacter => const_string!("Unexpected character"),
+        ParserError::SyntaxError => const_string!("Syntax error"),
+        ParserError::NotYetImplemented => const_string!("Not yet implemented"),
+    };
+    Error::new(ErrorKind::SyntaxError, Some(message))
+}
+
+// A node handler that performs only the syntax analysis and collects the source ranges of
+// function expressions.
+struct SyntaxChecker<'a> {
+    symbol_registry: &'a mut SymbolRegistry,
+    function_ranges: Vec<Range<usize>>,
+}
+
+impl<'s> NodeHandler<'s> for SyntaxChecker<'_> {
+    type Artifact = Vec<Range<usize>>;
+
+    fn start(&mut self) {}
+
+    fn accept(&mut self) -> Result<Self::Artifact, ParserError> {
+        Ok(std::mem::take(&mut self.function_ranges))
+    }
+
+    fn handle_nodes(&mut self, nodes: impl Iterator<Item = Node<'s>>) -> Result<(), ParserError> {
+        for node in nodes {
+            if let Node::FunctionExpression(_, range) = node {
+                self.function_ranges.push(range);
+            }
+        }
+        Ok(())
+    }
+
+    fn make_symbol(&mut self, lexeme: &str) -> Symbol {
+        self.symbol_registry.intern_str(lexeme)
+    }
+}
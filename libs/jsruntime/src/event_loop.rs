@@ -0,0 +1,404 @@
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+use jsgc::HandleMut;
+use jsgc::Trace;
+use jsgc::VisitList;
+use rustc_hash::FxHashMap;
+
+use crate::Error;
+use crate::Runtime;
+use crate::builtins::BuiltinFunctionParams;
+use crate::future::FutureRunner;
+use crate::logger;
+use crate::types::CallContext;
+use crate::types::CallContextFlags;
+use crate::types::Lambda;
+use crate::types::Object;
+use crate::types::Property;
+use crate::types::Status;
+use crate::types::Value;
+
+// The maximum delay of timers in milliseconds.  Same as browsers.
+const MAX_DELAY: f64 = i32::MAX as f64;
+
+// Repeating timers fire at most once per millisecond so that the time always advances.
+const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A task posted by `Runtime::post_task()`.
+type HostTask<X> = Box<dyn FnOnce(&mut Runtime<X>)>;
+
+/// The event loop processing macrotasks.
+///
+/// Microtasks such as promise jobs are processed in `JobRunner` after each macrotask.
+pub(crate) struct EventLoop<X> {
+    clock: Clock,
+    tasks: VecDeque<HostTask<X>>,
+    timers: FxHashMap<u32, Timer>,
+    // Active timers ordered by their due times.  Timers having the same due time are ordered by
+    // their IDs in order to run them in the order of registration.
+    timer_queue: BTreeSet<(Duration, u32)>,
+    next_timer_id: u32,
+    pub(crate) futures: FutureRunner<X>,
+}
+
+impl<X> EventLoop<X> {
+    pub(crate) fn new() -> Self {
+        Self {
+            clock: Clock::Real(Instant::now()),
+            tasks: Default::default(),
+            timers: Default::default(),
+            timer_queue: Default::default(),
+            next_timer_id: 1,
+            futures: FutureRunner::new(),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.tasks.is_empty() && self.timer_queue.is_empty() && self.futures.is_empty()
+    }
+
+    fn add_timer(&mut self, delay: Duration, timer: Timer) -> u32 {
+        let id = self.next_timer_id;
+        self.next_timer_id += 1;
+        let due = self.clock.now() + delay;
+        self.timer_queue.insert((due, id));
+        self.timers.insert(id, timer.with_due(due));
+        id
+    }
+
+    fn remove_timer(&mut self, id: u32) {
+        if let Some(timer) = self.timers.remove(&id) {
+            self.timer_queue.remove(&(timer.due, id));
+        }
+    }
+
+    fn next_timer(&self) -> Option<(Duration, u32)> {
+        self.timer_queue.first().cloned()
+    }
+
+    // Waits until a future is woken or the clock reaches `until`.
+    fn wait(&mut self, until: Option<Duration>) {
+        match (&mut self.clock, until) {
+            (Clock::Virtual(now), Some(until)) => *now = (*now).max(until),
+            (clock, until) => {
+                let timeout = until.map(|until| until.saturating_sub(clock.now()));
+                self.futures.wait(timeout);
+            }
+        }
+    }
+}
+
+impl<X> Trace for EventLoop<X> {
+    fn trace(&self, visits: &mut VisitList) {
+        for timer in self.timers.values() {
+            timer.callback.trace(visits);
+            timer.args.trace(visits);
+        }
+        self.futures.trace(visits);
+    }
+}
+
+enum Clock {
+    // Holds the time origin.
+    Real(Instant),
+    // Holds the current time.
+    Virtual(Duration),
+}
+
+impl Clock {
+    fn now(&self) -> Duration {
+        match self {
+            Self::Real(origin) => origin.elapsed(),
+            Self::Virtual(now) => *now,
+        }
+    }
+}
+
+struct Timer {
+    due: Duration,
+    callback: HandleMut<Object>,
+    args: Vec<Value>,
+    // `None` for timers created by `setTimeout()`.
+    interval: Option<Duration>,
+}
+
+impl Timer {
+    fn with_due(self, due: Duration) -> Self {
+        Self { due, ..self }
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Uses a virtual clock in the event loop.
+    ///
+    /// The virtual clock starts from zero and advances only when the event loop waits for a
+    /// timer.  The event loop jumps to the due time of the timer without sleeping even if there
+    /// are pending futures.  This is useful for deterministic tests.
+    pub fn enable_virtual_clock(&mut self) {
+        self.event_loop.clock = Clock::Virtual(Duration::ZERO);
+    }
+
+    /// Returns the current time of the clock used in the event loop.
+    pub fn now(&self) -> Duration {
+        self.event_loop.clock.now()
+    }
+
+    /// Posts a task to the macrotask queue of the event loop.
+    ///
+    /// Tasks are performed in the order of posting by `run_until_idle()` or `run_until()`.
+    pub fn post_task<F>(&mut self, task: F)
+    where
+        F: FnOnce(&mut Self) + 'static,
+    {
+        self.event_loop.tasks.push_back(Box::new(task));
+    }
+
+    /// Returns `true` if there are pending tasks, active timers or pending futures.
+    pub fn has_pending_tasks(&self) -> bool {
+        !self.event_loop.is_idle()
+    }
+
+    /// Runs the event loop until there are no pending tasks, no active timers and no pending
+    /// futures.
+    ///
+    /// Microtasks are performed after each task.  The loop stops when a timer callback throws an
+    /// exception or the script is terminated, and the exception is returned.  Remaining tasks
+    /// are kept and the loop can be resumed.
+    pub fn run_until_idle(&mut self) -> Result<(), Value> {
+        self.run_event_loop(None).map(|_| ())
+    }
+
+    /// Runs the event loop until it becomes idle or the clock reaches `deadline`.
+    ///
+    /// Returns `true` if the event loop became idle.  See `run_until_idle()` for details.
+    pub fn run_until(&mut self, deadline: Duration) -> Result<bool, Value> {
+        self.run_event_loop(Some(deadline))
+    }
+
+    fn run_event_loop(&mut self, deadline: Option<Duration>) -> Result<bool, Value> {
+        logger::debug!(event = "run_event_loop", ?deadline);
+        loop {
+            self.poll_futures();
+            self.perform_microtasks()?;
+            let now = self.event_loop.clock.now();
+            let result = if let Some(task) = self.event_loop.tasks.pop_front() {
+                task(self);
+                Ok(())
+            } else {
+                match self.event_loop.next_timer() {
+                    Some((due, id)) if due <= now => self.run_timer(id),
+                    next => {
+                        if next.is_none() && self.event_loop.futures.is_empty() {
+                            return Ok(true);
+                        }
+                        if deadline.is_some_and(|deadline| now >= deadline) {
+                            return Ok(false);
+                        }
+                        let until = next.map(|(due, _)| due).into_iter().chain(deadline).min();
+                        self.event_loop.wait(until);
+                        continue;
+                    }
+                }
+            };
+            self.perform_microtasks()?;
+            result?;
+            if deadline.is_some_and(|deadline| self.event_loop.clock.now() > deadline) {
+                return Ok(self.event_loop.is_idle());
+            }
+        }
+    }
+
+    fn perform_microtasks(&mut self) -> Result<(), Value> {
+        if self.drain_jobs() {
+            let error =
+                self.create_internal_error(Some(const_string_handle!("Execution terminated")));
+            return Err(Value::Object(error));
+        }
+        Ok(())
+    }
+
+    fn run_timer(&mut self, id: u32) -> Result<(), Value> {
+        let timer = self.event_loop.timers.get(&id).unwrap();
+        logger::debug!(event = "run_timer", id, ?timer.callback);
+        let callback = timer.callback;
+        let args = timer.args.clone();
+        match timer.interval {
+            Some(interval) => {
+                // Reschedule the timer before calling the callback so that it can be cancelled in
+                // the callback.
+                let due = timer.due;
+                self.event_loop.timer_queue.remove(&(due, id));
+                let due = due + interval;
+                self.event_loop.timer_queue.insert((due, id));
+                self.event_loop.timers.get_mut(&id).unwrap().due = due;
+            }
+            None => self.event_loop.remove_timer(id),
+        }
+        self.call_from_host(
+            callback,
+            &Value::Undefined,
+            &args,
+            CallContextFlags::empty(),
+        )
+        .map(|_| ())
+    }
+
+    pub(crate) fn init_event_loop_functions(&mut self) {
+        self.define_event_loop_function("setTimeout", 1, set_timeout);
+        self.define_event_loop_function("setInterval", 1, set_interval);
+        self.define_event_loop_function("clearTimeout", 0, clear_timer);
+        self.define_event_loop_function("clearInterval", 0, clear_timer);
+        self.define_event_loop_function("queueMicrotask", 1, queue_microtask);
+    }
+
+    fn define_event_loop_function(&mut self, name: &str, length: u16, lambda: Lambda<X>) {
+        let name_string = self.create_string_from_utf8(name);
+        let func = self.create_builtin_function(&BuiltinFunctionParams {
+            lambda,
+            name: name_string,
+            length,
+            slots: &[],
+        });
+        let key = self.symbol_registry.intern_str(name).into();
+        let result = self
+            .builtins
+            .global_object
+            .define_own_property(key, Property::data_wxc(Value::Object(func)));
+        debug_assert!(matches!(result, Ok(true)));
+    }
+}
+
+macro_rules! define_lambda {
+    ($name:ident => $imp:ident $(, $arg:expr)?) => {
+        extern "C" fn $name<X>(
+            runtime: &mut Runtime<X>,
+            context: &mut CallContext,
+            retv: &mut Value,
+        ) -> Status {
+            match $imp(runtime, context $(, $arg)?) {
+                Ok(value) => {
+                    *retv = value;
+                    Status::Normal
+                }
+                Err(err) => {
+                    *retv = runtime.create_exception_with_stack(err, context);
+                    Status::Exception
+                }
+            }
+        }
+    };
+}
+
+define_lambda!(set_timeout => set_timer, false);
+define_lambda!(set_interval => set_timer, true);
+define_lambda!(clear_timer => clear_timer_imp);
+define_lambda!(queue_microtask => queue_microtask_imp);
+
+// HTML: 8.6 Timers
+fn set_timer<X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    repeat: bool,
+) -> Result<Value, Error> {
+    let args = context.args();
+    let callback = match args.first() {
+        Some(Value::Object(callback)) if callback.is_callable() => *callback,
+        // TODO(feat): compile a string
+        _ => return type_error!("callback is not a function"),
+    };
+    let delay = match args.get(1) {
+        Some(delay) => runtime.value_to_number(delay)?,
+        None => 0.0,
+    };
+    let delay = if delay > 0.0 {
+        Duration::from_millis(delay.min(MAX_DELAY) as u64)
+    } else {
+        Duration::ZERO
+    };
+    let timer = Timer {
+        due: Duration::ZERO,
+        callback,
+        args: args.get(2..).unwrap_or_default().to_vec(),
+        interval: repeat.then(|| delay.max(MIN_INTERVAL)),
+    };
+    let id = runtime.event_loop.add_timer(delay, timer);
+    logger::debug!(event = "set_timer", id, ?delay, repeat);
+    Ok(Value::Number(id as f64))
+}
+
+fn clear_timer_imp<X>(runtime: &mut Runtime<X>, context: &mut CallContext) -> Result<Value, Error> {
+    if let Some(Value::Number(id)) = context.args().first() {
+        logger::debug!(event = "clear_timer", id);
+        runtime.event_loop.remove_timer(*id as u32);
+    }
+    Ok(Value::Undefined)
+}
+
+// HTML: 8.7 Microtask queuing
+fn queue_microtask_imp<X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+) -> Result<Value, Error> {
+    match context.args().first() {
+        Some(Value::Object(callback)) if callback.is_callable() => {
+            runtime.job_runner.emit_microtask(*callback);
+            Ok(Value::Undefined)
+        }
+        _ => type_error!("callback is not a function"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicRuntime;
+
+    #[test]
+    fn test_timer_queue() {
+        let mut runtime = BasicRuntime::new();
+        runtime.enable_virtual_clock();
+        let callback = runtime.builtins.object_constructor;
+        let timer = |delay| Timer {
+            due: Duration::ZERO,
+            callback,
+            args: vec![],
+            interval: delay,
+        };
+        let a = runtime
+            .event_loop
+            .add_timer(Duration::from_millis(10), timer(None));
+        let b = runtime.event_loop.add_timer(Duration::ZERO, timer(None));
+        let c = runtime.event_loop.add_timer(Duration::ZERO, timer(None));
+        assert_eq!(runtime.event_loop.next_timer(), Some((Duration::ZERO, b)));
+        runtime.event_loop.remove_timer(b);
+        assert_eq!(runtime.event_loop.next_timer(), Some((Duration::ZERO, c)));
+        runtime.event_loop.remove_timer(c);
+        assert_eq!(
+            runtime.event_loop.next_timer(),
+            Some((Duration::from_millis(10), a))
+        );
+        runtime.event_loop.remove_timer(a);
+        assert!(runtime.event_loop.is_idle());
+    }
+
+    #[test]
+    fn test_virtual_clock() {
+        let mut runtime = BasicRuntime::new();
+        runtime.enable_virtual_clock();
+        let timer = Timer {
+            due: Duration::ZERO,
+            callback: runtime.builtins.object_constructor,
+            args: vec![],
+            interval: None,
+        };
+        runtime.event_loop.add_timer(Duration::from_secs(1), timer);
+        assert_eq!(runtime.run_until(Duration::from_millis(500)), Ok(false));
+        assert_eq!(runtime.now(), Duration::from_millis(500));
+        assert!(runtime.has_pending_tasks());
+        assert_eq!(runtime.run_until(Duration::from_secs(2)), Ok(true));
+        assert_eq!(runtime.now(), Duration::from_secs(1));
+    }
+}
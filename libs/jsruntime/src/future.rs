@@ -0,0 +1,399 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Wake;
+use std::task::Waker;
+use std::time::Duration;
+
+use jsgc::HandleMut;
+use jsgc::Trace;
+use jsgc::VisitList;
+use rustc_hash::FxHashMap;
+
+use crate::HostFunction;
+use crate::IntoJsValue;
+use crate::Runtime;
+use crate::lambda::LambdaId;
+use crate::logger;
+use crate::types::CallContext;
+use crate::types::Lambda;
+use crate::types::Object;
+use crate::types::Status;
+use crate::types::Value;
+
+// Converts the output of a future into a value used for settling the promise.
+type Settle<X> = Box<dyn FnOnce(&mut Runtime<X>) -> Result<Value, Value>>;
+
+type HostFuture<X> = Pin<Box<dyn Future<Output = Settle<X>>>>;
+
+/// Futures spawned by `Runtime::spawn()` and promises awaited by `PromiseFuture`s.
+pub(crate) struct FutureRunner<X> {
+    // Spawned futures and the promises to be settled when they complete.
+    futures: FxHashMap<u32, (HandleMut<Object>, HostFuture<X>)>,
+    next_future_id: u32,
+    wake_queue: Arc<WakeQueue>,
+    // Keyed by the address of the awaiting promise object.
+    awaiting: FxHashMap<usize, Awaiting>,
+}
+
+impl<X> FutureRunner<X> {
+    pub(crate) fn new() -> Self {
+        Self {
+            futures: Default::default(),
+            next_future_id: 1,
+            wake_queue: Default::default(),
+            awaiting: Default::default(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.futures.is_empty()
+    }
+
+    /// Blocks the current thread until a future is woken or the timeout elapses.
+    pub(crate) fn wait(&self, timeout: Option<Duration>) {
+        let woken = self.wake_queue.woken.lock().unwrap();
+        if !woken.is_empty() {
+            return;
+        }
+        // Spurious wakeups are harmless.
+        match timeout {
+            Some(timeout) => {
+                let _ = self.wake_queue.condvar.wait_timeout(woken, timeout);
+            }
+            None => {
+                let _ = self.wake_queue.condvar.wait(woken);
+            }
+        }
+    }
+
+    fn take_woken(&self) -> Vec<u32> {
+        std::mem::take(&mut *self.wake_queue.woken.lock().unwrap())
+    }
+
+    fn waker(&self, id: u32) -> Waker {
+        Waker::from(Arc::new(FutureWaker {
+            id,
+            queue: self.wake_queue.clone(),
+        }))
+    }
+}
+
+impl<X> Trace for FutureRunner<X> {
+    fn trace(&self, visits: &mut VisitList) {
+        for (promise, _) in self.futures.values() {
+            promise.trace(visits);
+        }
+        for awaiting in self.awaiting.values() {
+            awaiting.promise.trace(visits);
+            awaiting.awaiting.trace(visits);
+        }
+    }
+}
+
+#[derive(Default)]
+struct WakeQueue {
+    woken: Mutex<Vec<u32>>,
+    condvar: Condvar,
+}
+
+struct FutureWaker {
+    id: u32,
+    queue: Arc<WakeQueue>,
+}
+
+impl Wake for FutureWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.queue.woken.lock().unwrap().push(self.id);
+        self.queue.condvar.notify_one();
+    }
+}
+
+struct Awaiting {
+    // The promise awaited.
+    promise: HandleMut<Object>,
+    // The promise object used for receiving the result.
+    awaiting: HandleMut<Object>,
+    state: Rc<RefCell<PromiseFutureState>>,
+}
+
+/// A future that completes when a promise is settled.
+///
+/// Created by `Runtime::promise_to_future()`.  The future makes progress only while the event
+/// loop is running.  The output value becomes invalid after the next garbage collection unless
+/// it's rooted.
+pub struct PromiseFuture {
+    state: Rc<RefCell<PromiseFutureState>>,
+}
+
+#[derive(Default)]
+struct PromiseFutureState {
+    result: Option<Result<Value, Value>>,
+    waker: Option<Waker>,
+}
+
+impl Future for PromiseFuture {
+    type Output = Result<Value, Value>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<X: 'static> Runtime<X> {
+    /// Spawns a future and returns a promise settled when the future completes.
+    ///
+    /// The future is polled in the event loop.  The promise is resolved with the output of the
+    /// future converted by `IntoJsValue`, or rejected if the conversion fails.
+    pub fn spawn<F>(&mut self, future: F) -> Value
+    where
+        F: Future + 'static,
+        F::Output: IntoJsValue,
+    {
+        let promise = self.create_pending_promise();
+        let future: HostFuture<X> = Box::pin(async move {
+            let output = future.await;
+            Box::new(move |runtime: &mut Runtime<X>| output.into_js_value(runtime)) as Settle<X>
+        });
+        let runner = &mut self.event_loop.futures;
+        let id = runner.next_future_id;
+        runner.next_future_id += 1;
+        logger::debug!(event = "spawn", id, ?promise);
+        runner.futures.insert(id, (promise, future));
+        // The future will be polled for the first time in the event loop.
+        runner.waker(id).wake();
+        Value::Object(promise)
+    }
+
+    /// Registers a Rust function returning a future as a global function.
+    ///
+    /// The function returns a promise settled when the future completes.  The promise is
+    /// rejected if an argument cannot be converted.
+    ///
+    /// ```ignore
+    /// runtime.register_async_function("fetch", |url: String| async move { load(url).await });
+    /// ```
+    pub fn register_async_function<F, Args>(&mut self, name: &str, host_fn: F)
+    where
+        F: HostFunction<X, Args>,
+        F::Output: Future + 'static,
+        <F::Output as Future>::Output: IntoJsValue,
+    {
+        logger::debug!(event = "register_async_function", name);
+        let lambda = into_async_lambda(host_fn);
+        self.define_host_function(name, lambda);
+    }
+
+    /// Returns a future that completes when a promise is settled.
+    ///
+    /// A value other than a promise is treated as an already resolved promise.
+    pub fn promise_to_future(&mut self, value: &Value) -> PromiseFuture {
+        let state: Rc<RefCell<PromiseFutureState>> = Default::default();
+        match value {
+            Value::Object(promise) if self.is_promise_object(*promise) => {
+                let awaiting = self.create_promise_object(promise_future_coroutine);
+                self.event_loop.futures.awaiting.insert(
+                    awaiting.as_addr(),
+                    Awaiting {
+                        promise: *promise,
+                        awaiting,
+                        state: state.clone(),
+                    },
+                );
+                // TODO: a promise can be awaited by only a single promise at this point.
+                self.emit_promise_resolved(awaiting, value.clone());
+            }
+            _ => state.borrow_mut().result = Some(Ok(value.clone())),
+        }
+        PromiseFuture { state }
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Polls woken futures.
+    pub(crate) fn poll_futures(&mut self) {
+        let woken = self
+            .event_loop
+            .futures
+            .wake_queue
+            .woken
+            .lock()
+            .unwrap()
+            .split_off(0);
+        for id in woken {
+            // A future may be woken multiple times.
+            let Some((promise, mut future)) = self.event_loop.futures.futures.remove(&id) else {
+                continue;
+            };
+            let waker = self.event_loop.futures.waker(id);
+            let mut cx = Context::from_waker(&waker);
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(settle) => {
+                    logger::debug!(event = "future_ready", id, ?promise);
+                    match settle(self) {
+                        Ok(value) => self.emit_promise_resolved(promise, value),
+                        Err(error) => self.emit_promise_rejected(promise, error),
+                    }
+                }
+                Poll::Pending => {
+                    self.event_loop
+                        .futures
+                        .futures
+                        .insert(id, (promise, future));
+                }
+            }
+        }
+    }
+
+    fn create_pending_promise(&mut self) -> HandleMut<Object> {
+        self.create_promise_object(pending_promise_coroutine)
+    }
+
+    fn create_promise_object(&mut self, lambda: Lambda<X>) -> HandleMut<Object> {
+        let closure = self.create_closure(lambda, LambdaId::HOST, 0);
+        let coroutine = self.create_coroutine(closure, 0, 0, 0);
+        let promise = self.create_promise(coroutine);
+        let mut object = self.create_object();
+        object.set_prototype(self.builtins.promise_prototype);
+        object.set_promise(promise);
+        object
+    }
+}
+
+// Passes the result through like the coroutine of a promise created by `new Promise()`.
+extern "C" fn pending_promise_coroutine<X>(
+    _runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    retv: &mut Value,
+) -> Status {
+    let (status, value) = coroutine_result(context);
+    *retv = value;
+    status
+}
+
+// Sends the result of the awaited promise to the `PromiseFuture`.
+extern "C" fn promise_future_coroutine<X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    retv: &mut Value,
+) -> Status {
+    let (status, value) = coroutine_result(context);
+    let awaiting = match context.args().first() {
+        Some(Value::Object(awaiting)) => *awaiting,
+        _ => unreachable!(),
+    };
+    if let Some(awaiting) = runtime
+        .event_loop
+        .futures
+        .awaiting
+        .remove(&awaiting.as_addr())
+    {
+        let mut state = awaiting.state.borrow_mut();
+        state.result = Some(match status {
+            Status::Normal => Ok(value.clone()),
+            _ => Err(value.clone()),
+        });
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+    *retv = value;
+    status
+}
+
+// The arguments of a coroutine for a promise are the promise object, the result and the error.
+// Either the result or the error is valid.
+fn coroutine_result(context: &CallContext) -> (Status, Value) {
+    let result = context.args().get(1).unwrap();
+    if result.is_valid() {
+        (Status::Normal, result.clone())
+    } else {
+        let error = context.args().get(2).unwrap();
+        debug_assert!(error.is_valid());
+        (Status::Exception, error.clone())
+    }
+}
+
+// See also `host_function::into_typed_lambda()`.
+fn into_async_lambda<F, Args, X>(host_fn: F) -> Lambda<X>
+where
+    X: 'static,
+    F: HostFunction<X, Args>,
+    F::Output: Future + 'static,
+    <F::Output as Future>::Output: IntoJsValue,
+{
+    debug_assert_eq!(std::mem::size_of::<F>(), 0, "Function must have zero size");
+    std::mem::forget(host_fn);
+    async_host_fn_wrapper::<F, Args, X>
+}
+
+extern "C" fn async_host_fn_wrapper<F, Args, X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    retv: &mut Value,
+) -> Status
+where
+    X: 'static,
+    F: HostFunction<X, Args>,
+    F::Output: Future + 'static,
+    <F::Output as Future>::Output: IntoJsValue,
+{
+    // SAFETY: `into_async_lambda()` ensured that F is zero sized.
+    #[allow(clippy::uninit_assumed_init)]
+    let host_fn = unsafe { std::mem::MaybeUninit::<F>::uninit().assume_init() };
+    let saved = runtime.enter_host_function(context);
+    let result = host_fn.invoke(runtime, context.this(), context.args());
+    runtime.leave_host_function(saved);
+    std::mem::forget(host_fn);
+    *retv = match result {
+        Ok(future) => runtime.spawn(future),
+        Err(error) => {
+            let promise = runtime.create_pending_promise();
+            runtime.emit_promise_rejected(promise, error);
+            Value::Object(promise)
+        }
+    };
+    Status::Normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicRuntime;
+
+    #[test]
+    fn test_spawn() {
+        let mut runtime = BasicRuntime::new();
+        let promise = runtime.spawn(async { 1 });
+        let mut future = std::pin::pin!(runtime.promise_to_future(&promise));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(runtime.run_until_idle(), Ok(()));
+        assert_eq!(future.poll(&mut cx), Poll::Ready(Ok(Value::from(1))));
+    }
+
+    #[test]
+    fn test_promise_to_future_non_promise() {
+        let mut runtime = BasicRuntime::new();
+        let mut future = std::pin::pin!(runtime.promise_to_future(&Value::TRUE));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(Ok(Value::TRUE)));
+    }
+}
@@ -0,0 +1,312 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use jsgc::HandleMut;
+use jsgc::Trace;
+use jsgc::VisitList;
+use jsparser::Symbol;
+
+use crate::FromJsValue;
+use crate::HostFunction;
+use crate::IntoJsValue;
+use crate::Runtime;
+use crate::builtins::BuiltinFunctionParams;
+use crate::host_function::into_typed_lambda;
+use crate::logger;
+use crate::types::CallContext;
+use crate::types::Lambda;
+use crate::types::Object;
+use crate::types::Property;
+use crate::types::Status;
+use crate::types::Value;
+
+/// Rust data stored in a host object.
+///
+/// The memory block is reclaimed and the data is dropped when the host object becomes
+/// unreachable.
+//
+// DO NOT CHANGE THE ORDER OF THE FIELDS.
+// `type_id` is read without knowing `T`.
+#[repr(C)]
+pub(crate) struct HostData<T> {
+    type_id: TypeId,
+    value: T,
+}
+
+impl<T: Trace> Trace for HostData<T> {
+    fn trace(&self, visits: &mut VisitList) {
+        self.value.trace(visits);
+    }
+}
+
+/// A handle to a host object holding a value of `T`.
+///
+/// Like `Value`, the handle becomes invalid after the next garbage collection unless the host
+/// object is rooted.
+pub struct HostObject<T> {
+    object: HandleMut<Object>,
+    data: HandleMut<HostData<T>>,
+}
+
+impl<T> HostObject<T> {
+    /// Returns the host object.
+    pub fn object(&self) -> Value {
+        Value::Object(self.object)
+    }
+}
+
+impl<T> Clone for HostObject<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for HostObject<T> {}
+
+impl<T> Deref for HostObject<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data.value
+    }
+}
+
+impl<T> DerefMut for HostObject<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data.value
+    }
+}
+
+impl<T: 'static> FromJsValue for HostObject<T> {
+    fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+        match value {
+            Value::Object(object) if object.is_host_object() => {
+                let addr = object.host_data_addr();
+                // SAFETY: The data of a host object is always a `HostData` and its first field is
+                // a `TypeId`.
+                let type_id = unsafe { *(addr as *const TypeId) };
+                if type_id == TypeId::of::<T>() {
+                    return Ok(HostObject {
+                        object: *object,
+                        data: HandleMut::from_addr(addr).unwrap(),
+                    });
+                }
+            }
+            _ => (),
+        }
+        runtime.throw(type_error!("incompatible host object"))
+    }
+}
+
+impl<T> IntoJsValue for HostObject<T> {
+    fn into_js_value<X>(self, _runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        Ok(self.object())
+    }
+}
+
+/// A builder to define a host class whose instances hold a value of `T`.
+///
+/// Created by `Runtime::define_class()`.
+pub struct ClassBuilder<'a, X, T> {
+    runtime: &'a mut Runtime<X>,
+    name: &'a str,
+    constructor: HandleMut<Object>,
+    prototype: HandleMut<Object>,
+    _phantom: PhantomData<T>,
+}
+
+impl<X, T> ClassBuilder<'_, X, T> {
+    /// Defines a method on the prototype object.
+    ///
+    /// Use `This<HostObject<T>>` as a parameter in order to access the Rust data.
+    pub fn method<F, Args>(mut self, name: &str, func: F) -> Self
+    where
+        F: HostFunction<X, Args>,
+        F::Output: IntoJsValue,
+    {
+        let func = self.create_function(name, into_typed_lambda(func));
+        let key = self.runtime.symbol_registry.intern_str(name).into();
+        let result = self
+            .prototype
+            .define_own_property(key, Property::data_wxc(Value::Object(func)));
+        debug_assert!(matches!(result, Ok(true)));
+        self
+    }
+
+    /// Defines a getter of an accessor property on the prototype object.
+    pub fn getter<F, Args>(self, name: &str, func: F) -> Self
+    where
+        F: HostFunction<X, Args>,
+        F::Output: IntoJsValue,
+    {
+        let getter = self.create_function(name, into_typed_lambda(func));
+        self.define_accessor(name, Some(getter), None)
+    }
+
+    /// Defines a setter of an accessor property on the prototype object.
+    ///
+    /// The new value is passed as the first argument.
+    pub fn setter<F, Args>(self, name: &str, func: F) -> Self
+    where
+        F: HostFunction<X, Args>,
+        F::Output: IntoJsValue,
+    {
+        let setter = self.create_function(name, into_typed_lambda(func));
+        self.define_accessor(name, None, Some(setter))
+    }
+
+    /// Defines the class as a global variable and returns the constructor.
+    pub fn build(self) -> Value {
+        logger::debug!(event = "define_class", name = self.name);
+        let value = Value::Object(self.constructor);
+        let key = self.runtime.symbol_registry.intern_str(self.name).into();
+        let result = self
+            .runtime
+            .builtins
+            .global_object
+            .define_own_property(key, Property::data_wxc(value.clone()));
+        debug_assert!(matches!(result, Ok(true)));
+        value
+    }
+
+    fn create_function(&mut self, name: &str, lambda: Lambda<X>) -> HandleMut<Object> {
+        let name = self.runtime.create_string_from_utf8(name);
+        self.runtime
+            .create_builtin_function(&BuiltinFunctionParams {
+                lambda,
+                name,
+                length: 0,
+                slots: &[],
+            })
+    }
+
+    fn define_accessor(
+        mut self,
+        name: &str,
+        getter: Option<HandleMut<Object>>,
+        setter: Option<HandleMut<Object>>,
+    ) -> Self {
+        let key = self.runtime.symbol_registry.intern_str(name).into();
+        let (old_getter, old_setter) = match self.prototype.get_own_property(&key) {
+            Some(prop) if !prop.is_data_property() => (prop.getter(), prop.setter()),
+            _ => (None, None),
+        };
+        let accessor = self
+            .runtime
+            .create_accessor(getter.or(old_getter), setter.or(old_setter));
+        let prop = Property::accessor_xc(accessor);
+        let result = self.prototype.define_own_property(key, prop);
+        debug_assert!(matches!(result, Ok(true)));
+        self
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Starts defining a host class.
+    ///
+    /// The constructor is a Rust function returning a value of `T` which is stored in the object
+    /// created by the `new` operator.  The value is traced during garbage collection and dropped
+    /// when the object is reclaimed.  Calling the constructor without `new` throws a `TypeError`.
+    ///
+    /// ```ignore
+    /// runtime
+    ///     .define_class("Counter", |count: f64| Counter { count })
+    ///     .method("increment", |This(mut c): This<HostObject<Counter>>| c.count += 1.0)
+    ///     .getter("count", |This(c): This<HostObject<Counter>>| c.count)
+    ///     .build();
+    /// ```
+    pub fn define_class<'a, T, F, Args>(
+        &'a mut self,
+        name: &'a str,
+        constructor: F,
+    ) -> ClassBuilder<'a, X, T>
+    where
+        T: Trace + 'static,
+        F: HostFunction<X, Args, Output = T>,
+    {
+        let lambda = into_constructor_lambda(constructor);
+        let name_string = self.create_string_from_utf8(name);
+        let mut constructor = self.create_builtin_function(&BuiltinFunctionParams {
+            lambda,
+            name: name_string,
+            length: 0,
+            slots: &[],
+        });
+        constructor.set_constructor();
+
+        let mut prototype = self.create_object();
+        prototype.set_prototype(self.builtins.object_prototype);
+
+        let result = constructor.define_own_property(
+            Symbol::PROTOTYPE.into(),
+            Property::data_xxx(Value::Object(prototype)),
+        );
+        debug_assert!(matches!(result, Ok(true)));
+        let result = prototype.define_own_property(
+            Symbol::CONSTRUCTOR.into(),
+            Property::data_wxc(Value::Object(constructor)),
+        );
+        debug_assert!(matches!(result, Ok(true)));
+
+        ClassBuilder {
+            runtime: self,
+            name,
+            constructor,
+            prototype,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+// See also `host_function::into_typed_lambda()`.
+fn into_constructor_lambda<F, Args, X, T>(host_fn: F) -> Lambda<X>
+where
+    T: Trace + 'static,
+    F: HostFunction<X, Args, Output = T>,
+{
+    debug_assert_eq!(std::mem::size_of::<F>(), 0, "Function must have zero size");
+    std::mem::forget(host_fn);
+    host_constructor_wrapper::<F, Args, X, T>
+}
+
+extern "C" fn host_constructor_wrapper<F, Args, X, T>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    retv: &mut Value,
+) -> Status
+where
+    T: Trace + 'static,
+    F: HostFunction<X, Args, Output = T>,
+{
+    // SAFETY: `into_constructor_lambda()` ensured that F is zero sized.
+    #[allow(clippy::uninit_assumed_init)]
+    let host_fn = unsafe { std::mem::MaybeUninit::<F>::uninit().assume_init() };
+    let saved = runtime.enter_host_function(context);
+    let result = match context.this() {
+        Value::Object(this) if context.is_new() => host_fn
+            .invoke(runtime, context.this(), context.args())
+            .map(|value| (*this, value)),
+        _ => runtime.throw(type_error!(
+            "class constructor cannot be invoked without 'new'"
+        )),
+    };
+    runtime.leave_host_function(saved);
+    std::mem::forget(host_fn);
+    match result {
+        Ok((mut this, value)) => {
+            let data = runtime.heap.alloc_mut(HostData {
+                type_id: TypeId::of::<T>(),
+                value,
+            });
+            this.set_host_data(data);
+            *retv = Value::Object(this);
+            Status::Normal
+        }
+        Err(value) => {
+            *retv = value;
+            Status::Exception
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::marker::PhantomData;
+
+use crate::FromJsValue;
+use crate::IntoJsValue;
+use crate::Runtime;
+use crate::logger;
+use crate::types::CallContext;
+use crate::types::Lambda;
+use crate::types::Status;
+use crate::types::Value;
+
+/// A parameter of a host function registered by `Runtime::register_function()`.
+///
+/// Each parameter consumes arguments from the left.  A missing argument is treated as
+/// `undefined`.
+pub trait HostArg: Sized {
+    fn extract<X>(
+        runtime: &mut Runtime<X>,
+        this: &Value,
+        args: &[Value],
+        index: &mut usize,
+    ) -> Result<Self, Value>;
+}
+
+impl<T: FromJsValue> HostArg for T {
+    fn extract<X>(
+        runtime: &mut Runtime<X>,
+        _this: &Value,
+        args: &[Value],
+        index: &mut usize,
+    ) -> Result<Self, Value> {
+        let arg = args.get(*index).unwrap_or(&Value::Undefined);
+        *index += 1;
+        T::from_js_value(runtime, arg)
+    }
+}
+
+/// A parameter receiving the `this` value.
+///
+/// No argument is consumed.
+pub struct This<T>(pub T);
+
+impl<T: FromJsValue> HostArg for This<T> {
+    fn extract<X>(
+        runtime: &mut Runtime<X>,
+        this: &Value,
+        _args: &[Value],
+        _index: &mut usize,
+    ) -> Result<Self, Value> {
+        T::from_js_value(runtime, this).map(This)
+    }
+}
+
+/// A parameter receiving the remaining arguments.
+pub struct Rest<T>(pub Vec<T>);
+
+impl<T: FromJsValue> HostArg for Rest<T> {
+    fn extract<X>(
+        runtime: &mut Runtime<X>,
+        _this: &Value,
+        args: &[Value],
+        index: &mut usize,
+    ) -> Result<Self, Value> {
+        let rest = args.get(*index..).unwrap_or_default();
+        *index = args.len();
+        rest.iter()
+            .map(|arg| T::from_js_value(runtime, arg))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Rest)
+    }
+}
+
+/// A Rust function which can be registered by `Runtime::register_function()`.
+///
+/// This trait is implemented for functions whose parameters implement `HostArg`.  The first
+/// parameter can optionally be `&mut Runtime<X>`.  The return type must implement `IntoJsValue`
+/// when the function is registered.
+pub trait HostFunction<X, Args>: 'static {
+    type Output;
+
+    fn invoke(
+        &self,
+        runtime: &mut Runtime<X>,
+        this: &Value,
+        args: &[Value],
+    ) -> Result<Self::Output, Value>;
+}
+
+/// A marker type used for functions taking `&mut Runtime<X>` as the first parameter.
+#[doc(hidden)]
+pub struct WithRuntime<Args>(PhantomData<Args>);
+
+macro_rules! impl_host_function {
+    ($($arg:ident),*) => {
+        impl<X, F, R, $($arg,)*> HostFunction<X, ($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            $($arg: HostArg,)*
+        {
+            type Output = R;
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn invoke(
+                &self,
+                runtime: &mut Runtime<X>,
+                this: &Value,
+                args: &[Value],
+            ) -> Result<R, Value> {
+                let mut index = 0;
+                $(let $arg = $arg::extract(runtime, this, args, &mut index)?;)*
+                Ok(self($($arg),*))
+            }
+        }
+
+        impl<X, F, R, $($arg,)*> HostFunction<X, WithRuntime<($($arg,)*)>> for F
+        where
+            F: Fn(&mut Runtime<X>, $($arg),*) -> R + 'static,
+            $($arg: HostArg,)*
+        {
+            type Output = R;
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn invoke(
+                &self,
+                runtime: &mut Runtime<X>,
+                this: &Value,
+                args: &[Value],
+            ) -> Result<R, Value> {
+                let mut index = 0;
+                $(let $arg = $arg::extract(runtime, this, args, &mut index)?;)*
+                Ok(self(runtime, $($arg),*))
+            }
+        }
+    };
+}
+
+impl_host_function!();
+impl_host_function!(A1);
+impl_host_function!(A1, A2);
+impl_host_function!(A1, A2, A3);
+impl_host_function!(A1, A2, A3, A4);
+impl_host_function!(A1, A2, A3, A4, A5);
+impl_host_function!(A1, A2, A3, A4, A5, A6);
+impl_host_function!(A1, A2, A3, A4, A5, A6, A7);
+impl_host_function!(A1, A2, A3, A4, A5, A6, A7, A8);
+
+// This function generates a wrapper function for each `host_fn` at compile time.
+// See also `types::into_lambda()`.
+pub(crate) fn into_typed_lambda<F, Args, X>(host_fn: F) -> Lambda<X>
+where
+    F: HostFunction<X, Args>,
+    F::Output: IntoJsValue,
+{
+    debug_assert_eq!(std::mem::size_of::<F>(), 0, "Function must have zero size");
+    std::mem::forget(host_fn);
+    typed_host_fn_wrapper::<F, Args, X>
+}
+
+extern "C" fn typed_host_fn_wrapper<F, Args, X>(
+    runtime: &mut Runtime<X>,
+    context: &mut CallContext,
+    retv: &mut Value,
+) -> Status
+where
+    F: HostFunction<X, Args>,
+    F::Output: IntoJsValue,
+{
+    // SAFETY: `into_typed_lambda()` ensured that F is zero sized.
+    #[allow(clippy::uninit_assumed_init)]
+    let host_fn = unsafe { std::mem::MaybeUninit::<F>::uninit().assume_init() };
+    let saved = runtime.enter_host_function(context);
+    let result = host_fn
+        .invoke(runtime, context.this(), context.args())
+        .and_then(|output| output.into_js_value(runtime));
+    runtime.leave_host_function(saved);
+    std::mem::forget(host_fn);
+    match result {
+        Ok(value) => {
+            *retv = value;
+            Status::Normal
+        }
+        Err(value) => {
+            *retv = value;
+            Status::Exception
+        }
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Registers a Rust function as a global function.
+    ///
+    /// Unlike `register_host_function()`, arguments are converted into the parameter types of the
+    /// function and the return value is converted into a JavaScript value.  A `TypeError` is
+    /// thrown if an argument cannot be converted.
+    ///
+    /// ```ignore
+    /// runtime.register_function("add", |a: f64, b: f64| a + b);
+    /// ```
+    pub fn register_function<F, Args>(&mut self, name: &str, host_fn: F)
+    where
+        F: HostFunction<X, Args>,
+        F::Output: IntoJsValue,
+    {
+        logger::debug!(event = "register_function", name);
+        let lambda = into_typed_lambda(host_fn);
+        self.define_host_function(name, lambda);
+    }
+}
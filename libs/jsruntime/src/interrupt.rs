@@ -0,0 +1,220 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+
+use jsgc::HandleMut;
+
+use crate::Runtime;
+use crate::logger;
+use crate::types::Object;
+use crate::types::Value;
+
+/// The reason why the execution of a script was terminated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Termination {
+    /// `InterruptHandle::interrupt()` was called.
+    Interrupted,
+
+    /// The fuel has been exhausted.
+    OutOfFuel,
+
+    /// The heap limit has been exceeded.
+    OutOfMemory,
+}
+
+/// A handle to request termination of a script running in a `Runtime`.
+///
+/// The handle can be sent to other threads.
+#[derive(Clone)]
+pub struct InterruptHandle(Arc<AtomicU8>);
+
+impl InterruptHandle {
+    /// Requests termination of the running script.
+    ///
+    /// The request is handled at the next function call or loop iteration in JavaScript code.
+    /// The termination cannot be caught by `try` statements in the script.  If no script is
+    /// running, the next script will be terminated immediately.
+    pub fn interrupt(&self) {
+        self.0.fetch_or(Interrupter::INTERRUPTED, Ordering::Relaxed);
+    }
+}
+
+/// The state of interrupts polled in generated code.
+pub(crate) struct Interrupter {
+    /// Non-zero if the running script must be terminated.
+    ///
+    /// The address of the value is embedded in generated code.  `Arc` provides a stable address.
+    flags: Arc<AtomicU8>,
+
+    /// The remaining fuel.
+    ///
+    /// The fuel is consumed by one at each function call and loop iteration only if the fuel
+    /// metering is enabled.  Boxed for the same reason as `flags`.
+    fuel: Box<i64>,
+
+    /// The reason of the last termination.
+    termination: Option<Termination>,
+}
+
+impl Interrupter {
+    const INTERRUPTED: u8 = 1 << 0;
+    const OUT_OF_FUEL: u8 = 1 << 1;
+    const OUT_OF_MEMORY: u8 = 1 << 2;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            flags: Default::default(),
+            fuel: Box::new(i64::MAX),
+            termination: None,
+        }
+    }
+
+    pub(crate) fn flags_addr(&self) -> usize {
+        self.flags.as_ptr() as usize
+    }
+
+    pub(crate) fn fuel_addr(&self) -> usize {
+        &*self.fuel as *const i64 as usize
+    }
+
+    fn is_terminating(&self) -> bool {
+        self.flags.load(Ordering::Relaxed) != 0
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Returns a handle to request termination of a running script from other threads.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle(self.interrupter.flags.clone())
+    }
+
+    /// Enables the fuel metering.
+    ///
+    /// This affects only programs compiled after this call.
+    pub fn enable_fuel_metering(&mut self) {
+        self.pref.enable_fuel_metering = true;
+    }
+
+    /// Returns the remaining fuel.
+    pub fn fuel(&self) -> u64 {
+        (*self.interrupter.fuel).max(0) as u64
+    }
+
+    /// Sets the fuel.
+    ///
+    /// A running script is terminated when the fuel is exhausted.  The fuel is consumed by one at
+    /// each function call and loop iteration.  See also `enable_fuel_metering()`.
+    pub fn set_fuel(&mut self, fuel: u64) {
+        *self.interrupter.fuel = fuel.min(i64::MAX as u64) as i64;
+    }
+
+    /// Returns the reason of the termination if the last script was terminated.
+    pub fn last_termination(&self) -> Option<Termination> {
+        self.interrupter.termination
+    }
+
+    pub(crate) fn is_terminating(&self) -> bool {
+        self.interrupter.is_terminating()
+    }
+
+    /// Called from generated code when an interrupt is detected.
+    ///
+    /// Returns an exception to be thrown.
+    pub(crate) fn handle_interrupt(&mut self) -> HandleMut<Object> {
+        logger::debug!(event = "handle_interrupt");
+        if *self.interrupter.fuel < 0 {
+            self.interrupter
+                .flags
+                .fetch_or(Interrupter::OUT_OF_FUEL, Ordering::Relaxed);
+        }
+        debug_assert!(self.interrupter.is_terminating());
+        self.create_internal_error(Some(const_string_handle!("Execution terminated")))
+    }
+
+    /// Called from generated code when the heap limit has been exceeded.
+    ///
+    /// Returns an exception to be thrown.  Like interrupts, this terminates the script.  Garbage
+    /// cannot be collected at this point because objects referenced only from the native stack
+    /// cannot be found.  See `recover_from_heap_exhaustion()`.
+    pub(crate) fn handle_heap_exhausted(&mut self) -> HandleMut<Object> {
+        logger::debug!(event = "handle_heap_exhausted");
+        self.interrupter
+            .flags
+            .fetch_or(Interrupter::OUT_OF_MEMORY, Ordering::Relaxed);
+        self.create_range_error(Some(const_string_handle!("Out of memory")))
+    }
+
+    /// Called when the execution of a script finishes.
+    ///
+    /// Replaces the result with an error if the script was terminated.
+    pub(crate) fn finish_execution(
+        &mut self,
+        result: Result<Value, Value>,
+    ) -> Result<Value, Value> {
+        if self.finish_termination() {
+            // The termination cannot be caught in the script.
+            let error = result.err().unwrap_or_else(|| {
+                let error =
+                    self.create_internal_error(Some(const_string_handle!("Execution terminated")));
+                Value::Object(error)
+            });
+            let mut roots = vec![];
+            match error {
+                Value::String(string) => roots.push(string.as_addr()),
+                Value::Object(object) => roots.push(object.as_addr()),
+                _ => (),
+            }
+            self.recover_from_heap_exhaustion(roots);
+            return Err(error);
+        }
+        result
+    }
+
+    /// Called when the execution of a script finishes.
+    ///
+    /// Returns `true` if the script was terminated.
+    pub(crate) fn finish_termination(&mut self) -> bool {
+        let flags = self.interrupter.flags.swap(0, Ordering::Relaxed);
+        self.interrupter.termination = if flags & Interrupter::INTERRUPTED != 0 {
+            Some(Termination::Interrupted)
+        } else if flags & Interrupter::OUT_OF_FUEL != 0 {
+            Some(Termination::OutOfFuel)
+        } else if flags & Interrupter::OUT_OF_MEMORY != 0 {
+            Some(Termination::OutOfMemory)
+        } else {
+            None
+        };
+        self.interrupter.termination.is_some()
+    }
+
+    /// Collects garbage if the last script was terminated due to the heap exhaustion.
+    ///
+    /// This is performed only after the native stack has been unwound.  The heap is no longer
+    /// exhausted once enough memory is reclaimed, and the next script can run normally.
+    pub(crate) fn recover_from_heap_exhaustion(&mut self, roots: Vec<usize>) {
+        if self.interrupter.termination != Some(Termination::OutOfMemory) || self.is_running() {
+            return;
+        }
+        logger::debug!(event = "recover_from_heap_exhaustion");
+        self.collect_garbage(roots);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interrupt_handle_is_send() {
+        fn assert_send<T: Send + Sync>() {}
+        assert_send::<InterruptHandle>();
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let interrupter = Interrupter::new();
+        assert!(!interrupter.is_terminating());
+        InterruptHandle(interrupter.flags.clone()).interrupt();
+        assert!(interrupter.is_terminating());
+    }
+}
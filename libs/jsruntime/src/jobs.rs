@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
 
 use jsgc::HandleMut;
+use jsparser::Symbol;
 
 use crate::Runtime;
 use crate::logger;
@@ -15,8 +16,64 @@ use crate::types::Value;
 impl<X> Runtime<X> {
     /// Perform all jobs.
     pub fn process_jobs(&mut self) {
+        self.drain_jobs();
+    }
+
+    /// Performs all jobs and returns `true` if the script was terminated.
+    ///
+    /// Promises rejected without any handler are reported after all jobs have been performed.
+    pub(crate) fn drain_jobs(&mut self) -> bool {
+        let mut terminated = false;
         while let Some(msg) = self.job_runner.next_msg() {
+            let outermost = self.enter_stack();
             self.handle_message(msg);
+            self.leave_stack(outermost);
+            if self.is_terminating() {
+                // Remaining jobs are kept in the queue.
+                terminated = self.finish_termination();
+                self.recover_from_heap_exhaustion(vec![]);
+                break;
+            }
+        }
+        self.clear_kept_objects();
+        if !terminated {
+            self.notify_unhandled_rejections();
+        }
+        terminated
+    }
+
+    /// Sets a callback function called for each promise rejected without any handler.
+    ///
+    /// The callback function is called at the end of `process_jobs()` for promises which are
+    /// still unhandled at that point.  Unhandled rejections are logged as warnings if no
+    /// callback function is set.
+    pub fn set_unhandled_rejection_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&mut Self, &UnhandledRejection) + 'static,
+    {
+        self.unhandled_rejection_callback = Some(Box::new(callback));
+    }
+
+    fn notify_unhandled_rejections(&mut self) {
+        let rejections = self.job_runner.take_unhandled_rejections();
+        if rejections.is_empty() {
+            return;
+        }
+        // The callback is taken temporarily so that it can mutably borrow the runtime.
+        let mut callback = self.unhandled_rejection_callback.take();
+        for (_, reason) in rejections {
+            let rejection = UnhandledRejection {
+                stack: error_stack(&reason),
+                reason,
+            };
+            match callback {
+                Some(ref mut callback) => callback(self, &rejection),
+                None => logger::warn!(event = "unhandled_rejection", ?rejection),
+            }
+        }
+        // Keep a callback newly set in the callback.
+        if self.unhandled_rejection_callback.is_none() {
+            self.unhandled_rejection_callback = callback;
         }
     }
 
@@ -29,6 +86,11 @@ impl<X> Runtime<X> {
             Message::PromiseRejected { object, ref error } => {
                 self.process_promise(object, &Value::None, error)
             }
+            Message::FinalizationCleanup {
+                callback,
+                ref held_value,
+            } => self.process_finalization_cleanup(callback, held_value),
+            Message::Microtask { callback } => self.process_microtask(callback),
         }
     }
 
@@ -57,11 +119,51 @@ impl<X> Runtime<X> {
         let args = [object.into(), result.clone(), error.clone()];
         let mut context = CallContext::new_for_promise(coroutine, &args);
         let mut retv = Value::None;
+        let realm = self.enter_realm(coroutine.closure.realm);
         let lambda = Lambda::from(coroutine.closure.lambda);
         let status = lambda(self, &mut context, &mut retv);
+        self.leave_realm(realm);
         (status, retv)
     }
 
+    // finalization
+
+    // 9.13 CleanupFinalizationRegistry ( finalizationRegistry )
+    fn process_finalization_cleanup(&mut self, callback: HandleMut<Object>, held_value: &Value) {
+        logger::debug!(
+            event = "process_finalization_cleanup",
+            ?callback,
+            ?held_value
+        );
+        let args = [held_value.clone()];
+        let context = CallContext::new_for_entry(&args);
+        let mut retv = Value::None;
+        // 9.5.3 HostEnqueueFinalizationRegistryCleanupJob ( finalizationRegistry )
+        //
+        // An abrupt completion of the cleanup callback is reported but not propagated.
+        if let Status::Exception =
+            self.call_with_context(&context, callback, &Value::Undefined, &args, &mut retv)
+        {
+            logger::warn!(event = "process_finalization_cleanup", exception = ?retv);
+        }
+    }
+
+    // microtasks
+
+    // HTML: 8.7 Microtask queuing
+    fn process_microtask(&mut self, callback: HandleMut<Object>) {
+        logger::debug!(event = "process_microtask", ?callback);
+        let args: [Value; 0] = [];
+        let context = CallContext::new_for_entry(&args);
+        let mut retv = Value::None;
+        // An exception thrown from the callback is reported but not propagated.
+        if let Status::Exception =
+            self.call_with_context(&context, callback, &Value::Undefined, &args, &mut retv)
+        {
+            logger::warn!(event = "process_microtask", exception = ?retv);
+        }
+    }
+
     pub fn emit_promise_resolved(&mut self, object: HandleMut<Object>, result: Value) {
         debug_assert!(self.is_promise_object(object));
         match result {
@@ -78,9 +180,39 @@ impl<X> Runtime<X> {
     }
 }
 
+pub(crate) type UnhandledRejectionCallback<X> =
+    Box<dyn FnMut(&mut Runtime<X>, &UnhandledRejection)>;
+
+/// A promise rejected without any handler.
+///
+/// See `Runtime::set_unhandled_rejection_callback()`.
+#[derive(Debug)]
+pub struct UnhandledRejection {
+    /// The reason of the rejection.
+    pub reason: Value,
+
+    /// The `stack` property of the reason if it's an error object having it.
+    pub stack: Option<std::string::String>,
+}
+
+fn error_stack(reason: &Value) -> Option<std::string::String> {
+    match reason {
+        Value::Object(object) => match object.get_value(&Symbol::STACK.into()) {
+            Some(Value::String(stack)) => {
+                Some(std::string::String::from_utf16_lossy(stack.as_slice()))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[derive(Default)]
 pub struct JobRunner {
     messages: VecDeque<Message>,
+    // Rejected promises without any handler, which will be reported at the end of
+    // `drain_jobs()`.
+    unhandled_rejections: Vec<(HandleMut<Promise>, Value)>,
 }
 
 impl JobRunner {
@@ -88,7 +220,11 @@ impl JobRunner {
 
     fn await_promise(&mut self, object: HandleMut<Object>, awaiting: HandleMut<Object>) {
         logger::debug!(event = "await_promise", ?object, ?awaiting);
-        match object.promise().do_await(awaiting) {
+        let mut promise = object.promise();
+        if promise.is_rejected() && !promise.is_handled() {
+            self.track_promise_rejection(promise, RejectionOperation::Handle);
+        }
+        match promise.do_await(awaiting) {
             Some(Ok(result)) => self.emit_promise_resolved(awaiting, result),
             Some(Err(error)) => self.emit_promise_rejected(awaiting, error),
             None => (),
@@ -107,6 +243,27 @@ impl JobRunner {
             .push_back(Message::PromiseRejected { object, error });
     }
 
+    // finalization
+
+    pub(crate) fn emit_finalization_cleanup(
+        &mut self,
+        callback: HandleMut<Object>,
+        held_value: Value,
+    ) {
+        logger::debug!(event = "emit_finalization_cleanup", ?callback, ?held_value);
+        self.messages.push_back(Message::FinalizationCleanup {
+            callback,
+            held_value,
+        });
+    }
+
+    // microtasks
+
+    pub(crate) fn emit_microtask(&mut self, callback: HandleMut<Object>) {
+        logger::debug!(event = "emit_microtask", ?callback);
+        self.messages.push_back(Message::Microtask { callback });
+    }
+
     fn next_msg(&mut self) -> Option<Message> {
         self.messages.pop_front()
     }
@@ -120,11 +277,30 @@ impl JobRunner {
 
     fn reject_promise(&mut self, mut promise: HandleMut<Promise>, error: Value) {
         logger::debug!(event = "reject_promise", ?promise, ?error);
-        if let Some(awaiting) = promise.reject(&error) {
-            self.emit_promise_rejected(awaiting, error);
+        match promise.reject(&error) {
+            Some(awaiting) => self.emit_promise_rejected(awaiting, error),
+            None if !promise.is_handled() => {
+                self.track_promise_rejection(promise, RejectionOperation::Reject(error))
+            }
+            None => (),
         }
     }
 
+    // 27.2.1.9 HostPromiseRejectionTracker ( promise, operation )
+    fn track_promise_rejection(&mut self, promise: HandleMut<Promise>, op: RejectionOperation) {
+        logger::debug!(event = "track_promise_rejection", ?promise, ?op);
+        match op {
+            RejectionOperation::Reject(error) => self.unhandled_rejections.push((promise, error)),
+            // A handler was added to a rejected promise before it was reported.  Handlers added
+            // after that are not reported.
+            RejectionOperation::Handle => self.unhandled_rejections.retain(|(p, _)| *p != promise),
+        }
+    }
+
+    fn take_unhandled_rejections(&mut self) -> Vec<(HandleMut<Promise>, Value)> {
+        std::mem::take(&mut self.unhandled_rejections)
+    }
+
     pub(crate) fn collect_gc_roots(&self, roots: &mut Vec<usize>) {
         for msg in self.messages.iter() {
             match msg {
@@ -144,11 +320,37 @@ impl JobRunner {
                         _ => (),
                     }
                 }
+                Message::FinalizationCleanup {
+                    callback,
+                    held_value,
+                } => {
+                    roots.push(callback.as_addr());
+                    match held_value {
+                        Value::String(string) => roots.push(string.as_addr()),
+                        Value::Object(object) => roots.push(object.as_addr()),
+                        _ => (),
+                    }
+                }
+                Message::Microtask { callback } => roots.push(callback.as_addr()),
+            }
+        }
+        for (promise, error) in self.unhandled_rejections.iter() {
+            roots.push(promise.as_addr());
+            match error {
+                Value::String(string) => roots.push(string.as_addr()),
+                Value::Object(object) => roots.push(object.as_addr()),
+                _ => (),
             }
         }
     }
 }
 
+#[derive(Debug)]
+enum RejectionOperation {
+    Reject(Value),
+    Handle,
+}
+
 // messages
 
 #[derive(Debug)]
@@ -161,6 +363,13 @@ enum Message {
         object: HandleMut<Object>,
         error: Value,
     },
+    FinalizationCleanup {
+        callback: HandleMut<Object>,
+        held_value: Value,
+    },
+    Microtask {
+        callback: HandleMut<Object>,
+    },
 }
 
 #[cfg(test)]
@@ -216,4 +425,41 @@ mod tests {
         runtime.job_runner.collect_gc_roots(&mut roots);
         assert_eq!(roots.len(), 10);
     }
+
+    #[test]
+    fn test_finalization_cleanup() {
+        use crate::types::FinalizationRegistry;
+        use crate::types::HeldValue;
+
+        let mut runtime = BasicRuntime::new();
+
+        let callback = runtime.builtins.object_constructor;
+        let registry = runtime.heap.alloc_mut(FinalizationRegistry::new(callback));
+        let mut object = runtime.create_object();
+        object.set_prototype(runtime.builtins.finalization_registry_prototype);
+        object.set_finalization_registry(registry);
+
+        let target = runtime.create_object();
+        let cell = runtime.heap.alloc_finalization_cell(
+            target,
+            HeldValue {
+                registry: object,
+                value: Value::Undefined,
+            },
+        );
+        object.finalization_registry().register(cell, None);
+
+        runtime.collect_garbage(vec![object.as_addr()]);
+        match runtime.job_runner.next_msg() {
+            Some(Message::FinalizationCleanup {
+                callback: actual,
+                held_value,
+            }) => {
+                assert_eq!(actual, callback);
+                assert!(matches!(held_value, Value::Undefined));
+            }
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+        assert!(runtime.job_runner.next_msg().is_none());
+    }
 }
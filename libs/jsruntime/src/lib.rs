@@ -1,13 +1,37 @@
 #[macro_use]
 mod macros;
 
+mod api;
+mod arguments;
 mod backend;
 mod builtins;
+mod code_cache;
+mod convert;
+mod coverage;
+mod debugger;
+mod eval;
+mod event_loop;
+mod future;
+mod host_class;
+mod host_function;
+mod interrupt;
 mod jobs;
 mod lambda;
+mod profiler;
+mod realm;
+mod rooted;
 mod semantics;
+#[cfg(feature = "serde")]
+mod serde_value;
+mod stack;
+mod stack_trace;
+mod template;
+mod type_feedback;
 mod types;
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use itertools::Itertools;
 
 use jsgc::Handle;
@@ -16,14 +40,27 @@ use jsgc::Heap;
 use jsgc::Trace;
 use jsparser::Symbol;
 use jsparser::SymbolRegistry;
+use rustc_hash::FxHashMap;
 
 use backend::CodeRegistry;
 use builtins::Builtins;
+use coverage::Coverage;
+use debugger::DebuggerCallback;
+use debugger::PausePoints;
+use event_loop::EventLoop;
+use interrupt::Interrupter;
 use jobs::JobRunner;
+use jobs::UnhandledRejectionCallback;
 use lambda::LambdaKind;
 use lambda::LambdaRegistry;
+use profiler::Profiler;
+use rooted::RootSet;
 use semantics::Program;
+use stack::StackGuard;
+use type_feedback::TypeFeedback;
+use types::Accessor;
 use types::CallContext;
+use types::CallContextFlags;
 use types::Capture;
 use types::Closure;
 use types::Coroutine;
@@ -36,7 +73,42 @@ use types::ReturnValue;
 use types::Status;
 
 pub use backend::CompileError;
+pub use code_cache::CodeCacheError;
+pub use convert::FromJsValue;
+pub use convert::IntoJsValue;
+pub use coverage::CoverageRange;
+pub use coverage::FunctionCoverage;
+pub use coverage::ScriptCoverage;
+pub use debugger::BreakpointId;
+pub use debugger::DebugScope;
+pub use debugger::Pause;
+pub use debugger::PausePoint;
+pub use debugger::PauseReason;
+pub use debugger::StepAction;
+pub use future::PromiseFuture;
+pub use host_class::ClassBuilder;
+pub use host_class::HostObject;
+pub use host_function::HostArg;
+pub use host_function::HostFunction;
+pub use host_function::Rest;
+pub use host_function::This;
+pub use interrupt::InterruptHandle;
+pub use interrupt::Termination;
+pub use jobs::UnhandledRejection;
 pub use lambda::LambdaId; // TODO: private
+pub use profiler::CpuProfile;
+pub use profiler::ProfileNode;
+pub use realm::RealmId;
+pub use rooted::Rooted;
+#[cfg(feature = "serde")]
+pub use serde_value::Deserializer;
+#[cfg(feature = "serde")]
+pub use serde_value::Serde;
+#[cfg(feature = "serde")]
+pub use serde_value::SerdeError;
+#[cfg(feature = "serde")]
+pub use serde_value::Serializer;
+pub use stack_trace::StackFrame;
 pub use types::String;
 pub use types::Value;
 
@@ -51,6 +123,9 @@ struct RuntimePref {
     /// The maximum call stack depth.
     max_call_stack_depth: u16,
 
+    /// The maximum size in bytes of the native stack used for evaluating JavaScript code.
+    stack_size: usize,
+
     /// Enables the scope cleanup checker.
     ///
     /// Insert IR instructions to check if the cleanup for each scope is performed properly.
@@ -59,14 +134,52 @@ struct RuntimePref {
 
     /// Enables the runtime assertions.
     enable_runtime_assert: bool,
+
+    /// Enables the fuel metering.
+    ///
+    /// Insert IR instructions to consume the fuel at each function call and loop iteration.
+    enable_fuel_metering: bool,
+
+    /// Enables the debugger hooks.
+    ///
+    /// Insert IR instructions to notify the debugger of exceptions thrown by `throw` statements
+    /// and to check breakpoints and steps at pause points.
+    enable_debugger: bool,
+
+    /// Enables the sampling CPU profiler.
+    ///
+    /// Insert IR instructions to take a sample at each function call and loop iteration when the
+    /// profiler requests.
+    enable_profiler: bool,
+
+    /// Enables the code coverage.
+    ///
+    /// Insert IR instructions to count calls of each function and executions of each block.
+    enable_coverage: bool,
+
+    /// The number of calls of a function before it's recompiled with the type feedback.
+    ///
+    /// Insert IR instructions to collect the type feedback in functions compiled with the
+    /// optimization.  `0` disables the recompilation.
+    optimization_threshold: u32,
+
+    /// Allows `eval()` and the `Function` constructor to generate code from strings.
+    allow_code_generation_from_strings: bool,
 }
 
 impl Default for RuntimePref {
     fn default() -> Self {
         Self {
             max_call_stack_depth: 4096,
+            stack_size: 1024 * 1024,
             enable_scope_cleanup_checker: false,
             enable_runtime_assert: false,
+            enable_fuel_metering: false,
+            enable_debugger: false,
+            enable_profiler: false,
+            enable_coverage: false,
+            optimization_threshold: 1000,
+            allow_code_generation_from_strings: true,
         }
     }
 }
@@ -103,9 +216,31 @@ pub struct Runtime<X> {
     code_registry: CodeRegistry<X>,
     programs: Vec<Program>,
     heap: Heap,
+    // The intrinsics of the current realm.
     builtins: Builtins,
+    // The intrinsics of all realms indexed by `RealmId`.
+    realms: Vec<Builtins>,
+    // The current realm.
+    realm: RealmId,
     job_runner: JobRunner,
+    event_loop: EventLoop<X>,
+    interrupter: Interrupter,
+    profiler: Profiler,
+    coverage: Coverage,
+    type_feedback: TypeFeedback,
+    stack_guard: StackGuard,
+    // The call context of the host function being called.
+    host_call_context: *const CallContext,
+    // [[KeptAlive]] of the surrounding agent.
+    kept_objects: Vec<HandleMut<Object>>,
+    // [[TemplateMap]] of all realms.
+    template_map: FxHashMap<(RealmId, usize), HandleMut<Object>>,
+    // Values rooted by `Rooted` handles.
+    roots: Rc<RefCell<RootSet>>,
     monitor: Option<Box<dyn Monitor>>,
+    unhandled_rejection_callback: Option<UnhandledRejectionCallback<X>>,
+    debugger: Option<DebuggerCallback<X>>,
+    pause_points: PausePoints,
     extension: X,
 }
 
@@ -121,13 +256,29 @@ impl<X> Runtime<X> {
             code_registry: CodeRegistry::new(),
             programs: vec![],
             heap,
-            builtins,
+            builtins: builtins.clone(),
+            realms: vec![builtins],
+            realm: RealmId::DEFAULT,
             job_runner: Default::default(),
+            event_loop: EventLoop::new(),
+            interrupter: Interrupter::new(),
+            profiler: Profiler::new(),
+            coverage: Coverage::new(),
+            type_feedback: TypeFeedback::new(),
+            stack_guard: StackGuard::new(),
+            host_call_context: std::ptr::null(),
+            kept_objects: vec![],
+            template_map: Default::default(),
+            roots: Default::default(),
             monitor: None,
+            unhandled_rejection_callback: None,
+            debugger: None,
+            pause_points: PausePoints::new(),
             extension,
         };
 
         runtime.init_builtin_objects();
+        runtime.init_event_loop_functions();
 
         runtime
     }
@@ -148,6 +299,21 @@ impl<X> Runtime<X> {
         self.pref.enable_runtime_assert = true;
     }
 
+    /// Sets the number of calls of a function before it's recompiled with the type feedback.
+    ///
+    /// `0` disables the recompilation.  The default value is 1000.
+    pub fn set_optimization_threshold(&mut self, threshold: u32) {
+        self.pref.optimization_threshold = threshold;
+    }
+
+    /// Allows or disallows generating code from strings.
+    ///
+    /// If disallowed, `eval()` and the `Function` constructor throw an `EvalError` like a browser
+    /// applying a Content Security Policy without `'unsafe-eval'`.  Allowed by default.
+    pub fn allow_code_generation_from_strings(&mut self, allow: bool) {
+        self.pref.allow_code_generation_from_strings = allow;
+    }
+
     pub fn set_monitor(&mut self, monitor: Box<dyn Monitor>) {
         self.monitor = Some(monitor);
     }
@@ -157,9 +323,13 @@ impl<X> Runtime<X> {
         F: Fn(&mut Self, &[Value]) -> R + Send + Sync + 'static,
         R: Clone + ReturnValue,
     {
-        let symbol = self.symbol_registry.intern_str(name);
-        logger::debug!(event = "register_host_function", name, ?symbol);
+        logger::debug!(event = "register_host_function", name);
         let lambda = types::into_lambda(host_fn);
+        self.define_host_function(name, lambda);
+    }
+
+    fn define_host_function(&mut self, name: &str, lambda: Lambda<X>) {
+        let symbol = self.symbol_registry.intern_str(name);
         let closure = self.create_closure(lambda, LambdaId::HOST, 0);
         let mut object = self.create_object();
         object.set_prototype(self.builtins.function_prototype);
@@ -190,7 +360,10 @@ impl<X> Runtime<X> {
         let lambda_id = self.programs[program_id.index()].entry_lambda_id();
         let lambda = self.code_registry.get_lambda(lambda_id).unwrap();
         let module = self.programs[program_id.index()].module;
-        self.call_entry_lambda(lambda_id, lambda, module)
+        let realm = self.enter_realm(self.programs[program_id.index()].realm);
+        let result = self.call_entry_lambda(lambda_id, lambda, module);
+        self.leave_realm(realm);
+        self.finish_execution(result)
     }
 
     /// Runs a program.
@@ -217,7 +390,10 @@ impl<X> Runtime<X> {
             self.code_registry.get_lambda(lambda_id).unwrap()
         };
         let module = self.programs[program_id.index()].module;
-        let value = self.call_entry_lambda(lambda_id, lambda, module)?;
+        let realm = self.enter_realm(self.programs[program_id.index()].realm);
+        let result = self.call_entry_lambda(lambda_id, lambda, module);
+        self.leave_realm(realm);
+        let value = self.finish_execution(result)?;
         // TODO(perf): Memory related to `lambda` can be removed safely after the call.
         // Because the top-level statements are performed only once.
         Ok(value)
@@ -238,12 +414,55 @@ impl<X> Runtime<X> {
         roots.push(handle.as_addr());
         self.heap.collect_garbage(&roots);
         self.heap.remove_tracer(handle);
+        self.schedule_finalization_cleanup_jobs();
+    }
+
+    /// Sets the maximum size in bytes of the heap memory.
+    ///
+    /// When the limit is exceeded, the running script is terminated at the next function call or
+    /// loop iteration in JavaScript code.  The termination cannot be caught in the script.  Then,
+    /// garbage is collected after the script finishes so that the next script can run if enough
+    /// memory is reclaimed.  Values held by the embedder must be rooted by `root()` in order to
+    /// keep them alive.  There is no limit by default.
+    pub fn set_heap_limit(&mut self, limit: usize) {
+        self.heap.set_limit(limit);
+    }
+
+    /// Sets a callback function called when the heap limit is about to be exceeded.
+    ///
+    /// The callback function receives the current limit and the heap size in bytes required,
+    /// and returns a new limit.  Returning a larger value grows the heap.  Returning the current
+    /// limit terminates the script.
+    pub fn set_near_heap_limit_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(usize, usize) -> usize + 'static,
+    {
+        self.heap.set_near_limit_callback(callback);
     }
 
     pub fn heap_stats(&self) -> jsgc::Stats {
         self.heap.stats()
     }
 
+    /// Writes a heap snapshot in the `.heapsnapshot` format which can be loaded into the Memory
+    /// panel of Chrome DevTools.
+    ///
+    /// Unlike `collect_garbage()`, no garbage collection is performed.  Unreachable objects are
+    /// included in the snapshot.
+    pub fn write_heap_snapshot<W>(&mut self, writer: &mut W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let handle = Handle::from_ref(self);
+        self.heap.add_tracer(handle);
+        let mut roots = vec![];
+        self.job_runner.collect_gc_roots(&mut roots);
+        roots.push(handle.as_addr());
+        let result = self.heap.write_snapshot(&roots, writer);
+        self.heap.remove_tracer(handle);
+        result
+    }
+
     fn get_index_of_coroutine_function(
         &self,
         program_id: ProgramId,
@@ -263,7 +482,7 @@ impl<X> Runtime<X> {
         coroutine_index
     }
 
-    fn call(
+    fn call_with_context(
         &mut self,
         caller: &CallContext,
         callable: HandleMut<Object>,
@@ -271,10 +490,45 @@ impl<X> Runtime<X> {
         args: &[Value],
         retv: &mut Value,
     ) -> Status {
+        self.call_with_flags(
+            caller,
+            callable,
+            this,
+            args,
+            CallContextFlags::empty(),
+            retv,
+        )
+    }
+
+    fn call_with_flags(
+        &mut self,
+        caller: &CallContext,
+        callable: HandleMut<Object>,
+        this: &Value,
+        args: &[Value],
+        flags: CallContextFlags,
+        retv: &mut Value,
+    ) -> Status {
+        if self.is_stack_overflow() {
+            let error = self.create_range_error(Some(const_string_handle!(
+                "Maximum call stack size exceeded"
+            )));
+            *retv = Value::Object(error);
+            return Status::Exception;
+        }
         let closure = callable.closure();
-        let mut context = caller.new_child(callable, closure, this, args);
+        let mut context = caller.new_child(callable, closure, this, args, flags);
+        self.call_in_realm(&mut context, retv)
+    }
+
+    /// Calls the closure in a call context in the realm where the closure was created.
+    fn call_in_realm(&mut self, context: &mut CallContext, retv: &mut Value) -> Status {
+        let closure = context.closure();
+        let realm = self.enter_realm(closure.realm);
         let lambda = Lambda::from(closure.lambda);
-        lambda(self, &mut context, retv)
+        let status = lambda(self, context, retv);
+        self.leave_realm(realm);
+        status
     }
 
     /// Calls an entry lambda function.
@@ -288,7 +542,9 @@ impl<X> Runtime<X> {
         let args: [_; 0] = [];
         let mut context = CallContext::new_for_entry(&args);
         let mut retv = Value::Undefined;
+        let outermost = self.enter_stack();
         let status = lambda(self, &mut context, &mut retv);
+        self.leave_stack(outermost);
         retv.into_result(status)
     }
 
@@ -368,6 +624,7 @@ impl<X> Runtime<X> {
             std::alloc::Layout::array::<*mut Capture>(num_captures as usize).unwrap();
         let (layout, _) = BASE_LAYOUT.extend(storage_layout).unwrap();
 
+        let realm = self.realm;
         self.heap.alloc_layout_mut(layout, move |ptr| {
             // SAFETY: `ptr` is a non-null pointer to a `Closure`.
             unsafe {
@@ -375,6 +632,7 @@ impl<X> Runtime<X> {
                     lambda: lambda.into(),
                     lambda_id,
                     num_captures,
+                    realm,
                     captures: [],
                 })
             };
@@ -437,6 +695,14 @@ impl<X> Runtime<X> {
         self.heap.alloc_mut(Object::new())
     }
 
+    fn create_accessor(
+        &mut self,
+        getter: Option<HandleMut<Object>>,
+        setter: Option<HandleMut<Object>>,
+    ) -> HandleMut<Accessor> {
+        self.heap.alloc_mut(Accessor::new(getter, setter))
+    }
+
     fn make_property_key(&mut self, value: &Value) -> Result<PropertyKey, Error> {
         match value {
             Value::None => unreachable!(),
@@ -462,15 +728,119 @@ impl<X> Runtime<X> {
         object.define_own_property(key.clone(), Property::data_wec(value.clone()))
     }
 
+    // 10.1.8.1 OrdinaryGet ( O, P, Receiver )
+    //
+    // Returns `None` if the property is not found in the prototype chain.
+    fn get_property_value(
+        &mut self,
+        caller: &CallContext,
+        object: HandleMut<Object>,
+        key: &PropertyKey,
+    ) -> Result<Option<Value>, Value> {
+        if let Some(value) = object.mapped_value(key) {
+            return Ok(Some(value.clone()));
+        }
+        let getter = match object.find_property(key) {
+            None => return Ok(None),
+            Some(prop) if prop.is_data_property() => return Ok(Some(prop.value().clone())),
+            Some(prop) => prop.getter(),
+        };
+        match getter {
+            Some(getter) => self
+                .call_accessor(caller, getter, &Value::Object(object), &[])
+                .map(Some),
+            None => Ok(Some(Value::Undefined)),
+        }
+    }
+
+    // 10.1.9.2 OrdinarySetWithOwnDescriptor ( O, P, V, Receiver, ownDesc )
+    //
+    // A TypeError is thrown if the [[Set]] fails in strict mode code.  Otherwise, the failure is
+    // silently ignored.
+    fn set_property_value(
+        &mut self,
+        caller: &CallContext,
+        mut object: HandleMut<Object>,
+        key: &PropertyKey,
+        value: &Value,
+        strict: bool,
+    ) -> Result<(), Value> {
+        match object.find_property(key) {
+            Some(prop) if !prop.is_data_property() => {
+                if let Some(setter) = prop.setter() {
+                    let args = [value.clone()];
+                    self.call_accessor(caller, setter, &Value::Object(object), &args)?;
+                    return Ok(());
+                }
+            }
+            Some(prop) if !prop.is_writable() => (),
+            // A new own property is created if the property is inherited.
+            _ if object.get_own_property(key).is_some() || object.is_extensible() => {
+                object.set_value(key, value);
+                return Ok(());
+            }
+            _ => (),
+        }
+        if strict {
+            return self.throw(type_error!("cannot assign to the property"));
+        }
+        Ok(())
+    }
+
+    // 9.1.1.4.5 SetMutableBinding ( N, V, S )
+    fn set_global_binding_value(
+        &mut self,
+        caller: &CallContext,
+        key: &PropertyKey,
+        value: &Value,
+        strict: bool,
+    ) -> Result<(), Value> {
+        let global_object = self.builtins.global_object;
+        if strict && global_object.find_property(key).is_none() {
+            return self.throw(reference_error!("not defined"));
+        }
+        self.set_property_value(caller, global_object, key, value, strict)
+    }
+
+    fn call_accessor(
+        &mut self,
+        caller: &CallContext,
+        func: HandleMut<Object>,
+        this: &Value,
+        args: &[Value],
+    ) -> Result<Value, Value> {
+        let mut retv = Value::Undefined;
+        let status = self.call_with_context(caller, func, this, args, &mut retv);
+        retv.into_result(status)
+    }
+
     // 7.3.25 CopyDataProperties ( target, source, excludedItems )
-    fn copy_data_properties(&mut self, target: &mut Object, source: &Value) -> Result<(), Error> {
-        let from = source.to_object()?;
-        for (key, prop) in from.iter_own_properties() {
+    fn copy_data_properties(
+        &mut self,
+        caller: &CallContext,
+        target: &mut Object,
+        source: &Value,
+    ) -> Result<(), Value> {
+        let from = self.throw(source.to_object())?;
+        // Getters may change the properties of the source object.
+        let keys: Vec<PropertyKey> = from
+            .iter_own_properties()
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys.iter() {
             // TODO: excludedItems
-            if prop.is_enumerable() {
-                // TODO: 7.3.2 Get ( O, P )
-                self.create_data_property(target, key, prop.value())?;
+            if !from
+                .get_own_property(key)
+                .is_some_and(Property::is_enumerable)
+            {
+                continue;
             }
+            // 7.3.2 Get ( O, P )
+            let value = self
+                .get_property_value(caller, from, key)?
+                .unwrap_or(Value::Undefined);
+            let result = self.create_data_property(target, key, &value);
+            self.throw(result)?;
         }
         Ok(())
     }
@@ -478,7 +848,8 @@ impl<X> Runtime<X> {
     fn push_value(&mut self, target: &mut Object, value: &Value) -> Result<(), Error> {
         const LENGTH: PropertyKey = PropertyKey::Symbol(Symbol::LENGTH);
 
-        let length = match target.get_value(&LENGTH) {
+        // `length` is always an own data property of the array being created.
+        let length = match target.get_own_property(&LENGTH).map(Property::value) {
             Some(Value::Number(v)) => *v,
             _ => unreachable!(),
         };
@@ -515,7 +886,16 @@ impl<X> Drop for Runtime<X> {
 // TODO(feat): derive(Trace)
 impl<X> Trace for Runtime<X> {
     fn trace(&self, visits: &mut jsgc::VisitList) {
-        self.builtins.trace(visits);
+        // `self.builtins` is a copy of one of `self.realms`.
+        for builtins in self.realms.iter() {
+            builtins.trace(visits);
+        }
+        self.kept_objects.trace(visits);
+        for template in self.template_map.values() {
+            template.trace(visits);
+        }
+        self.roots.borrow().trace(visits);
+        self.event_loop.trace(visits);
         // TODO: tracing X if X implements Trace.
     }
 }
@@ -548,9 +928,11 @@ impl Error {
 
 #[derive(Clone, Copy, Debug)]
 pub enum ErrorKind {
+    EvalError,
     SyntaxError,
     TypeError,
     RangeError,
+    ReferenceError,
     InternalError,
 }
 
@@ -565,4 +947,35 @@ mod tests {
         // The runtime holds the global object.
         assert!(runtime.heap_stats().num_objects > 0);
     }
+
+    #[test]
+    fn test_heap_stats() {
+        let runtime = BasicRuntime::new();
+        let stats = runtime.heap_stats();
+        assert!(stats.num_bytes > 0);
+        assert_eq!(
+            stats
+                .types
+                .iter()
+                .map(|stats| stats.num_objects)
+                .sum::<usize>(),
+            stats.num_objects
+        );
+        assert!(
+            stats
+                .types
+                .iter()
+                .any(|stats| stats.type_name == std::any::type_name::<Object>())
+        );
+    }
+
+    #[test]
+    fn test_write_heap_snapshot() {
+        let mut runtime = BasicRuntime::new();
+        let mut buf = vec![];
+        runtime.write_heap_snapshot(&mut buf).unwrap();
+        let json = std::string::String::from_utf8(buf).unwrap();
+        assert!(json.starts_with(r#"{"snapshot":"#));
+        assert!(json.contains(std::any::type_name::<Object>()));
+    }
 }
@@ -40,6 +40,15 @@ macro_rules! error {
     };
 }
 
+macro_rules! eval_error {
+    () => {
+        error!($crate::ErrorKind::EvalError)
+    };
+    ($message:literal) => {
+        error!($crate::ErrorKind::EvalError, $message)
+    };
+}
+
 macro_rules! type_error {
     () => {
         error!($crate::ErrorKind::TypeError)
@@ -49,6 +58,15 @@ macro_rules! type_error {
     };
 }
 
+macro_rules! reference_error {
+    () => {
+        error!($crate::ErrorKind::ReferenceError)
+    };
+    ($message:literal) => {
+        error!($crate::ErrorKind::ReferenceError, $message)
+    };
+}
+
 macro_rules! range_error {
     () => {
         error!($crate::ErrorKind::RangeError)
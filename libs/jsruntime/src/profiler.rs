@@ -0,0 +1,261 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use rustc_hash::FxHashMap;
+
+use crate::Runtime;
+use crate::lambda::LambdaId;
+use crate::logger;
+use crate::semantics::SourceOffset;
+use crate::types::CallContext;
+
+/// A profile recorded by the sampling CPU profiler.
+///
+/// The structure follows the `Profile` type in the Chrome DevTools Protocol which is saved in
+/// `.cpuprofile` files.
+#[derive(Debug)]
+pub struct CpuProfile {
+    /// The nodes of the call tree.  The first node is the root node.
+    pub nodes: Vec<ProfileNode>,
+
+    /// The time when the profiling started in microseconds since the Unix epoch.
+    pub start_time: u64,
+
+    /// The time when the profiling stopped in microseconds since the Unix epoch.
+    pub end_time: u64,
+
+    /// The IDs of the nodes sampled in order.
+    pub samples: Vec<u32>,
+
+    /// The intervals in microseconds between samples.
+    ///
+    /// The first element is the interval between `start_time` and the first sample.
+    pub time_deltas: Vec<u64>,
+}
+
+/// A node in the call tree of a `CpuProfile`.
+#[derive(Debug)]
+pub struct ProfileNode {
+    /// The ID of the node which is unique in the profile.
+    pub id: u32,
+
+    /// The lambda function of the node, or `None` for the root node.
+    pub lambda_id: Option<LambdaId>,
+
+    /// The name of the function, or `None` for the root node, top-level code and anonymous
+    /// functions.
+    pub function: Option<std::string::String>,
+
+    /// The name of the program set by `Runtime::set_program_name()`.
+    pub script: Option<std::string::String>,
+
+    /// The 1-based line number of the function definition, or 0 if unknown.
+    pub line: u32,
+
+    /// The 1-based column number of the function definition in UTF-16 code units, or 0 if
+    /// unknown.
+    pub column: u32,
+
+    /// The number of samples taken while the function was running on the top of the stack.
+    pub hit_count: u32,
+
+    /// The IDs of the child nodes.
+    pub children: Vec<u32>,
+}
+
+/// The state of the sampling CPU profiler.
+pub(crate) struct Profiler {
+    /// `true` if a sample must be taken.
+    ///
+    /// A timer thread sets the flag periodically and generated code polls it at the same points as
+    /// interrupts.  The address of the value is embedded in generated code.  `Arc` provides a
+    /// stable address.
+    tick: Arc<AtomicBool>,
+
+    /// The current profiling session, or `None` if the profiler is not running.
+    session: Option<ProfilingSession>,
+}
+
+struct ProfilingSession {
+    // The timer thread stops when the sender is dropped.
+    stop: mpsc::Sender<()>,
+    timer: JoinHandle<()>,
+    start: Instant,
+    start_time: u64,
+    last_sample: Instant,
+    nodes: Vec<ProfileNode>,
+    // A map from a pair of the parent node ID and the lambda function to the child node ID.
+    children: FxHashMap<(u32, LambdaId), u32>,
+    samples: Vec<u32>,
+    time_deltas: Vec<u64>,
+}
+
+impl Profiler {
+    const ROOT_ID: u32 = 1;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            tick: Default::default(),
+            session: None,
+        }
+    }
+
+    pub(crate) fn tick_addr(&self) -> usize {
+        self.tick.as_ptr() as usize
+    }
+}
+
+impl ProfilingSession {
+    fn new(tick: Arc<AtomicBool>, interval: Duration) -> Self {
+        let (stop, receiver) = mpsc::channel::<()>();
+        let timer = std::thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+                tick.store(true, Ordering::Relaxed);
+            }
+        });
+        let start_time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        let start = Instant::now();
+        Self {
+            stop,
+            timer,
+            start,
+            start_time,
+            last_sample: start,
+            nodes: vec![ProfileNode {
+                id: Profiler::ROOT_ID,
+                lambda_id: None,
+                function: None,
+                script: None,
+                line: 0,
+                column: 0,
+                hit_count: 0,
+                children: vec![],
+            }],
+            children: Default::default(),
+            samples: vec![],
+            time_deltas: vec![],
+        }
+    }
+
+    fn node_mut(&mut self, id: u32) -> &mut ProfileNode {
+        &mut self.nodes[id as usize - 1]
+    }
+
+    fn finish(self) -> CpuProfile {
+        drop(self.stop);
+        // The timer thread never panics.
+        let _ = self.timer.join();
+        CpuProfile {
+            nodes: self.nodes,
+            start_time: self.start_time,
+            end_time: self.start_time + self.start.elapsed().as_micros() as u64,
+            samples: self.samples,
+            time_deltas: self.time_deltas,
+        }
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Starts the sampling CPU profiler.
+    ///
+    /// A sample is taken at the first function call or loop iteration in JavaScript code after
+    /// each `interval` elapses.  Programs compiled before the first call to this method are not
+    /// sampled.  The profile recorded so far is discarded if the profiler is already running.
+    pub fn start_profiling(&mut self, interval: Duration) {
+        logger::debug!(event = "start_profiling", ?interval);
+        self.pref.enable_profiler = true;
+        if let Some(session) = self.profiler.session.take() {
+            session.finish();
+        }
+        self.profiler.tick.store(false, Ordering::Relaxed);
+        let tick = self.profiler.tick.clone();
+        self.profiler.session = Some(ProfilingSession::new(tick, interval));
+    }
+
+    /// Stops the sampling CPU profiler and returns the recorded profile.
+    ///
+    /// Returns `None` if the profiler is not running.
+    pub fn stop_profiling(&mut self) -> Option<CpuProfile> {
+        logger::debug!(event = "stop_profiling");
+        let profile = self.profiler.session.take()?.finish();
+        self.profiler.tick.store(false, Ordering::Relaxed);
+        Some(profile)
+    }
+
+    /// Called from generated code when the profiler requests a sample.
+    ///
+    /// `lambda_id` is the lambda function running on the top of the stack and `context` is the
+    /// call context for the functions called from it.
+    pub(crate) fn take_profile_sample(&mut self, context: &CallContext, lambda_id: LambdaId) {
+        self.profiler.tick.store(false, Ordering::Relaxed);
+
+        // The session is taken temporarily so that nodes can be made from the runtime.
+        let Some(mut session) = self.profiler.session.take() else {
+            return;
+        };
+
+        // The innermost function comes first.
+        let mut stack = vec![lambda_id];
+        let mut context = context.caller();
+        while let Some(callee) = context {
+            // The call site is recorded in the call context of the callee.
+            if let Some((lambda_id, _)) = callee.call_site() {
+                stack.push(lambda_id);
+            }
+            context = callee.caller();
+        }
+
+        let mut id = Profiler::ROOT_ID;
+        for &lambda_id in stack.iter().rev() {
+            id = match session.children.get(&(id, lambda_id)) {
+                Some(&child_id) => child_id,
+                None => {
+                    let child_id = session.nodes.len() as u32 + 1;
+                    session
+                        .nodes
+                        .push(self.make_profile_node(child_id, lambda_id));
+                    session.node_mut(id).children.push(child_id);
+                    session.children.insert((id, lambda_id), child_id);
+                    child_id
+                }
+            };
+        }
+        session.node_mut(id).hit_count += 1;
+
+        let now = Instant::now();
+        session.samples.push(id);
+        session
+            .time_deltas
+            .push((now - session.last_sample).as_micros() as u64);
+        session.last_sample = now;
+
+        self.profiler.session = Some(session);
+    }
+
+    fn make_profile_node(&self, id: u32, lambda_id: LambdaId) -> ProfileNode {
+        let lambda_info = self.lambda_registry.get(lambda_id);
+        let program = &self.programs[lambda_info.program_id.index()];
+        let func = &program.functions[lambda_info.function_index as usize];
+        let offset = SourceOffset::from(func.source_range.start);
+        let frame = self.make_stack_frame(lambda_id, offset, false);
+        ProfileNode {
+            id,
+            lambda_id: Some(lambda_id),
+            function: frame.function,
+            script: frame.script,
+            line: frame.line,
+            column: frame.column,
+            hit_count: 0,
+            children: vec![],
+        }
+    }
+}
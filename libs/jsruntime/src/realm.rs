@@ -0,0 +1,126 @@
+use crate::Rooted;
+use crate::Runtime;
+use crate::builtins::Builtins;
+use crate::logger;
+use crate::types::CallContext;
+use crate::types::Value;
+
+/// An identifier of a realm.
+///
+/// Each realm has its own global object and intrinsic objects such as `Object.prototype`.
+/// All realms in a runtime share the same heap memory so that objects can be passed across
+/// realms.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct RealmId(u16);
+
+impl RealmId {
+    /// The realm created together with the runtime.
+    pub const DEFAULT: Self = Self(0);
+
+    fn new(index: usize) -> Self {
+        debug_assert!(index < u16::MAX as usize);
+        Self(index as u16)
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Creates a new realm.
+    ///
+    /// The global object of the new realm has the built-in global properties and the event loop
+    /// functions.  Host functions registered by `register_host_function()` and similar methods
+    /// are defined only in the realm which is current at that time.
+    ///
+    /// The current realm is not changed.
+    pub fn create_realm(&mut self) -> RealmId {
+        assert!(self.realms.len() < u16::MAX as usize, "too many realms");
+        let realm = RealmId::new(self.realms.len());
+        logger::debug!(event = "create_realm", ?realm);
+        let builtins = Builtins::new(&mut self.heap);
+        self.realms.push(builtins);
+        let saved = self.enter_realm(realm);
+        self.init_builtin_objects();
+        self.init_event_loop_functions();
+        self.leave_realm(saved);
+        realm
+    }
+
+    /// Returns the current realm.
+    pub fn realm(&self) -> RealmId {
+        self.realm
+    }
+
+    /// Changes the current realm.
+    ///
+    /// Programs parsed after this call are evaluated in `realm` regardless of the current realm
+    /// at the evaluation time.  Methods operating on the global object such as `get_global()`
+    /// and `register_host_function()` operate on the global object of the current realm.
+    pub fn set_realm(&mut self, realm: RealmId) {
+        logger::debug!(event = "set_realm", ?realm);
+        self.enter_realm(realm);
+    }
+
+    /// Returns the global object of `realm`.
+    pub fn realm_global_object(&self, realm: RealmId) -> Rooted {
+        self.root(&Value::Object(self.realms[realm.index()].global_object))
+    }
+
+    /// Makes `realm` the current realm.
+    ///
+    /// Returns the previous current realm which must be passed to `leave_realm()`.
+    pub(crate) fn enter_realm(&mut self, realm: RealmId) -> RealmId {
+        let saved = self.realm;
+        if realm != saved {
+            // `self.builtins` always holds the intrinsics of the current realm.
+            self.builtins = self.realms[realm.index()].clone();
+            self.realm = realm;
+        }
+        saved
+    }
+
+    pub(crate) fn leave_realm(&mut self, saved: RealmId) {
+        self.enter_realm(saved);
+    }
+
+    /// Makes the realm of the function being called the current realm.
+    ///
+    /// Built-in functions call this method so that they use the intrinsics of their own realm
+    /// when they are called from another realm.
+    pub(crate) fn enter_callee_realm(&mut self, context: &CallContext) -> RealmId {
+        match context.func() {
+            Some(func) => self.enter_realm(func.closure().realm),
+            None => self.realm,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicRuntime;
+
+    #[test]
+    fn test_create_realm() {
+        let mut runtime = BasicRuntime::new();
+        let default_global = runtime.global_object().get();
+
+        let realm = runtime.create_realm();
+        assert_ne!(realm, RealmId::DEFAULT);
+        assert_eq!(runtime.realm(), RealmId::DEFAULT);
+        assert_eq!(runtime.global_object().get(), default_global);
+
+        let global = runtime.realm_global_object(realm).get();
+        assert_ne!(global, default_global);
+
+        runtime.set_realm(realm);
+        assert_eq!(runtime.global_object().get(), global);
+        assert_ne!(
+            runtime.builtins.object_prototype,
+            runtime.realms[RealmId::DEFAULT.index()].object_prototype
+        );
+    }
+}
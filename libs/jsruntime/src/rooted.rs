@@ -0,0 +1,113 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use jsgc::Trace;
+use jsgc::VisitList;
+
+use crate::Runtime;
+use crate::types::Value;
+
+/// A handle to a value which is kept alive across garbage collections.
+///
+/// A `Value` holding a string or an object is a raw handle to a memory block in the heap and it
+/// becomes invalid once the memory block is reclaimed by `Runtime::collect_garbage()`.  A
+/// `Rooted` handle registers the value as a GC root until the handle is dropped.
+///
+/// A `Rooted` handle must not outlive the runtime that created it.
+pub struct Rooted {
+    index: usize,
+    roots: Rc<RefCell<RootSet>>,
+}
+
+impl Rooted {
+    /// Returns the rooted value.
+    pub fn get(&self) -> Value {
+        self.roots.borrow().values[self.index].clone()
+    }
+}
+
+impl Clone for Rooted {
+    fn clone(&self) -> Self {
+        let index = self.roots.borrow_mut().add(self.get());
+        Self {
+            index,
+            roots: self.roots.clone(),
+        }
+    }
+}
+
+impl Drop for Rooted {
+    fn drop(&mut self) {
+        self.roots.borrow_mut().remove(self.index);
+    }
+}
+
+impl std::fmt::Debug for Rooted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Rooted").field(&self.get()).finish()
+    }
+}
+
+/// A set of values rooted by `Rooted` handles.
+#[derive(Default)]
+pub(crate) struct RootSet {
+    // `Value::None` is stored in free slots.
+    values: Vec<Value>,
+    free_indices: Vec<usize>,
+}
+
+impl RootSet {
+    fn add(&mut self, value: Value) -> usize {
+        debug_assert!(value.is_valid());
+        match self.free_indices.pop() {
+            Some(index) => {
+                self.values[index] = value;
+                index
+            }
+            None => {
+                self.values.push(value);
+                self.values.len() - 1
+            }
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.values[index] = Value::None;
+        self.free_indices.push(index);
+    }
+}
+
+impl Trace for RootSet {
+    fn trace(&self, visits: &mut VisitList) {
+        for value in self.values.iter() {
+            value.trace(visits);
+        }
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Creates a `Rooted` handle in order to keep a value alive across garbage collections.
+    pub fn root(&self, value: &Value) -> Rooted {
+        let index = self.roots.borrow_mut().add(value.clone());
+        Rooted {
+            index,
+            roots: self.roots.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_set() {
+        let mut roots = RootSet::default();
+        assert_eq!(roots.add(Value::Undefined), 0);
+        assert_eq!(roots.add(Value::Null), 1);
+        roots.remove(0);
+        assert!(!roots.values[0].is_valid());
+        assert_eq!(roots.add(Value::TRUE), 0);
+        assert_eq!(roots.values.len(), 2);
+    }
+}
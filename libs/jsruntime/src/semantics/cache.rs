@@ -0,0 +1,270 @@
+use crate::RealmId;
+use crate::code_cache::CodeCacheError;
+use crate::code_cache::Decode;
+use crate::code_cache::Encode;
+use crate::code_cache::Reader;
+use crate::code_cache::Writer;
+
+use super::CompileCommand;
+use super::Function;
+use super::FunctionFlags;
+use super::Program;
+use super::Symbol;
+use super::TemplateStrings;
+use super::ThisBinding;
+
+// The name, the source text and the realm of a program are not stored in a code cache.
+impl Encode for Program {
+    fn encode(&self, writer: &mut Writer) {
+        self.functions.encode(writer);
+        self.scope_tree.encode(writer);
+        // The order of the elements does not matter.
+        let global_symbols: Vec<_> = self.global_symbols.iter().copied().collect();
+        global_symbols.encode(writer);
+        self.global_declarations.encode(writer);
+        self.module.encode(writer);
+    }
+}
+
+impl Decode for Program {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(Program {
+            functions: Decode::decode(reader)?,
+            scope_tree: Decode::decode(reader)?,
+            global_symbols: Vec::<Symbol>::decode(reader)?.into_iter().collect(),
+            global_declarations: Decode::decode(reader)?,
+            module: Decode::decode(reader)?,
+            name: None,
+            source: String::new(),
+            realm: RealmId::DEFAULT,
+        })
+    }
+}
+
+impl Encode for Function {
+    fn encode(&self, writer: &mut Writer) {
+        self.name.encode(writer);
+        self.id.encode(writer);
+        self.commands.encode(writer);
+        self.scope_ref.encode(writer);
+        self.num_captures.encode(writer);
+        self.num_params.encode(writer);
+        self.length.encode(writer);
+        self.num_locals.encode(writer);
+        self.this_binding.encode(writer);
+        self.source_range.encode(writer);
+        self.coverage_blocks.encode(writer);
+        self.flags.bits().encode(writer);
+    }
+}
+
+impl Decode for Function {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(Function {
+            name: Decode::decode(reader)?,
+            id: Decode::decode(reader)?,
+            commands: Decode::decode(reader)?,
+            scope_ref: Decode::decode(reader)?,
+            num_captures: Decode::decode(reader)?,
+            num_params: Decode::decode(reader)?,
+            length: Decode::decode(reader)?,
+            num_locals: Decode::decode(reader)?,
+            this_binding: Decode::decode(reader)?,
+            source_range: Decode::decode(reader)?,
+            coverage_blocks: Decode::decode(reader)?,
+            flags: FunctionFlags::from_bits(Decode::decode(reader)?)
+                .ok_or(CodeCacheError::InvalidFormat)?,
+        })
+    }
+}
+
+impl Encode for ThisBinding {
+    fn encode(&self, writer: &mut Writer) {
+        let tag: u8 = match self {
+            Self::None => 0,
+            Self::ThisArgument => 1,
+            Self::Capture => 2,
+            Self::GlobalObject => 3,
+            Self::Quirk => 4,
+        };
+        tag.encode(writer);
+    }
+}
+
+impl Decode for ThisBinding {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        match u8::decode(reader)? {
+            0 => Ok(Self::None),
+            1 => Ok(Self::ThisArgument),
+            2 => Ok(Self::Capture),
+            3 => Ok(Self::GlobalObject),
+            4 => Ok(Self::Quirk),
+            _ => Err(CodeCacheError::InvalidFormat),
+        }
+    }
+}
+
+impl Encode for TemplateStrings {
+    fn encode(&self, writer: &mut Writer) {
+        self.cooked.encode(writer);
+        self.raw.encode(writer);
+    }
+}
+
+impl Decode for TemplateStrings {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(TemplateStrings {
+            cooked: Decode::decode(reader)?,
+            raw: Decode::decode(reader)?,
+        })
+    }
+}
+
+// Generates the codec of `CompileCommand` from a table of tags and variants.
+//
+// Tags must not be changed without incrementing `FORMAT_VERSION` in the `code_cache` module.
+macro_rules! impl_codec_for_compile_command {
+    ($($tag:literal => $variant:ident $(($($field:ident),+))?,)*) => {
+        impl Encode for CompileCommand {
+            fn encode(&self, writer: &mut Writer) {
+                match self {
+                    $(
+                        Self::$variant $(($($field),+))? => {
+                            writer.put_u8($tag);
+                            $($($field.encode(writer);)+)?
+                        }
+                    )*
+                }
+            }
+        }
+
+        impl Decode for CompileCommand {
+            fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+                let command = match u8::decode(reader)? {
+                    $(
+                        $tag => Self::$variant $(($({
+                            let $field = Decode::decode(reader)?;
+                            $field
+                        }),+))?,
+                    )*
+                    _ => return Err(CodeCacheError::InvalidFormat),
+                };
+                Ok(command)
+            }
+        }
+    };
+}
+
+impl_codec_for_compile_command! {
+    0 => Nop,
+    1 => Batch(a0),
+    2 => Undefined,
+    3 => Null,
+    4 => Boolean(a0),
+    5 => Number(a0),
+    6 => String(a0),
+    7 => Object,
+    8 => Function(a0),
+    9 => Lambda(a0),
+    10 => Closure(a0, a1),
+    11 => Coroutine(a0, a1),
+    12 => Promise,
+    13 => Exception,
+    14 => This,
+    15 => VariableReference(a0),
+    16 => PropertyReference(a0),
+    17 => ToPropertyKey,
+    18 => LoadFormalParameters(a0),
+    19 => AllocateLocals(a0),
+    20 => MutableVariable,
+    21 => ImmutableVariable,
+    22 => DeclareVariables(a0),
+    23 => DeclareFunctions(a0),
+    24 => DeclareFunction,
+    25 => Call(a0, a1),
+    26 => New(a0, a1),
+    27 => PushScope(a0),
+    28 => PopScope(a0),
+    29 => ToNumeric,
+    30 => ToString,
+    31 => ConcatStrings(a0),
+    32 => CreateDataProperty,
+    33 => CopyDataProperties,
+    34 => PushArrayElement,
+    35 => Delete,
+    36 => Void,
+    37 => Typeof,
+    38 => UnaryPlus,
+    39 => UnaryMinus,
+    40 => BitwiseNot,
+    41 => LogicalNot,
+    42 => Exponentiation,
+    43 => Multiplication,
+    44 => Division,
+    45 => Remainder,
+    46 => Addition,
+    47 => Subtraction,
+    48 => LeftShift,
+    49 => SignedRightShift,
+    50 => UnsignedRightShift,
+    51 => LessThan,
+    52 => GreaterThan,
+    53 => LessThanOrEqual,
+    54 => GreaterThanOrEqual,
+    55 => Instanceof,
+    56 => In,
+    57 => Equality,
+    58 => Inequality,
+    59 => StrictEquality,
+    60 => StrictInequality,
+    61 => BitwiseAnd,
+    62 => BitwiseXor,
+    63 => BitwiseOr,
+    64 => Ternary,
+    65 => Assignment,
+    66 => FalsyShortCircuit,
+    67 => TruthyShortCircuit,
+    68 => NullishShortCircuit,
+    69 => Truthy,
+    70 => NonNullish,
+    71 => IfThen(a0),
+    72 => Else(a0),
+    73 => IfElseStatement,
+    74 => IfStatement,
+    75 => WhileLoop(a0),
+    76 => DoWhileLoop(a0),
+    77 => ForLoop(a0, a1),
+    78 => LoopInit,
+    79 => LoopTest,
+    80 => LoopNext,
+    81 => LoopBody,
+    82 => LoopEnd,
+    83 => CaseBlock(a0, a1),
+    84 => CaseClause(a0, a1),
+    85 => Switch(a0, a1, a2),
+    86 => LabelStart(a0, a1),
+    87 => LabelEnd(a0, a1),
+    88 => Try,
+    89 => Catch(a0),
+    90 => Finally(a0),
+    91 => TryEnd,
+    92 => Continue(a0),
+    93 => Break(a0),
+    94 => Return(a0),
+    95 => Throw(a0),
+    96 => Environment(a0),
+    97 => JumpTable(a0),
+    98 => Await(a0),
+    99 => Resume,
+    100 => Discard,
+    101 => Swap,
+    102 => Duplicate(a0),
+    103 => Dereference,
+    104 => Debugger(a0),
+    105 => PlaceHolder,
+    106 => Coverage(a0),
+    107 => RestParameter(a0),
+    108 => DirectEval(a0, a1),
+    109 => CompletionValue,
+    110 => TemplateObject(a0),
+}
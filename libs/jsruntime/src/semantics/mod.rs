@@ -1,5 +1,9 @@
+mod cache;
 mod scope;
 
+use std::ops::Range;
+use std::rc::Rc;
+
 use bitflags::bitflags;
 use itertools::Itertools;
 use jsparser::SymbolRegistry;
@@ -24,6 +28,7 @@ use jsparser::syntax::UpdateOperator;
 
 use crate::Error as RuntimeError;
 use crate::ProgramId;
+use crate::RealmId;
 use crate::Runtime;
 use crate::Value;
 use crate::lambda::LambdaId;
@@ -34,30 +39,61 @@ use scope::ScopeTreeBuilder;
 
 pub use scope::ScopeRef;
 pub use scope::ScopeTree;
+pub use scope::Variable;
 pub use scope::VariableRef;
 
 logging::define_logger! {}
 
 impl<X> Runtime<X> {
     /// Parses a given source text as a script.
+    ///
+    /// The program will be evaluated in the current realm.
     pub fn parse_script(&mut self, source: &str) -> Result<ProgramId, ParserError> {
         logger::debug!(event = "parse", source_kind = "script");
         let analyzer = Analyzer::new_for_script(self);
         let processor = Processor::new(analyzer, false);
         let program = Parser::for_script(source, processor).parse()?;
-        Ok(self.register_program(program))
+        Ok(self.register_program(program, source))
+    }
+
+    /// Parses a given source text passed to `eval()`.
+    ///
+    /// The program will be evaluated in the current realm.
+    pub(crate) fn parse_eval(
+        &mut self,
+        source: &str,
+        params: EvalParams,
+    ) -> Result<ProgramId, ParserError> {
+        logger::debug!(
+            event = "parse",
+            source_kind = "eval",
+            direct = params.direct,
+            strict = params.strict
+        );
+        let strict = params.strict;
+        let analyzer = Analyzer::new_for_eval(self, params);
+        let mut processor = Processor::new(analyzer, false);
+        if strict {
+            processor.set_strict_mode();
+        }
+        let program = Parser::for_script(source, processor).parse()?;
+        Ok(self.register_program(program, source))
     }
 
     /// Parses a given source text as a module.
+    ///
+    /// The program will be evaluated in the current realm.
     pub fn parse_module(&mut self, source: &str) -> Result<ProgramId, ParserError> {
         logger::debug!(event = "parse", source_kind = "module");
         let analyzer = Analyzer::new_for_module(self);
         let processor = Processor::new(analyzer, true);
         let program = Parser::for_module(source, processor).parse()?;
-        Ok(self.register_program(program))
+        Ok(self.register_program(program, source))
     }
 
-    fn register_program(&mut self, program: Program) -> ProgramId {
+    pub(crate) fn register_program(&mut self, mut program: Program, source: &str) -> ProgramId {
+        program.source = source.to_owned();
+        program.realm = self.realm;
         let index = self.programs.len();
         let program_id = ProgramId::new(index);
         for (index, function) in program.functions.iter().enumerate() {
@@ -136,8 +172,24 @@ pub struct Program {
     /// The global variables used in the program.
     pub global_symbols: FxHashSet<Symbol>,
 
+    /// The global variables declared by `var` and function declarations in the program.
+    ///
+    /// These are defined as properties of the global object when the program is parsed.
+    pub global_declarations: Vec<Symbol>,
+
     /// `true` if the program is a JavaScript module.
     pub module: bool,
+
+    /// The name of the program shown in stack traces.
+    pub name: Option<String>,
+
+    /// The source text of the program.
+    pub source: String,
+
+    /// The realm where the program is evaluated.
+    ///
+    /// Global declarations in the program are defined on the global object of this realm.
+    pub realm: RealmId,
 }
 
 impl Program {
@@ -167,6 +219,12 @@ pub struct Function {
     /// The number of formal parameters of the function.
     pub num_params: u16,
 
+    /// The value of the `length` property of the function object.
+    ///
+    /// This is the number of formal parameters to the left of the first one that has a default
+    /// value or the rest parameter.
+    pub length: u16,
+
     /// The number of local variables used in the function except for temporal variables created by
     /// a compiler.
     pub num_locals: u16,
@@ -174,6 +232,17 @@ pub struct Function {
     /// Controls how the `this` binding is resolved.
     pub this_binding: ThisBinding,
 
+    /// The range of the function definition in the source text.
+    ///
+    /// The range of the top-level code of a program is an empty range at the beginning of the
+    /// source text.
+    pub source_range: SourceRange,
+
+    /// The ranges of the blocks counted by `CompileCommand::Coverage` commands.
+    ///
+    /// The operand of a `CompileCommand::Coverage` command is an index of this list.
+    pub coverage_blocks: Vec<SourceRange>,
+
     /// Flags.
     flags: FunctionFlags,
 }
@@ -209,6 +278,12 @@ bitflags! {
 
         /// The `this` binding is captured by descendant closures.
         const THIS_BINDING_CAPTURED = 1 << 1;
+
+        /// The elements of the `arguments` object are mapped to the formal parameters.
+        const MAPPED_ARGUMENTS = 1 << 2;
+
+        /// The function is strict mode code.
+        const STRICT = 1 << 3;
     }
 }
 
@@ -223,6 +298,16 @@ impl Function {
         self.flags.contains(FunctionFlags::THIS_BINDING_CAPTURED)
     }
 
+    /// Returns `true` if the `arguments` object is a mapped arguments object.
+    pub fn has_mapped_arguments(&self) -> bool {
+        self.flags.contains(FunctionFlags::MAPPED_ARGUMENTS)
+    }
+
+    /// Returns `true` if the function is strict mode code.
+    pub fn is_strict(&self) -> bool {
+        self.flags.contains(FunctionFlags::STRICT)
+    }
+
     pub fn print(&self, symbol_registry: &SymbolRegistry, index: usize, indent: &str) {
         let name = symbol_registry.resolve(self.name);
         debug_assert!(name.is_some());
@@ -240,12 +325,35 @@ impl Function {
         }
         println!("{indent} num_captures: {}", self.num_captures);
         println!("{indent} num_params: {}", self.num_params);
+        println!("{indent} length: {}", self.length);
         println!("{indent} num_locals: {}", self.num_locals);
         println!("{indent} this_binding: {:?}", self.this_binding);
+        println!("{indent} source_range: {:?}", self.source_range);
+        if !self.coverage_blocks.is_empty() {
+            println!("{indent} coverage_blocks:");
+            for (index, range) in self.coverage_blocks.iter().enumerate() {
+                println!("{indent}  #{index}: {range:?}");
+            }
+        }
         println!("{indent} flags: {:?}", self.flags);
     }
 }
 
+/// Parameters for the semantic analysis of eval code.
+pub(crate) struct EvalParams {
+    /// `true` if the eval code is evaluated by a direct eval.
+    pub direct: bool,
+
+    /// `true` if the caller of a direct eval is strict mode code.
+    pub strict: bool,
+
+    /// Variables of the caller visible from a direct eval.
+    ///
+    /// The index of a symbol in this list is the index of the capture in the closure of the eval
+    /// code.
+    pub captures: Vec<Symbol>,
+}
+
 /// A semantic analyzer.
 ///
 /// A semantic analyzer analyzes semantics of a JavaScript program.
@@ -263,6 +371,9 @@ struct Analyzer<'r, R> {
     functions: Vec<Function>,
 
     module: bool,
+
+    /// Available only when analyzing eval code.
+    eval: Option<EvalParams>,
 }
 
 trait AnalyzerSupport {
@@ -275,6 +386,14 @@ trait AnalyzerSupport {
     ) -> Result<bool, RuntimeError>;
 }
 
+impl<X> Runtime<X> {
+    /// Defines a global variable declared in a program loaded from a code cache.
+    pub(crate) fn define_global_declaration(&mut self, symbol: Symbol) {
+        let result = self.define_global_property(symbol, Property::data_wec(Value::Undefined));
+        debug_assert!(matches!(result, Ok(true)));
+    }
+}
+
 impl<X> AnalyzerSupport for Runtime<X> {
     fn make_symbol(&mut self, lexeme: &str) -> Symbol {
         self.symbol_registry.intern_str(lexeme)
@@ -300,6 +419,9 @@ impl<X> AnalyzerSupport for Runtime<X> {
 struct GlobalAnalysis {
     /// A scope tree builder used for building the scope tree of the JavaScript program.
     scope_tree_builder: ScopeTreeBuilder,
+
+    /// Scopes where direct evals are called.
+    direct_eval_scopes: Vec<ScopeRef>,
 }
 
 // We use a macro to get the mutable reference instead of use a method returning it in order to
@@ -334,6 +456,14 @@ where
         Self::new(support, true)
     }
 
+    /// Creates a semantic analyzer.
+    fn new_for_eval(support: &'r mut R, params: EvalParams) -> Self {
+        Self {
+            eval: Some(params),
+            ..Self::new(support, false)
+        }
+    }
+
     fn new(support: &'r mut R, module: bool) -> Self {
         Self {
             support,
@@ -341,6 +471,7 @@ where
             analysis_stack: vec![],
             functions: vec![],
             module,
+            eval: None,
         }
     }
 
@@ -361,6 +492,7 @@ where
             Node::Number(value, ..) => self.handle_number(value),
             Node::String(value, ..) => self.handle_string(value),
             Node::TemplateLiteral(n) => self.handle_template_literal(n),
+            Node::TemplateObject(cooked, raw) => self.handle_template_object(cooked, raw),
             Node::Array => self.handle_array(),
             Node::Object => self.handle_object(),
             Node::LiteralPropertyName(name) => self.handle_literal_property_name(name),
@@ -372,15 +504,18 @@ where
             Node::ArgumentListHead(empty, spread) => self.handle_argument_list_head(empty, spread),
             Node::ArgumentListItem(spread) => self.handle_argument_list_item(spread),
             Node::Arguments => self.handle_arguments(),
-            Node::CallExpression => self.handle_call_expression(),
-            Node::NewExpression(has_args) => self.handle_new_expression(has_args),
+            Node::CallExpression(offset) => self.handle_call_expression(offset),
+            Node::DirectEval(offset) => self.handle_direct_eval(offset),
+            Node::NewExpression(has_args, offset) => self.handle_new_expression(has_args, offset),
             Node::NonNullish => self.handle_non_nullish(),
             Node::OptionalChain(kind) => self.handle_optional_chain(kind),
             Node::UpdateExpression(op) => self.handle_update_expression(op),
             Node::UnaryExpression(op) => self.handle_operator(op.into()),
             Node::BinaryExpression(op) => self.handle_binary_expression(op),
-            Node::LogicalExpression(_op) => self.handle_conditional_expression(),
-            Node::ConditionalExpression => self.handle_conditional_expression(),
+            Node::LogicalExpression(_op) => self.handle_logical_expression(),
+            Node::ConditionalExpression(then_range, else_range) => {
+                self.handle_conditional_expression(then_range, else_range)
+            }
             Node::AssignmentExpression(AssignmentOperator::Assignment) => {
                 self.handle_operator(CompileCommand::Assignment)
             }
@@ -404,11 +539,13 @@ where
             Node::BindingElement(init) => self.handle_binding_element(init),
             Node::EmptyStatement => self.handle_empty_statement(),
             Node::ExpressionStatement => self.handle_expression_statement(),
-            Node::IfElseStatement => self.handle_if_else_statement(),
-            Node::IfStatement => self.handle_if_statement(),
-            Node::DoWhileStatement => self.handle_do_while_statement(),
-            Node::WhileStatement => self.handle_while_statement(),
-            Node::ForStatement(flags) => self.handle_for_statement(flags),
+            Node::IfElseStatement(then_range, else_range) => {
+                self.handle_if_else_statement(then_range, else_range)
+            }
+            Node::IfStatement(then_range) => self.handle_if_statement(then_range),
+            Node::DoWhileStatement(body_range) => self.handle_do_while_statement(body_range),
+            Node::WhileStatement(body_range) => self.handle_while_statement(body_range),
+            Node::ForStatement(flags, body_range) => self.handle_for_statement(flags, body_range),
             Node::ContinueStatement(symbol) => self.handle_continue_statement(symbol),
             Node::BreakStatement(symbol) => self.handle_break_statement(symbol),
             Node::ReturnStatement(n) => self.handle_return_statement(n),
@@ -422,7 +559,7 @@ where
                 self.handle_labelled_statement(symbol, is_iteration_statement)
             }
             Node::Label(symbol) => self.handle_label(symbol),
-            Node::ThrowStatement => self.handle_throw_statement(),
+            Node::ThrowStatement(offset) => self.handle_throw_statement(offset),
             Node::TryStatement => self.handle_try_statement(),
             Node::CatchClause(has_parameter) => self.handle_catch_clause(has_parameter),
             Node::FinallyClause => self.handle_finally_clause(),
@@ -430,16 +567,19 @@ where
             Node::TryBlock => self.handle_try_block(),
             Node::CatchBlock => self.handle_catch_block(),
             Node::FinallyBlock => self.handle_finally_block(),
-            Node::DebuggerStatement => self.handle_debugger_statement(),
+            Node::DebuggerStatement(offset) => self.handle_debugger_statement(offset),
             Node::FormalParameter => self.handle_formal_parameter(),
+            Node::FunctionRestParameter => self.handle_function_rest_parameter(),
             Node::FormalParameters(n) => self.handle_formal_parameters(n),
-            Node::FunctionDeclaration => self.handle_function_declaration(),
-            Node::AsyncFunctionDeclaration => self.handle_async_function_declaration(),
-            Node::FunctionExpression(named) => self.handle_function_expression(named),
-            Node::AsyncFunctionExpression(named) => self.handle_async_function_expression(named),
-            Node::ArrowFunction => self.handle_arrow_function(),
-            Node::AsyncArrowFunction => self.handle_async_arrow_function(),
-            Node::Method => self.handle_method(),
+            Node::FunctionDeclaration(range) => self.handle_function_declaration(range),
+            Node::AsyncFunctionDeclaration(range) => self.handle_async_function_declaration(range),
+            Node::FunctionExpression(named, range) => self.handle_function_expression(named, range),
+            Node::AsyncFunctionExpression(named, range) => {
+                self.handle_async_function_expression(named, range)
+            }
+            Node::ArrowFunction(range) => self.handle_arrow_function(range),
+            Node::AsyncArrowFunction(range) => self.handle_async_arrow_function(range),
+            Node::Method(range) => self.handle_method(range),
             Node::AwaitExpression => self.handle_await_expression(),
             Node::Then(expr) => self.handle_then(expr),
             Node::Else(expr) => self.handle_else(expr),
@@ -463,6 +603,7 @@ where
             Node::ArrowFunctionContext => self.handle_arrow_function_context(),
             Node::AsyncArrowFunctionContext => self.handle_async_arrow_function_context(),
             Node::FunctionSignature => self.handle_function_signature(),
+            Node::UseStrict => self.handle_use_strict(),
             Node::Dereference => self.handle_dereference(),
             Node::ToString => self.handle_to_string(),
         }
@@ -491,6 +632,11 @@ where
         }
     }
 
+    fn handle_template_object(&mut self, cooked: Vec<Option<Vec<u16>>>, raw: Vec<Vec<u16>>) {
+        let strings = TemplateStrings { cooked, raw };
+        analysis_mut!(self).put_command(CompileCommand::TemplateObject(Rc::new(strings)));
+    }
+
     fn handle_array(&mut self) {
         // TODO(feat): 10.4.2.2 ArrayCreate ( length [ , proto ] )
         push_commands! {
@@ -543,12 +689,17 @@ where
         // nop
     }
 
-    fn handle_call_expression(&mut self) {
-        analysis_mut!(self).process_call_expression();
+    fn handle_call_expression(&mut self, offset: usize) {
+        analysis_mut!(self).process_call_expression(offset);
     }
 
-    fn handle_new_expression(&mut self, has_args: bool) {
-        analysis_mut!(self).process_new_expression(has_args);
+    fn handle_direct_eval(&mut self, offset: usize) {
+        let scope_ref = analysis_mut!(self).process_direct_eval(offset);
+        self.global_analysis.direct_eval_scopes.push(scope_ref);
+    }
+
+    fn handle_new_expression(&mut self, has_args: bool, offset: usize) {
+        analysis_mut!(self).process_new_expression(has_args, offset);
     }
 
     fn handle_non_nullish(&mut self) {
@@ -579,10 +730,21 @@ where
         analysis_mut!(self).process_sequence_expression();
     }
 
-    fn handle_conditional_expression(&mut self) {
+    fn handle_logical_expression(&mut self) {
         push_commands!(self; CompileCommand::Ternary);
     }
 
+    fn handle_conditional_expression(
+        &mut self,
+        then_range: Range<usize>,
+        else_range: Range<usize>,
+    ) {
+        let analysis = analysis_mut!(self);
+        analysis.end_coverage_block(else_range);
+        analysis.end_coverage_block(then_range);
+        analysis.push_command(CompileCommand::Ternary);
+    }
+
     fn handle_conditional_assignment(&mut self) {
         push_commands!(self; CompileCommand::Ternary, CompileCommand::Assignment);
     }
@@ -611,8 +773,8 @@ where
         // nop
     }
 
-    fn handle_binding_element(&mut self, _init: bool) {
-        // TODO
+    fn handle_binding_element(&mut self, init: bool) {
+        analysis_mut!(self).process_binding_element(init);
     }
 
     fn handle_empty_statement(&mut self) {
@@ -620,33 +782,47 @@ where
     }
 
     fn handle_expression_statement(&mut self) {
-        push_commands!(self; CompileCommand::Discard);
+        // The value of the last expression statement evaluated in the top-level code of eval
+        // code is the result of `eval()`.
+        //
+        // TODO(feat): other statements such as `var x;` never update the completion value.  But
+        // statements like `if (true) {}` update it with `undefined` in the specification.
+        if self.eval.is_some() && self.analysis_stack.len() == 1 {
+            push_commands!(self; CompileCommand::CompletionValue);
+        } else {
+            push_commands!(self; CompileCommand::Discard);
+        }
     }
 
-    fn handle_if_else_statement(&mut self) {
-        push_commands!(self; CompileCommand::IfElseStatement);
+    fn handle_if_else_statement(&mut self, then_range: Range<usize>, else_range: Range<usize>) {
+        let analysis = analysis_mut!(self);
+        analysis.end_coverage_block(else_range);
+        analysis.end_coverage_block(then_range);
+        analysis.push_command(CompileCommand::IfElseStatement);
     }
 
-    fn handle_if_statement(&mut self) {
-        push_commands!(self; CompileCommand::IfStatement);
+    fn handle_if_statement(&mut self, then_range: Range<usize>) {
+        let analysis = analysis_mut!(self);
+        analysis.end_coverage_block(then_range);
+        analysis.push_command(CompileCommand::IfStatement);
     }
 
-    fn handle_do_while_statement(&mut self) {
+    fn handle_do_while_statement(&mut self, body_range: Range<usize>) {
         // See handle_loop_start() for the reason why we always pop the lexical scope here.
         self.global_analysis.scope_tree_builder.pop();
-        analysis_mut!(self).process_do_while_statement();
+        analysis_mut!(self).process_do_while_statement(body_range);
     }
 
-    fn handle_while_statement(&mut self) {
+    fn handle_while_statement(&mut self, body_range: Range<usize>) {
         // See handle_loop_start() for the reason why we always pop the lexical scope here.
         self.global_analysis.scope_tree_builder.pop();
-        analysis_mut!(self).process_while_statement();
+        analysis_mut!(self).process_while_statement(body_range);
     }
 
-    fn handle_for_statement(&mut self, flags: LoopFlags) {
+    fn handle_for_statement(&mut self, flags: LoopFlags, body_range: Range<usize>) {
         // See handle_loop_start() for the reason why we always pop the lexical scope here.
         self.global_analysis.scope_tree_builder.pop();
-        analysis_mut!(self).process_for_statement(flags);
+        analysis_mut!(self).process_for_statement(flags, body_range);
     }
 
     fn handle_continue_statement(&mut self, symbol: Symbol) {
@@ -695,8 +871,8 @@ where
         analysis_mut!(self).process_label(symbol);
     }
 
-    fn handle_throw_statement(&mut self) {
-        analysis_mut!(self).process_throw_statement();
+    fn handle_throw_statement(&mut self, offset: usize) {
+        analysis_mut!(self).process_throw_statement(offset);
     }
 
     fn handle_try_statement(&mut self) {
@@ -733,25 +909,33 @@ where
         self.global_analysis.scope_tree_builder.pop();
     }
 
-    fn handle_debugger_statement(&mut self) {
-        push_commands!(self; CompileCommand::Debugger);
+    fn handle_debugger_statement(&mut self, offset: usize) {
+        push_commands!(self; CompileCommand::Debugger(SourceOffset::new(offset)));
     }
 
     fn handle_formal_parameter(&mut self) {
         analysis_mut!(self).process_formal_parameter(&mut self.global_analysis);
     }
 
+    fn handle_function_rest_parameter(&mut self) {
+        analysis_mut!(self).process_rest_parameter(&mut self.global_analysis);
+    }
+
     fn handle_formal_parameters(&mut self, _n: u32) {
         // TODO
     }
 
-    fn end_function_scope(&mut self) {
+    fn end_function_scope(&mut self, range: Range<usize>) {
         let mut analysis = self.analysis_stack.pop().unwrap();
         debug_assert!(analysis.symbol_stack.is_empty());
 
         let func_scope_ref = analysis.end_scope();
         // DO NOT CALL `self.global_analysis.scope_tree_builder.pop()` HERE.
 
+        // The `arguments` object must be created before function-scoped variables are added
+        // because a "VariableStatement" never redeclares it.
+        analysis.process_arguments_object(&mut self.global_analysis);
+
         // Add Function-scoped variables defined by "VariableStatement"s to the function scope.
         analysis.process_function_scoped_variables(&mut self.global_analysis);
 
@@ -787,7 +971,7 @@ where
         let this_mode_lexical = matches!(analysis.this_mode, ThisMode::Lexical);
         let outer_this_captured = this_local && (this_used || this_captured) && this_mode_lexical;
 
-        self.apply_analysis(analysis, func_scope_ref);
+        self.apply_analysis(analysis, func_scope_ref, SourceRange::new(range));
 
         let func_index = self.functions.len() - 1;
         let analysis = self.analysis_mut();
@@ -798,8 +982,8 @@ where
         analysis.process_unresolved_references(&unresolved_references, func_index);
     }
 
-    fn handle_function_declaration(&mut self) {
-        self.end_function_scope();
+    fn handle_function_declaration(&mut self, range: Range<usize>) {
+        self.end_function_scope(range);
 
         let func = self.functions.last().unwrap();
         let batch_index = analysis_mut!(self).process_closure_declaration(func.scope_ref, func.id);
@@ -809,55 +993,60 @@ where
             .add_function_declaration(scope_ref, batch_index);
     }
 
-    fn handle_async_function_declaration(&mut self) {
-        self.end_coroutine_body();
+    fn handle_async_function_declaration(&mut self, range: Range<usize>) {
+        self.end_coroutine_body(range.clone());
 
         // Node::FunctionDeclaration for the outer ramp function.
-        self.handle_function_declaration();
+        self.handle_function_declaration(range);
     }
 
-    fn handle_function_expression(&mut self, named: bool) {
-        self.do_handle_function_expression(named, false);
+    fn handle_function_expression(&mut self, named: bool, range: Range<usize>) {
+        self.do_handle_function_expression(named, false, range);
     }
 
-    fn handle_async_function_expression(&mut self, named: bool) {
-        self.end_coroutine_body();
+    fn handle_async_function_expression(&mut self, named: bool, range: Range<usize>) {
+        self.end_coroutine_body(range.clone());
 
         // Node::FunctionExpression for the outer ramp function.
-        self.do_handle_function_expression(named, false);
+        self.do_handle_function_expression(named, false, range);
     }
 
-    fn do_handle_function_expression(&mut self, named: bool, coroutine: bool) {
-        self.end_function_scope();
+    fn do_handle_function_expression(&mut self, named: bool, coroutine: bool, range: Range<usize>) {
+        self.end_function_scope(range);
 
         let func = self.functions.last().unwrap();
         analysis_mut!(self).process_closure_expression(func.scope_ref, func.id, named, coroutine);
     }
 
-    fn handle_arrow_function(&mut self) {
-        self.do_handle_arrow_function(false);
+    fn handle_arrow_function(&mut self, range: Range<usize>) {
+        self.do_handle_arrow_function(false, range);
     }
 
-    fn handle_async_arrow_function(&mut self) {
-        self.end_coroutine_body();
+    fn handle_async_arrow_function(&mut self, range: Range<usize>) {
+        self.end_coroutine_body(range.clone());
 
         // Node::ArrowFunction for the outer ramp function.
-        self.do_handle_arrow_function(false);
+        self.do_handle_arrow_function(false, range);
     }
 
-    fn handle_method(&mut self) {
-        self.end_function_scope();
+    fn handle_method(&mut self, range: Range<usize>) {
+        self.end_function_scope(range);
 
         let func = self.functions.last().unwrap();
         analysis_mut!(self).process_closure_expression(func.scope_ref, func.id, true, false);
     }
 
-    fn do_handle_arrow_function(&mut self, coroutine: bool) {
-        // TODO: An ArrowFunction does not define local variables for arguments, super, this, or
+    fn do_handle_arrow_function(&mut self, coroutine: bool, range: Range<usize>) {
+        // An ArrowFunction does not define local variables for arguments, super, this, or
         // new.target.  Any reference to arguments, super, this, or new.target within an
         // ArrowFunction must resolve to a variable in a lexically enclosing environment.
+        //
+        // `arguments` is never added to the scope of an ArrowFunction in
+        // `process_arguments_object()` and references to it are resolved in outer scopes.
+        //
+        // TODO(feat): super, new.target
 
-        self.end_function_scope();
+        self.end_function_scope(range);
 
         let func = self.functions.last().unwrap();
         analysis_mut!(self).process_closure_expression(func.scope_ref, func.id, false, coroutine);
@@ -871,11 +1060,16 @@ where
     }
 
     fn handle_then(&mut self, expr: bool) {
-        push_commands!(self; CompileCommand::Truthy, CompileCommand::IfThen(expr));
+        let analysis = analysis_mut!(self);
+        analysis.push_command(CompileCommand::Truthy);
+        analysis.push_command(CompileCommand::IfThen(expr));
+        analysis.start_coverage_block();
     }
 
     fn handle_else(&mut self, expr: bool) {
-        push_commands!(self; CompileCommand::Else(expr));
+        let analysis = analysis_mut!(self);
+        analysis.push_command(CompileCommand::Else(expr));
+        analysis.start_coverage_block();
     }
 
     fn handle_falsy_short_circuit(&mut self) {
@@ -983,29 +1177,38 @@ where
                     .flags
                     .insert(FunctionAnalysisFlags::THIS_BINDING_LOCAL);
             }
+            // Functions defined in strict mode code are also strict mode code.
+            if parent.is_strict() {
+                analysis.flags.insert(FunctionAnalysisFlags::STRICT);
+            }
+        }
+
+        // Module code is always strict mode code.
+        if self.module {
+            analysis.flags.insert(FunctionAnalysisFlags::STRICT);
         }
 
         self.analysis_stack.push(analysis);
     }
 
     fn handle_function_context(&mut self, name: Symbol) {
-        // TODO(feat): 'use strict'
-        let this_mode = if self.module {
-            ThisMode::Strict
-        } else {
-            ThisMode::Global
-        };
+        let this_mode = self.this_mode_for_function();
         self.start_function_scope(name, LambdaKind::Normal, this_mode);
     }
 
     fn handle_async_function_context(&mut self, name: Symbol) {
-        // TODO(feat): 'use strict'
-        let this_mode = if self.module {
+        let this_mode = self.this_mode_for_function();
+        self.start_function_scope(name, LambdaKind::Ramp, this_mode);
+    }
+
+    // A function containing a Use Strict Directive is changed to `ThisMode::Strict` in
+    // `handle_use_strict()`.
+    fn this_mode_for_function(&self) -> ThisMode {
+        if self.module || self.analysis().is_strict() {
             ThisMode::Strict
         } else {
             ThisMode::Global
-        };
-        self.start_function_scope(name, LambdaKind::Ramp, this_mode);
+        }
     }
 
     fn handle_arrow_function_context(&mut self) {
@@ -1022,6 +1225,15 @@ where
         }
     }
 
+    fn handle_use_strict(&mut self) {
+        // The coroutine function body has already started if the function is an async function.
+        let len = self.analysis_stack.len();
+        let n = if self.analysis().is_coroutine() { 2 } else { 1 };
+        for (i, analysis) in self.analysis_stack[(len - n)..].iter_mut().enumerate() {
+            analysis.set_strict(len - n + i == 0);
+        }
+    }
+
     // The async function is translated into a ramp function.  The ramp function creates a
     // coroutine every time it's called.  The coroutine function body is built from the async
     // function body.  It will be rewritten into a state machine for the coroutine.
@@ -1048,9 +1260,9 @@ where
 
     // Generate compile commands for the bottom-half of the coroutine.
     // See //libs/jsruntime/docs/internals.md.
-    fn end_coroutine_body(&mut self) {
+    fn end_coroutine_body(&mut self, range: Range<usize>) {
         // TODO(perf): Some of the local variables can be placed on the stack.
-        self.do_handle_function_expression(false, true);
+        self.do_handle_function_expression(false, true, range);
 
         let func = self.functions.last().unwrap();
         push_commands!(
@@ -1112,7 +1324,61 @@ where
         unresolved_reference
     }
 
-    fn apply_analysis(&mut self, analysis: FunctionAnalysis, scope_ref: ScopeRef) {
+    // Returns the index of the capture holding a variable of the caller of a direct eval.
+    fn eval_capture_index(&self, symbol: Symbol) -> Option<u16> {
+        self.eval
+            .as_ref()?
+            .captures
+            .iter()
+            .position(|&capture| capture == symbol)
+            .map(|index| index as u16)
+    }
+
+    // A reference to a variable of the caller of a direct eval is resolved to a capture in the
+    // closure of the eval code.
+    fn add_eval_capture(&mut self, global_scope_ref: ScopeRef, reference: &Reference, index: u16) {
+        let builder = &mut self.global_analysis.scope_tree_builder;
+        if !builder.has_variable_in(global_scope_ref, reference.symbol) {
+            builder.add_capture(global_scope_ref, reference.symbol, index);
+        }
+        if let Some(func_index) = reference.func_index {
+            let func = &mut self.functions[func_index];
+            builder.add_capture(func.scope_ref, reference.symbol, func.num_captures);
+            func.num_captures += 1;
+        }
+    }
+
+    // Variables visible from a direct eval are passed to the eval code at runtime.  So, they
+    // must be accessible from the function calling the direct eval.
+    fn process_direct_evals(&mut self) {
+        let GlobalAnalysis {
+            scope_tree_builder,
+            direct_eval_scopes,
+        } = &mut self.global_analysis;
+        if direct_eval_scopes.is_empty() {
+            return;
+        }
+        let func_indices: FxHashMap<u16, usize> = self
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(index, func)| (func.scope_ref.id(), index))
+            .collect();
+        for &scope_ref in direct_eval_scopes.iter() {
+            scope_tree_builder.capture_visible_variables(scope_ref, |func_scope_ref| {
+                let func = &mut self.functions[func_indices[&func_scope_ref.id()]];
+                func.num_captures += 1;
+                func.num_captures - 1
+            });
+        }
+    }
+
+    fn apply_analysis(
+        &mut self,
+        analysis: FunctionAnalysis,
+        scope_ref: ScopeRef,
+        source_range: SourceRange,
+    ) {
         let this_used = analysis
             .flags
             .contains(FunctionAnalysisFlags::THIS_BINDING_USED);
@@ -1131,9 +1397,19 @@ where
             (_, false, ThisMode::Global) => ThisBinding::GlobalObject,
         };
         let mut flags = FunctionFlags::empty();
-        if self.analysis_stack.is_empty() {
+        // Eval code is called as a closure capturing variables of the caller.
+        if self.analysis_stack.is_empty() && self.eval.is_none() {
             flags.insert(FunctionFlags::ENTRY_FUNCTION);
         }
+        if analysis
+            .flags
+            .contains(FunctionAnalysisFlags::MAPPED_ARGUMENTS)
+        {
+            flags.insert(FunctionFlags::MAPPED_ARGUMENTS);
+        }
+        if analysis.is_strict() {
+            flags.insert(FunctionFlags::STRICT);
+        }
         // The global object is never captured.  It can be directly accessible in any scope.
         if this_captured && this_local {
             flags.insert(FunctionFlags::THIS_BINDING_CAPTURED);
@@ -1145,8 +1421,13 @@ where
             scope_ref,
             num_captures: 0,
             num_params: analysis.num_params,
+            length: analysis
+                .expected_argument_count
+                .unwrap_or(analysis.num_params),
             num_locals: analysis.num_locals,
             this_binding,
+            source_range,
+            coverage_blocks: analysis.coverage_blocks,
             flags,
         });
     }
@@ -1167,6 +1448,19 @@ where
             self.start_function_scope(Symbol::NONE, LambdaKind::Ramp, ThisMode::Strict);
             // The module is always treated as an async function body.
             self.start_coroutine_body();
+        } else if let Some(EvalParams { direct, strict, .. }) = self.eval {
+            if direct {
+                // The `this` binding of the caller will be specified in the `this` parameter.
+                self.start_function_scope(Symbol::NONE, LambdaKind::Normal, ThisMode::Strict);
+                analysis_mut!(self)
+                    .flags
+                    .insert(FunctionAnalysisFlags::THIS_BINDING_LOCAL);
+            } else {
+                self.start_function_scope(Symbol::NONE, LambdaKind::Normal, ThisMode::Global);
+            }
+            if strict {
+                analysis_mut!(self).set_strict(true);
+            }
         } else {
             self.start_function_scope(Symbol::NONE, LambdaKind::Normal, ThisMode::Global);
         }
@@ -1176,13 +1470,26 @@ where
         logger::debug!(event = "accept");
 
         if self.module {
-            self.end_coroutine_body();
+            self.end_coroutine_body(0..0);
         }
 
         let mut analysis = self.analysis_stack.pop().unwrap();
         debug_assert!(analysis.symbol_stack.is_empty());
 
         let global_scope_ref = analysis.end_scope();
+
+        // Variables declared in eval code are local to the eval code except for non-strict
+        // indirect eval code.
+        //
+        // TODO(feat): variables declared in non-strict direct eval code must be added to the
+        // variable environment of the caller.
+        if let Some(eval) = self.eval.as_ref()
+            && (eval.direct || analysis.is_strict())
+        {
+            analysis.process_function_scoped_variables(&mut self.global_analysis);
+            analysis.function_scoped_variables.clear();
+        }
+
         self.global_analysis.scope_tree_builder.pop();
 
         let unresolved_references = self.resolve_references(&mut analysis);
@@ -1192,9 +1499,14 @@ where
 
         let mut globals_in_global_scope = FxHashSet::default();
         let mut global_symbols = FxHashSet::default();
+        let mut global_declarations = vec![];
 
         // References to global properties.
         for reference in unresolved_references.iter() {
+            if let Some(index) = self.eval_capture_index(reference.symbol) {
+                self.add_eval_capture(global_scope_ref, reference, index);
+                continue;
+            }
             match reference.func_index {
                 Some(func_index) => {
                     let func_scope_ref = self.functions[func_index].scope_ref;
@@ -1227,6 +1539,7 @@ where
                 .support
                 .define_global_property(symbol, Property::data_wec(Value::Undefined));
             debug_assert!(matches!(result, Ok(true)));
+            global_declarations.push(symbol);
             // TODO: this
             if !globals_in_global_scope.contains(&symbol) {
                 self.global_analysis
@@ -1237,7 +1550,22 @@ where
             global_symbols.insert(symbol);
         }
 
-        self.apply_analysis(analysis, global_scope_ref);
+        self.apply_analysis(analysis, global_scope_ref, SourceRange::default());
+
+        if let Some(eval) = self.eval.as_ref() {
+            self.functions.last_mut().unwrap().num_captures = eval.captures.len() as u16;
+            // A direct eval in the eval code can refer to any variable of the caller.
+            if !self.global_analysis.direct_eval_scopes.is_empty() {
+                for (index, &symbol) in eval.captures.iter().enumerate() {
+                    let builder = &mut self.global_analysis.scope_tree_builder;
+                    if !builder.has_variable_in(global_scope_ref, symbol) {
+                        builder.add_capture(global_scope_ref, symbol, index as u16);
+                    }
+                }
+            }
+        }
+
+        self.process_direct_evals();
 
         let scope_tree = self.global_analysis.scope_tree_builder.build();
         if !scope_tree.validate() {
@@ -1248,7 +1576,11 @@ where
             functions: std::mem::take(&mut self.functions),
             scope_tree,
             global_symbols,
+            global_declarations,
             module: self.module,
+            name: None,
+            source: String::new(),
+            realm: RealmId::DEFAULT,
         })
     }
 
@@ -1286,8 +1618,10 @@ struct FunctionAnalysis {
 
     /// A stack to keep symbols defined as "BindingIdentifier"s.
     ///
-    /// The second member of a tuple is used for keeping the index of a placeholder command.
-    /// Because the type of a lexical declaration cannot be known at "LexicalBinding".
+    /// The second member of a tuple is initially the index of the next command, which is the
+    /// first command of the initializer of a formal parameter if it exists.  It's replaced with
+    /// the index of a placeholder command at "LexicalBinding" because the type of a lexical
+    /// declaration cannot be known at that point.
     symbol_stack: Vec<(Symbol, usize)>,
 
     /// A set of function-scoped variables.
@@ -1317,6 +1651,12 @@ struct FunctionAnalysis {
 
     coroutine: CoroutineAnalysis,
 
+    /// The ranges of the blocks counted for code coverage.
+    coverage_blocks: Vec<SourceRange>,
+
+    /// A stack to hold the indices of the coverage blocks whose ranges are not determined yet.
+    coverage_block_stack: Vec<u32>,
+
     /// A stack to hold the number of arguments of a function call.
     nargs_stack: Vec<u16>,
 
@@ -1331,6 +1671,11 @@ struct FunctionAnalysis {
     /// The number of formal parameters.
     num_params: u16,
 
+    /// `ExpectedArgumentCount` of the formal parameters.
+    ///
+    /// `None` if every formal parameter is counted.
+    expected_argument_count: Option<u16>,
+
     /// The number of local variables.
     num_locals: u16,
 
@@ -1396,6 +1741,17 @@ bitflags! {
         /// The `this` binding will be always resolved to the global object if this flag is not
         /// set.
         const THIS_BINDING_LOCAL = 1 << 4;
+
+        /// The formal parameters contain a default value or a rest parameter.
+        ///
+        /// `IsSimpleParameterList` is false.
+        const NON_SIMPLE_PARAMETERS = 1 << 5;
+
+        /// The `arguments` object is mapped to the formal parameters.
+        const MAPPED_ARGUMENTS = 1 << 6;
+
+        /// The function is strict mode code.
+        const STRICT = 1 << 7;
     }
 }
 
@@ -1425,6 +1781,19 @@ impl FunctionAnalysis {
         self.flags.insert(FunctionAnalysisFlags::COROUTINE);
     }
 
+    fn is_strict(&self) -> bool {
+        self.flags.contains(FunctionAnalysisFlags::STRICT)
+    }
+
+    // 11.2.2 Strict Mode Code
+    fn set_strict(&mut self, is_entry_function: bool) {
+        self.flags.insert(FunctionAnalysisFlags::STRICT);
+        // The `this` binding of the entry function is always the global object.
+        if !is_entry_function && matches!(self.this_mode, ThisMode::Global) {
+            self.this_mode = ThisMode::Strict;
+        }
+    }
+
     fn push_command(&mut self, command: CompileCommand) {
         self.commands.push(command);
     }
@@ -1486,22 +1855,38 @@ impl FunctionAnalysis {
         self.process_identifier_reference(Symbol::INTERNAL_ERROR);
         let utf16 = message.encode_utf16().collect_vec();
         self.commands.push(CompileCommand::String(utf16));
-        self.commands.push(CompileCommand::New(1));
-        self.commands.push(CompileCommand::Throw);
+        self.commands
+            .push(CompileCommand::New(1, SourceOffset::UNKNOWN));
+        self.commands
+            .push(CompileCommand::Throw(SourceOffset::UNKNOWN));
     }
 
-    fn process_call_expression(&mut self) {
+    fn process_call_expression(&mut self, offset: usize) {
         let nargs = self.nargs_stack.pop().unwrap();
-        self.commands.push(CompileCommand::Call(nargs));
+        self.commands
+            .push(CompileCommand::Call(nargs, SourceOffset::new(offset)));
     }
 
-    fn process_new_expression(&mut self, has_args: bool) {
+    fn process_direct_eval(&mut self, offset: usize) -> ScopeRef {
+        let nargs = self.nargs_stack.pop().unwrap();
+        self.commands
+            .push(CompileCommand::DirectEval(nargs, SourceOffset::new(offset)));
+        // Eval code may use the `this` binding and the `arguments` object of the caller.
+        let scope_ref = self.scope_ref();
+        self.flags.insert(FunctionAnalysisFlags::THIS_BINDING_USED);
+        self.references
+            .push(Reference::new(Symbol::ARGUMENTS, scope_ref));
+        scope_ref
+    }
+
+    fn process_new_expression(&mut self, has_args: bool, offset: usize) {
         let nargs = if has_args {
             self.nargs_stack.pop().unwrap()
         } else {
             0
         };
-        self.commands.push(CompileCommand::New(nargs));
+        self.commands
+            .push(CompileCommand::New(nargs, SourceOffset::new(offset)));
     }
 
     fn process_lexical_binding(&mut self, init: bool) {
@@ -1624,7 +2009,58 @@ impl FunctionAnalysis {
     }
 
     fn process_binding_identifier(&mut self, symbol: Symbol) {
-        self.symbol_stack.push((symbol, 0));
+        self.symbol_stack.push((symbol, self.commands.len()));
+    }
+
+    fn process_binding_element(&mut self, init: bool) {
+        debug_assert!(!self.symbol_stack.is_empty());
+
+        if !init {
+            return;
+        }
+
+        let (symbol, start) = *self.symbol_stack.last().unwrap();
+
+        if let Some(CompileCommand::Function(name @ Symbol::NONE)) = self.commands.last_mut() {
+            // 8.4.5 Runtime Semantics: NamedEvaluation
+            *name = symbol;
+        }
+
+        // Convert `param = initializer` into:
+        //
+        //   if (param === undefined) {
+        //     param = initializer;
+        //   }
+        //
+        // The commands for the condition are inserted before the commands of the initializer.
+        // This is performed only once for each formal parameter and no command index pointing to
+        // the commands of the initializer is kept at this point.
+        self.commands.splice(
+            start..start,
+            [
+                CompileCommand::VariableReference(symbol),
+                CompileCommand::Undefined,
+                CompileCommand::Swap,
+                CompileCommand::StrictEquality,
+                CompileCommand::Truthy,
+                CompileCommand::IfThen(false),
+            ],
+        );
+        self.commands
+            .push(CompileCommand::VariableReference(symbol));
+        self.commands.push(CompileCommand::Swap);
+        self.commands.push(CompileCommand::Assignment);
+        self.commands.push(CompileCommand::Discard);
+        self.commands.push(CompileCommand::IfStatement);
+
+        let scope_ref = self.scope_ref();
+        self.references.push(Reference::new(symbol, scope_ref));
+        self.references.push(Reference::new(symbol, scope_ref));
+
+        // 15.1.5 Static Semantics: ExpectedArgumentCount
+        self.expected_argument_count.get_or_insert(self.num_params);
+        self.flags
+            .insert(FunctionAnalysisFlags::NON_SIMPLE_PARAMETERS);
     }
 
     fn process_non_nullish(&mut self) {
@@ -1638,9 +2074,10 @@ impl FunctionAnalysis {
 
     fn process_optional_chain(&mut self, kind: PropertyAccessKind) {
         match kind {
-            PropertyAccessKind::Call => {
+            PropertyAccessKind::Call(offset) => {
                 let nargs = self.nargs_stack.pop().unwrap();
-                self.commands.push(CompileCommand::Call(nargs));
+                self.commands
+                    .push(CompileCommand::Call(nargs, SourceOffset::new(offset)));
                 self.commands.push(CompileCommand::Else(true));
                 self.commands.push(CompileCommand::Undefined);
                 self.commands.push(CompileCommand::Ternary);
@@ -1743,6 +2180,63 @@ impl FunctionAnalysis {
         self.num_params += 1;
     }
 
+    fn process_rest_parameter(&mut self, global_analysis: &mut GlobalAnalysis) {
+        let index = self.num_params;
+        self.process_formal_parameter(global_analysis);
+        self.commands.push(CompileCommand::RestParameter(index));
+
+        // 15.1.5 Static Semantics: ExpectedArgumentCount
+        self.expected_argument_count.get_or_insert(index);
+        self.flags
+            .insert(FunctionAnalysisFlags::NON_SIMPLE_PARAMETERS);
+    }
+
+    // Step#15..22 in 10.2.11 FunctionDeclarationInstantiation ( func, argumentsList )
+    //
+    // The `arguments` object is created only if it's referred in the function.  References in
+    // nested arrow functions have already been propagated to `self.references`.
+    fn process_arguments_object(&mut self, global_analysis: &mut GlobalAnalysis) {
+        if matches!(self.this_mode, ThisMode::Lexical) || self.is_coroutine() {
+            return;
+        }
+
+        if !self
+            .references
+            .iter()
+            .any(|reference| reference.symbol == Symbol::ARGUMENTS)
+        {
+            return;
+        }
+
+        // A formal parameter or a lexically declared variable named `arguments` hides the
+        // `arguments` object.
+        if global_analysis
+            .scope_tree_builder
+            .has_variable(Symbol::ARGUMENTS)
+        {
+            return;
+        }
+
+        // A variable declared by a "VariableStatement" is bound to the `arguments` object.  A
+        // function declared by a "FunctionDeclaration" overwrites it in `DeclareFunctions`.
+        self.function_scoped_variables.remove(&Symbol::ARGUMENTS);
+
+        global_analysis
+            .scope_tree_builder
+            .add_arguments(self.num_locals);
+        self.num_locals += 1;
+
+        let strict = self.is_strict();
+        let simple = !self
+            .flags
+            .contains(FunctionAnalysisFlags::NON_SIMPLE_PARAMETERS);
+        if !strict && simple {
+            // The mapped `arguments` object accesses the formal parameters via captures.
+            global_analysis.scope_tree_builder.set_params_captured();
+            self.flags.insert(FunctionAnalysisFlags::MAPPED_ARGUMENTS);
+        }
+    }
+
     fn process_mutable_bindings(&mut self, n: u32, global_analysis: &mut GlobalAnalysis) {
         debug_assert!(self.symbol_stack.len() >= n as usize);
         let i = self.symbol_stack.len() - n as usize;
@@ -1838,64 +2332,117 @@ impl FunctionAnalysis {
     fn process_loop_start(&mut self, scope_ref: ScopeRef) {
         self.start_scope(scope_ref, false);
 
-        // The placeholder command will be replaced with an appropriate command in
+        // The placeholder commands will be replaced with appropriate commands in
         // `process_loop_end()`.
         let start_index = self.reserve_commands(1);
-        self.loop_stack.push(LoopAnalysis { start_index });
+        let body_index = self.reserve_commands(1);
+        self.loop_stack.push(LoopAnalysis {
+            start_index,
+            body_index,
+        });
     }
 
     fn process_loop_init_expression(&mut self) {
         // Discard the evaluation result of the expression like as ExpressionStatement.
         self.commands.push(CompileCommand::Discard);
         self.commands.push(CompileCommand::LoopInit);
+        self.reserve_loop_body_command();
     }
 
     fn process_loop_init_declaration(&mut self) {
         self.commands.push(CompileCommand::LoopInit);
+        self.reserve_loop_body_command();
     }
 
     fn process_loop_test(&mut self) {
         self.commands.push(CompileCommand::LoopTest);
+        self.reserve_loop_body_command();
     }
 
     fn process_loop_next(&mut self) {
         self.commands.push(CompileCommand::LoopNext);
+        self.reserve_loop_body_command();
+    }
+
+    // The loop body starts after the last one of the loop markers preceding it.  We cannot know
+    // which marker is the last one until the loop ends.  So, we reserve a placeholder command
+    // after each marker and replace the previous one with `CompileCommand::Nop`.
+    fn reserve_loop_body_command(&mut self) {
+        let body_index = self.reserve_commands(1);
+        let loop_analysis = self.loop_stack.last_mut().unwrap();
+        let prev_index = std::mem::replace(&mut loop_analysis.body_index, body_index);
+        debug_assert!(matches!(
+            self.commands[prev_index],
+            CompileCommand::PlaceHolder
+        ));
+        self.commands[prev_index] = CompileCommand::Nop;
     }
 
     fn process_loop_body(&mut self) {
         self.commands.push(CompileCommand::LoopBody);
     }
 
-    fn process_loop_end(&mut self, command: CompileCommand) {
+    fn process_loop_end(&mut self, command: CompileCommand, body_range: Range<usize>) {
         self.commands.push(CompileCommand::LoopEnd);
-        let LoopAnalysis { start_index } = self.loop_stack.pop().unwrap();
+        let LoopAnalysis {
+            start_index,
+            body_index,
+        } = self.loop_stack.pop().unwrap();
         debug_assert!(matches!(
             self.commands[start_index],
             CompileCommand::PlaceHolder
         ));
         self.commands[start_index] = command;
+        debug_assert!(matches!(
+            self.commands[body_index],
+            CompileCommand::PlaceHolder
+        ));
+        self.commands[body_index] = CompileCommand::Coverage(self.coverage_blocks.len() as u32);
+        self.coverage_blocks.push(SourceRange::new(body_range));
 
         self.end_scope();
     }
 
-    fn process_do_while_statement(&mut self) {
+    fn process_do_while_statement(&mut self, body_range: Range<usize>) {
         self.commands.push(CompileCommand::LoopTest);
-        self.process_loop_end(CompileCommand::DoWhileLoop(self.num_do_while_statements));
+        self.process_loop_end(
+            CompileCommand::DoWhileLoop(self.num_do_while_statements),
+            body_range,
+        );
         self.num_do_while_statements += 1;
     }
 
-    fn process_while_statement(&mut self) {
+    fn process_while_statement(&mut self, body_range: Range<usize>) {
         self.commands.push(CompileCommand::LoopBody);
-        self.process_loop_end(CompileCommand::WhileLoop(self.num_while_statements));
+        self.process_loop_end(
+            CompileCommand::WhileLoop(self.num_while_statements),
+            body_range,
+        );
         self.num_while_statements += 1;
     }
 
-    fn process_for_statement(&mut self, flags: LoopFlags) {
+    fn process_for_statement(&mut self, flags: LoopFlags, body_range: Range<usize>) {
         self.commands.push(CompileCommand::LoopBody);
-        self.process_loop_end(CompileCommand::ForLoop(self.num_for_statements, flags));
+        self.process_loop_end(
+            CompileCommand::ForLoop(self.num_for_statements, flags),
+            body_range,
+        );
         self.num_for_statements += 1;
     }
 
+    // The range of the block will be set in `end_coverage_block()`.
+    fn start_coverage_block(&mut self) {
+        let index = self.coverage_blocks.len() as u32;
+        self.coverage_blocks.push(SourceRange::default());
+        self.coverage_block_stack.push(index);
+        self.commands.push(CompileCommand::Coverage(index));
+    }
+
+    fn end_coverage_block(&mut self, range: Range<usize>) {
+        let index = self.coverage_block_stack.pop().unwrap();
+        self.coverage_blocks[index as usize] = SourceRange::new(range);
+    }
+
     fn process_case_block(&mut self, scope_ref: ScopeRef) {
         // Step#3..7 in 14.12.4 Runtime Semantics: Evaluation
         self.start_scope(scope_ref, false);
@@ -2009,8 +2556,9 @@ impl FunctionAnalysis {
             .push(CompileCommand::LabelEnd(symbol, is_iteration_statement));
     }
 
-    fn process_throw_statement(&mut self) {
-        self.commands.push(CompileCommand::Throw);
+    fn process_throw_statement(&mut self, offset: usize) {
+        self.commands
+            .push(CompileCommand::Throw(SourceOffset::new(offset)));
     }
 
     fn process_try_block(&mut self) {
@@ -2140,6 +2688,7 @@ struct Scope {
 
 struct LoopAnalysis {
     start_index: usize,
+    body_index: usize,
 }
 
 #[derive(Default)]
@@ -2168,7 +2717,7 @@ struct CoroutineAnalysis {
 }
 
 /// A compile command.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CompileCommand {
     // Inserting commands in the middle is an inefficient operation.  For avoiding such an
     // operation, the following commands are introduced.
@@ -2190,6 +2739,10 @@ pub enum CompileCommand {
     Boolean(bool),
     Number(f64),
     String(Vec<u16>),
+    // A `TemplateObject` pushes the template object of a tagged template.  The template strings
+    // are shared between clones of the command because their address identifies the tagged
+    // template in the template registry of the realm.
+    TemplateObject(Rc<TemplateStrings>),
     Object,
     Function(Symbol),
     Lambda(LambdaId),
@@ -2205,14 +2758,23 @@ pub enum CompileCommand {
     ToPropertyKey,
 
     LoadFormalParameters(u16),
+
+    // A `RestParameter(i)` stores an array containing the arguments from the `i`-th position into
+    // the `i`-th formal parameter.
+    RestParameter(u16),
+
     AllocateLocals(u16),
     MutableVariable,
     ImmutableVariable,
     DeclareVariables(ScopeRef),
     DeclareFunctions(ScopeRef),
     DeclareFunction,
-    Call(u16),
-    New(u16),
+    Call(u16, SourceOffset),
+    // A `DirectEval(n, offset)` performs a call like a `Call(n, offset)`.  If the callee is
+    // %eval%, the first argument is evaluated as a direct eval with the variables visible from
+    // the current scope.
+    DirectEval(u16, SourceOffset),
+    New(u16, SourceOffset),
     PushScope(ScopeRef),
     PopScope(ScopeRef),
 
@@ -2333,7 +2895,7 @@ pub enum CompileCommand {
     Continue(Symbol),
     Break(Symbol),
     Return(u32),
-    Throw,
+    Throw(SourceOffset),
 
     // coroutine
     Environment(u16),
@@ -2342,18 +2904,83 @@ pub enum CompileCommand {
     Resume,
 
     Discard,
+    // A `CompletionValue` pops a value and stores it as the completion value of eval code.
+    CompletionValue,
     Swap,
     Duplicate(u8), // 0 or 1
     Dereference,
 
     // debugger
-    Debugger,
+    Debugger(SourceOffset),
+
+    // code coverage
+    Coverage(u32),
 
     // A special command used as a placeholder in a command list, which will be replaced actual
     // command later.  The final command list must not contain placeholder commands.
     PlaceHolder,
 }
 
+/// The template strings of a tagged template.
+///
+/// A cooked string is `None` if the template string contains an invalid escape sequence.
+#[derive(Debug, PartialEq)]
+pub struct TemplateStrings {
+    pub cooked: Vec<Option<Vec<u16>>>,
+    pub raw: Vec<Vec<u16>>,
+}
+
+/// A byte offset in the source text of a program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SourceOffset(u32);
+
+impl SourceOffset {
+    /// Used for code generated without a corresponding source location.
+    pub const UNKNOWN: Self = Self(u32::MAX);
+
+    fn new(offset: usize) -> Self {
+        debug_assert!(offset < u32::MAX as usize);
+        Self(offset.min(u32::MAX as usize) as u32)
+    }
+
+    pub fn get(&self) -> Option<usize> {
+        if *self == Self::UNKNOWN {
+            None
+        } else {
+            Some(self.0 as usize)
+        }
+    }
+}
+
+/// A range of byte offsets in the source text of a program.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SourceRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl SourceRange {
+    fn new(range: Range<usize>) -> Self {
+        debug_assert!(range.end < u32::MAX as usize);
+        Self {
+            start: range.start.min(u32::MAX as usize) as u32,
+            end: range.end.min(u32::MAX as usize) as u32,
+        }
+    }
+}
+
+impl From<SourceOffset> for u32 {
+    fn from(value: SourceOffset) -> Self {
+        value.0
+    }
+}
+
+impl From<u32> for SourceOffset {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
 impl From<UnaryOperator> for CompileCommand {
     fn from(value: UnaryOperator) -> Self {
         match value {
@@ -2470,6 +3097,7 @@ impl Reference {
 mod tests {
     use super::*;
     use crate::LambdaRegistry;
+    use assert_matches::assert_matches;
     use jsparser::Parser;
     use jsparser::Processor;
     use jsparser::SymbolRegistry;
@@ -2656,6 +3284,107 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_default_parameter() {
+        test(script!("function f(a = 1, b) {}"), |program, stub| {
+            assert_eq!(program.functions[0].num_params, 2);
+            assert_eq!(program.functions[0].length, 0);
+            assert_eq!(
+                program.functions[0].commands,
+                [
+                    CompileCommand::LoadFormalParameters(2),
+                    CompileCommand::AllocateLocals(0),
+                    CompileCommand::PushScope(scope_ref!(2)),
+                    CompileCommand::DeclareVariables(scope_ref!(2)),
+                    CompileCommand::DeclareFunctions(scope_ref!(2)),
+                    CompileCommand::VariableReference(symbol!(stub, "a")),
+                    CompileCommand::Undefined,
+                    CompileCommand::Swap,
+                    CompileCommand::StrictEquality,
+                    CompileCommand::Truthy,
+                    CompileCommand::IfThen(false),
+                    CompileCommand::Number(1.0),
+                    CompileCommand::VariableReference(symbol!(stub, "a")),
+                    CompileCommand::Swap,
+                    CompileCommand::Assignment,
+                    CompileCommand::Discard,
+                    CompileCommand::IfStatement,
+                    CompileCommand::PopScope(scope_ref!(2)),
+                ],
+            );
+        });
+    }
+
+    #[test]
+    fn test_rest_parameter() {
+        test(script!("function f(a, ...b) {}"), |program, _stub| {
+            assert_eq!(program.functions[0].num_params, 2);
+            assert_eq!(program.functions[0].length, 1);
+            assert!(
+                program.functions[0]
+                    .commands
+                    .contains(&CompileCommand::RestParameter(1))
+            );
+        });
+    }
+
+    #[test]
+    fn test_arguments_object() {
+        test(
+            script!("function f(a) { arguments } function g(a = 0) { arguments } function h() {}"),
+            |program, _stub| {
+                assert_eq!(program.functions[0].num_locals, 1);
+                assert!(program.functions[0].has_mapped_arguments());
+                assert_eq!(program.functions[1].num_locals, 1);
+                assert!(!program.functions[1].has_mapped_arguments());
+                assert_eq!(program.functions[2].num_locals, 0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_arguments_object_in_arrow_function() {
+        test(
+            script!("function f() { return () => arguments }"),
+            |program, _stub| {
+                // The arrow function captures the `arguments` object of the enclosing function.
+                assert_eq!(program.functions[0].num_locals, 0);
+                assert_eq!(program.functions[0].num_captures, 1);
+                assert_eq!(program.functions[1].num_locals, 1);
+            },
+        );
+    }
+
+    #[test]
+    fn test_use_strict() {
+        test(
+            script!(
+                "function f(a) { 'use strict'; arguments; return () => this } function g() { this }"
+            ),
+            |program, _stub| {
+                // The arrow function defined in strict mode code is also strict mode code.
+                assert!(program.functions[0].is_strict());
+                assert!(program.functions[1].is_strict());
+                assert!(!program.functions[1].has_mapped_arguments());
+                assert_matches!(program.functions[1].this_binding, ThisBinding::ThisArgument);
+                assert!(!program.functions[2].is_strict());
+                assert_matches!(program.functions[2].this_binding, ThisBinding::Quirk);
+                assert!(!program.functions[3].is_strict());
+            },
+        );
+    }
+
+    #[test]
+    fn test_use_strict_in_script() {
+        test(
+            script!("'use strict'; function f() {}"),
+            |program, _stub| {
+                assert!(program.functions[0].is_strict());
+                assert!(program.functions[1].is_strict());
+            },
+        );
+    }
+
     fn test(src: Source, validate: fn(&Program, &Stub)) {
         let mut stub = Stub::default();
         let mut parser = match src {
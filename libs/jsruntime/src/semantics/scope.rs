@@ -5,11 +5,17 @@
 use bitflags::bitflags;
 use itertools::Itertools;
 use jsparser::SymbolRegistry;
+use rustc_hash::FxHashSet;
 
 use super::Locator;
 use super::Reference;
 use super::Symbol;
 
+use crate::code_cache::CodeCacheError;
+use crate::code_cache::Decode;
+use crate::code_cache::Encode;
+use crate::code_cache::Reader;
+use crate::code_cache::Writer;
 use crate::logger;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -101,6 +107,27 @@ impl ScopeTree {
             .map(move |(index, variable)| (VariableRef::new(scope_ref.0, index as u16), variable))
     }
 
+    /// Iterates over scopes from `scope_ref` to the function scope containing it.
+    pub fn iter_function_scopes(
+        &self,
+        scope_ref: ScopeRef,
+    ) -> impl Iterator<Item = (ScopeRef, &Scope)> {
+        let mut next = scope_ref;
+        std::iter::from_fn(move || {
+            if next == ScopeRef::NONE {
+                return None;
+            }
+            let scope_ref = next;
+            let scope = &self.scopes[scope_ref.index()];
+            next = if scope.is_function() {
+                ScopeRef::NONE
+            } else {
+                scope.outer
+            };
+            Some((scope_ref, scope))
+        })
+    }
+
     pub fn find_variable(&self, scope_ref: ScopeRef, symbol: Symbol) -> VariableRef {
         let mut scope_ref = scope_ref;
         loop {
@@ -128,6 +155,25 @@ impl ScopeTree {
         }
     }
 
+    /// Iterates over variables visible from `scope_ref`.
+    ///
+    /// Variables in inner scopes come first.  Variables shadowed by others and global variables
+    /// are excluded.  The order is used for passing variables to a direct eval.
+    pub fn visible_variables(
+        &self,
+        scope_ref: ScopeRef,
+    ) -> impl Iterator<Item = (VariableRef, &Variable)> {
+        collect_visible_variables(&self.scopes, scope_ref)
+            .into_iter()
+            .map(|variable_ref| {
+                let scope = &self.scopes[variable_ref.scope_index()];
+                (
+                    variable_ref,
+                    &scope.variables[variable_ref.variable_index()],
+                )
+            })
+    }
+
     pub fn compute_locator(&self, variable_ref: VariableRef) -> Locator {
         let scope = &self.scopes[variable_ref.scope_index()];
         let variable = &scope.variables[variable_ref.variable_index()];
@@ -233,6 +279,17 @@ impl ScopeTreeBuilder {
         });
     }
 
+    pub fn add_arguments(&mut self, index: u16) {
+        let scope = &mut self.scopes[self.current.index()];
+        debug_assert!(scope.is_function());
+        scope.variables.push(Variable {
+            symbol: Symbol::ARGUMENTS,
+            index,
+            kind: VariableKind::Local,
+            flags: VariableFlags::MUTABLE | VariableFlags::ARGUMENTS,
+        });
+    }
+
     pub fn add_capture(&mut self, scope_ref: ScopeRef, symbol: Symbol, index: u16) {
         let scope = &mut self.scopes[scope_ref.index()];
         debug_assert!(scope.is_function());
@@ -271,6 +328,32 @@ impl ScopeTreeBuilder {
         scope.variables[variable_ref.variable_index()].set_captured();
     }
 
+    /// Marks all formal parameters in the current scope as captured.
+    pub fn set_params_captured(&mut self) {
+        let scope = &mut self.scopes[self.current.index()];
+        debug_assert!(scope.is_function());
+        for variable in scope
+            .variables
+            .iter_mut()
+            .filter(|variable| matches!(variable.kind, VariableKind::Param))
+        {
+            variable.set_captured();
+        }
+    }
+
+    /// Returns `true` if a variable with `symbol` has been added to the current scope.
+    pub fn has_variable(&self, symbol: Symbol) -> bool {
+        self.has_variable_in(self.current, symbol)
+    }
+
+    /// Returns `true` if a variable with `symbol` has been added to `scope_ref`.
+    pub fn has_variable_in(&self, scope_ref: ScopeRef, symbol: Symbol) -> bool {
+        self.scopes[scope_ref.index()]
+            .variables
+            .iter()
+            .any(|variable| variable.symbol == symbol)
+    }
+
     #[allow(unused)]
     pub fn max_scope_depth(&self, scope_ref: ScopeRef) -> u16 {
         let scope = &self.scopes[scope_ref.index()];
@@ -306,6 +389,38 @@ impl ScopeTreeBuilder {
         }
     }
 
+    /// Makes all variables visible from `scope_ref` accessible in the function containing it.
+    ///
+    /// Variables are marked as captured and variables in outer functions are captured by every
+    /// function between them and `scope_ref`.  `next_capture_index` is called for allocating the
+    /// index of a capture in a function scope.
+    pub fn capture_visible_variables(
+        &mut self,
+        scope_ref: ScopeRef,
+        mut next_capture_index: impl FnMut(ScopeRef) -> u16,
+    ) {
+        // Variables are visited in order from the innermost scope.  So, `add_capture()` never
+        // sorts variables in a scope which have not been visited yet.
+        for variable_ref in collect_visible_variables(&self.scopes, scope_ref) {
+            let variable =
+                &self.scopes[variable_ref.scope_index()].variables[variable_ref.variable_index()];
+            let symbol = variable.symbol;
+            if !variable.is_capture() {
+                self.set_captured(variable_ref);
+            }
+            let mut next = scope_ref;
+            while next.index() != variable_ref.scope_index() {
+                let scope = &self.scopes[next.index()];
+                let outer = scope.outer;
+                if scope.is_function() {
+                    let index = next_capture_index(next);
+                    self.add_capture(next, symbol, index);
+                }
+                next = outer;
+            }
+        }
+    }
+
     pub fn build(&mut self) -> ScopeTree {
         ScopeTree {
             scopes: std::mem::take(&mut self.scopes),
@@ -313,6 +428,26 @@ impl ScopeTreeBuilder {
     }
 }
 
+// Collects variables visible from `scope_ref` in order from the innermost scope.
+fn collect_visible_variables(scopes: &[Scope], scope_ref: ScopeRef) -> Vec<VariableRef> {
+    let mut symbols = FxHashSet::default();
+    let mut variable_refs = vec![];
+    let mut scope_ref = scope_ref;
+    while scope_ref != ScopeRef::NONE {
+        let scope = &scopes[scope_ref.index()];
+        for (index, variable) in scope.variables.iter().enumerate() {
+            if matches!(variable.kind, VariableKind::Global) {
+                continue;
+            }
+            if symbols.insert(variable.symbol) {
+                variable_refs.push(VariableRef::new(scope_ref.0, index as u16));
+            }
+        }
+        scope_ref = scope.outer;
+    }
+    variable_refs
+}
+
 impl Default for ScopeTreeBuilder {
     fn default() -> Self {
         Self {
@@ -453,6 +588,11 @@ impl Variable {
         self.flags.contains(VariableFlags::FUNCTION_SCOPED)
     }
 
+    /// Returns `true` if the variable holds the `arguments` object of the function.
+    pub fn is_arguments(&self) -> bool {
+        self.flags.contains(VariableFlags::ARGUMENTS)
+    }
+
     fn set_captured(&mut self) {
         self.flags.insert(VariableFlags::CAPTURED)
     }
@@ -490,6 +630,10 @@ impl<'a> std::fmt::Display for VariableDisplay<'a> {
             debug_assert!(matches!(variable.kind, VariableKind::Local));
             write!(f, "^")?;
         }
+        if variable.is_arguments() {
+            debug_assert!(matches!(variable.kind, VariableKind::Local));
+            write!(f, "&")?;
+        }
         match variable.kind {
             VariableKind::Param => write!(f, "P@{}", variable.index)?,
             VariableKind::Local => write!(f, "L@{}", variable.index)?,
@@ -525,5 +669,91 @@ bitflags! {
         const MUTABLE         = 1 << 0;
         const CAPTURED        = 1 << 1;
         const FUNCTION_SCOPED = 1 << 2;
+        const ARGUMENTS       = 1 << 3;
+    }
+}
+
+// code cache
+
+impl Encode for ScopeTree {
+    fn encode(&self, writer: &mut Writer) {
+        self.scopes.encode(writer);
+    }
+}
+
+impl Decode for ScopeTree {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(Self {
+            scopes: Decode::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for Scope {
+    fn encode(&self, writer: &mut Writer) {
+        self.variables.encode(writer);
+        self.function_declarations.encode(writer);
+        self.outer.encode(writer);
+        self.depth.encode(writer);
+        self.max_child_block_depth.encode(writer);
+        let kind: u8 = match self.kind {
+            ScopeKind::Block => 0,
+            ScopeKind::Function => 1,
+        };
+        kind.encode(writer);
+    }
+}
+
+impl Decode for Scope {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        let mut variables: Vec<Variable> = Decode::decode(reader)?;
+        // Symbols are remapped when they are loaded.  The variables must be sorted again for
+        // the binary search in `ScopeTree::find_variable()`.
+        variables.sort_unstable_by_key(|variable| variable.symbol);
+        Ok(Self {
+            variables,
+            function_declarations: Decode::decode(reader)?,
+            outer: Decode::decode(reader)?,
+            depth: Decode::decode(reader)?,
+            max_child_block_depth: Decode::decode(reader)?,
+            kind: match u8::decode(reader)? {
+                0 => ScopeKind::Block,
+                1 => ScopeKind::Function,
+                _ => return Err(CodeCacheError::InvalidFormat),
+            },
+        })
+    }
+}
+
+impl Encode for Variable {
+    fn encode(&self, writer: &mut Writer) {
+        self.symbol.encode(writer);
+        self.index.encode(writer);
+        let kind: u8 = match self.kind {
+            VariableKind::Param => 0,
+            VariableKind::Local => 1,
+            VariableKind::Capture => 2,
+            VariableKind::Global => 3,
+        };
+        kind.encode(writer);
+        self.flags.bits().encode(writer);
+    }
+}
+
+impl Decode for Variable {
+    fn decode(reader: &mut Reader) -> Result<Self, CodeCacheError> {
+        Ok(Self {
+            symbol: Decode::decode(reader)?,
+            index: Decode::decode(reader)?,
+            kind: match u8::decode(reader)? {
+                0 => VariableKind::Param,
+                1 => VariableKind::Local,
+                2 => VariableKind::Capture,
+                3 => VariableKind::Global,
+                _ => return Err(CodeCacheError::InvalidFormat),
+            },
+            flags: VariableFlags::from_bits(Decode::decode(reader)?)
+                .ok_or(CodeCacheError::InvalidFormat)?,
+        })
     }
 }
@@ -0,0 +1,328 @@
+use serde::de;
+use serde::de::Deserializer as _;
+use serde::de::IntoDeserializer;
+use serde::de::Visitor;
+use serde::forward_to_deserialize_any;
+
+use jsparser::Symbol;
+
+use crate::Runtime;
+use crate::types::PropertyKey;
+use crate::types::Value;
+
+use super::SerdeError;
+
+/// A `serde::Deserializer` reading a JavaScript value on the heap of a runtime.
+///
+/// See `Runtime::deserialize()` for the data model.
+pub struct Deserializer<'a, X> {
+    runtime: &'a mut Runtime<X>,
+    value: Value,
+}
+
+impl<'a, X> Deserializer<'a, X> {
+    pub fn new(runtime: &'a mut Runtime<X>, value: &Value) -> Self {
+        Self {
+            runtime,
+            value: value.clone(),
+        }
+    }
+
+    // Returns the length of an array-like object.
+    fn array_length(&self) -> Option<u32> {
+        let object = match self.value {
+            Value::Object(object) if !object.is_callable() => object,
+            _ => return None,
+        };
+        match object.get_value(&Symbol::LENGTH.into()) {
+            Some(Value::Number(len)) => Some(*len as u32),
+            _ => None,
+        }
+    }
+
+    fn elements(&mut self, len: u32) -> Result<Vec<Value>, SerdeError> {
+        (0..len)
+            .map(|i| {
+                self.runtime
+                    .get_by_key(&self.value, &PropertyKey::from(i as f64))
+                    .map_err(SerdeError::Exception)
+            })
+            .collect()
+    }
+
+    fn entries(&mut self) -> Result<Vec<(std::string::String, Value)>, SerdeError> {
+        let Value::Object(object) = self.value else {
+            return Err(unexpected(&self.value, "an object"));
+        };
+        let keys: Vec<PropertyKey> = object
+            .iter_own_properties()
+            .filter(|(_, prop)| prop.is_enumerable())
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.into_iter()
+            .map(|key| {
+                let name = self.runtime.property_key_to_string(&key);
+                let value = self
+                    .runtime
+                    .get_by_key(&self.value, &key)
+                    .map_err(SerdeError::Exception)?;
+                Ok((name, value))
+            })
+            .collect()
+    }
+}
+
+impl<'de, X> de::Deserializer<'de> for Deserializer<'_, X> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.value {
+            Value::None | Value::Undefined | Value::Null => visitor.visit_unit(),
+            Value::Boolean(value) => visitor.visit_bool(value),
+            Value::Number(value) => visit_number(value, visitor),
+            Value::String(value) => {
+                visitor.visit_string(std::string::String::from_utf16_lossy(value.as_slice()))
+            }
+            Value::Object(object) if object.is_callable() => {
+                Err(unexpected(&self.value, "a non-function value"))
+            }
+            Value::Object(_) => match self.array_length() {
+                Some(len) => {
+                    let elements = self.elements(len)?;
+                    visitor.visit_seq(SeqAccess {
+                        runtime: self.runtime,
+                        elements: elements.into_iter(),
+                    })
+                }
+                None => {
+                    let entries = self.entries()?;
+                    visitor.visit_map(MapAccess {
+                        runtime: self.runtime,
+                        entries: entries.into_iter(),
+                        value: None,
+                    })
+                }
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.value {
+            Value::None | Value::Undefined | Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Enum variants are represented in the externally tagged representation.
+    fn deserialize_enum<V: Visitor<'de>>(
+        mut self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.value {
+            Value::String(value) => {
+                let variant = std::string::String::from_utf16_lossy(value.as_slice());
+                visitor.visit_enum(variant.into_deserializer())
+            }
+            Value::Object(_) => {
+                let mut entries = self.entries()?;
+                if entries.len() != 1 {
+                    return Err(unexpected(&self.value, "an object with a single property"));
+                }
+                let (variant, value) = entries.pop().unwrap();
+                visitor.visit_enum(EnumAccess {
+                    runtime: self.runtime,
+                    variant,
+                    value,
+                })
+            }
+            _ => Err(unexpected(&self.value, "a string or an object")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+struct SeqAccess<'a, X> {
+    runtime: &'a mut Runtime<X>,
+    elements: std::vec::IntoIter<Value>,
+}
+
+impl<'de, X> de::SeqAccess<'de> for SeqAccess<'_, X> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        match self.elements.next() {
+            Some(value) => seed
+                .deserialize(Deserializer::new(self.runtime, &value))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+struct MapAccess<'a, X> {
+    runtime: &'a mut Runtime<X>,
+    entries: std::vec::IntoIter<(std::string::String, Value)>,
+    value: Option<Value>,
+}
+
+impl<'de, X> de::MapAccess<'de> for MapAccess<'_, X> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        let value = self
+            .value
+            .take()
+            .expect("next_key_seed() must be called before");
+        seed.deserialize(Deserializer::new(self.runtime, &value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumAccess<'a, X> {
+    runtime: &'a mut Runtime<X>,
+    variant: std::string::String,
+    value: Value,
+}
+
+impl<'a, 'de, X> de::EnumAccess<'de> for EnumAccess<'a, X> {
+    type Error = SerdeError;
+    type Variant = Deserializer<'a, X>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), SerdeError> {
+        let variant = seed.deserialize(KeyDeserializer(self.variant))?;
+        Ok((variant, Deserializer::new(self.runtime, &self.value)))
+    }
+}
+
+impl<'de, X> de::VariantAccess<'de> for Deserializer<'_, X> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+// Property keys are strings but they can be deserialized into integers.
+struct KeyDeserializer(std::string::String);
+
+macro_rules! deserialize_integer_key {
+    ($($method:ident => $visit:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_integer_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+// Integral numbers are visited as integers so that they can be deserialized into integer types.
+fn visit_number<'de, V: Visitor<'de>>(value: f64, visitor: V) -> Result<V::Value, SerdeError> {
+    const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+    if value.fract() == 0.0 && value.abs() <= MAX_SAFE_INTEGER {
+        if value < 0.0 {
+            visitor.visit_i64(value as i64)
+        } else {
+            visitor.visit_u64(value as u64)
+        }
+    } else {
+        visitor.visit_f64(value)
+    }
+}
+
+fn unexpected(value: &Value, expected: &str) -> SerdeError {
+    SerdeError::Message(format!("invalid value: {value}, expected {expected}"))
+}
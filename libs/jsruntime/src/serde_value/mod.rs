@@ -0,0 +1,151 @@
+mod de;
+mod ser;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::FromJsValue;
+use crate::IntoJsValue;
+use crate::Runtime;
+use crate::types::Value;
+
+pub use de::Deserializer;
+pub use ser::Serializer;
+
+/// An error that occurred in a conversion between a JavaScript value and a Rust value by
+/// `Serializer` or `Deserializer`.
+#[derive(Debug, thiserror::Error)]
+pub enum SerdeError {
+    /// An error reported by a `Serialize` or `Deserialize` implementation.
+    #[error("{0}")]
+    Message(std::string::String),
+
+    /// An exception thrown while accessing a property of an object.
+    #[error("uncaught exception: {0}")]
+    Exception(Value),
+}
+
+impl serde::ser::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
+
+impl serde::de::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
+
+/// A wrapper to pass a value of `T` from and to JavaScript using `Serialize` and `Deserialize`.
+///
+/// This can be used as a parameter type and the return type of a host function.
+///
+/// ```ignore
+/// runtime.register_function("configure", |Serde(config): Serde<Config>| { ... });
+/// ```
+pub struct Serde<T>(pub T);
+
+impl<T: DeserializeOwned> FromJsValue for Serde<T> {
+    fn from_js_value<X>(runtime: &mut Runtime<X>, value: &Value) -> Result<Self, Value> {
+        runtime.deserialize(value).map(Serde)
+    }
+}
+
+impl<T: Serialize> IntoJsValue for Serde<T> {
+    fn into_js_value<X>(self, runtime: &mut Runtime<X>) -> Result<Value, Value> {
+        runtime.serialize(&self.0)
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Converts a Rust value into a JavaScript value.
+    ///
+    /// Structs and maps are converted into objects, sequences and tuples into arrays.  Enum
+    /// variants are converted in the externally tagged representation like `serde_json`.  An
+    /// error is thrown as a `TypeError`.
+    pub fn serialize<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<Value, Value> {
+        let result = value.serialize(Serializer::new(self));
+        self.throw_serde_error(result)
+    }
+
+    /// Converts a JavaScript value into a Rust value.
+    ///
+    /// Only own enumerable properties of objects are taken into account.  An object having a
+    /// numeric `length` property is treated as an array.  An error is thrown as a `TypeError`
+    /// unless it's an exception thrown from a getter.
+    pub fn deserialize<T: DeserializeOwned>(&mut self, value: &Value) -> Result<T, Value> {
+        let result = T::deserialize(Deserializer::new(self, value));
+        self.throw_serde_error(result)
+    }
+
+    fn throw_serde_error<T>(&mut self, result: Result<T, SerdeError>) -> Result<T, Value> {
+        result.map_err(|err| match err {
+            SerdeError::Message(message) => {
+                let message = self.create_string_from_utf8(&message);
+                Value::Object(self.create_type_error(Some(message)))
+            }
+            SerdeError::Exception(value) => value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::BasicRuntime;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Config {
+        name: std::string::String,
+        size: (u32, u32),
+        tags: Vec<std::string::String>,
+        limit: Option<f64>,
+        mode: Mode,
+        extra: BTreeMap<std::string::String, bool>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Mode {
+        Fast,
+        Custom { level: u8 },
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut runtime = BasicRuntime::new();
+        let config = Config {
+            name: "bee".to_string(),
+            size: (640, 480),
+            tags: vec!["a".to_string(), "b".to_string()],
+            limit: None,
+            mode: Mode::Custom { level: 3 },
+            extra: BTreeMap::from([("x".to_string(), true)]),
+        };
+        let value = runtime.serialize(&config).unwrap();
+        let size = runtime.get(&value, "size").unwrap().get();
+        assert_eq!(runtime.get_index(&size, 1).unwrap().get(), Value::from(480));
+        assert_eq!(runtime.get(&value, "limit").unwrap().get(), Value::Null);
+        let result: Config = runtime.deserialize(&value).unwrap();
+        assert_eq!(result, config);
+
+        let value = runtime.serialize(&Mode::Fast).unwrap();
+        assert_eq!(runtime.to_rust_string(&value).unwrap(), "Fast");
+        assert_eq!(runtime.deserialize(&value), Ok(Mode::Fast));
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        let mut runtime = BasicRuntime::new();
+        let value = runtime.new_string("abc").get();
+        assert!(runtime.deserialize::<u32>(&value).is_err());
+        assert!(runtime.deserialize::<u8>(&Value::from(256)).is_err());
+        assert!(runtime.deserialize::<u8>(&Value::from(1.5)).is_err());
+        let value = runtime.new_object().get();
+        assert!(runtime.deserialize::<Config>(&value).is_err());
+    }
+}
@@ -0,0 +1,532 @@
+use serde::Serialize;
+use serde::ser;
+use serde::ser::Serializer as _;
+
+use crate::Runtime;
+use crate::types::PropertyKey;
+use crate::types::Value;
+
+use super::SerdeError;
+
+/// A `serde::Serializer` creating a JavaScript value on the heap of a runtime.
+///
+/// See `Runtime::serialize()` for the data model.
+pub struct Serializer<'a, X> {
+    runtime: &'a mut Runtime<X>,
+}
+
+impl<'a, X> Serializer<'a, X> {
+    pub fn new(runtime: &'a mut Runtime<X>) -> Self {
+        Self { runtime }
+    }
+
+    // Wraps a value in an object having a single property named `variant`.
+    fn wrap(self, variant: &str, value: Value) -> Result<Value, SerdeError> {
+        let object = Value::Object(self.runtime.create_ordinary_object());
+        set_property(self.runtime, &object, variant, &value)?;
+        Ok(object)
+    }
+}
+
+impl<'a, X> ser::Serializer for Serializer<'a, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    type SerializeSeq = SerializeArray<'a, X>;
+    type SerializeTuple = SerializeArray<'a, X>;
+    type SerializeTupleStruct = SerializeArray<'a, X>;
+    type SerializeTupleVariant = SerializeArray<'a, X>;
+    type SerializeMap = SerializeObject<'a, X>;
+    type SerializeStruct = SerializeObject<'a, X>;
+    type SerializeStructVariant = SerializeObject<'a, X>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, SerdeError> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    // TODO: BigInt
+    fn serialize_i64(self, v: i64) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    // TODO: BigInt
+    fn serialize_u64(self, v: u64) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, SerdeError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, SerdeError> {
+        Ok(Value::Number(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, SerdeError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, SerdeError> {
+        Ok(Value::String(self.runtime.create_string_from_utf8(v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, SerdeError> {
+        let elements: Vec<Value> = v.iter().map(|byte| Value::from(*byte as f64)).collect();
+        Ok(Value::Object(self.runtime.create_array(&elements)))
+    }
+
+    fn serialize_none(self) -> Result<Value, SerdeError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, SerdeError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, SerdeError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, SerdeError> {
+        let value = value.serialize(Serializer::new(self.runtime))?;
+        self.wrap(variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Ok(SerializeArray {
+            runtime: self.runtime,
+            elements: Vec::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Ok(SerializeArray {
+            runtime: self.runtime,
+            elements: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        let object = Value::Object(self.runtime.create_ordinary_object());
+        Ok(SerializeObject {
+            runtime: self.runtime,
+            object,
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        let object = Value::Object(self.runtime.create_ordinary_object());
+        Ok(SerializeObject {
+            runtime: self.runtime,
+            object,
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+pub struct SerializeArray<'a, X> {
+    runtime: &'a mut Runtime<X>,
+    elements: Vec<Value>,
+    variant: Option<&'static str>,
+}
+
+impl<X> SerializeArray<'_, X> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let value = value.serialize(Serializer::new(self.runtime))?;
+        self.elements.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, SerdeError> {
+        let array = Value::Object(self.runtime.create_array(&self.elements));
+        match self.variant {
+            Some(variant) => Serializer::new(self.runtime).wrap(variant, array),
+            None => Ok(array),
+        }
+    }
+}
+
+impl<X> ser::SerializeSeq for SerializeArray<'_, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<X> ser::SerializeTuple for SerializeArray<'_, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<X> ser::SerializeTupleStruct for SerializeArray<'_, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<X> ser::SerializeTupleVariant for SerializeArray<'_, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+pub struct SerializeObject<'a, X> {
+    runtime: &'a mut Runtime<X>,
+    object: Value,
+    key: Option<PropertyKey>,
+    variant: Option<&'static str>,
+}
+
+impl<X> SerializeObject<'_, X> {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), SerdeError> {
+        let value = value.serialize(Serializer::new(self.runtime))?;
+        set_property(self.runtime, &self.object, key, &value)
+    }
+
+    fn finish(self) -> Result<Value, SerdeError> {
+        match self.variant {
+            Some(variant) => Serializer::new(self.runtime).wrap(variant, self.object),
+            None => Ok(self.object),
+        }
+    }
+}
+
+impl<X> ser::SerializeMap for SerializeObject<'_, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.key = Some(key.serialize(KeySerializer {
+            runtime: self.runtime,
+        })?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_key() must be called before");
+        let value = value.serialize(Serializer::new(self.runtime))?;
+        self.runtime
+            .set_by_key(&self.object, &key, &value)
+            .map_err(SerdeError::Exception)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<X> ser::SerializeStruct for SerializeObject<'_, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl<X> ser::SerializeStructVariant for SerializeObject<'_, X> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+fn set_property<X>(
+    runtime: &mut Runtime<X>,
+    object: &Value,
+    key: &str,
+    value: &Value,
+) -> Result<(), SerdeError> {
+    let key = runtime.symbol_registry.intern_str(key).into();
+    runtime
+        .set_by_key(object, &key, value)
+        .map_err(SerdeError::Exception)
+}
+
+// Map keys must be strings or integers.
+struct KeySerializer<'a, X> {
+    runtime: &'a mut Runtime<X>,
+}
+
+macro_rules! serialize_integer_key {
+    ($($method:ident: $ty:ty),*) => {
+        $(
+            #[allow(clippy::unnecessary_cast)]
+            fn $method(self, v: $ty) -> Result<PropertyKey, SerdeError> {
+                Ok(PropertyKey::from(v as f64))
+            }
+        )*
+    };
+}
+
+macro_rules! unsupported_key {
+    ($($method:ident($($ty:ty),*)),*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<PropertyKey, SerdeError> {
+                Err(key_must_be_string())
+            }
+        )*
+    };
+}
+
+impl<X> ser::Serializer for KeySerializer<'_, X> {
+    type Ok = PropertyKey;
+    type Error = SerdeError;
+
+    type SerializeSeq = ser::Impossible<PropertyKey, SerdeError>;
+    type SerializeTuple = ser::Impossible<PropertyKey, SerdeError>;
+    type SerializeTupleStruct = ser::Impossible<PropertyKey, SerdeError>;
+    type SerializeTupleVariant = ser::Impossible<PropertyKey, SerdeError>;
+    type SerializeMap = ser::Impossible<PropertyKey, SerdeError>;
+    type SerializeStruct = ser::Impossible<PropertyKey, SerdeError>;
+    type SerializeStructVariant = ser::Impossible<PropertyKey, SerdeError>;
+
+    serialize_integer_key! {
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64
+    }
+
+    unsupported_key! {
+        serialize_bool(bool),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str)
+    }
+
+    fn serialize_char(self, v: char) -> Result<PropertyKey, SerdeError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<PropertyKey, SerdeError> {
+        Ok(self.runtime.symbol_registry.intern_str(v).into())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<PropertyKey, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<PropertyKey, SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<PropertyKey, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<PropertyKey, SerdeError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(key_must_be_string())
+    }
+}
+
+fn key_must_be_string() -> SerdeError {
+    SerdeError::Message("key must be a string or an integer".to_string())
+}
@@ -0,0 +1,99 @@
+use crate::Runtime;
+use crate::logger;
+
+/// The state of the native stack polled in generated code.
+pub(crate) struct StackGuard {
+    /// The lowest address of the native stack available for JavaScript code.
+    ///
+    /// Zero while no JavaScript code is running.  The address of the value is embedded in
+    /// generated code.  `Box` provides a stable address.
+    limit: Box<usize>,
+}
+
+impl StackGuard {
+    pub(crate) fn new() -> Self {
+        Self { limit: Box::new(0) }
+    }
+
+    pub(crate) fn limit_addr(&self) -> usize {
+        &*self.limit as *const usize as usize
+    }
+}
+
+/// Returns an approximate value of the current stack pointer.
+#[inline(always)]
+fn stack_pointer() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+impl<X> Runtime<X> {
+    /// Sets the maximum call stack depth.
+    ///
+    /// A `RangeError` is thrown when the depth of nested function calls exceeds the limit.  This
+    /// affects only programs compiled after this call.
+    pub fn set_max_call_stack_depth(&mut self, depth: u16) {
+        self.pref.max_call_stack_depth = depth;
+    }
+
+    /// Sets the maximum size in bytes of the native stack used for evaluating JavaScript code.
+    ///
+    /// A `RangeError` is thrown when the native stack grows beyond the size.  The size must be
+    /// sufficiently smaller than the stack size of the current thread so that Rust code called
+    /// from JavaScript code has room on the stack.  The default size is 1 MiB.
+    pub fn set_stack_size(&mut self, size: usize) {
+        self.pref.stack_size = size;
+    }
+
+    pub(crate) fn stack_limit_addr(&self) -> usize {
+        self.stack_guard.limit_addr()
+    }
+
+    /// Called before entering JavaScript code from Rust code.
+    ///
+    /// Returns `true` if this is the outermost entry.  In this case, `leave_stack()` must be
+    /// called after leaving JavaScript code.
+    pub(crate) fn enter_stack(&mut self) -> bool {
+        if *self.stack_guard.limit != 0 {
+            return false;
+        }
+        let limit = stack_pointer().saturating_sub(self.pref.stack_size).max(1);
+        logger::debug!(event = "enter_stack", limit);
+        *self.stack_guard.limit = limit;
+        true
+    }
+
+    pub(crate) fn leave_stack(&mut self, outermost: bool) {
+        if outermost {
+            *self.stack_guard.limit = 0;
+        }
+    }
+
+    /// Returns `true` if JavaScript code is running.
+    pub(crate) fn is_running(&self) -> bool {
+        *self.stack_guard.limit != 0
+    }
+
+    /// Returns `true` if the native stack is about to overflow.
+    pub(crate) fn is_stack_overflow(&self) -> bool {
+        stack_pointer() < *self.stack_guard.limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BasicRuntime;
+
+    #[test]
+    fn test_enter_stack() {
+        let mut runtime = BasicRuntime::new();
+        assert!(!runtime.is_stack_overflow());
+        assert!(runtime.enter_stack());
+        assert!(!runtime.enter_stack());
+        assert!(!runtime.is_stack_overflow());
+        runtime.leave_stack(false);
+        assert_ne!(*runtime.stack_guard.limit, 0);
+        runtime.leave_stack(true);
+        assert_eq!(*runtime.stack_guard.limit, 0);
+    }
+}
@@ -0,0 +1,243 @@
+use jsgc::HandleMut;
+use jsparser::Symbol;
+
+use crate::Error;
+use crate::ProgramId;
+use crate::Runtime;
+use crate::lambda::LambdaId;
+use crate::lambda::LambdaKind;
+use crate::semantics::SourceOffset;
+use crate::types::CallContext;
+use crate::types::Object;
+use crate::types::Property;
+use crate::types::Value;
+
+/// The maximum number of frames in the `stack` property of an error object.
+const STACK_TRACE_LIMIT: usize = 10;
+
+/// A frame in a stack trace.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StackFrame {
+    /// The name of the function, or `None` for top-level code and anonymous functions.
+    pub function: Option<std::string::String>,
+
+    /// The name of the program set by `Runtime::set_program_name()`.
+    pub script: Option<std::string::String>,
+
+    /// The 1-based line number of the call site, or 0 if unknown.
+    pub line: u32,
+
+    /// The 1-based column number of the call site in UTF-16 code units, or 0 if unknown.
+    pub column: u32,
+
+    /// `true` if the function was called as a constructor.
+    pub constructor: bool,
+}
+
+impl std::fmt::Display for StackFrame {
+    // Formats the frame in the same way as V8.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let script = self.script.as_deref().unwrap_or("<anonymous>");
+        let location = if self.line > 0 {
+            format!("{script}:{}:{}", self.line, self.column)
+        } else {
+            script.to_owned()
+        };
+        match (self.function.as_deref(), self.constructor) {
+            (Some(function), true) => write!(f, "new {function} ({location})"),
+            (Some(function), false) => write!(f, "{function} ({location})"),
+            (None, _) => write!(f, "{location}"),
+        }
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Sets the name of a program shown in stack traces such as a file name.
+    pub fn set_program_name(&mut self, program_id: ProgramId, name: &str) {
+        self.programs[program_id.index()].name = Some(name.to_owned());
+    }
+
+    /// Captures the current JavaScript stack trace.
+    ///
+    /// The innermost frame comes first.  This method is intended to be called from a host
+    /// function registered by `register_host_function()`.  An empty list is returned if no
+    /// JavaScript code is running.
+    pub fn capture_stack_trace(&self) -> Vec<StackFrame> {
+        // SAFETY: `host_call_context` is null or a valid pointer to the call context of the host
+        // function being called.
+        match unsafe { self.host_call_context.as_ref() } {
+            Some(context) => self.collect_stack_frames(context, usize::MAX),
+            None => vec![],
+        }
+    }
+
+    /// Called before calling a host function.
+    ///
+    /// Returns the previous value which must be passed to `leave_host_function()`.
+    pub(crate) fn enter_host_function(&mut self, context: &CallContext) -> *const CallContext {
+        std::mem::replace(&mut self.host_call_context, context)
+    }
+
+    pub(crate) fn leave_host_function(&mut self, saved: *const CallContext) {
+        self.host_call_context = saved;
+    }
+
+    /// Defines the `stack` property on an error object created in a built-in function.
+    pub(crate) fn set_error_stack(&mut self, error: HandleMut<Object>, context: &CallContext) {
+        let frames = self.collect_stack_frames(context, STACK_TRACE_LIMIT);
+        self.define_error_stack(error, &frames);
+    }
+
+    /// Defines the `stack` property on an error object created in a lambda function.
+    ///
+    /// `context` is the call context of the lambda function and `offset` is the location where
+    /// the error is created.
+    pub(crate) fn set_error_stack_at(
+        &mut self,
+        error: HandleMut<Object>,
+        context: &CallContext,
+        lambda_id: LambdaId,
+        offset: SourceOffset,
+    ) {
+        let mut frames = vec![self.make_stack_frame(lambda_id, offset, context.is_new())];
+        frames.extend(self.collect_stack_frames(context, STACK_TRACE_LIMIT - 1));
+        self.define_error_stack(error, &frames);
+    }
+
+    fn define_error_stack(&mut self, mut error: HandleMut<Object>, frames: &[StackFrame]) {
+        let mut stack = match self.error_to_string(error) {
+            Ok(header) => header.code_units().collect::<Vec<_>>(),
+            Err(_) => "Error".encode_utf16().collect(),
+        };
+        for frame in frames.iter() {
+            stack.extend(format!("\n    at {frame}").encode_utf16());
+        }
+        let stack = self.create_string(&stack);
+        // TODO: error handling
+        let _ = error.define_own_property(
+            Symbol::STACK.into(),
+            Property::data_wxc(Value::String(stack)),
+        );
+    }
+
+    /// Creates an exception for an error returned from a built-in function.
+    pub(crate) fn create_exception_with_stack(
+        &mut self,
+        err: Error,
+        context: &CallContext,
+    ) -> Value {
+        let exception = self.create_exception(err);
+        if let Value::Object(error) = exception {
+            self.set_error_stack(error, context);
+        }
+        exception
+    }
+
+    pub(crate) fn collect_stack_frames(
+        &self,
+        context: &CallContext,
+        limit: usize,
+    ) -> Vec<StackFrame> {
+        let mut frames = vec![];
+        let mut context = Some(context);
+        while let Some(callee) = context {
+            if frames.len() >= limit {
+                break;
+            }
+            // The call site is recorded in the call context of the callee.
+            let caller = callee.caller();
+            if let Some((lambda_id, offset)) = callee.call_site() {
+                let constructor = caller.is_some_and(CallContext::is_new);
+                frames.push(self.make_stack_frame(lambda_id, offset, constructor));
+            }
+            context = caller;
+        }
+        frames
+    }
+
+    pub(crate) fn make_stack_frame(
+        &self,
+        lambda_id: LambdaId,
+        offset: SourceOffset,
+        constructor: bool,
+    ) -> StackFrame {
+        let lambda_info = self.lambda_registry.get(lambda_id);
+        let program = &self.programs[lambda_info.program_id.index()];
+        let mut function_index = lambda_info.function_index as usize;
+        if let LambdaKind::Coroutine = lambda_info.kind {
+            // The ramp function is placed just after the coroutine function.
+            function_index += 1;
+        }
+        let name = program.functions[function_index].name;
+        let function = if name == Symbol::NONE {
+            None
+        } else {
+            self.symbol_registry
+                .resolve(name)
+                .map(std::string::String::from_utf16_lossy)
+        };
+        let (line, column) = offset
+            .get()
+            .and_then(|offset| line_and_column(&program.source, offset))
+            .unwrap_or((0, 0));
+        StackFrame {
+            function,
+            script: program.name.clone(),
+            line,
+            column,
+            constructor,
+        }
+    }
+}
+
+/// Computes the 1-based line and column numbers of a byte offset in a source text.
+pub(crate) fn line_and_column(source: &str, offset: usize) -> Option<(u32, u32)> {
+    let text = source.get(..offset)?;
+    let mut line = 1;
+    let mut line_start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\r' if matches!(chars.peek(), Some((_, '\n'))) => (),
+            '\n' | '\r' | '\u{2028}' | '\u{2029}' => {
+                line += 1;
+                line_start = i + ch.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    let column = text[line_start..].encode_utf16().count() + 1;
+    Some((line, column as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_and_column() {
+        assert_eq!(line_and_column("f()", 0), Some((1, 1)));
+        assert_eq!(line_and_column("a;\nb;\r\nf()", 7), Some((3, 1)));
+        assert_eq!(line_and_column("'\u{1F600}'; f()", 8), Some((1, 6)));
+        assert_eq!(line_and_column("f()", 4), None);
+    }
+
+    #[test]
+    fn test_stack_frame_display() {
+        let mut frame = StackFrame {
+            function: Some("f".to_owned()),
+            script: Some("a.js".to_owned()),
+            line: 2,
+            column: 3,
+            constructor: false,
+        };
+        assert_eq!(frame.to_string(), "f (a.js:2:3)");
+        frame.constructor = true;
+        assert_eq!(frame.to_string(), "new f (a.js:2:3)");
+        frame.function = None;
+        assert_eq!(frame.to_string(), "a.js:2:3");
+        frame.script = None;
+        frame.line = 0;
+        assert_eq!(frame.to_string(), "<anonymous>");
+    }
+}
@@ -0,0 +1,70 @@
+use jsgc::HandleMut;
+use jsparser::Symbol;
+
+use crate::Runtime;
+use crate::logger;
+use crate::semantics::TemplateStrings;
+use crate::types::Object;
+use crate::types::Property;
+use crate::types::PropertyFlags;
+use crate::types::Value;
+
+impl<X> Runtime<X> {
+    // 13.2.8.4 GetTemplateObject ( templateLiteral )
+    //
+    // [[TemplateMap]] of each realm is keyed by the address of `strings`, which is unique to
+    // each tagged template in a program.  Programs are never freed, so the address is never
+    // reused for another tagged template.
+    pub(crate) fn get_template_object(&mut self, strings: &TemplateStrings) -> HandleMut<Object> {
+        let key = (self.realm, std::ptr::from_ref(strings) as usize);
+        if let Some(template) = self.template_map.get(&key) {
+            return *template;
+        }
+
+        logger::debug!(event = "get_template_object", ?strings);
+        let cooked_strings: Vec<Value> = strings
+            .cooked
+            .iter()
+            .map(|cooked| match cooked {
+                Some(cooked) => Value::String(self.create_string(cooked)),
+                None => Value::Undefined,
+            })
+            .collect();
+        let raw_strings: Vec<Value> = strings
+            .raw
+            .iter()
+            .map(|raw| Value::String(self.create_string(raw)))
+            .collect();
+
+        // The elements and `length` are non-writable and non-configurable.  So the objects are
+        // frozen once they become non-extensible.
+        let mut raw = self.create_template_array(&raw_strings);
+        raw.prevent_extensions();
+        let mut template = self.create_template_array(&cooked_strings);
+        // TODO: error handling
+        let _ = template
+            .define_own_property(Symbol::RAW.into(), Property::data_xxx(Value::Object(raw)));
+        template.prevent_extensions();
+
+        self.template_map.insert(key, template);
+        template
+    }
+
+    fn create_template_array(&mut self, elements: &[Value]) -> HandleMut<Object> {
+        // TODO(feat): 10.4.2.2 ArrayCreate ( length [ , proto ] )
+        let mut array = self.create_object();
+        array.set_prototype(self.builtins.object_prototype);
+        // TODO: error handling
+        for (index, element) in elements.iter().enumerate() {
+            let _ = array.define_own_property(
+                (index as f64).into(),
+                Property::data(element.clone(), PropertyFlags::ENUMERABLE),
+            );
+        }
+        let _ = array.define_own_property(
+            Symbol::LENGTH.into(),
+            Property::data_xxx(Value::from(elements.len() as u32)),
+        );
+        array
+    }
+}
@@ -0,0 +1,130 @@
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+
+use crate::Runtime;
+use crate::backend;
+use crate::lambda::LambdaId;
+use crate::logger;
+use crate::types::CallContext;
+use crate::types::Value;
+
+/// Type feedback collected by generated code for the optimizing recompilation.
+///
+/// Functions compiled with the optimization count their calls and record the kinds of values
+/// observed at operands of numeric operators and the lambda functions called at call sites.  A
+/// function is recompiled when the number of calls reaches `RuntimePref::optimization_threshold`.
+/// The recompiled code speculates that operands observed only as numbers are always numbers and
+/// inlines small lambda functions which are the only ones called at call sites.  Additions,
+/// subtractions and multiplications of operands observed only as int32 values are performed with
+/// 32-bit integers.  When the speculation fails or an int32 operation overflows, the closure
+/// falls back to the generic code and the function is never optimized again.
+pub(crate) struct TypeFeedback {
+    // The slots for each lambda function.  The first slot counts calls of the function and the
+    // others hold bit sets of the kinds of values observed at operands or call targets.  The
+    // addresses of the slots are embedded in generated code.  So, the slots are never freed.
+    slots: FxHashMap<LambdaId, Box<[u32]>>,
+
+    // Lambda functions whose optimized code has been invalidated or failed to be compiled.
+    deoptimized: FxHashSet<LambdaId>,
+}
+
+impl TypeFeedback {
+    /// The index of the slot counting calls.
+    pub(crate) const CALL_COUNT_INDEX: usize = 0;
+
+    /// The bit set of values observed only as int32 values.
+    pub(crate) const INT32_ONLY: u32 = 1 << Value::KIND_NUMBER;
+
+    /// The bit recorded together with the number kind when a number is not an int32 value.
+    ///
+    /// Fractional numbers, NaN, infinities, -0 and numbers out of the int32 range are not int32
+    /// values.
+    pub(crate) const NON_INT32: u32 = 1 << 31;
+
+    /// The value of a slot recording call targets when different lambda functions are called.
+    pub(crate) const MEGAMORPHIC: u32 = u32::MAX;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: Default::default(),
+            deoptimized: Default::default(),
+        }
+    }
+
+    /// Returns `true` if only numbers have been observed.
+    pub(crate) fn is_number_only(kinds: u32) -> bool {
+        kinds & !Self::NON_INT32 == Self::INT32_ONLY
+    }
+
+    /// Returns `true` if only int32 values have been observed.
+    pub(crate) fn is_int32_only(kinds: u32) -> bool {
+        kinds == Self::INT32_ONLY
+    }
+
+    /// Returns the address of the slots for a function.
+    ///
+    /// The slots are allocated when this method is called for the function at the first time.
+    pub(crate) fn slots_addr(&mut self, lambda_id: LambdaId, num_slots: usize) -> usize {
+        let slots = self
+            .slots
+            .entry(lambda_id)
+            .or_insert_with(|| vec![0; num_slots].into_boxed_slice());
+        debug_assert_eq!(slots.len(), num_slots);
+        slots.as_ptr() as usize
+    }
+
+    /// Returns the slots for a function.
+    pub(crate) fn slots(&self, lambda_id: LambdaId) -> Option<&[u32]> {
+        self.slots.get(&lambda_id).map(|slots| &slots[..])
+    }
+}
+
+impl<X> Runtime<X> {
+    /// Called from generated code when a function is called `optimization_threshold` times.
+    ///
+    /// Recompiles the function with the type feedback and replaces the lambda function of the
+    /// closure being called.  The current call continues in the generic code.
+    pub(crate) fn optimize_lambda(&mut self, context: &CallContext, lambda_id: LambdaId) {
+        if self.type_feedback.deoptimized.contains(&lambda_id) {
+            return;
+        }
+        let lambda = match self.code_registry.get_optimized_lambda(lambda_id) {
+            Some(lambda) => lambda,
+            None => {
+                logger::debug!(event = "optimize_lambda", ?lambda_id);
+                let lambda_info = self.lambda_registry.get(lambda_id);
+                let program_id = lambda_info.program_id;
+                let function_index = lambda_info.function_index as usize;
+                // The function keeps running the generic code if the optimization fails.
+                if backend::optimize_function(self, program_id, function_index)
+                    .inspect_err(|err| logger::error!(event = "optimize_lambda", ?lambda_id, ?err))
+                    .is_err()
+                {
+                    self.type_feedback.deoptimized.insert(lambda_id);
+                    return;
+                }
+                self.code_registry.get_optimized_lambda(lambda_id).unwrap()
+            }
+        };
+        context.closure().lambda = lambda.into();
+    }
+
+    /// Called from optimized code when a speculation fails.
+    ///
+    /// Invalidates the optimized code and replaces the lambda function of the closure being
+    /// called with the generic code.  Other closures still running the optimized code fall back
+    /// when their speculations fail.
+    ///
+    /// The current call continues in the optimized code.  The optimized code performs the
+    /// operation whose speculation failed in the generic way and subsequent speculations in the
+    /// call are still checked.  So, the result of the call is the same as the generic code.
+    pub(crate) fn deoptimize_lambda(&mut self, context: &CallContext, lambda_id: LambdaId) {
+        logger::debug!(event = "deoptimize_lambda", ?lambda_id);
+        if self.type_feedback.deoptimized.insert(lambda_id) {
+            self.code_registry.remove_optimized_lambda(lambda_id);
+        }
+        if let Some(lambda) = self.code_registry.get_lambda(lambda_id) {
+            context.closure().lambda = lambda.into();
+        }
+    }
+}
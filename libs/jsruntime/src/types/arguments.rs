@@ -0,0 +1,42 @@
+use jsgc::HandleMut;
+
+use crate::types::Capture;
+
+/// A data type to represent the `[[ParameterMap]]` of a mapped arguments object.
+///
+/// The element at an index holds the capture of the formal parameter mapped to the property of
+/// the arguments object with the same index.  `None` if the property is not mapped.
+#[derive(jsgc_derive::Trace)]
+pub struct ParameterMap {
+    captures: Vec<Option<HandleMut<Capture>>>,
+}
+
+impl ParameterMap {
+    /// Creates a parameter map for `len` arguments without any mapped property.
+    pub fn new(len: usize) -> Self {
+        Self {
+            captures: vec![None; len],
+        }
+    }
+
+    /// Maps the property at `index` to a formal parameter.
+    ///
+    /// Does nothing if no argument is passed at `index`.
+    pub fn map(&mut self, index: usize, capture: HandleMut<Capture>) {
+        if let Some(slot) = self.captures.get_mut(index) {
+            *slot = Some(capture);
+        }
+    }
+
+    /// Removes the mapping of the property at `index`.
+    pub fn unmap(&mut self, index: usize) {
+        if let Some(slot) = self.captures.get_mut(index) {
+            *slot = None;
+        }
+    }
+
+    /// Returns the capture of the formal parameter mapped to the property at `index`.
+    pub fn get(&self, index: usize) -> Option<HandleMut<Capture>> {
+        self.captures.get(index).copied().flatten()
+    }
+}
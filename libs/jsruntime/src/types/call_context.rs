@@ -2,6 +2,8 @@ use std::ffi::c_void;
 
 use jsgc::HandleMut;
 
+use crate::lambda::LambdaId;
+use crate::semantics::SourceOffset;
 use crate::types::Closure;
 use crate::types::Coroutine;
 use crate::types::Object;
@@ -29,9 +31,16 @@ pub struct CallContext {
     func: Option<HandleMut<Object>>,
 
     /// A pointer to the call context of the caller.
-    #[allow(unused)]
     caller: *const CallContext,
 
+    /// The ID of the lambda function containing the call site.
+    ///
+    /// `LambdaId::HOST` if the call is performed in Rust code.
+    call_site_lambda_id: u32,
+
+    /// The offset of the call site in the source text.
+    call_site_offset: u32,
+
     /// Flags.
     flags: CallContextFlags,
 
@@ -45,7 +54,6 @@ pub struct CallContext {
     argc_max: u16,
 
     /// A pointer to the arguments.
-    // TODO(feat): arguments object
     argv: *const Value,
 }
 
@@ -56,6 +64,8 @@ impl CallContext {
     pub const THIS_OFFSET: usize = std::mem::offset_of!(Self, this);
     pub const FUNC_OFFSET: usize = std::mem::offset_of!(Self, func);
     pub const CALLER_OFFSET: usize = std::mem::offset_of!(Self, caller);
+    pub const CALL_SITE_LAMBDA_ID_OFFSET: usize = std::mem::offset_of!(Self, call_site_lambda_id);
+    pub const CALL_SITE_OFFSET_OFFSET: usize = std::mem::offset_of!(Self, call_site_offset);
     pub const FLAGS_OFFSET: usize = std::mem::offset_of!(Self, flags);
     pub const DEPTH_OFFSET: usize = std::mem::offset_of!(Self, depth);
     pub const ARGC_OFFSET: usize = std::mem::offset_of!(Self, argc);
@@ -68,6 +78,8 @@ impl CallContext {
             this: Value::Undefined,
             func: None,
             caller: std::ptr::null(),
+            call_site_lambda_id: LambdaId::HOST.into(),
+            call_site_offset: SourceOffset::UNKNOWN.into(),
             flags: CallContextFlags::empty(),
             depth: 0,
             argc: args.len() as u16,
@@ -82,6 +94,8 @@ impl CallContext {
             this: Value::Undefined,
             func: None,
             caller: std::ptr::null(),
+            call_site_lambda_id: LambdaId::HOST.into(),
+            call_site_offset: SourceOffset::UNKNOWN.into(),
             flags: CallContextFlags::empty(),
             depth: 0,
             argc: args.len() as u16,
@@ -96,13 +110,16 @@ impl CallContext {
         closure: HandleMut<Closure>,
         this: &Value,
         args: &[Value],
+        flags: CallContextFlags,
     ) -> Self {
         Self {
             envp: closure.as_ptr() as *mut std::ffi::c_void,
             this: this.clone(),
             func: Some(func),
             caller: self,
-            flags: CallContextFlags::empty(),
+            call_site_lambda_id: LambdaId::HOST.into(),
+            call_site_offset: SourceOffset::UNKNOWN.into(),
+            flags,
             depth: self.depth + 1,
             argc: args.len() as u16,
             argc_max: args.len() as u16,
@@ -123,6 +140,29 @@ impl CallContext {
         self.func
     }
 
+    pub(crate) fn caller(&self) -> Option<&CallContext> {
+        // SAFETY: `caller` is null or a valid pointer to the call context of the caller which
+        // outlives `self`.
+        unsafe { self.caller.as_ref() }
+    }
+
+    pub(crate) fn depth(&self) -> u16 {
+        self.depth
+    }
+
+    /// Returns the lambda function and the source offset of the call site, or `None` if the call
+    /// is performed in Rust code.
+    pub(crate) fn call_site(&self) -> Option<(LambdaId, SourceOffset)> {
+        if self.call_site_lambda_id == u32::from(LambdaId::HOST) {
+            None
+        } else {
+            Some((
+                self.call_site_lambda_id.into(),
+                self.call_site_offset.into(),
+            ))
+        }
+    }
+
     pub(crate) fn closure(&self) -> HandleMut<Closure> {
         HandleMut::from_ptr(self.envp as *mut Closure)
             .expect("must be a non-null pointer to a Closure")
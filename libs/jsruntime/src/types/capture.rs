@@ -39,6 +39,14 @@ impl Capture {
         }
     }
 
+    pub(crate) fn set_value(&mut self, value: Value) {
+        debug_assert!(!self.target.is_null());
+        // SAFETY: `self.target` is a non-null pointer to a `Value`.
+        unsafe {
+            *self.target = value;
+        }
+    }
+
     pub(crate) fn escape(&mut self) {
         debug_assert!(!self.is_escaped());
         debug_assert!(!self.target.is_null());
@@ -2,6 +2,7 @@ use jsgc::HandleMut;
 use jsgc::Trace;
 use jsgc::VisitList;
 
+use crate::RealmId;
 use crate::lambda::LambdaId;
 use crate::types::Capture;
 use crate::types::LambdaAddr;
@@ -30,14 +31,22 @@ pub struct Closure {
     /// can remove this field.
     pub num_captures: u16,
 
+    /// The realm where the closure was created.
+    ///
+    /// This field is placed in the padding before `captures`.
+    pub realm: RealmId,
+
     /// A variable-length list of captures used in the lambda function.
     pub captures: [HandleMut<Capture>; 0],
 }
 
 base::static_assert_eq!(align_of::<Closure>(), 8);
+base::static_assert_eq!(std::mem::offset_of!(Closure, captures), 16);
 
 impl Closure {
     pub(crate) const LAMBDA_OFFSET: usize = std::mem::offset_of!(Self, lambda);
+    pub(crate) const LAMBDA_ID_OFFSET: usize = std::mem::offset_of!(Self, lambda_id);
+    pub(crate) const REALM_OFFSET: usize = std::mem::offset_of!(Self, realm);
     pub(crate) const CAPTURES_OFFSET: usize = std::mem::offset_of!(Self, captures);
 
     pub(crate) fn put_capture(&mut self, index: usize, capture: HandleMut<Capture>) {
@@ -0,0 +1,96 @@
+use jsgc::HandleMut;
+use jsgc::Trace;
+use jsgc::WeakHandle;
+
+use crate::types::Object;
+use crate::types::Value;
+
+/// A cell registered in a FinalizationRegistry object.
+///
+/// The `[[WeakRefTarget]]` is held weakly by the cell.
+pub type FinalizationCell = jsgc::FinalizationCell<Object, HeldValue>;
+
+/// A data type to represent the internal slots of a FinalizationRegistry object.
+#[derive(jsgc_derive::Trace)]
+pub struct FinalizationRegistry {
+    // [[CleanupCallback]]
+    cleanup_callback: HandleMut<Object>,
+
+    // [[Cells]]
+    cells: Vec<FinalizationRecord>,
+}
+
+impl FinalizationRegistry {
+    pub fn new(cleanup_callback: HandleMut<Object>) -> Self {
+        Self {
+            cleanup_callback,
+            cells: vec![],
+        }
+    }
+
+    pub fn cleanup_callback(&self) -> HandleMut<Object> {
+        self.cleanup_callback
+    }
+
+    pub fn register(
+        &mut self,
+        cell: HandleMut<FinalizationCell>,
+        unregister_token: Option<WeakHandle<Object>>,
+    ) {
+        self.cells.push(FinalizationRecord {
+            cell,
+            unregister_token,
+        });
+    }
+
+    /// Removes all cells registered with `unregister_token`.
+    ///
+    /// Returns `true` if at least one cell is removed.
+    pub fn unregister(&mut self, unregister_token: HandleMut<Object>) -> bool {
+        let len = self.cells.len();
+        self.cells.retain(|record| {
+            !matches!(record.unregister_token, Some(token) if token.get() == Some(unregister_token))
+        });
+        self.cells.len() != len
+    }
+
+    /// Removes a cell whose target has been reclaimed.
+    ///
+    /// Returns `false` if the cell has already been unregistered.
+    pub fn remove_cell(&mut self, cell: HandleMut<FinalizationCell>) -> bool {
+        debug_assert!(cell.target().is_none());
+        match self.cells.iter().position(|record| record.cell == cell) {
+            Some(index) => {
+                self.cells.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for FinalizationRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FinalizationRegistry(cells: {})", self.cells.len())
+    }
+}
+
+/// A Record in `[[Cells]]`.
+#[derive(jsgc_derive::Trace)]
+struct FinalizationRecord {
+    // [[WeakRefTarget]], [[HeldValue]]
+    cell: HandleMut<FinalizationCell>,
+
+    // [[UnregisterToken]]
+    unregister_token: Option<WeakHandle<Object>>,
+}
+
+/// A value held strongly by a `FinalizationCell`.
+#[derive(jsgc_derive::Trace)]
+pub struct HeldValue {
+    /// The FinalizationRegistry object that the cell belongs to.
+    pub registry: HandleMut<Object>,
+
+    // [[HeldValue]]
+    pub value: Value,
+}
@@ -41,9 +41,11 @@ where
     // it isn't dropped (even if the callback panics).
     #[allow(clippy::uninit_assumed_init)]
     let host_fn = unsafe { std::mem::MaybeUninit::<F>::uninit().assume_init() };
+    let saved = runtime.enter_host_function(context);
     let args = context.args();
     // TODO: The return value is copied twice.  That's inefficient.
     let result = host_fn(runtime, args);
+    runtime.leave_host_function(saved);
     *retv = result.value();
     result.status()
 }
@@ -2,10 +2,12 @@
 // memory layout of a type you want to change.  It's recommended to use compile-time assertions
 // that ensure the memory layout of the type.
 
+mod arguments;
 mod call_context;
 mod capture;
 mod closure;
 mod coroutine;
+mod finalization_registry;
 mod lambda;
 pub mod number;
 pub mod object;
@@ -13,16 +15,21 @@ mod promise;
 pub mod string;
 mod value;
 
+pub use arguments::ParameterMap;
 pub use call_context::CallContext;
 pub use call_context::CallContextFlags;
 pub use capture::Capture;
 pub use closure::Closure;
 pub use coroutine::Coroutine;
+pub use finalization_registry::FinalizationCell;
+pub use finalization_registry::FinalizationRegistry;
+pub use finalization_registry::HeldValue;
 pub use lambda::Lambda;
 pub use lambda::LambdaAddr;
 pub use lambda::ReturnValue;
 pub use lambda::Status;
 pub use lambda::into_lambda;
+pub use object::Accessor;
 pub use object::Object;
 pub use object::ObjectFlags;
 pub use object::Property;
@@ -2,6 +2,7 @@ use std::fmt::Debug;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::mem::ManuallyDrop;
 
 use bitflags::bitflags;
 use rustc_hash::FxHashMap;
@@ -10,10 +11,14 @@ use jsgc::Handle;
 use jsgc::HandleMut;
 use jsgc::Trace;
 use jsgc::VisitList;
+use jsgc::WeakHandle;
 use jsparser::Symbol;
 
 use crate::Error;
+use crate::types::Capture;
 use crate::types::Closure;
+use crate::types::FinalizationRegistry;
+use crate::types::ParameterMap;
 use crate::types::Promise;
 use crate::types::String;
 use crate::types::Value;
@@ -83,24 +88,50 @@ impl PartialEq for PropertyKey {
 
 // 6.1.7.1 Property Attributes
 
-// TODO(feat): accessor property
-// The accessor property can be represented as a tuple of two pointers.  Its size is within
-// `size_of::<Value>()` (16 bytes) in any architectures.  Replace `value: Value` with
-// `value: [u8; 16]` and define access methods on `Property`.
-//
 // TODO(refactor): memory layout
 // The type of the discriminant value of `Value` is `u8`.  So, there is enough space for storing
 // `flags` in `Value`.  We can use the same memory layout in `Value` and `Property`.  When we
 // represents the [[Get]] and [[Set]] by using a pair of offsets or indexes shorter than 6 bytes,
 // we can also place it in `Value`.
 pub struct Property {
-    /// The `[[Value]]` attribute.
-    value: Value,
+    /// The `[[Value]]` attribute, or the `[[Get]]` and `[[Set]]` attributes of the accessor
+    /// property.
+    slot: PropertySlot,
 
     /// Flags for boolean attributes.
     flags: PropertyFlags,
 }
 
+base::static_assert_eq!(size_of::<Property>(), 24);
+
+// The accessor property is less common than the data property.  Its attributes are stored in a
+// separate heap cell so that the accessor property doesn't increase the size of `Property`.
+//
+// The active field is determined by `PropertyFlags::DATA`.
+union PropertySlot {
+    value: ManuallyDrop<Value>,
+    accessor: HandleMut<Accessor>,
+}
+
+/// The `[[Get]]` and `[[Set]]` attributes of an accessor property.
+pub struct Accessor {
+    getter: Option<HandleMut<Object>>,
+    setter: Option<HandleMut<Object>>,
+}
+
+impl Accessor {
+    pub fn new(getter: Option<HandleMut<Object>>, setter: Option<HandleMut<Object>>) -> Self {
+        Self { getter, setter }
+    }
+}
+
+impl Trace for Accessor {
+    fn trace(&self, visits: &mut VisitList) {
+        self.getter.trace(visits);
+        self.setter.trace(visits);
+    }
+}
+
 // NOTE: Current we use `data_*()` factory methods in order hide internal details of this type.
 // Because we'll change its memory layout in the future.
 impl Property {
@@ -131,11 +162,25 @@ impl Property {
     /// Creates a data property.
     pub const fn data(value: Value, flags: PropertyFlags) -> Self {
         Self {
-            value,
+            slot: PropertySlot {
+                value: ManuallyDrop::new(value),
+            },
             flags: PropertyFlags::DATA.union(flags),
         }
     }
 
+    /// Creates an accessor property with `[[Enumerable]]=false` and `[[Configurable]]=true`.
+    pub fn accessor_xc(accessor: HandleMut<Accessor>) -> Self {
+        Self {
+            slot: PropertySlot { accessor },
+            flags: PropertyFlags::CONFIGURABLE,
+        }
+    }
+
+    pub const fn is_data_property(&self) -> bool {
+        self.flags.is_data_property()
+    }
+
     pub const fn is_writable(&self) -> bool {
         self.flags.is_writable()
     }
@@ -149,8 +194,39 @@ impl Property {
     }
 
     pub fn value(&self) -> &Value {
+        assert!(self.flags.is_data_property());
+        // SAFETY: `value` is the active field of the data property.
+        unsafe { &self.slot.value }
+    }
+
+    fn set_value(&mut self, value: Value) {
         debug_assert!(self.flags.is_data_property());
-        &self.value
+        self.slot.value = ManuallyDrop::new(value);
+    }
+
+    pub fn getter(&self) -> Option<HandleMut<Object>> {
+        self.accessor().getter
+    }
+
+    pub fn setter(&self) -> Option<HandleMut<Object>> {
+        self.accessor().setter
+    }
+
+    fn accessor(&self) -> &Accessor {
+        assert!(!self.flags.is_data_property());
+        // SAFETY: `accessor` is the active field of the accessor property.
+        unsafe { &self.slot.accessor }
+    }
+}
+
+impl Trace for Property {
+    fn trace(&self, visits: &mut VisitList) {
+        if self.is_data_property() {
+            self.value().trace(visits);
+        } else {
+            // SAFETY: `accessor` is the active field of the accessor property.
+            unsafe { self.slot.accessor }.trace(visits);
+        }
     }
 }
 
@@ -275,26 +351,45 @@ impl Object {
     // value.  Returning the reference to the value works properly if and only if the value is used
     // before it's overwritten.  At this point, we are not sure whether or not it's always works in
     // any expression.
+    //
+    // `None` is returned for accessor properties as well as missing properties because the
+    // getter cannot be called here.  Use `Runtime::get_property_value()` in order to perform
+    // [[Get]].
     pub fn get_value(&self, key: &PropertyKey) -> Option<&Value> {
-        self.properties
-            .get(key)
-            .map(|prop| &prop.value)
-            .or_else(|| {
-                self.prototype
-                    .as_ref()
-                    .and_then(|prototype| prototype.get_value(key))
-            })
+        if let Some(value) = self.mapped_value(key) {
+            return Some(value);
+        }
+        self.find_property(key)
+            .filter(|prop| prop.is_data_property())
+            .map(Property::value)
     }
 
-    // TODO(feat): strict, writable
+    /// Finds a property in the prototype chain.
+    pub fn find_property(&self, key: &PropertyKey) -> Option<&Property> {
+        self.properties.get(key).or_else(|| {
+            self.prototype
+                .as_ref()
+                .and_then(|prototype| prototype.find_property(key))
+        })
+    }
+
+    // The `[[Writable]]` attribute and the `[[Extensible]]` internal slot are not checked, and an
+    // own accessor property is replaced with a data property.  Use
+    // `Runtime::set_property_value()` in order to perform [[Set]].
     pub fn set_value(&mut self, key: &PropertyKey, value: &Value) {
         self.properties
             .entry(key.clone())
             .and_modify(|prop| {
-                // TODO: debug_assert!(prop.is_writable());
-                prop.value = value.clone();
+                if prop.is_data_property() {
+                    prop.set_value(value.clone());
+                } else {
+                    *prop = Property::data_wec(value.clone());
+                }
             })
-            .or_insert(Property::data_xxx(value.clone()));
+            .or_insert(Property::data_wec(value.clone()));
+        if let Some(mut capture) = self.mapped_capture(key) {
+            capture.set_value(value.clone());
+        }
     }
 
     pub fn get_own_property(&self, key: &PropertyKey) -> Option<&Property> {
@@ -303,6 +398,18 @@ impl Object {
 
     // TODO(feat): 10.1.6.3 ValidateAndApplyPropertyDescriptor ( O, P, extensible, Desc, current )
     pub fn define_own_property(&mut self, key: PropertyKey, prop: Property) -> Result<bool, Error> {
+        if !self.is_extensible() && !self.properties.contains_key(&key) {
+            return Ok(false);
+        }
+        if let Some(mut capture) = self.mapped_capture(&key) {
+            // Step#7 in 10.4.4.2 [[DefineOwnProperty]] ( P, Desc )
+            if prop.is_data_property() {
+                capture.set_value(prop.value().clone());
+            }
+            if !prop.is_data_property() || !prop.is_writable() {
+                self.unmap_argument(&key);
+            }
+        }
         self.properties.insert(key, prop);
         Ok(true)
     }
@@ -339,6 +446,102 @@ impl Object {
         self.set_handle_mut(promise);
     }
 
+    pub(crate) fn is_weak_ref(&self) -> bool {
+        self.flags.contains(ObjectFlags::WEAK_REF)
+    }
+
+    pub(crate) fn weak_ref(&self) -> WeakHandle<Object> {
+        debug_assert!(self.is_weak_ref());
+        WeakHandle::from_addr(self.kernel.data).expect("must be a non-null pointer to a WeakRef")
+    }
+
+    pub(crate) fn set_weak_ref(&mut self, weak_ref: WeakHandle<Object>) {
+        // The weak reference itself must be kept alive while the object is alive.
+        self.kernel.data = weak_ref.as_addr();
+        self.kernel.tracing = true;
+        self.flags.insert(ObjectFlags::WEAK_REF);
+    }
+
+    pub(crate) fn is_finalization_registry(&self) -> bool {
+        self.flags.contains(ObjectFlags::FINALIZATION_REGISTRY)
+    }
+
+    pub(crate) fn finalization_registry(&self) -> HandleMut<FinalizationRegistry> {
+        debug_assert!(self.is_finalization_registry());
+        HandleMut::from_addr(self.kernel.data)
+            .expect("must be a non-null pointer to a FinalizationRegistry")
+    }
+
+    pub(crate) fn set_finalization_registry(&mut self, registry: HandleMut<FinalizationRegistry>) {
+        self.set_handle_mut(registry);
+        self.flags.insert(ObjectFlags::FINALIZATION_REGISTRY);
+    }
+
+    pub(crate) fn is_host_object(&self) -> bool {
+        self.flags.contains(ObjectFlags::HOST_OBJECT)
+    }
+
+    /// Returns the address of the Rust data stored in the host object.
+    pub(crate) fn host_data_addr(&self) -> usize {
+        debug_assert!(self.is_host_object());
+        self.kernel.data
+    }
+
+    pub(crate) fn set_host_data<T>(&mut self, data: HandleMut<T>) {
+        self.set_handle_mut(data);
+        self.flags.insert(ObjectFlags::HOST_OBJECT);
+    }
+
+    pub(crate) fn is_arguments(&self) -> bool {
+        self.flags.contains(ObjectFlags::ARGUMENTS)
+    }
+
+    pub(crate) fn parameter_map(&self) -> HandleMut<ParameterMap> {
+        debug_assert!(self.is_arguments());
+        HandleMut::from_addr(self.kernel.data)
+            .expect("must be a non-null pointer to a ParameterMap")
+    }
+
+    pub(crate) fn set_parameter_map(&mut self, map: HandleMut<ParameterMap>) {
+        self.set_handle_mut(map);
+        self.flags.insert(ObjectFlags::ARGUMENTS);
+    }
+
+    /// Returns the value of the formal parameter mapped to a property of a mapped arguments
+    /// object.
+    ///
+    /// The value of the mapped property held in the object is not updated when the formal
+    /// parameter is updated.  So, the value must be read via this method.
+    pub(crate) fn mapped_value(&self, key: &PropertyKey) -> Option<&Value> {
+        let capture = self.mapped_capture(key)?;
+        // SAFETY: The capture is kept alive while the arguments object is alive.
+        unsafe { Some(&*(capture.value() as *const Value)) }
+    }
+
+    // 10.4.4 Arguments Exotic Objects
+    fn mapped_capture(&self, key: &PropertyKey) -> Option<HandleMut<Capture>> {
+        if !self.is_arguments() {
+            return None;
+        }
+        let index = Self::argument_index(key)?;
+        self.parameter_map().get(index)
+    }
+
+    fn unmap_argument(&mut self, key: &PropertyKey) {
+        if let Some(index) = Self::argument_index(key) {
+            self.parameter_map().unmap(index);
+        }
+    }
+
+    fn argument_index(key: &PropertyKey) -> Option<usize> {
+        match key {
+            PropertyKey::Number(value) if *value >= 0. && value.fract() == 0. => {
+                Some(*value as usize)
+            }
+            _ => None,
+        }
+    }
+
     fn set_handle<T>(&mut self, handle: Handle<T>) {
         self.kernel.data = handle.as_addr();
         self.kernel.tracing = true;
@@ -359,6 +562,10 @@ impl Object {
         matches!(self.prototype, Some(p) if p == prototype)
     }
 
+    pub fn is_constructor(&self) -> bool {
+        self.flags.contains(ObjectFlags::CONSTRUCTOR)
+    }
+
     pub(crate) fn set_constructor(&mut self) {
         self.flags.insert(ObjectFlags::CONSTRUCTOR)
     }
@@ -375,6 +582,16 @@ impl Object {
         self.flags.contains(ObjectFlags::ERROR)
     }
 
+    /// Returns the value of the `[[Extensible]]` internal slot.
+    pub fn is_extensible(&self) -> bool {
+        !self.flags.contains(ObjectFlags::NON_EXTENSIBLE)
+    }
+
+    // 10.1.4.1 OrdinaryPreventExtensions ( O )
+    pub(crate) fn prevent_extensions(&mut self) {
+        self.flags.insert(ObjectFlags::NON_EXTENSIBLE);
+    }
+
     pub(crate) fn set_error(&mut self) {
         self.flags.insert(ObjectFlags::ERROR);
     }
@@ -405,7 +622,7 @@ impl Trace for Object {
         self.kernel.trace(visits);
         self.prototype.trace(visits);
         for prop in self.properties.values() {
-            prop.value().trace(visits);
+            prop.trace(visits);
         }
         for slot in self.slots.iter() {
             slot.trace(visits);
@@ -436,8 +653,13 @@ impl Trace for Kernel {
 bitflags! {
     #[derive(Clone, Copy)]
     pub struct ObjectFlags: u8 {
-        const CONSTRUCTOR = 1 << 0;
-        const CALLABLE    = 1 << 1;
-        const ERROR       = 1 << 2;
+        const CONSTRUCTOR           = 1 << 0;
+        const CALLABLE              = 1 << 1;
+        const ERROR                 = 1 << 2;
+        const WEAK_REF              = 1 << 3;
+        const FINALIZATION_REGISTRY = 1 << 4;
+        const HOST_OBJECT           = 1 << 5;
+        const ARGUMENTS             = 1 << 6;
+        const NON_EXTENSIBLE        = 1 << 7;
     }
 }
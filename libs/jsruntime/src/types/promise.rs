@@ -10,6 +10,8 @@ pub struct Promise {
     coroutine: HandleMut<Coroutine>,
     awaiting: Option<HandleMut<Object>>,
     state: PromiseState,
+    // [[PromiseIsHandled]]
+    is_handled: bool,
 }
 
 impl Promise {
@@ -18,6 +20,7 @@ impl Promise {
             coroutine,
             awaiting: None,
             state: PromiseState::Pending,
+            is_handled: false,
         }
     }
 
@@ -25,6 +28,14 @@ impl Promise {
         self.coroutine
     }
 
+    pub fn is_handled(&self) -> bool {
+        self.is_handled
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        matches!(self.state, PromiseState::Rejected(_))
+    }
+
     pub fn resolve(&mut self, result: &Value) -> Option<HandleMut<Object>> {
         debug_assert!(matches!(self.state, PromiseState::Pending));
         self.state = PromiseState::Resolved(result.clone());
@@ -38,6 +49,7 @@ impl Promise {
     }
 
     pub fn do_await(&mut self, awaiting: HandleMut<Object>) -> Option<Result<Value, Value>> {
+        self.is_handled = true;
         match self.state {
             PromiseState::Pending => {
                 self.awaiting = Some(awaiting);
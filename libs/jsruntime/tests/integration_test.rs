@@ -1,6 +1,18 @@
 use assert_matches::assert_matches;
 
+use jsruntime::BasicRuntime;
+use jsruntime::CoverageRange;
+use jsruntime::HostObject;
+use jsruntime::LambdaId;
+use jsruntime::Monitor;
+use jsruntime::PauseReason;
+use jsruntime::RealmId;
+use jsruntime::Rest;
 use jsruntime::Runtime;
+use jsruntime::StackFrame;
+use jsruntime::StepAction;
+use jsruntime::Termination;
+use jsruntime::This;
 use jsruntime::Value;
 
 logging::init!();
@@ -74,6 +86,1156 @@ impl Validator {
     }
 }
 
+#[test]
+fn test_weak_ref_and_finalization_registry() {
+    const SOURCE: &str = r#"
+var cleaned = '';
+var registry = new FinalizationRegistry((held) => { cleaned += held; });
+var target = {};
+var ref = new WeakRef(target);
+var token = {};
+registry.register(target, 'a');
+registry.register({}, 'b', token);
+registry.unregister(token);
+print(ref.deref() === target);
+target = null;
+"#;
+
+    let mut runtime =
+        Runtime::with_extension(Validator::new(vec![Value::TRUE, Value::TRUE, Value::TRUE]));
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().actual_values.push(args[0].clone());
+    });
+    let program_id = runtime.parse_script(SOURCE).unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    // The target is kept alive until the end of the current job.
+    runtime.process_jobs();
+    runtime.collect_garbage(vec![]);
+    runtime.process_jobs();
+
+    let program_id = runtime
+        .parse_script("print(cleaned === 'a'); print(ref.deref() === undefined);")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    runtime.extension().validate();
+}
+
+#[test]
+fn test_heap_limit() {
+    let mut runtime = BasicRuntime::new();
+    let limit = runtime.heap_stats().num_bytes + 4096;
+    runtime.set_heap_limit(limit);
+    let program_id = runtime
+        .parse_script("let a = []; while (true) { a = [a]; }")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Err(Value::Object(_)));
+    assert_eq!(runtime.last_termination(), Some(Termination::OutOfMemory));
+}
+
+#[test]
+fn test_heap_limit_recovery() {
+    let mut runtime = Runtime::with_extension(Validator::new(vec![]));
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().actual_values.push(args[0].clone());
+    });
+    let limit = runtime.heap_stats().num_bytes + 4096;
+    runtime.set_heap_limit(limit);
+    let program_id = runtime
+        .parse_script("try { let a = []; while (true) { a = [a]; } } catch (e) { print(e); }")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Err(Value::Object(_)));
+    assert_eq!(runtime.last_termination(), Some(Termination::OutOfMemory));
+    // The termination cannot be caught.
+    runtime.extension().validate();
+
+    // The garbage has been collected and the next script runs normally.
+    let program_id = runtime.parse_script("let b = [[]]; b.length").unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(runtime.last_termination(), None);
+}
+
+#[test]
+fn test_near_heap_limit_callback() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let called = Rc::new(Cell::new(false));
+
+    let mut runtime = BasicRuntime::new();
+    let limit = runtime.heap_stats().num_bytes + 4096;
+    runtime.set_heap_limit(limit);
+    {
+        let called = called.clone();
+        runtime.set_near_heap_limit_callback(move |limit, _num_bytes| {
+            called.set(true);
+            limit
+        });
+    }
+    let program_id = runtime
+        .parse_script("let a = []; while (true) { a = [a]; }")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Err(Value::Object(_)));
+    assert!(called.get());
+}
+
+#[test]
+fn test_max_call_stack_depth() {
+    let mut runtime = BasicRuntime::new();
+    runtime.set_max_call_stack_depth(16);
+    let program_id = runtime
+        .parse_script("function f(n) { return n === 0 ? 0 : f(n - 1) + 1; } f(8)")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    let program_id = runtime
+        .parse_script("function f(n) { return n === 0 ? 0 : f(n - 1) + 1; } f(32)")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Err(Value::Object(_)));
+}
+
+#[test]
+fn test_stack_size() {
+    const SOURCE: &str = r#"
+var depth = 0;
+function f() { depth++; f(); }
+try {
+  f();
+} catch (e) {
+  print(e.name === 'RangeError' && e.message === 'Maximum call stack size exceeded');
+}
+print(depth);
+"#;
+
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().push(args[0].clone());
+    });
+    // The native stack must overflow before the call stack depth exceeds the limit.
+    runtime.set_max_call_stack_depth(u16::MAX);
+    runtime.set_stack_size(64 * 1024);
+    let program_id = runtime.parse_script(SOURCE).unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(runtime.extension()[0], Value::TRUE);
+    let Value::Number(small_depth) = runtime.extension()[1] else {
+        panic!("depth must be a number");
+    };
+    assert!(small_depth < u16::MAX as f64);
+
+    // A larger stack allows deeper calls.
+    runtime.set_stack_size(256 * 1024);
+    let program_id = runtime
+        .parse_script("depth = 0; try { f(); } catch (e) {} print(depth);")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    let Value::Number(large_depth) = runtime.extension()[2] else {
+        panic!("depth must be a number");
+    };
+    assert!(large_depth > small_depth);
+    assert!(large_depth < u16::MAX as f64);
+}
+
+#[test]
+fn test_allow_code_generation_from_strings() {
+    const SOURCE: &str = r#"
+var a, b;
+try { eval('1'); } catch (e) { a = e.name; }
+try { new Function('return 1'); } catch (e) { b = e.name; }
+var ok = a === 'EvalError' && b === 'EvalError';
+"#;
+
+    let mut runtime = BasicRuntime::new();
+    runtime.allow_code_generation_from_strings(false);
+    let program_id = runtime.parse_script(SOURCE).unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_matches!(runtime.get_global("ok").get(), Value::Boolean(true));
+}
+
+#[test]
+fn test_capture_stack_trace() {
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.register_host_function("trace", |runtime, _args| {
+        let frames = runtime.capture_stack_trace();
+        *runtime.extension_mut() = frames;
+    });
+    let program_id = runtime
+        .parse_script("function f() {\n  trace();\n}\nnew f();")
+        .unwrap();
+    runtime.set_program_name(program_id, "test.js");
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(
+        runtime.extension(),
+        &[
+            StackFrame {
+                function: Some("f".to_owned()),
+                script: Some("test.js".to_owned()),
+                line: 2,
+                column: 3,
+                constructor: true,
+            },
+            StackFrame {
+                function: None,
+                script: Some("test.js".to_owned()),
+                line: 4,
+                column: 1,
+                constructor: false,
+            },
+        ]
+    );
+    assert!(runtime.capture_stack_trace().is_empty());
+}
+
+#[test]
+fn test_interrupt() {
+    let mut runtime = BasicRuntime::new();
+    let handle = runtime.interrupt_handle();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        handle.interrupt();
+    });
+    let program_id = runtime.parse_script("while (true) {}").unwrap();
+    let result = runtime.run(program_id, true);
+    thread.join().unwrap();
+    assert_matches!(result, Err(Value::Object(_)));
+    assert_eq!(runtime.last_termination(), Some(Termination::Interrupted));
+
+    // The runtime can run other scripts after the termination.
+    let program_id = runtime.parse_script("1").unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(runtime.last_termination(), None);
+}
+
+#[test]
+fn test_fuel() {
+    let mut runtime = Runtime::with_extension(Validator::new(vec![]));
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().actual_values.push(args[0].clone());
+    });
+    runtime.enable_fuel_metering();
+    runtime.set_fuel(1000);
+    let program_id = runtime
+        .parse_script("try { while (true) {} } catch (e) { print(e); }")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Err(Value::Object(_)));
+    assert_eq!(runtime.last_termination(), Some(Termination::OutOfFuel));
+    assert_eq!(runtime.fuel(), 0);
+    // The termination cannot be caught.
+    runtime.extension().validate();
+}
+
+#[test]
+fn test_fuel_evaluate() {
+    let mut runtime = BasicRuntime::new();
+    runtime.enable_fuel_metering();
+    runtime.set_fuel(1000);
+    let program_id = runtime.parse_script("while (true) {}").unwrap();
+    runtime.compile(program_id, true).unwrap();
+    let result = runtime.evaluate(program_id);
+    assert_matches!(result, Err(Value::Object(_)));
+    assert_eq!(runtime.last_termination(), Some(Termination::OutOfFuel));
+
+    // The termination does not affect the next script.
+    runtime.set_fuel(1000);
+    let program_id = runtime.parse_script("1").unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(runtime.last_termination(), None);
+}
+
+#[test]
+fn test_call_function() {
+    let mut runtime = BasicRuntime::new();
+    let program_id = runtime
+        .parse_script("function add(a, b) { return { sum: a + b }; } function C(x) { this.x = x; }")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let add = runtime.get_global("add").get();
+    let args = [Value::from(1), Value::from(2)];
+    let result = runtime.call(&add, &Value::Undefined, &args).unwrap();
+    // Values returned from the API are kept alive across garbage collections.
+    runtime.collect_garbage(vec![]);
+    let sum = runtime.get(&result.get(), "sum").unwrap();
+    assert_eq!(sum.get(), Value::from(3));
+    assert_eq!(runtime.keys(&result.get()).unwrap(), ["sum"]);
+
+    let c = runtime.get_global("C");
+    let arg = runtime.new_string("hello");
+    let object = runtime.construct(&c.get(), &[arg.get()]).unwrap();
+    let x = runtime.get(&object.get(), "x").unwrap();
+    assert_eq!(runtime.to_rust_string(&x.get()).unwrap(), "hello");
+
+    let program_id = runtime
+        .parse_script("function F() { this.b = 2; return {a: 1}; }")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    let f = runtime.get_global("F");
+    let object = runtime.construct(&f.get(), &[]).unwrap().get();
+    assert_eq!(runtime.get(&object, "a").unwrap().get(), Value::from(1));
+    assert_eq!(runtime.get(&object, "b").unwrap().get(), Value::Undefined);
+
+    let array = runtime.new_array(&[Value::from(1), Value::TRUE]);
+    runtime.collect_garbage(vec![]);
+    let array = array.get();
+    assert_eq!(runtime.get(&array, "length").unwrap().get(), Value::from(2));
+    assert_eq!(runtime.get_index(&array, 1).unwrap().get(), Value::TRUE);
+    runtime.set_index(&array, 0, &Value::Null).unwrap();
+    assert_eq!(runtime.get_index(&array, 0).unwrap().get(), Value::Null);
+
+    let result = runtime.call(&Value::Undefined, &Value::Undefined, &[]);
+    assert_matches!(result.map_err(|err| err.get()), Err(Value::Object(_)));
+}
+
+#[test]
+fn test_register_function() {
+    fn add(a: f64, b: f64) -> f64 {
+        a + b
+    }
+
+    let mut runtime = Runtime::with_extension(Validator::new(vec![
+        Value::from(3),
+        Value::from(6),
+        Value::from(2),
+        Value::TRUE,
+        Value::TRUE,
+    ]));
+    runtime.register_function("print", |runtime: &mut Runtime<Validator>, value: Value| {
+        runtime.extension_mut().actual_values.push(value);
+    });
+    runtime.register_function("add", add);
+    runtime.register_function("sum", |Rest(values): Rest<f64>| values.iter().sum::<f64>());
+    runtime.register_function("len", |This(x): This<Vec<f64>>| x.len());
+    runtime.register_function("parse", |s: String| s.parse::<f64>());
+    let program_id = runtime
+        .parse_script(
+            r#"
+print(add(1, 2));
+print(sum(1, 2, 3));
+print(len.call([1, 2]));
+try { add('1', 2); } catch (e) { print(e.name === 'TypeError'); }
+try { parse('x'); } catch (e) { print(e.name === 'TypeError'); }
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    runtime.extension().validate();
+}
+
+#[test]
+fn test_define_class() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use jsgc::Trace;
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counter {
+        count: f64,
+        callback: Value,
+    }
+
+    impl Trace for Counter {
+        fn trace(&self, visits: &mut jsgc::VisitList) {
+            self.callback.trace(visits);
+        }
+    }
+
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let mut runtime = Runtime::with_extension(Validator::new(vec![
+        Value::from(1),
+        Value::from(2),
+        Value::from(10),
+        Value::from(10),
+        Value::TRUE,
+        Value::TRUE,
+    ]));
+    runtime.register_function("print", |runtime: &mut Runtime<Validator>, value: Value| {
+        runtime.extension_mut().actual_values.push(value);
+    });
+    runtime
+        .define_class("Counter", |count: f64, callback: Value| Counter {
+            count,
+            callback,
+        })
+        .method(
+            "increment",
+            |runtime: &mut Runtime<Validator>, This(mut counter): This<HostObject<Counter>>| {
+                counter.count += 1.0;
+                let callback = counter.callback.clone();
+                runtime
+                    .call(&callback, &Value::Undefined, &[Value::from(counter.count)])
+                    .map_err(|_| "callback failed")
+            },
+        )
+        .getter("count", |This(counter): This<HostObject<Counter>>| {
+            counter.count
+        })
+        .setter(
+            "count",
+            |This(mut counter): This<HostObject<Counter>>, count: f64| {
+                counter.count = count;
+            },
+        )
+        .build();
+    let program_id = runtime
+        .parse_script(
+            r#"
+(function () {
+  const counter = new Counter(0, print);
+  counter.increment();
+  counter.increment();
+  counter.count = 9;
+  counter.increment();
+  print(counter.count);
+})();
+try { Counter.prototype.increment.call({}); } catch (e) { print(e.name === 'TypeError'); }
+try { Counter(0, print); } catch (e) { print(e.name === 'TypeError'); }
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    runtime.extension().validate();
+
+    // The Rust data is dropped when the host object is reclaimed.
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 0);
+    runtime.collect_garbage(vec![]);
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_recursive_getter() {
+    use jsgc::Trace;
+
+    struct Node;
+
+    impl Trace for Node {
+        fn trace(&self, _visits: &mut jsgc::VisitList) {}
+    }
+
+    let mut runtime = BasicRuntime::new();
+    runtime.set_stack_size(64 * 1024);
+    let constructor = runtime
+        .define_class("Node", || Node)
+        .getter(
+            "depth",
+            |runtime: &mut BasicRuntime, This(this): This<Value>| {
+                runtime.get(&this, "depth").map_err(|_| "too deep")
+            },
+        )
+        .build();
+    let node = runtime.construct(&constructor, &[]).unwrap();
+    // The stack overflow caused by the getter called from the host is reported as an error.
+    let result = runtime.get(&node.get(), "depth");
+    assert_matches!(result.map_err(|err| err.get()), Err(Value::Object(_)));
+}
+
+#[test]
+fn test_event_loop() {
+    use std::time::Duration;
+
+    let mut runtime = Runtime::with_extension(Validator::new(vec![
+        Value::from(1),
+        Value::from(2),
+        Value::from(3),
+    ]));
+    runtime.enable_virtual_clock();
+    runtime.register_function("print", |runtime: &mut Runtime<Validator>, value: Value| {
+        runtime.extension_mut().actual_values.push(value);
+    });
+    let program_id = runtime
+        .parse_script("setTimeout(() => print(3), 3000); setTimeout(() => print(2), 1000);")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    runtime.post_task(|runtime| {
+        let print = runtime.get_global("print");
+        let result = runtime.call(&print.get(), &Value::Undefined, &[Value::from(1)]);
+        assert_matches!(result, Ok(_));
+    });
+
+    assert_eq!(runtime.run_until(Duration::from_secs(2)), Ok(false));
+    assert_eq!(runtime.now(), Duration::from_secs(2));
+    assert!(runtime.has_pending_tasks());
+    assert_eq!(runtime.run_until_idle(), Ok(()));
+    assert_eq!(runtime.now(), Duration::from_secs(3));
+    assert!(!runtime.has_pending_tasks());
+    runtime.extension().validate();
+
+    // An exception thrown from a timer callback stops the event loop.
+    let program_id = runtime
+        .parse_script("setTimeout(() => { throw 0; }); setTimeout(() => print(4), 10);")
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(runtime.run_until_idle(), Err(Value::from(0)));
+    assert!(runtime.has_pending_tasks());
+}
+
+#[test]
+fn test_async_function() {
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Waker;
+
+    let mut runtime = Runtime::with_extension(Validator::new(vec![Value::from(3), Value::TRUE]));
+    runtime.register_function("print", |runtime: &mut Runtime<Validator>, value: Value| {
+        runtime.extension_mut().actual_values.push(value);
+    });
+    runtime.register_async_function("add", |a: f64, b: f64| async move { a + b });
+    runtime.register_async_function("fail", || async { Err::<(), _>("failed") });
+    let program_id = runtime
+        .parse_script(
+            r#"
+async function main() {
+  print(await add(1, 2));
+  try { await fail(); } catch (e) { print(e.name === 'TypeError'); }
+  return 10;
+}
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let main = runtime.get_global("main");
+    let promise = runtime.call(&main.get(), &Value::Undefined, &[]).unwrap();
+    let mut future = std::pin::pin!(runtime.promise_to_future(&promise.get()));
+    let mut cx = Context::from_waker(Waker::noop());
+    assert!(future.as_mut().poll(&mut cx).is_pending());
+    assert_eq!(runtime.run_until_idle(), Ok(()));
+    assert_eq!(future.poll(&mut cx), Poll::Ready(Ok(Value::from(10))));
+    runtime.extension().validate();
+}
+
+#[test]
+fn test_unhandled_rejection() {
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.set_unhandled_rejection_callback(|runtime, rejection| {
+        let stack = rejection.stack.clone();
+        runtime
+            .extension_mut()
+            .push((rejection.reason.clone(), stack));
+    });
+    let program_id = runtime
+        .parse_script(
+            r#"
+async function f() { throw new Error('oops'); }
+async function g() { throw 1; }
+f();
+(async function () { try { await g(); } catch (e) {} })();
+new Promise(function (resolve, reject) { reject(2); });
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    runtime.process_jobs();
+
+    let rejections = std::mem::take(runtime.extension_mut());
+    assert_eq!(rejections.len(), 2);
+    assert_matches!(rejections[0], (Value::Object(_), Some(ref stack)) => {
+        assert!(stack.starts_with("Error: oops"));
+    });
+    assert_eq!(rejections[1], (Value::from(2), None));
+
+    // Rejections are reported only once.
+    runtime.process_jobs();
+    assert!(runtime.extension().is_empty());
+}
+
+#[test]
+fn test_realms() {
+    let mut runtime = Runtime::with_extension(Validator::new(vec![Value::TRUE; 8]));
+    runtime.register_function("print", |runtime: &mut Runtime<Validator>, value: Value| {
+        runtime.extension_mut().actual_values.push(value);
+    });
+
+    let realm = runtime.create_realm();
+    assert_eq!(runtime.realm(), RealmId::DEFAULT);
+    runtime.set_realm(realm);
+    let program_id = runtime
+        .parse_script(
+            r#"
+var x = 1;
+function make() { return {}; }
+function fail() { return undefined.x; }
+function outer() { return function () { return undefined.x; }; }
+"#,
+        )
+        .unwrap();
+    runtime.set_realm(RealmId::DEFAULT);
+
+    // The program is evaluated in the realm where it was parsed.
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(runtime.get_global("x").get(), Value::Undefined);
+    let other = runtime.realm_global_object(realm).get();
+    assert_eq!(runtime.get(&other, "x").unwrap().get(), Value::from(1));
+
+    runtime.set_global("other", &other);
+    let program_id = runtime
+        .parse_script(
+            r#"
+print(other.x === 1 && typeof x === 'undefined');
+print(other.Object !== Object);
+print(other.Object.prototype.isPrototypeOf(other.make()));
+print(other.Object.prototype.isPrototypeOf(new other.Object()));
+print(!Object.prototype.isPrototypeOf(new other.Object()));
+// Functions called across realms use the intrinsics of the realm where they were created.
+try { other.fail(); } catch (e) { print(other.TypeError.prototype.isPrototypeOf(e)); }
+try { other.outer()(); } catch (e) { print(other.TypeError.prototype.isPrototypeOf(e)); }
+try {
+  other.Array.prototype.forEach.call([0], function () { undefined.x; });
+} catch (e) {
+  print(TypeError.prototype.isPrototypeOf(e));
+}
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    runtime.extension().validate();
+}
+
+#[test]
+fn test_code_cache() {
+    const SOURCE: &str = r#"
+var count = 0;
+function counter() { return function () { return ++count; }; }
+var next = counter();
+print(next());
+async function f(x) { return await x + next(); }
+f(1);
+f(10);
+"#;
+
+    let mut runtime = BasicRuntime::new();
+    let program_id = runtime.parse_script(SOURCE).unwrap();
+    let data = runtime.create_code_cache(program_id);
+
+    let mut runtime = Runtime::with_extension(Validator::new(vec![Value::from(1)]));
+    runtime.register_function("print", |runtime: &mut Runtime<Validator>, value: Value| {
+        runtime.extension_mut().actual_values.push(value);
+    });
+    // Symbols are interned in a different order.
+    let program_id = runtime
+        .parse_script("var next, counter, x, count, f;")
+        .unwrap();
+    assert_matches!(runtime.run(program_id, true), Ok(_));
+
+    let program_id = runtime.load_code_cache(SOURCE, false, &data).unwrap();
+    assert_matches!(runtime.run(program_id, true), Ok(_));
+    runtime.process_jobs();
+    assert_eq!(runtime.get_global("count").get(), Value::from(3));
+    runtime.extension().validate();
+}
+
+#[test]
+fn test_debugger() {
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.set_debugger(|runtime, pause| {
+        let exception = match pause.reason {
+            PauseReason::DebuggerStatement => None,
+            PauseReason::Exception(ref value) => Some(value.clone()),
+            PauseReason::Breakpoint(_) | PauseReason::Step => unreachable!(),
+        };
+        let names = pause
+            .scopes
+            .iter()
+            .map(|scope| {
+                let mut names = scope
+                    .variables
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>();
+                names.sort();
+                names.join(",")
+            })
+            .collect::<Vec<_>>();
+        let result = runtime.evaluate_on_pause(pause, "a + b + c");
+        let frame = pause.frames[0].to_string();
+        runtime
+            .extension_mut()
+            .push((exception, frame, names, result));
+    });
+    let program_id = runtime
+        .parse_script(
+            r#"
+function f(a) {
+  let b = a + 1;
+  {
+    const c = b * 2;
+    debugger;
+  }
+  return b;
+}
+f(1);
+try { throw 3; } catch (e) {}
+"#,
+        )
+        .unwrap();
+    runtime.set_program_name(program_id, "test.js");
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let pauses = std::mem::take(runtime.extension_mut());
+    assert_eq!(pauses.len(), 2);
+    assert_eq!(pauses[0].0, None);
+    assert_eq!(pauses[0].1, "f (test.js:6:5)");
+    assert_eq!(pauses[0].2, ["c", "a,b"]);
+    assert_eq!(pauses[0].3, Ok(Value::from(7)));
+    assert_eq!(pauses[1].0, Some(Value::from(3)));
+    assert_eq!(pauses[1].1, "test.js:11:7");
+    // `a` is not defined in the global scope.
+    assert_matches!(pauses[1].3, Err(Value::Object(_)));
+}
+
+#[test]
+fn test_breakpoints_and_steps() {
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.set_debugger(|runtime, pause| {
+        let reason = match pause.reason {
+            PauseReason::Breakpoint(_) => "breakpoint",
+            PauseReason::Step => "step",
+            _ => unreachable!(),
+        };
+        let line = pause.frames[0].line;
+        match runtime.extension().len() {
+            0 => runtime.step(StepAction::Over),
+            1 => runtime.step(StepAction::Out),
+            _ => (),
+        }
+        runtime.extension_mut().push((reason, line));
+    });
+    let program_id = runtime
+        .parse_script(
+            r#"
+function g(x) { return x * 2; }
+function f(a) {
+  let b = g(a);
+  let c = g(b);
+  return c;
+}
+var x = f(1);
+x.toString();
+"#,
+        )
+        .unwrap();
+
+    let lines = runtime
+        .pause_points(program_id)
+        .iter()
+        .map(|point| point.line)
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [4, 5, 8, 9]);
+
+    assert!(runtime.set_breakpoint(program_id, 6, 1).is_none());
+    let (id, point) = runtime.set_breakpoint(program_id, 4, 1).unwrap();
+    assert_eq!(point.line, 4);
+
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    // The step over `g(a)` pauses at `g(b)` and the step out of `f()` pauses in the caller.
+    assert_eq!(
+        runtime.extension().as_slice(),
+        [("breakpoint", 4), ("step", 5), ("step", 9)]
+    );
+
+    // A removed breakpoint no longer pauses the execution.
+    runtime.remove_breakpoint(id);
+    let program_id = runtime.parse_script("f(2);").unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+    assert_eq!(runtime.extension().len(), 3);
+}
+
+#[test]
+fn test_cpu_profile() {
+    let mut runtime = BasicRuntime::new();
+    runtime.register_host_function("sleep", |_, _| {
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    });
+    runtime.start_profiling(std::time::Duration::from_micros(100));
+    let program_id = runtime
+        .parse_script(
+            r#"
+function g() {
+  for (let i = 0; i < 5; i++) sleep();
+}
+function f() { g(); }
+f();
+"#,
+        )
+        .unwrap();
+    runtime.set_program_name(program_id, "test.js");
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let profile = runtime.stop_profiling().unwrap();
+    assert!(runtime.stop_profiling().is_none());
+    assert!(!profile.samples.is_empty());
+    assert_eq!(profile.samples.len(), profile.time_deltas.len());
+
+    // (root) -> (anonymous) -> f -> g
+    let node = |id: u32| &profile.nodes[id as usize - 1];
+    let mut path = vec![];
+    let mut current = node(1);
+    while let Some(&child) = current.children.first() {
+        current = node(child);
+        path.push((current.function.clone(), current.line, current.column));
+    }
+    assert_eq!(
+        path,
+        [
+            (None, 1, 1),
+            (Some("f".to_owned()), 5, 1),
+            (Some("g".to_owned()), 2, 1),
+        ]
+    );
+    assert_eq!(current.script.as_deref(), Some("test.js"));
+    assert!(current.hit_count > 0);
+    assert!(profile.samples.contains(&current.id));
+}
+
+#[test]
+fn test_coverage() {
+    const SOURCE: &str = r#"function f(x) {
+  if (x > 0) {
+    return 1;
+  } else {
+    return 2;
+  }
+}
+for (let i = 0; i < 3; i++) f(i);
+let a = false ? "then" : "else";
+async function g() { if (a) { return; } }
+g();
+"#;
+    let mut runtime = BasicRuntime::new();
+    runtime.start_coverage();
+    let program_id = runtime.parse_script(SOURCE).unwrap();
+    runtime.set_program_name(program_id, "test.js");
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let range = |text: &str, count: u64| {
+        let start_offset = SOURCE.find(text).unwrap() as u32;
+        CoverageRange {
+            start_offset,
+            end_offset: start_offset + text.len() as u32,
+            count,
+        }
+    };
+
+    let coverage = runtime.take_coverage();
+    assert_eq!(coverage.len(), 1);
+    let script = &coverage[0];
+    assert_eq!(script.url.as_deref(), Some("test.js"));
+    assert_eq!(script.functions.len(), 3);
+
+    let top_level = &script.functions[0];
+    assert_eq!(top_level.function_name, None);
+    assert_eq!(
+        top_level.ranges,
+        [
+            range(SOURCE, 1),
+            range("f(i);", 3),
+            range(r#""then""#, 0),
+            range(r#""else""#, 1),
+        ]
+    );
+
+    let function = |name: &str| {
+        script
+            .functions
+            .iter()
+            .find(|function| function.function_name.as_deref() == Some(name))
+            .unwrap()
+    };
+    assert_eq!(
+        function("f").ranges,
+        [
+            range(&SOURCE[..SOURCE.find("\n}").unwrap() + 2], 3),
+            range("{\n    return 1;\n  }", 2),
+            range("{\n    return 2;\n  }", 1),
+        ]
+    );
+    // The blocks in an async function are counted in its coroutine.
+    assert_eq!(
+        function("g").ranges,
+        [
+            range("async function g() { if (a) { return; } }", 1),
+            range("{ return; }", 1),
+        ]
+    );
+
+    let lcov = runtime.coverage_to_lcov(&coverage);
+    assert!(lcov.starts_with("TN:\nSF:test.js\n"));
+    assert!(lcov.contains("FNDA:3,f\n"));
+    assert!(lcov.contains("DA:3,2\n"));
+    assert!(lcov.contains("DA:5,1\n"));
+    assert!(lcov.ends_with("end_of_record\n"));
+
+    // The counters are reset.
+    let coverage = runtime.take_coverage();
+    assert!(
+        coverage[0]
+            .functions
+            .iter()
+            .flat_map(|function| function.ranges.iter())
+            .all(|range| range.count == 0)
+    );
+}
+
+#[test]
+fn test_type_feedback() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct CompiledFunctions(Rc<RefCell<Vec<LambdaId>>>);
+
+    impl Monitor for CompiledFunctions {
+        fn print_function_ir(&mut self, id: LambdaId, _ir: &dyn std::fmt::Display) {
+            self.0.borrow_mut().push(id);
+        }
+    }
+
+    let compiled = Rc::new(RefCell::new(vec![]));
+
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.set_optimization_threshold(2);
+    runtime.set_monitor(Box::new(CompiledFunctions(compiled.clone())));
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().push(args[0].clone());
+    });
+    let program_id = runtime
+        .parse_script(
+            r#"
+function f(a, b) { return (a + b) * 2; }
+function g(a, b) { return a + b; }
+let x = 0;
+for (let i = 0; i < 10; i++) x = f(x, g(i, 0));
+print(x);
+print(f(true, 2));
+print(f(undefined, 1));
+print(g("a", 1));
+print(f(3, 4));
+print(g(3, 4));
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let printed: Vec<String> = runtime
+        .extension()
+        .iter()
+        .map(|value| format!("{value}"))
+        .collect();
+    assert_eq!(printed, ["2026", "6", "NaN", "a1", "14", "7"]);
+
+    // `f` and `g` are compiled twice.  They are never optimized again after the deoptimization.
+    let compiled = compiled.borrow();
+    let num_compiled = |id: &LambdaId| compiled.iter().filter(|other| *other == id).count();
+    assert_eq!(
+        compiled.iter().filter(|id| num_compiled(id) == 2).count(),
+        4
+    );
+    assert!(compiled.iter().all(|id| num_compiled(id) <= 2));
+}
+
+#[test]
+fn test_int32_speculation() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct FunctionIrs(Rc<RefCell<Vec<String>>>);
+
+    impl Monitor for FunctionIrs {
+        fn print_function_ir(&mut self, _id: LambdaId, ir: &dyn std::fmt::Display) {
+            self.0.borrow_mut().push(ir.to_string());
+        }
+    }
+
+    let irs = Rc::new(RefCell::new(vec![]));
+
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.set_optimization_threshold(2);
+    runtime.set_monitor(Box::new(FunctionIrs(irs.clone())));
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().push(args[0].clone());
+    });
+    let program_id = runtime
+        .parse_script(
+            r#"
+function add(a, b) { return (a + b) - b; }
+function mul(a, b) { return a * b; }
+let sum = 0;
+for (let i = 0; i < 10; i++) sum += add(i, 1) + mul(i, 2);
+print(sum);
+print(add(2147483647, 1));
+print(1 / mul(-1, 0) < 0);
+print(mul(65536, 65536));
+print(add(1.5, 1));
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let printed: Vec<String> = runtime
+        .extension()
+        .iter()
+        .map(|value| format!("{value}"))
+        .collect();
+    // The overflow in the addition deoptimizes `add`, but the rest of the current call in the
+    // optimized code still computes the same result as the generic code.
+    assert_eq!(printed, ["135", "2147483647", "true", "4294967296", "1.5"]);
+
+    let irs = irs.borrow();
+    assert!(irs.iter().any(|ir| ir.contains("sadd_overflow")));
+    assert!(irs.iter().any(|ir| ir.contains("smul_overflow")));
+}
+
+#[test]
+fn test_inlining() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct FunctionIrs(Rc<RefCell<Vec<(LambdaId, String)>>>);
+
+    impl Monitor for FunctionIrs {
+        fn print_function_ir(&mut self, id: LambdaId, ir: &dyn std::fmt::Display) {
+            self.0.borrow_mut().push((id, ir.to_string()));
+        }
+    }
+
+    let irs = Rc::new(RefCell::new(vec![]));
+
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.set_optimization_threshold(2);
+    runtime.set_monitor(Box::new(FunctionIrs(irs.clone())));
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().push(args[0].clone());
+    });
+    let program_id = runtime
+        .parse_script(
+            r#"
+function apply(f, x) { return f(x) + 1; }
+function square(x) { return x * x; }
+const k = 3;
+const scale = (x) => x * k;
+let sum = 0;
+for (let i = 0; i < 10; i++) sum += apply(scale, i);
+print(sum);
+print(apply(square, 4));
+print(apply(scale, 5));
+print(apply(() => "a", 0));
+"#,
+        )
+        .unwrap();
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let printed: Vec<String> = runtime
+        .extension()
+        .iter()
+        .map(|value| format!("{value}"))
+        .collect();
+    assert_eq!(printed, ["145", "17", "16", "a1"]);
+
+    // `scale` is inlined into `apply` in the optimized code.
+    let irs = irs.borrow();
+    let recompiled = irs.iter().enumerate().any(|(i, (id, ir))| {
+        !ir.contains("fmul")
+            && irs[i + 1..]
+                .iter()
+                .any(|(other, ir)| other == id && ir.contains("fmul"))
+    });
+    assert!(recompiled);
+}
+
+#[test]
+fn test_scalar_replacement() {
+    let mut runtime = Runtime::with_extension(vec![]);
+    runtime.set_optimization_threshold(2);
+    runtime.register_host_function("print", |runtime, args| {
+        runtime.extension_mut().push(args[0].clone());
+    });
+    let program_id = runtime
+        .parse_script(
+            r#"
+function sum(n, k) {
+  const scale = (x) => x * k;
+  let sum = 0;
+  for (let i = 0; i < n; i++) sum += scale(i);
+  return sum;
+}
+function make(k) {
+  const scale = (x) => x * k;
+  return scale;
+}
+let total = 0;
+for (let i = 0; i < 100; i++) total += sum(10, i);
+print(total);
+let scale;
+for (let i = 0; i < 10; i++) scale = make(i);
+print(scale(3));
+"#,
+        )
+        .unwrap();
+
+    let count_objects = |runtime: &Runtime<Vec<Value>>, name: &str| {
+        runtime
+            .heap_stats()
+            .types
+            .iter()
+            .filter(|stats| stats.type_name.ends_with(name))
+            .map(|stats| stats.num_objects)
+            .sum::<usize>()
+    };
+    let num_closures = count_objects(&runtime, "::Closure");
+    let num_captures = count_objects(&runtime, "::Capture");
+
+    let result = runtime.run(program_id, true);
+    assert_matches!(result, Ok(_));
+
+    let printed: Vec<String> = runtime
+        .extension()
+        .iter()
+        .map(|value| format!("{value}"))
+        .collect();
+    assert_eq!(printed, ["222750", "27"]);
+
+    // The closure in `sum` is created only before `sum` is optimized.  The closure in `make`
+    // escapes and it's created in every call.
+    assert!(count_objects(&runtime, "::Closure") - num_closures < 50);
+    assert!(count_objects(&runtime, "::Capture") - num_captures < 50);
+}
+
 pub fn evaluate_multiple_programs(
     sources: &[(&str, bool)],
     expected_values: Vec<Value>,
@@ -0,0 +1,10 @@
+try {
+  x = 1;
+} catch (e) {
+  print(e.name); ///="ReferenceError"
+}
+
+function f() {
+  return this;
+}
+print(f() === undefined); ///=true
@@ -0,0 +1,36 @@
+function f(a, b) {
+  return arguments;
+}
+
+const args = f(1, 2, 3);
+print(args.length); ///=3
+print(args[0]); ///=1
+print(args[2]); ///=3
+
+function mapped(a) {
+  arguments[0] = 2;
+  print(a); ///=2
+  a = 3;
+  print(arguments[0]); ///=3
+}
+mapped(1);
+
+function unmapped(a = 0) {
+  arguments[0] = 2;
+  print(a); ///=1
+  a = 3;
+  print(arguments[0]); ///=2
+}
+unmapped(1);
+
+function outer() {
+  return (() => arguments[0])();
+}
+print(outer(4)); ///=4
+
+function shadowed(arguments) {
+  return arguments;
+}
+print(shadowed(5)); ///=5
+
+print(typeof (function() { return arguments.callee; })()); ///="function"
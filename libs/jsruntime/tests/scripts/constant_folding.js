@@ -0,0 +1,27 @@
+print(-1 >>> 0); ///=4294967295
+print(1 << 31); ///=-2147483648
+print(-8 >> 1); ///=-4
+print(1 / 0); ///=Infinity
+print(5 % -2); ///=1
+print(2 ** 10); ///=1024
+print(~~3.7); ///=3
+print(0 === -0); ///=true
+print(NaN == NaN); ///=false
+print(null == undefined); ///=true
+print(null == 0); ///=false
+print(1 == true); ///=true
+print(1 === true); ///=false
+print(!NaN); ///=true
+print(+null); ///=0
+print(1 + null); ///=1
+print(undefined < 1); ///=false
+let a = 2;
+let b = a * 3;
+print(a + b); ///=8
+a = "x";
+print(a + b); ///="x6"
+let c = 1;
+if (b > 0) {
+  c = 3;
+}
+print(c * 2); ///=6
@@ -0,0 +1,9 @@
+function f(a, b = a + 1, c = 3) {
+  return a * 100 + b * 10 + c;
+}
+
+print(f(1)); ///=123
+print(f(1, undefined, 0)); ///=120
+print(f(1, 4)); ///=143
+print(f.length); ///=1
+print(((a = 1) => a)()); ///=1
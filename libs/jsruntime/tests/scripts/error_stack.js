@@ -0,0 +1,22 @@
+function f() {
+  return new Error('boom');
+}
+
+const e = f();
+print(e.stack); ///="Error: boom\n    at f (<anonymous>:2:10)\n    at <anonymous>:5:11"
+
+try {
+  Object.defineProperty({}, 'a', { get: null });
+} catch (e) {
+  print(e.stack); ///="TypeError\n    at <anonymous>:9:3"
+}
+
+function g() {
+  return undefined();
+}
+
+try {
+  g();
+} catch (e) {
+  print(e.stack); ///="TypeError\n    at g (<anonymous>:15:10)\n    at <anonymous>:19:3"
+}
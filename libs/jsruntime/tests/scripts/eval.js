@@ -1,6 +1,11 @@
+print(eval('1 + 2')); ///=3
+print(eval('')); ///=undefined
+print(eval(42)); ///=42
+print(eval('"a"; "b"')); ///="b"
+
 try {
-  eval('');
-  print(0);
+  eval('1 +');
 } catch (e) {
-  print(1); ///=1
+  print(e.name); ///="SyntaxError"
+  print(e.message); ///="Syntax error"
 }
@@ -0,0 +1,50 @@
+function f(a) {
+  let b = 2;
+  return eval('a + b');
+}
+print(f(1)); ///=3
+
+function g() {
+  let x = 1;
+  eval('x = 2');
+  return x;
+}
+print(g()); ///=2
+
+function h() {
+  let x = 1;
+  const inc = eval('() => ++x');
+  inc();
+  inc();
+  return x;
+}
+print(h()); ///=3
+
+function shadow() {
+  let x = 1;
+  {
+    let x = 2;
+    return eval('x');
+  }
+}
+print(shadow()); ///=2
+
+const o = {
+  m() {
+    return eval('this');
+  },
+};
+print(o.m() === o); ///=true
+
+function strict() {
+  'use strict';
+  eval('var z = 1');
+  return typeof z;
+}
+print(strict()); ///="undefined"
+
+function nested() {
+  let x = 1;
+  return eval('eval("x + 1")');
+}
+print(nested()); ///=2
@@ -0,0 +1,13 @@
+var x = 'global';
+
+function f() {
+  let x = 'local';
+  return (0, eval)('x');
+}
+print(f()); ///="global"
+
+const indirect = eval;
+print(indirect('this') === globalThis); ///=true
+
+indirect('var y = 1');
+print(y); ///=1
@@ -0,0 +1,8 @@
+print(typeof FinalizationRegistry); ///="function"
+print(FinalizationRegistry.prototype.constructor === FinalizationRegistry); ///=true
+const registry = new FinalizationRegistry(() => {});
+const target = {};
+const token = {};
+print(registry.register(target, 1, token)); ///=undefined
+print(registry.unregister(token)); ///=true
+print(registry.unregister(token)); ///=false
@@ -0,0 +1,40 @@
+const add = new Function('a', 'b', 'return a + b');
+print(add(1, 2)); ///=3
+print(add.name); ///="anonymous"
+print(add.length); ///=2
+
+const f = Function('return 42');
+print(f()); ///=42
+
+const g = new Function();
+print(g()); ///=undefined
+
+const h = new Function('a, b', 'c', 'return a + b + c');
+print(h(1, 2, 3)); ///=6
+
+var x = 'global';
+function outer() {
+  let x = 'local';
+  return new Function('return x')();
+}
+print(outer()); ///="global"
+
+try {
+  new Function('}), (function () {');
+} catch (e) {
+  print(e.name); ///="SyntaxError"
+}
+
+try {
+  new Function('/*', '*/){');
+} catch (e) {
+  print(e.name); ///="SyntaxError"
+}
+
+try {
+  new Function('}); var leaked; (function () {');
+} catch (e) {
+  print(e.name); ///="SyntaxError"
+}
+print(typeof leaked); ///="undefined"
+print('leaked' in globalThis); ///="false"
@@ -0,0 +1,6 @@
+print((function() {}).length); ///=0
+print((function(a, b) {}).length); ///=2
+print((function(a, b = 1, c) {}).length); ///=1
+print((function(a, ...b) {}).length); ///=1
+print(((a, b) => a).length); ///=2
+print((async function(a) {}).length); ///=1
@@ -2,8 +2,29 @@ print(typeof Function.prototype.toString); ///="function"
 print(Function.prototype.toString.length); ///=0
 
 function a() {};
+print(a.toString()); ///="function a() {}"
+
+function b(x, /* y */ z) {
+  return x + z;
+}
+print(b.toString()); ///="function b(x, /* y */ z) {\n  return x + z;\n}"
+
+const c = (x) => x * 2;
+print(c.toString()); ///="(x) => x * 2"
+
+const d = async function () {};
+print(d.toString()); ///="async function () {}"
+
+const o = { m(x) { return x; } };
+print(o.m.toString()); ///="m(x) { return x; }"
+
+print(new Function('a', 'b', 'return a + b').toString()); ///="function anonymous(a,b\n) {\nreturn a + b\n}"
+
+print(Function.prototype.call.toString()); ///="function call() { [native code] }"
+print(a.bind(null).toString()); ///="function a() { [native code] }"
+
 try {
-  print(a.toString()); // TODO
+  Function.prototype.toString.call({});
 } catch (e) {
-  print(e.name); ///="InternalError"
+  print(e.name); ///="TypeError"
 }
@@ -0,0 +1,16 @@
+function A() {
+  this.a = 1;
+  return { b: 2 };
+}
+
+function B() {
+  this.a = 1;
+  return 2;
+}
+
+const a = new A();
+print(a.a); ///=undefined
+print(a.b); ///=2
+
+const b = new B();
+print(b.a); ///=1
@@ -0,0 +1,6 @@
+print(typeof Object.isExtensible); ///="function"
+print(Object.isExtensible.length); ///=1
+
+print(Object.isExtensible({})); ///=true
+print(Object.isExtensible(1)); ///=false
+print(Object.isExtensible(Object.preventExtensions({}))); ///=false
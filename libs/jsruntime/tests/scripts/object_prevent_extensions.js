@@ -0,0 +1,15 @@
+print(typeof Object.preventExtensions); ///="function"
+print(Object.preventExtensions.length); ///=1
+
+const o = {};
+print(Object.preventExtensions(o) === o); ///=true
+print(Object.preventExtensions(1)); ///=1
+
+o.a = 1;
+print(o.a); ///=undefined
+
+try {
+  Object.defineProperty(o, 'a', { value: 1 });
+} catch (e) {
+  print(e.name); ///="TypeError"
+}
@@ -0,0 +1,4 @@
+setTimeout(() => print(3)); ///#3=3
+queueMicrotask(() => print(1)); ///#1=1
+queueMicrotask(() => queueMicrotask(() => print(2))); ///#2=2
+print(0); ///#0=0
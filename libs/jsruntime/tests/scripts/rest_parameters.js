@@ -0,0 +1,11 @@
+function f(a, ...rest) {
+  return rest;
+}
+
+const r = f(1, 2, 3);
+print(r.length); ///=2
+print(r[0]); ///=2
+print(r[1]); ///=3
+print(f(1).length); ///=0
+print(f.length); ///=1
+print(((...args) => args.length)(1, 2, 3)); ///=3
@@ -0,0 +1,8 @@
+let count = 0;
+let id = setInterval(() => {
+  count++;
+  if (count === 3) {
+    clearInterval(id);
+  }
+}, 100);
+setTimeout(() => print(count), 1000); ///=3
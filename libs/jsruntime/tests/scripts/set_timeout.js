@@ -0,0 +1,6 @@
+print(0); ///#0=0
+setTimeout((a, b) => print(a + b), 20, 1, 3); ///#4=4
+setTimeout(() => print(3), 10); ///#3=3
+setTimeout(() => print(2)); ///#2=2
+clearTimeout(setTimeout(() => print(-1), 0));
+print(1); ///#1=1
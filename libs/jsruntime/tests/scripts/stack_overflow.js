@@ -0,0 +1,13 @@
+function f() {
+  return f() + 1;
+}
+
+try {
+  f();
+} catch (e) {
+  print(e.name); ///="RangeError"
+  print(e.message); ///="Maximum call stack size exceeded"
+}
+
+// The runtime can call functions after the stack overflow.
+print(1 + 1); ///=2
@@ -0,0 +1,18 @@
+'use strict';
+
+try {
+  x = 1;
+} catch (e) {
+  print(e.name); ///="ReferenceError"
+}
+
+// No global variable is created.
+try {
+  print(x);
+} catch (e) {
+  print(e.name); ///="ReferenceError"
+}
+
+globalThis.y = 1;
+y = 2;
+print(y); ///=2
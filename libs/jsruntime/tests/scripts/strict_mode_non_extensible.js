@@ -0,0 +1,21 @@
+function f(o) {
+  'use strict';
+  o.b = 1;
+}
+
+const o = { a: 1 };
+Object.preventExtensions(o);
+
+o.a = 2;
+print(o.a); ///=2
+
+// Silently ignored in non-strict mode code.
+o.b = 1;
+print(o.hasOwnProperty('b')); ///=false
+
+try {
+  f(o);
+} catch (e) {
+  print(e.name); ///="TypeError"
+}
+print(o.hasOwnProperty('b')); ///=false
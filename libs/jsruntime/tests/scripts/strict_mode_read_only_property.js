@@ -0,0 +1,32 @@
+'use strict';
+
+const o = {};
+
+Object.defineProperty(o, 'a', { value: 1 });
+try {
+  o.a = 2;
+} catch (e) {
+  print(e.name); ///="TypeError"
+}
+print(o.a); ///=1
+
+Object.defineProperty(o, 'b', {
+  get: function () {
+    return 1;
+  },
+});
+try {
+  o.b = 2;
+} catch (e) {
+  print(e.name); ///="TypeError"
+}
+print(o.b); ///=1
+
+// An inherited read-only property cannot be shadowed by an assignment.
+const p = Object.create(o);
+try {
+  p.a = 2;
+} catch (e) {
+  print(e.name); ///="TypeError"
+}
+print(p.hasOwnProperty('a')); ///=false
@@ -0,0 +1,20 @@
+function f() {
+  'use strict';
+  return this;
+}
+
+function g() {
+  'use strict';
+  return () => this;
+}
+
+function h() {
+  return this;
+}
+
+print(f() === undefined); ///=true
+print(g()() === undefined); ///=true
+print(h() === globalThis); ///=true
+
+const o = { f: f };
+print(o.f() === o); ///=true
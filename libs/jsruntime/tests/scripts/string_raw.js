@@ -0,0 +1,17 @@
+print(String.raw`a\tb${1}c` === 'a\\tb1c'); ///=true
+print(String.raw`${1}${2}${3}`); ///="123"
+print(String.raw({ raw: ['x', 'y', 'z'] }, 1)); ///="x1yz"
+print(String.raw({ raw: ['x', 'y'] }, 1, 2, 3)); ///="x1y"
+
+const raw = { length: 2 };
+raw[0] = 'a';
+raw[1] = 'b';
+print(String.raw({ raw }, 0)); ///="a0b"
+
+print(String.raw({ raw: [] }, 1)); ///=""
+
+try {
+  String.raw();
+} catch (e) {
+  print(e.name); ///="TypeError"
+}
@@ -0,0 +1,39 @@
+function tag(strings, a, b) {
+  print(strings.length); ///=3
+  print(strings[0]); ///="a"
+  print(strings[1]); ///="b"
+  print(strings[2]); ///="c"
+  print(a); ///=1
+  print(b); ///=2
+  return 'ok';
+}
+print(tag`a${1}b${2}c`); ///="ok"
+
+function args(strings, ...substitutions) {
+  return strings.length + ':' + substitutions.length;
+}
+print(args`x`); ///="1:0"
+print(args`${1}${2}`); ///="3:2"
+print(args`${`${1}`}`); ///="2:1"
+
+function make(prefix) {
+  return function (strings, value) {
+    return prefix + strings[0] + value;
+  };
+}
+print(make('>')`a${1}`); ///=">a1"
+
+const obj = {
+  name: 'obj',
+  tag(strings) {
+    return this.name + strings[0];
+  },
+};
+print(obj.tag`!`); ///="obj!"
+print(obj['tag']`?`); ///="obj?"
+
+try {
+  eval('obj?.tag`x`');
+} catch (e) {
+  print(e.name); ///="SyntaxError"
+}
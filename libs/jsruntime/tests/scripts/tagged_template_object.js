@@ -0,0 +1,22 @@
+function id(strings) {
+  return strings;
+}
+
+// The same template object is used for each evaluation of a tagged template.
+function get() {
+  return id`x${0}y`;
+}
+print(get() === get()); ///=true
+print(id`x${0}y` === id`x${0}y`); ///=false
+
+// The template object and its `raw` property are frozen.
+const t = get();
+print(Object.isExtensible(t)); ///=false
+print(Object.isExtensible(t.raw)); ///=false
+t[0] = 'z';
+print(t[0]); ///="x"
+t.raw = null;
+print(t.raw[1]); ///="y"
+print(t.length); ///=2
+print(t.propertyIsEnumerable('raw')); ///=false
+print(t.propertyIsEnumerable('length')); ///=false
@@ -0,0 +1,31 @@
+function cooked(strings) {
+  return strings[0];
+}
+
+function raw(strings) {
+  return strings.raw[0];
+}
+
+print(cooked`a\tb` === 'a\tb'); ///=true
+print(raw`a\tb` === 'a\\tb'); ///=true
+print(raw`a\tb`.length); ///=4
+print(cooked`\u{41}\x42C`); ///="ABC"
+print(raw`\u{41}` === '\\u{41}'); ///=true
+
+// The cooked string is undefined if the template string contains an invalid escape sequence.
+print(cooked`\unicode` === undefined); ///=true
+print(raw`\unicode` === '\\unicode'); ///=true
+print(cooked`\01` === undefined); ///=true
+print(cooked`\xZ` === undefined); ///=true
+print(cooked`\u{110000}` === undefined); ///=true
+
+// Line terminators are normalized.
+print(eval('cooked`a\r\nb`') === 'a\nb'); ///=true
+print(eval('raw`a\rb`') === 'a\nb'); ///=true
+
+// An invalid escape sequence is a syntax error in an untagged template.
+try {
+  eval('`\\unicode`');
+} catch (e) {
+  print(e.name); ///="SyntaxError"
+}
@@ -4,10 +4,9 @@ function a() {
 
 print(a() === globalThis); ///=true
 
-// TODO(feat): strict mode
-// function b() {
-//   "use strict";
-//   return this;
-// }
+function b() {
+  "use strict";
+  return this;
+}
 
-// print(b() === undefined); ///=true
+print(b() === undefined); ///=true
@@ -0,0 +1,5 @@
+print(typeof WeakRef); ///="function"
+print(WeakRef.prototype.constructor === WeakRef); ///=true
+const target = {};
+const ref = new WeakRef(target);
+print(ref.deref() === target); ///=true